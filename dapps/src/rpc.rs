@@ -18,6 +18,7 @@ use std::sync::{Arc, Mutex};
 use hyper;
 
 use ethcore_rpc::{Metadata, Origin};
+use ethcore_rpc::request_id::{read_request_id, RequestIdHandler};
 use jsonrpc_core::Middleware;
 use jsonrpc_core::reactor::RpcHandler;
 use jsonrpc_http_server::{Rpc, ServerHandler, PanicHandler, AccessControlAllowOrigin, HttpMetaExtractor};
@@ -29,7 +30,6 @@ pub fn rpc<T: Middleware<Metadata>>(
 ) -> Box<Endpoint> {
 	Box::new(RpcEndpoint {
 		handler: handler,
-		meta_extractor: Arc::new(MetadataExtractor),
 		panic_handler: panic_handler,
 		cors_domain: None,
 		// NOTE [ToDr] We don't need to do any hosts validation here. It's already done in router.
@@ -39,7 +39,6 @@ pub fn rpc<T: Middleware<Metadata>>(
 
 struct RpcEndpoint<T: Middleware<Metadata>> {
 	handler: RpcHandler<Metadata, T>,
-	meta_extractor: Arc<HttpMetaExtractor<Metadata>>,
 	panic_handler: Arc<Mutex<Option<Box<Fn() -> () + Send>>>>,
 	cors_domain: Option<Vec<AccessControlAllowOrigin>>,
 	allowed_hosts: Option<Vec<String>>,
@@ -48,17 +47,27 @@ struct RpcEndpoint<T: Middleware<Metadata>> {
 impl<T: Middleware<Metadata>> Endpoint for RpcEndpoint<T> {
 	fn to_async_handler(&self, _path: EndpointPath, control: hyper::Control) -> Box<Handler> {
 		let panic_handler = PanicHandler { handler: self.panic_handler.clone() };
-		Box::new(ServerHandler::new(
-				Rpc::new(self.handler.clone(), self.meta_extractor.clone()),
+		let request_id = Arc::new(Mutex::new(None));
+		let meta_extractor = MetadataExtractor {
+			request_id: request_id.clone(),
+		};
+		Box::new(RequestIdHandler::new(
+			ServerHandler::new(
+				Rpc::new(self.handler.clone(), Arc::new(meta_extractor)),
 				self.cors_domain.clone(),
 				self.allowed_hosts.clone(),
 				panic_handler,
 				control,
+			),
+			request_id,
 		))
 	}
 }
 
-struct MetadataExtractor;
+struct MetadataExtractor {
+	request_id: Arc<Mutex<Option<String>>>,
+}
+
 impl HttpMetaExtractor<Metadata> for MetadataExtractor {
 	fn read_metadata(&self, request: &hyper::server::Request<hyper::net::HttpStream>) -> Metadata {
 		let dapp_id = request.headers().get::<hyper::header::Origin>()
@@ -75,9 +84,13 @@ impl HttpMetaExtractor<Metadata> for MetadataExtractor {
 						None
 					})
 			});
+		let request_id = read_request_id(request.headers());
+		*self.request_id.lock().expect("Lock is never poisoned; qed") = Some(request_id.clone());
 		Metadata {
 			dapp_id: dapp_id,
 			origin: Origin::Dapps,
+			request_id: Some(request_id),
+			remote_ip: request.remote_addr().map(|addr| addr.ip()),
 		}
 	}
 }
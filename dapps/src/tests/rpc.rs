@@ -117,3 +117,66 @@ fn should_extract_metadata_from_custom_header() {
 	response.assert_status("HTTP/1.1 200 OK");
 	assert_eq!(response.body, "31\n{\"jsonrpc\":\"2.0\",\"result\":\"Hello World!\",\"id\":1}\n\n0\n\n".to_owned());
 }
+
+#[test]
+fn should_echo_request_id() {
+	// given
+	let mut io = MetaIoHandler::default();
+	io.add_method_with_meta("rpc_test", |_params, meta: Metadata| {
+		assert_eq!(meta.request_id, Some("test-123".to_owned()));
+		future::ok(Value::String("Hello World!".into())).boxed()
+	});
+	let server = serve_with_rpc(io);
+
+	// when
+	let req = r#"{"jsonrpc":"2.0","id":1,"method":"rpc_test","params":[]}"#;
+	let response = request(server, &format!(
+		"\
+			POST /rpc/ HTTP/1.1\r\n\
+			Host: 127.0.0.1:8080\r\n\
+			Connection: close\r\n\
+			X-Request-ID: test-123\r\n\
+			Content-Type: application/json\r\n\
+			Content-Length: {}\r\n\
+			\r\n\
+			{}\r\n\
+		",
+		req.as_bytes().len(),
+		req,
+	));
+
+	// then
+	response.assert_status("HTTP/1.1 200 OK");
+	response.assert_header("X-Request-ID", "test-123");
+}
+
+#[test]
+fn should_generate_request_id() {
+	// given
+	let mut io = MetaIoHandler::default();
+	io.add_method_with_meta("rpc_test", |_params, meta: Metadata| {
+		assert_eq!(meta.request_id.map(|id| id.len()), Some(36));
+		future::ok(Value::String("Hello World!".into())).boxed()
+	});
+	let server = serve_with_rpc(io);
+
+	// when
+	let req = r#"{"jsonrpc":"2.0","id":1,"method":"rpc_test","params":[]}"#;
+	let response = request(server, &format!(
+		"\
+			POST /rpc/ HTTP/1.1\r\n\
+			Host: 127.0.0.1:8080\r\n\
+			Connection: close\r\n\
+			Content-Type: application/json\r\n\
+			Content-Length: {}\r\n\
+			\r\n\
+			{}\r\n\
+		",
+		req.as_bytes().len(),
+		req,
+	));
+
+	// then
+	response.assert_status("HTTP/1.1 200 OK");
+	assert!(response.headers.iter().any(|header| header.starts_with("X-Request-ID: ")), "Missing request id in {:?}", response.headers);
+}
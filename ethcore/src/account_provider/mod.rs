@@ -18,23 +18,26 @@
 
 mod stores;
 
-use self::stores::{AddressBook, DappsSettingsStore, NewDappsPolicy};
-
-use std::fmt;
-use std::collections::{HashMap, HashSet};
-use std::time::{Instant, Duration};
-use util::RwLock;
+use self::stores::{AddressBook, DappsSettings, DappsSettingsStore, NewDappsPolicy, meta_matches};
+
+use std::{fmt, thread};
+use std::sync::{Arc, Weak, atomic};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Instant, Duration, SystemTime};
+use util::{RwLock, Mutex, H256, FixedHash, Hashable};
 use ethstore::{SimpleSecretStore, SecretStore, Error as SSError, EthStore, EthMultiStore,
-	random_string, SecretVaultRef, StoreAccountRef};
+	random_string, Mnemonic, SecretVaultRef, StoreAccountRef};
 use ethstore::dir::MemoryDirectory;
-use ethstore::ethkey::{Address, Message, Public, Secret, Random, Generator};
-use ethjson::misc::AccountMeta;
-use hardware_wallet::{Error as HardwareError, HardwareWalletManager, KeyPath};
+use ethstore::ethkey::{Address, Message, Public, Secret, Random, Generator, verify_address};
+use ethjson::misc::{AccountMeta, AddressBookEntry};
+use hardware_wallet::{Error as HardwareError, HardwareWalletManager, HardwareWalletManagerBuilder, KeyPath, WalletInfo};
 pub use ethstore::ethkey::Signature;
 
 /// Type of unlock.
 #[derive(Clone)]
-enum Unlock {
+pub enum Unlock {
 	/// If account is unlocked temporarily, it should be locked after first usage.
 	Temp,
 	/// Account unlocked permantently can always sign message.
@@ -42,6 +45,66 @@ enum Unlock {
 	Perm,
 	/// Account unlocked with a timeout
 	Timed(Instant),
+	/// Account unlocked permanently, but locked again after given time without usage.
+	/// Holds the inactivity timeout and the time of last usage.
+	PermWithTimeout(Duration, Instant),
+}
+
+type UnlockedAccounts = HashMap<StoreAccountRef, AccountData>;
+
+/// Kind of unlock reported to subscribers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnlockMode {
+	/// Unlocked for a single signing.
+	Temp,
+	/// Unlocked permanently.
+	Perm,
+	/// Unlocked until a deadline.
+	Timed,
+}
+
+impl<'a> From<&'a Unlock> for UnlockMode {
+	fn from(unlock: &'a Unlock) -> Self {
+		match *unlock {
+			Unlock::Temp => UnlockMode::Temp,
+			Unlock::Perm | Unlock::PermWithTimeout(..) => UnlockMode::Perm,
+			Unlock::Timed(_) => UnlockMode::Timed,
+		}
+	}
+}
+
+/// Change of account unlock state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnlockEvent {
+	/// Account was unlocked with a password.
+	Unlocked {
+		/// Unlocked account.
+		address: Address,
+		/// Kind of unlock.
+		mode: UnlockMode,
+	},
+	/// Unlock was consumed or has expired.
+	Locked {
+		/// Locked account.
+		address: Address,
+	},
+}
+
+/// Number of events buffered for each subscriber. Events are dropped for subscribers lagging behind.
+const UNLOCK_EVENTS_BUFFER: usize = 64;
+
+type UnlockSubscribers = Arc<Mutex<Vec<SyncSender<UnlockEvent>>>>;
+
+/// Sends the event to all subscribers, forgetting the ones which hung up.
+fn publish(subscribers: &Mutex<Vec<SyncSender<UnlockEvent>>>, event: UnlockEvent) {
+	subscribers.lock().retain(|subscriber| match subscriber.try_send(event.clone()) {
+		Err(TrySendError::Disconnected(_)) => false,
+		Err(TrySendError::Full(_)) => {
+			warn!("Unlock event subscriber is lagging behind; event dropped.");
+			true
+		},
+		Ok(()) => true,
+	});
 }
 
 /// Data associated with account.
@@ -51,6 +114,92 @@ struct AccountData {
 	password: String,
 }
 
+/// Number of consecutive failed unlock attempts tolerated before the account is locked out.
+const UNLOCK_ATTEMPTS_BEFORE_LOCKOUT: u32 = 5;
+/// Lockout imposed after the first series of failed attempts. Doubles with every further failure.
+const UNLOCK_LOCKOUT_SECS: u64 = 30;
+/// Upper bound for the lockout.
+const UNLOCK_MAX_LOCKOUT_SECS: u64 = 60 * 60;
+
+/// Whether unlocking an account may currently be attempted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnlockStatus {
+	/// Password will be checked.
+	Open,
+	/// Too many failed attempts, unlocking is refused until given time.
+	LockedUntil(Instant),
+}
+
+/// Unlocked account with its remaining unlock time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnlockedAccountInfo {
+	/// Unlocked account.
+	pub address: Address,
+	/// Kind of unlock: `temp`, `perm` or `timed`.
+	pub mode: String,
+	/// Milliseconds until the account gets locked. `None` for permanent unlocks, `0` for single-use ones.
+	pub expires_in_ms: Option<u64>,
+}
+
+fn millis(duration: Duration) -> u64 {
+	duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1_000_000
+}
+
+/// Maximal number of events kept in the audit log.
+const MAX_AUDIT_EVENTS: usize = 1000;
+
+/// Kind of account access recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditOperation {
+	/// Account was unlocked with a password.
+	Unlocked,
+	/// Message was signed with a stored account.
+	Signed,
+	/// Message was signed using a rolling token.
+	TokenSigned,
+	/// Transaction was signed by a hardware wallet.
+	HardwareSigned,
+	/// Temporary or timed unlock has ended.
+	Locked,
+}
+
+/// Single audit log entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+	/// Time of the access.
+	pub timestamp: SystemTime,
+	/// Accessed account.
+	pub address: Address,
+	/// Kind of access.
+	pub operation: AuditOperation,
+}
+
+type AuditLog = RwLock<VecDeque<AuditEvent>>;
+
+/// Appends an entry to the audit log, dropping the oldest one if the log is full.
+fn audit(log: &AuditLog, address: Address, operation: AuditOperation) {
+	let mut log = log.write();
+	if log.len() == MAX_AUDIT_EVENTS {
+		log.pop_front();
+	}
+	log.push_back(AuditEvent {
+		timestamp: SystemTime::now(),
+		address: address,
+		operation: operation,
+	});
+}
+
+/// Vault along with the number of accounts it holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultSummary {
+	/// Vault name.
+	pub name: String,
+	/// Whether the vault is currently opened.
+	pub is_open: bool,
+	/// Number of accounts in the vault, if it could be determined.
+	pub account_count: Option<usize>,
+}
+
 /// Signing error
 #[derive(Debug)]
 pub enum SignError {
@@ -60,6 +209,8 @@ pub enum SignError {
 	NotFound,
 	/// Low-level hardware device error.
 	Hardware(HardwareError),
+	/// Hardware wallet confirmation was not signed by the expected account.
+	InvalidConfirmation,
 	/// Low-level error from store
 	SStore(SSError)
 }
@@ -70,6 +221,7 @@ impl fmt::Display for SignError {
 			SignError::NotUnlocked => write!(f, "Account is locked"),
 			SignError::NotFound => write!(f, "Account does not exist"),
 			SignError::Hardware(ref e) => write!(f, "{}", e),
+			SignError::InvalidConfirmation => write!(f, "Hardware wallet confirmation was not signed by the expected account"),
 			SignError::SStore(ref e) => write!(f, "{}", e),
 		}
 	}
@@ -110,18 +262,39 @@ fn transient_sstore() -> EthMultiStore {
 
 type AccountToken = String;
 
+/// Default time after which rolling tokens expire.
+const DEFAULT_TOKEN_TTL_SECS: u64 = 15 * 60;
+
+fn unix_millis() -> u64 {
+	::std::time::UNIX_EPOCH.elapsed().map(millis).unwrap_or(0)
+}
+
 /// Account management.
 /// Responsible for unlocking accounts.
 pub struct AccountProvider {
-	unlocked: RwLock<HashMap<StoreAccountRef, AccountData>>,
+	unlocked: Arc<RwLock<UnlockedAccounts>>,
 	address_book: RwLock<AddressBook>,
 	dapps_settings: RwLock<DappsSettingsStore>,
 	/// Accounts on disk
 	sstore: Box<SecretStore>,
 	/// Accounts unlocked with rolling tokens
 	transient_sstore: EthMultiStore,
+	/// Validity of newly issued rolling tokens.
+	token_ttl: RwLock<Duration>,
 	/// Accounts in hardware wallets.
 	hardware_store: Option<HardwareWalletManager>,
+	/// Hardware wallets seen since startup, including the ones unplugged since.
+	seen_hardware_wallets: RwLock<HashMap<Address, WalletInfo>>,
+	/// Consecutive failed unlock attempts and time of the last one.
+	failed_attempts: RwLock<HashMap<StoreAccountRef, (u32, Instant)>>,
+	/// Recent account accesses.
+	audit_log: Arc<AuditLog>,
+	/// Receivers of unlock state changes.
+	unlock_subscribers: UnlockSubscribers,
+	/// Thread locking accounts after inactivity, started with the first inactivity timeout.
+	autolock_timer: Mutex<Option<thread::Thread>>,
+	/// Tells the auto-lock thread to quit.
+	exiting: Arc<AtomicBool>,
 }
 
 /// Account management settings.
@@ -146,7 +319,8 @@ impl AccountProvider {
 	pub fn new(sstore: Box<SecretStore>, settings: AccountProviderSettings) -> Self {
 		let mut hardware_store = None;
 		if settings.enable_hardware_wallets {
-			match HardwareWalletManager::new() {
+			let aliases_path = sstore.local_path().join("hardware_wallet_aliases.json");
+			match HardwareWalletManagerBuilder::default().aliases_path(aliases_path).build() {
 				Ok(manager) => {
 					manager.set_key_path(if settings.hardware_wallet_classic_key { KeyPath::EthereumClassic } else { KeyPath::Ethereum });
 					hardware_store = Some(manager)
@@ -154,25 +328,44 @@ impl AccountProvider {
 				Err(e) => warn!("Error initializing hardware wallets: {}", e),
 			}
 		}
+		Self::new_with_hardware(sstore, hardware_store)
+	}
+
+	/// Creates new account provider using given hardware wallet manager.
+	pub fn new_with_hardware(sstore: Box<SecretStore>, hardware_store: Option<HardwareWalletManager>) -> Self {
 		AccountProvider {
-			unlocked: RwLock::new(HashMap::new()),
+			unlocked: Arc::new(RwLock::new(HashMap::new())),
 			address_book: RwLock::new(AddressBook::new(&sstore.local_path())),
 			dapps_settings: RwLock::new(DappsSettingsStore::new(&sstore.local_path())),
 			sstore: sstore,
 			transient_sstore: transient_sstore(),
+			token_ttl: RwLock::new(Duration::from_secs(DEFAULT_TOKEN_TTL_SECS)),
 			hardware_store: hardware_store,
+			seen_hardware_wallets: RwLock::new(HashMap::new()),
+			failed_attempts: RwLock::new(HashMap::new()),
+			audit_log: Arc::new(RwLock::new(VecDeque::new())),
+			unlock_subscribers: Arc::new(Mutex::new(Vec::new())),
+			autolock_timer: Mutex::new(None),
+			exiting: Arc::new(AtomicBool::new(false)),
 		}
 	}
 
 	/// Creates not disk backed provider.
 	pub fn transient_provider() -> Self {
 		AccountProvider {
-			unlocked: RwLock::new(HashMap::new()),
+			unlocked: Arc::new(RwLock::new(HashMap::new())),
 			address_book: RwLock::new(AddressBook::transient()),
 			dapps_settings: RwLock::new(DappsSettingsStore::transient()),
 			sstore: Box::new(EthStore::open(Box::new(MemoryDirectory::default())).expect("MemoryDirectory load always succeeds; qed")),
 			transient_sstore: transient_sstore(),
+			token_ttl: RwLock::new(Duration::from_secs(DEFAULT_TOKEN_TTL_SECS)),
 			hardware_store: None,
+			seen_hardware_wallets: RwLock::new(HashMap::new()),
+			failed_attempts: RwLock::new(HashMap::new()),
+			audit_log: Arc::new(RwLock::new(VecDeque::new())),
+			unlock_subscribers: Arc::new(Mutex::new(Vec::new())),
+			autolock_timer: Mutex::new(None),
+			exiting: Arc::new(AtomicBool::new(false)),
 		}
 	}
 
@@ -203,12 +396,24 @@ impl AccountProvider {
 		Ok(Address::from(account.address).into())
 	}
 
+	/// Import an account from a BIP-39 mnemonic phrase, using the `m/44'/60'/0'/0/0` derivation path.
+	pub fn import_from_mnemonic(&self, mnemonic: &str, passphrase: &str, password: &str) -> Result<Address, Error> {
+		let secret = Mnemonic::new(mnemonic)?.secret(passphrase)?;
+		self.insert_account(secret, password)
+	}
+
 	/// Import a new presale wallet.
 	pub fn import_wallet(&self, json: &[u8], password: &str) -> Result<Address, Error> {
 		let account = self.sstore.import_wallet(SecretVaultRef::Root, json, password)?;
 		Ok(Address::from(account.address).into())
 	}
 
+	/// Export account as a JSON key file re-encrypted with `export_password`.
+	pub fn export_account(&self, address: &Address, current_password: &str, export_password: &str) -> Result<Vec<u8>, Error> {
+		let account = self.sstore.account_ref(address)?;
+		self.sstore.export_account(&account, current_password, export_password)
+	}
+
 	/// Checks whether an account with a given address is present.
 	pub fn has_account(&self, address: Address) -> Result<bool, Error> {
 		Ok(self.accounts()?.iter().any(|&a| a == address))
@@ -257,14 +462,21 @@ impl AccountProvider {
 		Ok(())
 	}
 
+	/// Settings of particular dapp, unless they have expired.
+	fn dapp_settings(&self, dapp: &DappId) -> Option<DappsSettings> {
+		let now = ::std::time::UNIX_EPOCH.elapsed().map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+		self.dapps_settings.read().settings().remove(dapp).and_then(|settings| match settings.expiry {
+			Some(expiry) if expiry <= now => None,
+			_ => Some(settings),
+		})
+	}
+
 	/// Gets addresses visile for dapp.
 	pub fn dapps_addresses(&self, dapp: DappId) -> Result<Vec<Address>, Error> {
-		let dapps = self.dapps_settings.read();
-
-		let accounts = dapps.settings().get(&dapp).map(|settings| settings.accounts.clone());
+		let accounts = self.dapp_settings(&dapp).and_then(|settings| settings.accounts);
 		match accounts {
 			Some(accounts) => Ok(accounts),
-			None => match dapps.policy() {
+			None => match self.dapps_settings.read().policy() {
 				NewDappsPolicy::AllAccounts => self.accounts(),
 				NewDappsPolicy::Whitelist(accounts) => self.filter_addresses(accounts),
 			}
@@ -273,10 +485,60 @@ impl AccountProvider {
 
 	/// Returns default account for particular dapp falling back to other allowed accounts if necessary.
 	pub fn default_address(&self, dapp: DappId) -> Result<Address, Error> {
-		self.dapps_addresses(dapp)?
-			.get(0)
-			.cloned()
-			.ok_or(SSError::InvalidAccount)
+		self.dapp_default_address(dapp.clone()).or_else(|_| {
+			self.dapps_addresses(dapp)?
+				.get(0)
+				.cloned()
+				.ok_or(SSError::InvalidAccount)
+		})
+	}
+
+	/// Returns account pinned as default for particular dapp, provided it's still visible to the dapp.
+	pub fn dapp_default_address(&self, dapp: DappId) -> Result<Address, Error> {
+		let default = self.dapp_settings(&dapp).and_then(|settings| settings.default_address);
+		match default {
+			Some(address) if self.dapps_addresses(dapp)?.contains(&address) => Ok(address),
+			_ => Err(SSError::InvalidAccount),
+		}
+	}
+
+	/// Pins default account for particular dapp. The account must be visible to the dapp.
+	pub fn set_dapp_default_address(&self, dapp: DappId, address: Address) -> Result<(), Error> {
+		if !self.dapps_addresses(dapp.clone())?.contains(&address) {
+			return Err(SSError::InvalidAccount);
+		}
+		self.dapps_settings.write().set_default_address(dapp, address);
+		Ok(())
+	}
+
+	/// Returns the timestamp after which the settings of particular dapp are ignored.
+	pub fn dapp_expiry(&self, dapp: DappId) -> Result<Option<u64>, Error> {
+		Ok(self.dapp_settings(&dapp).and_then(|settings| settings.expiry))
+	}
+
+	/// Sets the timestamp after which the settings of particular dapp are ignored.
+	/// The dapp falls back to the new dapps policy afterwards.
+	pub fn set_dapp_expiry(&self, dapp: DappId, expiry: Option<u64>) -> Result<(), Error> {
+		self.dapps_settings.write().set_expiry(dapp, expiry);
+		Ok(())
+	}
+
+	/// Sets addresses visible for dapp, its default account and settings expiry at once.
+	/// Nothing is changed if `default` is not one of the visible addresses. The current default is kept if `default` is `None`.
+	pub fn set_dapp_permissions(&self, dapp: DappId, addresses: Vec<Address>, default: Option<Address>, expiry: Option<u64>) -> Result<(), Error> {
+		let addresses = self.filter_addresses(addresses)?;
+		if default.map_or(false, |default| !addresses.contains(&default)) {
+			return Err(SSError::InvalidAccount);
+		}
+
+		let mut dapps_settings = self.dapps_settings.write();
+		let default = default.or_else(|| dapps_settings.settings().get(&dapp).and_then(|settings| settings.default_address));
+		dapps_settings.set_settings(dapp, DappsSettings {
+			accounts: Some(addresses),
+			default_address: default,
+			expiry: expiry,
+		});
+		Ok(())
 	}
 
 	/// Sets addresses visile for dapp.
@@ -304,6 +566,11 @@ impl AccountProvider {
 		self.address_book.read().get()
 	}
 
+	/// Returns address book entries with name or metadata containing `query`, ignoring case.
+	pub fn search_address_book(&self, query: &str) -> HashMap<Address, AccountMeta> {
+		self.address_book.read().search(&query.to_lowercase())
+	}
+
 	/// Returns each address along with metadata.
 	pub fn set_address_name(&self, account: Address, name: String) {
 		self.address_book.write().set_name(account, name)
@@ -319,6 +586,36 @@ impl AccountProvider {
 		self.address_book.write().remove(addr)
 	}
 
+	/// Exports the address book as JSON list of entries.
+	pub fn export_address_book(&self) -> Vec<u8> {
+		let mut entries = self.address_book.read().get().into_iter()
+			.map(|(address, meta)| AddressBookEntry {
+				address: address.into(),
+				name: meta.name,
+				meta: meta.meta,
+			})
+			.collect::<Vec<_>>();
+		entries.sort_by_key(|entry| entry.address.clone());
+
+		let mut exported = Vec::new();
+		AddressBookEntry::write_list(&entries, &mut exported).expect("Writing to a Vec never fails; qed");
+		exported
+	}
+
+	/// Imports address book entries exported with `export_address_book`. Existing entries are kept.
+	/// Returns number of added entries.
+	pub fn import_address_book(&self, data: &[u8]) -> Result<u32, Error> {
+		let entries = AddressBookEntry::read_list(data).map_err(|e| SSError::InvalidJsonKey(format!("{}", e)))?;
+		let entries = entries.into_iter()
+			.map(|entry| (entry.address.into(), AccountMeta {
+				name: entry.name,
+				meta: entry.meta,
+				uuid: None,
+			}))
+			.collect();
+		Ok(self.address_book.write().import(entries))
+	}
+
 	/// Returns each account along with name and meta.
 	pub fn accounts_info(&self) -> Result<HashMap<Address, AccountMeta>, Error> {
 		let r = self.sstore.accounts()?
@@ -328,15 +625,58 @@ impl AccountProvider {
 		Ok(r)
 	}
 
+	/// Returns accounts with name or metadata containing `query`, ignoring case.
+	pub fn search_accounts(&self, query: &str) -> Result<HashMap<Address, AccountMeta>, Error> {
+		let query = query.to_lowercase();
+		let r = self.accounts_info()?
+			.into_iter()
+			.filter(|&(_, ref meta)| meta_matches(meta, &query))
+			.collect();
+		Ok(r)
+	}
+
 	/// Returns each hardware account along with name and meta.
+	/// Meta of each account is a JSON object naming the kind of device, e.g. `{"device":"Ledger"}`.
 	pub fn hardware_accounts_info(&self) -> Result<HashMap<Address, AccountMeta>, Error> {
-		let r = self.hardware_accounts()?
+		let r = self.hardware_store.as_ref().map_or(Vec::new(), |h| h.list_wallets())
 			.into_iter()
-			.map(|address| (address.clone(), self.account_meta(address).ok().unwrap_or_default()))
+			.map(|wallet| (wallet.address, self.with_stored_meta(wallet.address, AccountMeta {
+				name: wallet.name,
+				meta: format!(r#"{{"device":"{}"}}"#, wallet.device_type),
+				uuid: None,
+			})))
 			.collect();
 		Ok(r)
 	}
 
+	/// Overrides device-provided name and meta of a hardware account with the ones set by the user.
+	/// Hardware accounts are not in the key store, so user-set values are kept in the address book.
+	fn with_stored_meta(&self, address: Address, defaults: AccountMeta) -> AccountMeta {
+		match self.address_book.read().get().remove(&address) {
+			Some(stored) => AccountMeta {
+				name: if stored.name.is_empty() || stored.name == "Anonymous" { defaults.name } else { stored.name },
+				meta: if stored.meta.is_empty() || stored.meta == "{}" { defaults.meta } else { stored.meta },
+				uuid: defaults.uuid,
+			},
+			None => defaults,
+		}
+	}
+
+	/// Returns all hardware wallets seen since startup, with a flag telling whether the wallet is still connected.
+	pub fn hardware_wallets(&self) -> Vec<(WalletInfo, bool)> {
+		let connected = self.hardware_store.as_ref().map_or(Vec::new(), |h| h.list_wallets());
+		let mut seen = self.seen_hardware_wallets.write();
+		for wallet in &connected {
+			seen.insert(wallet.address, wallet.clone());
+		}
+
+		let mut wallets: Vec<_> = seen.values()
+			.map(|wallet| (wallet.clone(), connected.iter().any(|w| w.address == wallet.address)))
+			.collect();
+		wallets.sort_by(|a, b| a.0.address.cmp(&b.0.address));
+		wallets
+	}
+
 	/// Returns each hardware account along with name and meta.
 	pub fn is_hardware_address(&self, address: Address) -> bool {
 		self.hardware_store.as_ref().and_then(|s| s.wallet_info(&address)).is_some()
@@ -345,11 +685,11 @@ impl AccountProvider {
 	/// Returns each account along with name and meta.
 	pub fn account_meta(&self, address: Address) -> Result<AccountMeta, Error> {
 		if let Some(info) = self.hardware_store.as_ref().and_then(|s| s.wallet_info(&address)) {
-			Ok(AccountMeta {
+			Ok(self.with_stored_meta(address, AccountMeta {
 				name: info.name,
 				meta: info.manufacturer,
 				uuid: None,
-			})
+			}))
 		} else {
 			let account = self.sstore.account_ref(&address)?;
 			Ok(AccountMeta {
@@ -389,43 +729,180 @@ impl AccountProvider {
 		self.sstore.change_password(&self.sstore.account_ref(address)?, &password, &new_password)
 	}
 
+	/// Returns recorded account accesses, oldest first.
+	pub fn audit_log(&self) -> Vec<AuditEvent> {
+		self.audit_log.read().iter().cloned().collect()
+	}
+
+	/// Removes all entries from the audit log.
+	pub fn clear_audit_log(&self) {
+		self.audit_log.write().clear();
+	}
+
+	fn audit(&self, address: Address, operation: AuditOperation) {
+		audit(&self.audit_log, address, operation);
+	}
+
+	/// Records that an unlock of given account ended.
+	fn locked(&self, address: Address) {
+		self.audit(address, AuditOperation::Locked);
+		publish(&self.unlock_subscribers, UnlockEvent::Locked { address: address });
+	}
+
+	/// Returns a channel receiving every unlock of an account and the end of every unlock.
+	pub fn subscribe_unlock_events(&self) -> Receiver<UnlockEvent> {
+		let (sender, receiver) = mpsc::sync_channel(UNLOCK_EVENTS_BUFFER);
+		self.unlock_subscribers.lock().push(sender);
+		receiver
+	}
+
+	/// Returns the time until which unlocking is refused, if any.
+	fn locked_until(&self, account: &StoreAccountRef) -> Option<Instant> {
+		match self.failed_attempts.read().get(account) {
+			Some(&(count, last)) if count >= UNLOCK_ATTEMPTS_BEFORE_LOCKOUT => {
+				let doublings = ::std::cmp::min(count - UNLOCK_ATTEMPTS_BEFORE_LOCKOUT, 7);
+				let lockout = ::std::cmp::min(UNLOCK_LOCKOUT_SECS << doublings, UNLOCK_MAX_LOCKOUT_SECS);
+				let until = last + Duration::from_secs(lockout);
+				if Instant::now() < until { Some(until) } else { None }
+			},
+			_ => None,
+		}
+	}
+
+	/// Returns whether unlocking given account may currently be attempted.
+	pub fn unlock_attempt_status(&self, address: Address) -> UnlockStatus {
+		self.sstore.account_ref(&address).ok()
+			.and_then(|account| self.locked_until(&account))
+			.map_or(UnlockStatus::Open, UnlockStatus::LockedUntil)
+	}
+
 	/// Helper method used for unlocking accounts.
 	fn unlock_account(&self, address: Address, password: String, unlock: Unlock) -> Result<(), Error> {
+		let account = self.sstore.account_ref(&address)?;
+		self.verify_password(&account, &password)?;
+		self.insert_unlocked(account, password, unlock);
+		Ok(())
+	}
+
+	/// Checks the password, keeping track of failed attempts.
+	fn verify_password(&self, account: &StoreAccountRef, password: &str) -> Result<(), Error> {
 		// verify password by signing dump message
 		// result may be discarded
-		let account = self.sstore.account_ref(&address)?;
-		let _ = self.sstore.sign(&account, &password, &Default::default())?;
+		self.with_password(account, password, |password| self.sstore.sign(account, password, &Default::default())).map(|_| ())
+	}
 
-		// check if account is already unlocked pernamently, if it is, do nothing
-		let mut unlocked = self.unlocked.write();
-		if let Some(data) = unlocked.get(&account) {
-			if let Unlock::Perm = data.unlock {
-				return Ok(())
+	/// Runs `operation` with a password supplied by the user. Refuses to run it while the account is locked out,
+	/// and counts the attempt as failed if the password turns out to be invalid.
+	fn with_password<T, F>(&self, account: &StoreAccountRef, password: &str, operation: F) -> Result<T, Error>
+		where F: FnOnce(&str) -> Result<T, Error>
+	{
+		if self.locked_until(account).is_some() {
+			return Err(Error::TemporarilyLocked);
+		}
+
+		match operation(password) {
+			Ok(result) => {
+				self.failed_attempts.write().remove(account);
+				Ok(result)
+			},
+			Err(Error::InvalidPassword) => {
+				let mut failed_attempts = self.failed_attempts.write();
+				let attempts = failed_attempts.entry(account.clone()).or_insert((0, Instant::now()));
+				*attempts = (attempts.0 + 1, Instant::now());
+				Err(Error::InvalidPassword)
+			},
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Inserts unlock of an account which password has been verified.
+	/// Subscribers are notified and the unlock is audited only if the unlock state changes.
+	fn insert_unlocked(&self, account: StoreAccountRef, password: String, unlock: Unlock) {
+		let address = account.address;
+		let mode = (&unlock).into();
+		{
+			let mut unlocked = self.unlocked.write();
+			// check if account is already unlocked pernamently, if it is, do nothing
+			let unchanged = match unlocked.get(&account).map(|data| &data.unlock) {
+				Some(&Unlock::Perm) | Some(&Unlock::PermWithTimeout(..)) => return,
+				Some(&Unlock::Temp) => match unlock {
+					Unlock::Temp => true,
+					_ => false,
+				},
+				_ => false,
+			};
+
+			let data = AccountData {
+				unlock: unlock,
+				password: password,
+			};
+			unlocked.insert(account, data);
+
+			if unchanged {
+				return;
 			}
 		}
 
-		let data = AccountData {
-			unlock: unlock,
-			password: password,
-		};
+		self.audit(address, AuditOperation::Unlocked);
+		publish(&self.unlock_subscribers, UnlockEvent::Unlocked { address: address, mode: mode });
+	}
+
+	/// Unlocks account only after the password is verified and the unlock is confirmed on a hardware wallet.
+	/// The wallet holding `hardware_address` has to sign a random challenge message.
+	pub fn unlock_account_with_hardware_confirmation(&self, address: Address, password: String, hardware_address: Address, unlock: Unlock) -> Result<(), SignError> {
+		let account = self.sstore.account_ref(&address)?;
+		self.verify_password(&account, &password)?;
 
-		unlocked.insert(account, data);
+		let challenge = format!("Unlock account 0x{} with challenge 0x{}", address.hex(), H256::random().hex()).into_bytes();
+		let signature = self.sign_message_with_hardware(hardware_address, challenge.clone())?;
+		match verify_address(&hardware_address, &signature, &personal_message_hash(&challenge)) {
+			Ok(true) => {},
+			_ => return Err(SignError::InvalidConfirmation),
+		}
+
+		self.insert_unlocked(account, password, unlock);
 		Ok(())
 	}
 
+	/// Returns password of an unlocked account and whether the unlock is single-use.
+	/// Expired unlocks are removed; inactivity timeouts are refreshed.
+	fn unlocked_password(&self, unlocked: &mut UnlockedAccounts, account: &StoreAccountRef) -> Result<(String, bool), SignError> {
+		let expired = match unlocked.get_mut(account) {
+			None => return Err(SignError::NotUnlocked),
+			Some(data) => match data.unlock {
+				Unlock::Timed(end) => Instant::now() > end,
+				Unlock::PermWithTimeout(timeout, ref mut last_used) => {
+					let now = Instant::now();
+					if now > *last_used + timeout {
+						true
+					} else {
+						*last_used = now;
+						false
+					}
+				},
+				Unlock::Temp | Unlock::Perm => false,
+			},
+		};
+
+		if expired {
+			unlocked.remove(account).expect("data exists: so key must exist: qed");
+			self.locked(account.address);
+			return Err(SignError::NotUnlocked);
+		}
+
+		let data = &unlocked[account];
+		let single_use = if let Unlock::Temp = data.unlock { true } else { false };
+		Ok((data.password.clone(), single_use))
+	}
+
 	fn password(&self, account: &StoreAccountRef) -> Result<String, SignError> {
 		let mut unlocked = self.unlocked.write();
-		let data = unlocked.get(account).ok_or(SignError::NotUnlocked)?.clone();
-		if let Unlock::Temp = data.unlock {
+		let (password, single_use) = self.unlocked_password(&mut unlocked, account)?;
+		if single_use {
 			unlocked.remove(account).expect("data exists: so key must exist: qed");
+			self.locked(account.address);
 		}
-		if let Unlock::Timed(ref end) = data.unlock {
-			if Instant::now() > *end {
-				unlocked.remove(account).expect("data exists: so key must exist: qed");
-				return Err(SignError::NotUnlocked);
-			}
-		}
-		Ok(data.password.clone())
+		Ok(password)
 	}
 
 	/// Unlocks account permanently.
@@ -443,6 +920,130 @@ impl AccountProvider {
 		self.unlock_account(account, password, Unlock::Timed(Instant::now() + Duration::from_millis(duration_ms as u64)))
 	}
 
+	/// Locks permanently unlocked account again after given time without signing.
+	/// Fails if the account is not unlocked permanently.
+	pub fn set_inactivity_timeout(&self, address: Address, timeout: Duration) -> Result<(), Error> {
+		let account = self.sstore.account_ref(&address)?;
+		{
+			let mut unlocked = self.unlocked.write();
+			let data = unlocked.get_mut(&account).ok_or(SSError::InvalidAccount)?;
+			let permanent = match data.unlock {
+				Unlock::Perm | Unlock::PermWithTimeout(..) => true,
+				Unlock::Temp | Unlock::Timed(_) => false,
+			};
+			if !permanent {
+				return Err(SSError::InvalidAccount);
+			}
+			data.unlock = Unlock::PermWithTimeout(timeout, Instant::now());
+		}
+		self.wake_autolock_timer();
+		Ok(())
+	}
+
+	/// Wakes up the auto-lock timer, so it picks up a changed inactivity timeout. Starts the timer on first use.
+	fn wake_autolock_timer(&self) {
+		let mut timer = self.autolock_timer.lock();
+		if let Some(ref thread) = *timer {
+			thread.unpark();
+			return;
+		}
+		*timer = Self::spawn_autolock_timer(Arc::downgrade(&self.unlocked), self.unlock_subscribers.clone(), Arc::downgrade(&self.audit_log), self.exiting.clone());
+	}
+
+	/// Locks permanently unlocked accounts again once their inactivity timeout expires. A single thread serves all
+	/// accounts; it sleeps until the earliest deadline and quits once the provider is dropped.
+	fn spawn_autolock_timer(unlocked: Weak<RwLock<UnlockedAccounts>>, subscribers: UnlockSubscribers, audit_log: Weak<AuditLog>, exiting: Arc<AtomicBool>)
+		-> Option<thread::Thread>
+	{
+		let spawned = thread::Builder::new().name("account_autolock".into()).spawn(move || loop {
+			if exiting.load(atomic::Ordering::Acquire) {
+				return;
+			}
+			let wait = {
+				let unlocked = match unlocked.upgrade() {
+					Some(unlocked) => unlocked,
+					None => return,
+				};
+				let now = Instant::now();
+				let mut unlocked = unlocked.write();
+				let expired = unlocked.iter()
+					.filter_map(|(account, data)| match data.unlock {
+						Unlock::PermWithTimeout(timeout, last_used) if now > last_used + timeout => Some(account.clone()),
+						_ => None,
+					})
+					.collect::<Vec<_>>();
+				for account in expired {
+					unlocked.remove(&account);
+					if let Some(audit_log) = audit_log.upgrade() {
+						audit(&audit_log, account.address, AuditOperation::Locked);
+					}
+					publish(&subscribers, UnlockEvent::Locked { address: account.address });
+				}
+				unlocked.values()
+					.filter_map(|data| match data.unlock {
+						Unlock::PermWithTimeout(timeout, last_used) => Some(last_used + timeout),
+						_ => None,
+					})
+					.min()
+					.map(|deadline| if deadline > now { deadline - now } else { Duration::from_millis(0) })
+			};
+			match wait {
+				Some(wait) => thread::park_timeout(wait),
+				None => thread::park(),
+			}
+		});
+		match spawned {
+			Ok(handle) => Some(handle.thread().clone()),
+			Err(e) => {
+				warn!("Unable to start account auto-lock thread: {}", e);
+				None
+			},
+		}
+	}
+
+	/// Locks all unlocked accounts. Returns the number of accounts locked.
+	pub fn lock_all_accounts(&self) -> usize {
+		let locked = self.unlocked.write().drain().map(|(account, _)| account.address).collect::<Vec<_>>();
+		for address in &locked {
+			self.locked(*address);
+		}
+		locked.len()
+	}
+
+	/// Lists unlocked accounts with their remaining unlock time. Expired unlocks are removed.
+	pub fn list_unlocked(&self) -> Vec<UnlockedAccountInfo> {
+		let now = Instant::now();
+		let mut expired = Vec::new();
+		let mut unlocked = self.unlocked.write();
+		let accounts = unlocked.iter()
+			.filter_map(|(account, data)| {
+				let (mode, end) = match data.unlock {
+					Unlock::Temp => ("temp", Some(now)),
+					Unlock::Perm => ("perm", None),
+					Unlock::PermWithTimeout(timeout, last_used) => ("perm", Some(last_used + timeout)),
+					Unlock::Timed(end) => ("timed", Some(end)),
+				};
+				match end {
+					Some(end) if now > end => {
+						expired.push(account.clone());
+						None
+					},
+					_ => Some(UnlockedAccountInfo {
+						address: account.address,
+						mode: mode.into(),
+						expires_in_ms: end.map(|end| millis(end.duration_since(now))),
+					}),
+				}
+			})
+			.collect();
+
+		for account in expired {
+			unlocked.remove(&account);
+			self.locked(account.address);
+		}
+		accounts
+	}
+
 	/// Checks if given account is unlocked
 	pub fn is_unlocked(&self, address: Address) -> bool {
 		let unlocked = self.unlocked.read();
@@ -454,8 +1055,58 @@ impl AccountProvider {
 	/// Signs the message. If password is not provided the account must be unlocked.
 	pub fn sign(&self, address: Address, password: Option<String>, message: Message) -> Result<Signature, SignError> {
 		let account = self.sstore.account_ref(&address)?;
-		let password = password.map(Ok).unwrap_or_else(|| self.password(&account))?;
-		Ok(self.sstore.sign(&account, &password, &message)?)
+		let signature = match password {
+			Some(password) => self.with_password(&account, &password, |password| self.sstore.sign(&account, password, &message))?,
+			None => self.sstore.sign(&account, &self.password(&account)?, &message)?,
+		};
+		self.audit(address, AuditOperation::Signed);
+		Ok(signature)
+	}
+
+	/// Signs all messages with a single password lookup. If password is not provided the account must be unlocked.
+	/// A temporary unlock is consumed only if every message gets signed.
+	pub fn batch_sign(&self, address: Address, password: Option<String>, messages: Vec<Message>) -> Result<Vec<Signature>, SignError> {
+		let account = self.sstore.account_ref(&address)?;
+		let mut unlocked = self.unlocked.write();
+		let (password, temp) = match password {
+			Some(password) => {
+				self.verify_password(&account, &password)?;
+				(password, false)
+			},
+			None => self.unlocked_password(&mut unlocked, &account)?,
+		};
+
+		let signatures = messages.iter()
+			.map(|message| self.sstore.sign(&account, &password, message))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		self.audit(address, AuditOperation::Signed);
+		if temp {
+			unlocked.remove(&account).expect("data exists: so key must exist: qed");
+			self.locked(address);
+		}
+		Ok(signatures)
+	}
+
+	/// Sets validity of rolling tokens issued from now on.
+	pub fn set_token_ttl(&self, ttl: Duration) {
+		*self.token_ttl.write() = ttl;
+	}
+
+	/// Generates a rolling token. Expiry time (in ms since epoch) is part of the token,
+	/// so it can't be altered without invalidating the token itself.
+	fn new_token(&self) -> AccountToken {
+		format!("{}:{}", random_string(16), unix_millis() + millis(*self.token_ttl.read()))
+	}
+
+	/// Removes account unlocked with `token` from the transient store if the token has expired.
+	fn check_token_expiry(&self, account: &StoreAccountRef, token: &str) -> Result<(), SignError> {
+		let expiry = token.rsplit(':').next().and_then(|expiry| expiry.parse::<u64>().ok()).unwrap_or(0);
+		if expiry < unix_millis() {
+			let _ = self.transient_sstore.remove_account(account, token);
+			return Err(SignError::NotUnlocked);
+		}
+		Ok(())
 	}
 
 	/// Signs given message with supplied token. Returns a token to use in next signing within this session.
@@ -463,19 +1114,21 @@ impl AccountProvider {
 		let account = self.sstore.account_ref(&address)?;
 		let is_std_password = self.sstore.test_password(&account, &token)?;
 
-		let new_token = random_string(16);
+		let new_token = self.new_token();
 		let signature = if is_std_password {
 			// Insert to transient store
 			self.sstore.copy_account(&self.transient_sstore, SecretVaultRef::Root, &account, &token, &new_token)?;
 			// sign
 			self.sstore.sign(&account, &token, &message)?
 		} else {
+			self.check_token_expiry(&account, &token)?;
 			// check transient store
 			self.transient_sstore.change_password(&account, &token, &new_token)?;
 			// and sign
 			self.transient_sstore.sign(&account, &new_token, &message)?
 		};
 
+		self.audit(address, AuditOperation::TokenSigned);
 		Ok((signature, new_token))
 	}
 
@@ -486,13 +1139,14 @@ impl AccountProvider {
 		let account = self.sstore.account_ref(&address)?;
 		let is_std_password = self.sstore.test_password(&account, &token)?;
 
-		let new_token = random_string(16);
+		let new_token = self.new_token();
 		let message = if is_std_password {
 			// Insert to transient store
 			self.sstore.copy_account(&self.transient_sstore, SecretVaultRef::Root, &account, &token, &new_token)?;
 			// decrypt
 			self.sstore.decrypt(&account, &token, shared_mac, message)?
 		} else {
+			self.check_token_expiry(&account, &token)?;
 			// check transient store
 			self.transient_sstore.change_password(&account, &token, &new_token)?;
 			// and decrypt
@@ -505,8 +1159,10 @@ impl AccountProvider {
 	/// Decrypts a message. If password is not provided the account must be unlocked.
 	pub fn decrypt(&self, address: Address, password: Option<String>, shared_mac: &[u8], message: &[u8]) -> Result<Vec<u8>, SignError> {
 		let account = self.sstore.account_ref(&address)?;
-		let password = password.map(Ok).unwrap_or_else(|| self.password(&account))?;
-		Ok(self.sstore.decrypt(&account, &password, shared_mac, message)?)
+		match password {
+			Some(password) => Ok(self.with_password(&account, &password, |password| self.sstore.decrypt(&account, password, shared_mac, message))?),
+			None => Ok(self.sstore.decrypt(&account, &self.password(&account)?, shared_mac, message)?),
+		}
 	}
 
 	/// Returns the underlying `SecretStore` reference if one exists.
@@ -551,6 +1207,67 @@ impl AccountProvider {
 			.map_err(Into::into)
 	}
 
+	/// Opens the vault if needed and permanently unlocks all of its accounts, assuming they share the vault password.
+	/// Accounts with a different password are left locked. Returns addresses of the unlocked accounts.
+	pub fn unlock_vault_accounts(&self, vault_name: &str, vault_password: &str) -> Result<Vec<Address>, Error> {
+		self.sstore.open_vault(vault_name, vault_password)?;
+
+		let vault = SecretVaultRef::Vault(vault_name.to_owned());
+		let mut addresses = Vec::new();
+		for account in self.sstore.accounts()?.into_iter().filter(|account| account.vault == vault) {
+			if !self.sstore.test_password(&account, vault_password)? {
+				continue;
+			}
+			self.unlock_account(account.address, vault_password.to_owned(), Unlock::Perm)?;
+			addresses.push(account.address);
+		}
+		Ok(addresses)
+	}
+
+	/// Locks all unlocked accounts of the vault. The vault stays open.
+	pub fn lock_vault_accounts(&self, vault_name: &str) -> Result<(), Error> {
+		let vault = SecretVaultRef::Vault(vault_name.to_owned());
+		let locked: Vec<_> = {
+			let mut unlocked = self.unlocked.write();
+			let accounts: Vec<_> = unlocked.keys().filter(|account| account.vault == vault).cloned().collect();
+			for account in &accounts {
+				unlocked.remove(account);
+			}
+			accounts
+		};
+
+		for account in locked {
+			self.locked(account.address);
+		}
+		Ok(())
+	}
+
+	/// List all vaults with their account counts.
+	pub fn vault_info(&self) -> Result<Vec<VaultSummary>, Error> {
+		let opened = self.sstore.list_opened_vaults()?;
+		let accounts = self.sstore.accounts()?;
+		let mut vaults: Vec<_> = self.sstore.list_vaults()?
+			.into_iter()
+			.map(|name| {
+				let is_open = opened.contains(&name);
+				let account_count = if is_open {
+					let vault = SecretVaultRef::Vault(name.clone());
+					Some(accounts.iter().filter(|account| account.vault == vault).count())
+				} else {
+					self.sstore.vault_accounts_count(&name).ok()
+				};
+
+				VaultSummary {
+					name: name,
+					is_open: is_open,
+					account_count: account_count,
+				}
+			})
+			.collect();
+		vaults.sort_by(|a, b| a.name.cmp(&b.name));
+		Ok(vaults)
+	}
+
 	/// Change vault password.
 	pub fn change_vault_password(&self, name: &str, new_password: &str) -> Result<(), Error> {
 		self.sstore.change_vault_password(name, new_password)
@@ -581,6 +1298,45 @@ impl AccountProvider {
 	/// Sign transaction with hardware wallet.
 	pub fn sign_with_hardware(&self, address: Address, transaction: &[u8]) -> Result<Signature, SignError> {
 		match self.hardware_store.as_ref().map(|s| s.sign_transaction(&address, transaction)) {
+			None | Some(Err(HardwareError::KeyNotFound)) => Err(SignError::NotFound),
+			Some(Err(e)) => Err(From::from(e)),
+			Some(Ok(s)) => {
+				self.audit(address, AuditOperation::HardwareSigned);
+				Ok(s)
+			},
+		}
+	}
+
+	/// Show hardware account address on the device and wait for the user to confirm it.
+	pub fn confirm_hardware_address(&self, address: Address) -> Result<(), SignError> {
+		match self.hardware_store.as_ref().map(|s| s.confirm_address(&address)) {
+			None | Some(Err(HardwareError::KeyNotFound)) => Err(SignError::NotFound),
+			Some(Err(e)) => Err(From::from(e)),
+			Some(Ok(())) => Ok(()),
+		}
+	}
+
+	/// Returns public key of a hardware account.
+	pub fn hardware_account_public_key(&self, address: Address) -> Result<Public, SignError> {
+		match self.hardware_store.as_ref().map(|s| s.get_public_key(&address)) {
+			None | Some(Err(HardwareError::KeyNotFound)) => Err(SignError::NotFound),
+			Some(Err(e)) => Err(From::from(e)),
+			Some(Ok(p)) => Ok(p),
+		}
+	}
+
+	/// Sign a message with hardware wallet, as in `personal_sign`. The wallet applies the message prefix.
+	pub fn sign_message_with_hardware(&self, address: Address, message: Vec<u8>) -> Result<Signature, SignError> {
+		match self.hardware_store.as_ref().map(|s| s.sign_message(&address, &message)) {
+			None | Some(Err(HardwareError::KeyNotFound)) => Err(SignError::NotFound),
+			Some(Err(e)) => Err(From::from(e)),
+			Some(Ok(s)) => Ok(s),
+		}
+	}
+
+	/// Sign EIP-712 typed data with hardware wallet.
+	pub fn sign_typed_data_with_hardware(&self, address: Address, domain_separator: &[u8; 32], message_hash: &[u8; 32]) -> Result<Signature, SignError> {
+		match self.hardware_store.as_ref().map(|s| s.sign_typed_data(&address, domain_separator, message_hash)) {
 			None | Some(Err(HardwareError::KeyNotFound)) => Err(SignError::NotFound),
 			Some(Err(e)) => Err(From::from(e)),
 			Some(Ok(s)) => Ok(s),
@@ -588,58 +1344,466 @@ impl AccountProvider {
 	}
 }
 
+impl Drop for AccountProvider {
+	fn drop(&mut self) {
+		self.exiting.store(true, atomic::Ordering::Release);
+		if let Some(ref thread) = *self.autolock_timer.lock() {
+			thread.unpark();
+		}
+	}
+}
+
+/// Hash of a message signed as in `personal_sign`.
+fn personal_message_hash(message: &[u8]) -> Message {
+	let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+	prefixed.extend_from_slice(message);
+	prefixed.sha3()
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{AccountProvider, Unlock, DappId};
-	use std::time::Instant;
-	use ethstore::ethkey::{Generator, Random};
-	use ethstore::StoreAccountRef;
+	use super::{AccountProvider, AccountProviderSettings, Unlock, UnlockStatus, UnlockEvent, UnlockMode, DappId, SignError, AuditOperation, VaultSummary};
+	use ethstore::Error as SSError;
+	use rustc_serialize::json::Json;
+	use std::collections::HashMap;
+	use std::time::{Instant, Duration};
+	use ethstore::ethkey::{Address, Generator, KeyPair, Message, Random, Signature};
+	use ethstore::{EthStore, StoreAccountRef};
+	use ethstore::dir::{MemoryDirectory, RootDiskDirectory};
+	use devtools::RandomTempPath;
+	use hardware_wallet::{WalletInfo, WalletDeviceType};
+	use hardware_wallet::mock::MockHardwareWalletManager;
+
+	fn hardware_provider(address: Address, signature: Signature) -> AccountProvider {
+		let wallet = WalletInfo {
+			name: "Nano S".into(),
+			manufacturer: "Ledger".into(),
+			serial: "0001".into(),
+			address: address,
+			firmware_version: None,
+			app_version: None,
+			device_type: WalletDeviceType::Ledger,
+		};
+		let mut signatures = HashMap::new();
+		signatures.insert(address, signature);
+		let sstore = Box::new(EthStore::open(Box::new(MemoryDirectory::default())).unwrap());
+		AccountProvider::new_with_hardware(sstore, Some(MockHardwareWalletManager::from_fixtures(vec![wallet], signatures)))
+	}
 
 	#[test]
-	fn unlock_account_temp() {
+	fn should_import_from_mnemonic() {
+		let ap = AccountProvider::transient_provider();
+		let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+		let address = ap.import_from_mnemonic(phrase, "", "test").unwrap();
+		assert_eq!(address, "9858effd232b4033e47d90003d41ec34ecaeda94".into());
+		assert!(ap.has_account(address).unwrap());
+		assert!(ap.import_from_mnemonic("abandon abandon abandon", "", "test").is_err());
+	}
+
+	#[test]
+	fn should_record_account_accesses() {
 		let kp = Random.generate().unwrap();
 		let ap = AccountProvider::transient_provider();
 		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.audit_log().is_empty());
+
 		assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
 		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
 		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
 		assert!(ap.sign(kp.address(), None, Default::default()).is_err());
+		let (_, token) = ap.sign_with_token(kp.address(), "test".into(), Default::default()).unwrap();
+		assert!(ap.sign_with_token(kp.address(), token, Default::default()).is_ok());
+
+		let log = ap.audit_log();
+		assert!(log.iter().all(|event| event.address == kp.address()));
+		assert!(log.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+		assert_eq!(log.into_iter().map(|event| event.operation).collect::<Vec<_>>(), vec![
+			AuditOperation::Unlocked,
+			AuditOperation::Locked,
+			AuditOperation::Signed,
+			AuditOperation::TokenSigned,
+			AuditOperation::TokenSigned,
+		]);
+
+		ap.clear_audit_log();
+		assert!(ap.audit_log().is_empty());
 	}
 
 	#[test]
-	fn unlock_account_perm() {
+	fn should_record_hardware_signing() {
+		let address: Address = 5.into();
+		let ap = hardware_provider(address, Signature::from_rsv(&1.into(), &2.into(), 1));
+		assert!(ap.sign_with_hardware(address, &[]).is_ok());
+		assert!(ap.sign_with_hardware(6.into(), &[]).is_err());
+
+		let log = ap.audit_log();
+		assert_eq!(log.len(), 1);
+		assert_eq!(log[0].address, address);
+		assert_eq!(log[0].operation, AuditOperation::HardwareSigned);
+	}
+
+	#[test]
+	fn should_batch_sign_with_single_temporary_unlock() {
 		let kp = Random.generate().unwrap();
 		let ap = AccountProvider::transient_provider();
 		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
-		assert!(ap.unlock_account_permanently(kp.address(), "test1".into()).is_err());
-		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
-		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
-		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		let messages: Vec<Message> = (1..6u64).map(Message::from).collect();
+		assert!(ap.batch_sign(kp.address(), None, messages.clone()).is_err());
+
 		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
-		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
-		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		let signatures = ap.batch_sign(kp.address(), None, messages.clone()).unwrap();
+		assert_eq!(signatures.len(), 5);
+		assert!(!ap.is_unlocked(kp.address()));
+		assert!(ap.batch_sign(kp.address(), None, messages.clone()).is_err());
+		assert!(ap.batch_sign(kp.address(), Some("test".into()), messages).is_ok());
 	}
 
 	#[test]
-	fn unlock_account_timer() {
+	fn should_export_account_with_new_password() {
 		let kp = Random.generate().unwrap();
 		let ap = AccountProvider::transient_provider();
 		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
-		assert!(ap.unlock_account_timed(kp.address(), "test1".into(), 60000).is_err());
-		assert!(ap.unlock_account_timed(kp.address(), "test".into(), 60000).is_ok());
-		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
-		ap.unlocked.write().get_mut(&StoreAccountRef::root(kp.address())).unwrap().unlock = Unlock::Timed(Instant::now());
-		assert!(ap.sign(kp.address(), None, Default::default()).is_err());
+		assert!(ap.export_account(&kp.address(), "wrong", "export").is_err());
+
+		let exported = ap.export_account(&kp.address(), "test", "export").unwrap();
+		let json = Json::from_str(::std::str::from_utf8(&exported).unwrap()).unwrap();
+		assert_eq!(json.find("address").and_then(Json::as_string), Some(&*kp.address().hex()));
+		assert!(ap.test_password(&kp.address(), "test").unwrap());
+
+		let other = AccountProvider::transient_provider();
+		assert!(other.import_wallet(&exported, "test").is_err());
+		assert_eq!(other.import_wallet(&exported, "export").unwrap(), kp.address());
 	}
 
 	#[test]
-	fn should_sign_and_return_token() {
-		// given
+	fn should_lock_out_after_repeated_failures() {
 		let kp = Random.generate().unwrap();
 		let ap = AccountProvider::transient_provider();
 		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert_eq!(ap.unlock_attempt_status(kp.address()), UnlockStatus::Open);
+		assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		assert_eq!(ap.unlock_attempt_status(kp.address()), UnlockStatus::Open);
 
-		// when
+		for _ in 1..10 {
+			assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		}
+
+		// correct password is not even checked
+		match ap.unlock_account_temporarily(kp.address(), "test".into()) {
+			Err(SSError::TemporarilyLocked) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match ap.unlock_attempt_status(kp.address()) {
+			UnlockStatus::LockedUntil(until) => assert!(until > Instant::now()),
+			UnlockStatus::Open => panic!("Account should be locked"),
+		}
+		assert!(!ap.is_unlocked(kp.address()));
+	}
+
+	#[test]
+	fn should_apply_lockout_to_signing_with_password() {
+		// given
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+
+		// when
+		for _ in 0..UNLOCK_ATTEMPTS_BEFORE_LOCKOUT {
+			assert!(ap.sign(kp.address(), Some("test1".into()), Default::default()).is_err());
+		}
+
+		// then
+		match ap.sign(kp.address(), Some("test".into()), Default::default()) {
+			Err(SignError::SStore(SSError::TemporarilyLocked)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match ap.decrypt(kp.address(), Some("test".into()), &[], &[]) {
+			Err(SignError::SStore(SSError::TemporarilyLocked)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match ap.unlock_account_temporarily(kp.address(), "test".into()) {
+			Err(SSError::TemporarilyLocked) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_reset_failed_attempts_on_unlock() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		for _ in 0..4 {
+			assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		}
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		assert_eq!(ap.unlock_attempt_status(kp.address()), UnlockStatus::Open);
+	}
+
+	#[test]
+	fn unlock_account_temp() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_err());
+	}
+
+	#[test]
+	fn should_publish_unlock_events() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		let events = ap.subscribe_unlock_events();
+
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+
+		assert_eq!(events.try_recv().unwrap(), UnlockEvent::Unlocked { address: kp.address(), mode: UnlockMode::Temp });
+		assert_eq!(events.try_recv().unwrap(), UnlockEvent::Locked { address: kp.address() });
+		assert!(events.try_recv().is_err());
+	}
+
+	#[test]
+	fn should_not_publish_unchanged_unlock() {
+		// given
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
+		let events = ap.subscribe_unlock_events();
+		ap.clear_audit_log();
+
+		// when
+		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+
+		// then
+		assert!(events.try_recv().is_err());
+		assert!(ap.audit_log().is_empty());
+	}
+
+	#[test]
+	fn should_publish_timed_unlock_expiry() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		let events = ap.subscribe_unlock_events();
+
+		assert!(ap.unlock_account_timed(kp.address(), "test".into(), 60000).is_ok());
+		ap.unlocked.write().get_mut(&StoreAccountRef::root(kp.address())).unwrap().unlock = Unlock::Timed(Instant::now());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_err());
+
+		assert_eq!(events.try_recv().unwrap(), UnlockEvent::Unlocked { address: kp.address(), mode: UnlockMode::Timed });
+		assert_eq!(events.try_recv().unwrap(), UnlockEvent::Locked { address: kp.address() });
+	}
+
+	#[test]
+	fn should_search_accounts_by_name_and_meta() {
+		let ap = AccountProvider::transient_provider();
+		let alice = ap.new_account("test").unwrap();
+		let bob = ap.new_account("test").unwrap();
+		let carol = ap.new_account("test").unwrap();
+		ap.set_account_name(alice, "Alice".into()).unwrap();
+		ap.set_account_name(bob, "Bob".into()).unwrap();
+		ap.set_account_name(carol, "Carol".into()).unwrap();
+		ap.set_account_meta(carol, r#"{"description":"Savings"}"#.into()).unwrap();
+
+		let found = ap.search_accounts("LIC").unwrap();
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[&alice].name, "Alice");
+
+		let found = ap.search_accounts("savings").unwrap();
+		assert_eq!(found.keys().collect::<Vec<_>>(), vec![&carol]);
+
+		assert!(ap.search_accounts("dave").unwrap().is_empty());
+	}
+
+	#[test]
+	fn should_search_address_book() {
+		let ap = AccountProvider::transient_provider();
+		ap.set_address_name(1.into(), "Exchange".into());
+		ap.set_address_name(2.into(), "Friend".into());
+		ap.set_address_meta(2.into(), "met at the exchange".into());
+		ap.set_address_name(3.into(), "Shop".into());
+
+		let found = ap.search_address_book("exchange");
+		assert_eq!(found.len(), 2);
+		assert!(found.contains_key(&1.into()) && found.contains_key(&2.into()));
+		assert!(ap.search_address_book("SHOP").contains_key(&3.into()));
+	}
+
+	#[test]
+	fn should_count_accounts_in_vaults() {
+		let temp_path = RandomTempPath::new();
+		let dir = RootDiskDirectory::create(temp_path.as_path()).unwrap();
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(dir)).unwrap()), AccountProviderSettings::default());
+		ap.create_vault("vault", "password").unwrap();
+		ap.create_vault("empty", "password").unwrap();
+		let first = ap.new_account("test").unwrap();
+		let second = ap.new_account("test").unwrap();
+		ap.new_account("test").unwrap();
+		ap.change_vault(first, "vault").unwrap();
+		ap.change_vault(second, "vault").unwrap();
+
+		assert_eq!(ap.vault_info().unwrap()[1], VaultSummary { name: "vault".into(), is_open: true, account_count: Some(2) });
+
+		ap.close_vault("vault").unwrap();
+		assert_eq!(ap.vault_info().unwrap(), vec![
+			VaultSummary { name: "empty".into(), is_open: true, account_count: Some(0) },
+			VaultSummary { name: "vault".into(), is_open: false, account_count: Some(2) },
+		]);
+	}
+
+	#[test]
+	fn should_unlock_and_lock_vault_accounts() {
+		let temp_path = RandomTempPath::new();
+		let dir = RootDiskDirectory::create(temp_path.as_path()).unwrap();
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(dir)).unwrap()), AccountProviderSettings::default());
+		ap.create_vault("vault", "password").unwrap();
+		let first = ap.new_account("password").unwrap();
+		let second = ap.new_account("password").unwrap();
+		let other = ap.new_account("other").unwrap();
+		ap.change_vault(first, "vault").unwrap();
+		ap.change_vault(second, "vault").unwrap();
+		ap.change_vault(other, "vault").unwrap();
+		ap.close_vault("vault").unwrap();
+
+		let mut unlocked = ap.unlock_vault_accounts("vault", "password").unwrap();
+		unlocked.sort();
+		let mut expected = vec![first, second];
+		expected.sort();
+		assert_eq!(unlocked, expected);
+		assert!(ap.sign(first, None, Default::default()).is_ok());
+		assert!(ap.sign(second, None, Default::default()).is_ok());
+		assert!(ap.sign(other, None, Default::default()).is_err());
+
+		ap.lock_vault_accounts("vault").unwrap();
+		assert!(ap.sign(first, None, Default::default()).is_err());
+		assert!(ap.sign(second, None, Default::default()).is_err());
+	}
+
+	#[test]
+	fn unlock_account_perm() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_permanently(kp.address(), "test1".into()).is_err());
+		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+	}
+
+	#[test]
+	fn should_lock_all_accounts() {
+		let kp1 = Random.generate().unwrap();
+		let kp2 = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		ap.insert_account(kp1.secret().clone(), "test").unwrap();
+		ap.insert_account(kp2.secret().clone(), "test").unwrap();
+		ap.unlock_account_permanently(kp1.address(), "test".into()).unwrap();
+		ap.unlock_account_timed(kp2.address(), "test".into(), 60000).unwrap();
+
+		assert_eq!(ap.lock_all_accounts(), 2);
+
+		assert!(!ap.is_unlocked(kp1.address()));
+		assert!(!ap.is_unlocked(kp2.address()));
+		assert_eq!(ap.lock_all_accounts(), 0);
+	}
+
+	#[test]
+	fn unlock_account_timer() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_timed(kp.address(), "test1".into(), 60000).is_err());
+		assert!(ap.unlock_account_timed(kp.address(), "test".into(), 60000).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		ap.unlocked.write().get_mut(&StoreAccountRef::root(kp.address())).unwrap().unlock = Unlock::Timed(Instant::now());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_err());
+	}
+
+	#[test]
+	fn should_list_unlocked_accounts() {
+		// given
+		let ap = AccountProvider::transient_provider();
+		let temp = ap.new_account("test").unwrap();
+		let perm = ap.new_account("test").unwrap();
+		let timed = ap.new_account("test").unwrap();
+		let expired = ap.new_account("test").unwrap();
+		ap.unlock_account_temporarily(temp, "test".into()).unwrap();
+		ap.unlock_account_permanently(perm, "test".into()).unwrap();
+		ap.unlock_account_timed(timed, "test".into(), 60000).unwrap();
+		ap.unlock_account_timed(expired, "test".into(), 60000).unwrap();
+		ap.unlocked.write().get_mut(&StoreAccountRef::root(expired)).unwrap().unlock = Unlock::Timed(Instant::now());
+
+		// when
+		let mut accounts = ap.list_unlocked();
+		accounts.sort_by(|a, b| a.mode.cmp(&b.mode));
+
+		// then
+		assert_eq!(accounts.len(), 3);
+		assert_eq!((accounts[0].address, accounts[0].mode.as_str(), accounts[0].expires_in_ms), (perm, "perm", None));
+		assert_eq!((accounts[1].address, accounts[1].mode.as_str(), accounts[1].expires_in_ms), (temp, "temp", Some(0)));
+		assert_eq!((accounts[2].address, accounts[2].mode.as_str()), (timed, "timed"));
+		let expires_in = accounts[2].expires_in_ms.unwrap();
+		assert!(expires_in > 50000 && expires_in <= 60000);
+		assert!(!ap.is_unlocked(expired));
+	}
+
+	#[test]
+	fn should_lock_after_inactivity() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.set_inactivity_timeout(kp.address(), Duration::from_millis(50)).is_err());
+		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
+		assert!(ap.set_inactivity_timeout(kp.address(), Duration::from_millis(50)).is_ok());
+		assert!(ap.sign(kp.address(), None, Default::default()).is_ok());
+		::std::thread::sleep(Duration::from_millis(100));
+		match ap.sign(kp.address(), None, Default::default()) {
+			Err(SignError::NotUnlocked) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(!ap.is_unlocked(kp.address()));
+	}
+
+	#[test]
+	fn should_audit_relock_by_single_timer() {
+		// given
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
+		let events = ap.subscribe_unlock_events();
+
+		// when
+		assert!(ap.set_inactivity_timeout(kp.address(), Duration::from_secs(60)).is_ok());
+		assert!(ap.set_inactivity_timeout(kp.address(), Duration::from_millis(50)).is_ok());
+		::std::thread::sleep(Duration::from_millis(200));
+
+		// then
+		assert!(ap.autolock_timer.lock().is_some());
+		assert!(!ap.is_unlocked(kp.address()));
+		assert_eq!(events.try_recv().unwrap(), UnlockEvent::Locked { address: kp.address() });
+		let last = ap.audit_log().pop().unwrap();
+		assert_eq!(last.address, kp.address());
+		assert_eq!(last.operation, AuditOperation::Locked);
+	}
+
+	#[test]
+	fn should_sign_and_return_token() {
+		// given
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+
+		// when
 		let (_signature, token) = ap.sign_with_token(kp.address(), "test".into(), Default::default()).unwrap();
 
 		// then
@@ -648,6 +1812,72 @@ mod tests {
 		assert!(ap.sign_with_token(kp.address(), token, Default::default()).is_err(), "Second usage of the same token should fail.");
 	}
 
+	#[test]
+	fn should_not_sign_with_expired_token() {
+		// given
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		ap.set_token_ttl(Duration::from_millis(50));
+		let (_signature, token) = ap.sign_with_token(kp.address(), "test".into(), Default::default()).unwrap();
+
+		// when
+		::std::thread::sleep(Duration::from_millis(100));
+
+		// then
+		match ap.sign_with_token(kp.address(), token.clone(), Default::default()) {
+			Err(SignError::NotUnlocked) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(ap.transient_sstore.test_password(&StoreAccountRef::root(kp.address()), &token).is_err());
+	}
+
+	#[test]
+	fn should_export_and_import_address_book() {
+		// given
+		let ap = AccountProvider::transient_provider();
+		ap.set_address_name(1.into(), "One".into());
+		ap.set_address_name(2.into(), "Two".into());
+		ap.set_address_meta(2.into(), "{\"x\":1}".into());
+		let before = ap.addresses_info();
+
+		// when
+		let exported = ap.export_address_book();
+		ap.remove_address(1.into());
+		ap.remove_address(2.into());
+		assert!(ap.addresses_info().is_empty());
+
+		// then
+		assert_eq!(ap.import_address_book(&exported).unwrap(), 2);
+		assert_eq!(ap.addresses_info(), before);
+		assert_eq!(ap.import_address_book(&exported).unwrap(), 0);
+	}
+
+	#[test]
+	fn should_not_overwrite_address_book_on_import() {
+		let ap = AccountProvider::transient_provider();
+		ap.set_address_name(1.into(), "Mine".into());
+		let data = br#"[
+			{"address":"0x0000000000000000000000000000000000000001","name":"Theirs","meta":"{}"},
+			{"address":"0x0000000000000000000000000000000000000003","name":"Three","meta":"{}"}
+		]"#;
+		assert_eq!(ap.import_address_book(data).unwrap(), 1);
+		let book = ap.addresses_info();
+		assert_eq!(book[&1.into()].name, "Mine");
+		assert_eq!(book[&3.into()].name, "Three");
+	}
+
+	#[test]
+	fn should_reject_malformed_address_book() {
+		let ap = AccountProvider::transient_provider();
+		match ap.import_address_book(b"[{\"address\":\"0x12\",\"name\":\"\",\"meta\":\"\"}]") {
+			Err(SSError::InvalidJsonKey(ref reason)) => assert!(!reason.is_empty()),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(ap.import_address_book(b"not json").is_err());
+		assert!(ap.addresses_info().is_empty());
+	}
+
 	#[test]
 	fn should_set_dapps_addresses() {
 		// given
@@ -666,6 +1896,102 @@ mod tests {
 		assert_eq!(ap.dapps_addresses(app.clone()).unwrap(), vec![1.into(), 2.into()]);
 	}
 
+	#[test]
+	fn should_set_dapp_default_address() {
+		// given
+		let ap = AccountProvider::transient_provider();
+		let app = DappId("app1".into());
+		ap.set_address_name(1.into(), "1".into());
+		ap.set_address_name(2.into(), "2".into());
+		ap.set_dapps_addresses(app.clone(), vec![1.into(), 2.into()]).unwrap();
+
+		// fallback to first allowed account
+		assert!(ap.dapp_default_address(app.clone()).is_err());
+		assert_eq!(ap.default_address(app.clone()).unwrap(), 1.into());
+
+		// when
+		assert!(ap.set_dapp_default_address(app.clone(), 3.into()).is_err());
+		ap.set_dapp_default_address(app.clone(), 2.into()).unwrap();
+
+		// then
+		assert_eq!(ap.dapp_default_address(app.clone()).unwrap(), 2.into());
+		assert_eq!(ap.default_address(app.clone()).unwrap(), 2.into());
+
+		// default survives reordering
+		ap.set_dapps_addresses(app.clone(), vec![2.into(), 1.into()]).unwrap();
+		ap.set_dapps_addresses(app.clone(), vec![1.into(), 2.into()]).unwrap();
+		assert_eq!(ap.default_address(app.clone()).unwrap(), 2.into());
+
+		// but not removal from the allowed list
+		ap.set_dapps_addresses(app.clone(), vec![1.into()]).unwrap();
+		assert!(ap.dapp_default_address(app.clone()).is_err());
+		assert_eq!(ap.default_address(app.clone()).unwrap(), 1.into());
+	}
+
+	#[test]
+	fn should_keep_accounts_visible_after_pinning_default_on_fresh_dapp() {
+		// given
+		let ap = AccountProvider::transient_provider();
+		let app = DappId("app1".into());
+		let first = ap.new_account("test").unwrap();
+		let second = ap.new_account("test").unwrap();
+
+		// when
+		ap.set_dapp_default_address(app.clone(), second).unwrap();
+
+		// then
+		let mut visible = ap.dapps_addresses(app.clone()).unwrap();
+		visible.sort();
+		let mut accounts = vec![first, second];
+		accounts.sort();
+		assert_eq!(visible, accounts);
+		assert_eq!(ap.default_address(app.clone()).unwrap(), second);
+	}
+
+	#[test]
+	fn should_not_change_dapp_permissions_with_invisible_default() {
+		// given
+		let ap = AccountProvider::transient_provider();
+		let app = DappId("app1".into());
+		ap.set_address_name(1.into(), "1".into());
+		ap.set_address_name(2.into(), "2".into());
+		ap.set_dapp_permissions(app.clone(), vec![1.into(), 2.into()], Some(2.into()), None).unwrap();
+
+		// when
+		let result = ap.set_dapp_permissions(app.clone(), vec![1.into()], Some(2.into()), Some(u64::max_value()));
+
+		// then
+		assert!(result.is_err());
+		assert_eq!(ap.dapps_addresses(app.clone()).unwrap(), vec![1.into(), 2.into()]);
+		assert_eq!(ap.dapp_default_address(app.clone()).unwrap(), 2.into());
+		assert_eq!(ap.dapp_expiry(app.clone()).unwrap(), None);
+	}
+
+	#[test]
+	fn should_ignore_expired_dapp_settings() {
+		// given
+		let ap = AccountProvider::transient_provider();
+		let app = DappId("app1".into());
+		ap.set_new_dapps_whitelist(Some(vec![1.into()])).unwrap();
+		ap.set_address_name(1.into(), "1".into());
+		ap.set_address_name(2.into(), "2".into());
+		ap.set_dapps_addresses(app.clone(), vec![2.into()]).unwrap();
+
+		// when
+		ap.set_dapp_expiry(app.clone(), Some(u64::max_value())).unwrap();
+
+		// then
+		assert_eq!(ap.dapp_expiry(app.clone()).unwrap(), Some(u64::max_value()));
+		assert_eq!(ap.dapps_addresses(app.clone()).unwrap(), vec![2.into()]);
+
+		// and when
+		ap.set_dapp_expiry(app.clone(), Some(1)).unwrap();
+
+		// then
+		assert_eq!(ap.dapp_expiry(app.clone()).unwrap(), None);
+		assert_eq!(ap.dapps_addresses(app.clone()).unwrap(), vec![1.into()]);
+	}
+
 	#[test]
 	fn should_set_dapps_policy() {
 		// given
@@ -689,4 +2015,154 @@ mod tests {
 		ap.set_new_dapps_whitelist(Some(vec![1.into()])).unwrap();
 		assert_eq!(ap.dapps_addresses("app1".into()).unwrap(), vec![1.into()]);
 	}
+
+	#[test]
+	fn should_list_hardware_accounts() {
+		// given
+		let address: Address = 5.into();
+		let ap = hardware_provider(address, Signature::default());
+		let kp = Random.generate().unwrap();
+		ap.insert_account(kp.secret().clone(), "test").unwrap();
+
+		// then
+		assert_eq!(ap.hardware_accounts().unwrap(), vec![address]);
+		assert_eq!(ap.accounts().unwrap(), vec![kp.address()]);
+		assert!(ap.is_hardware_address(address));
+		assert!(!ap.is_hardware_address(kp.address()));
+		let meta = ap.account_meta(address).unwrap();
+		assert_eq!(meta.name, "Nano S");
+		assert_eq!(meta.meta, "Ledger");
+		let info = ap.hardware_accounts_info().unwrap();
+		assert_eq!(info.len(), 1);
+		assert_eq!(info[&address].meta, r#"{"device":"Ledger"}"#);
+	}
+
+	#[test]
+	fn should_keep_user_meta_of_hardware_accounts() {
+		// given
+		let address: Address = 5.into();
+		let ap = hardware_provider(address, Signature::default());
+
+		// when
+		ap.set_address_name(address, "Savings".into());
+		ap.set_address_meta(address, r#"{"tags":["cold"]}"#.into());
+
+		// then
+		let info = ap.hardware_accounts_info().unwrap();
+		assert_eq!(info[&address].name, "Savings");
+		assert_eq!(info[&address].meta, r#"{"tags":["cold"]}"#);
+		let meta = ap.account_meta(address).unwrap();
+		assert_eq!(meta.name, "Savings");
+		assert_eq!(meta.meta, r#"{"tags":["cold"]}"#);
+	}
+
+	#[test]
+	fn should_use_device_name_when_only_hardware_meta_is_set() {
+		// given
+		let address: Address = 5.into();
+		let ap = hardware_provider(address, Signature::default());
+
+		// when
+		ap.set_address_meta(address, r#"{"tags":["cold"]}"#.into());
+
+		// then
+		let info = ap.hardware_accounts_info().unwrap();
+		assert_eq!(info[&address].name, "Nano S");
+		assert_eq!(info[&address].meta, r#"{"tags":["cold"]}"#);
+	}
+
+	#[test]
+	fn should_sign_with_hardware_wallet() {
+		// given
+		let address: Address = 5.into();
+		let signature = Signature::from_rsv(&1.into(), &2.into(), 1);
+		let ap = hardware_provider(address, signature.clone());
+
+		// then
+		assert_eq!(ap.sign_with_hardware(address, &[]).unwrap(), signature);
+		assert_eq!(ap.sign_message_with_hardware(address, b"hello".to_vec()).unwrap(), signature);
+		assert_eq!(ap.sign_typed_data_with_hardware(address, &[0; 32], &[0; 32]).unwrap(), signature);
+		match ap.sign_with_hardware(6.into(), &[]) {
+			Err(SignError::NotFound) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_not_unlock_hardware_account() {
+		// given
+		let address: Address = 5.into();
+		let ap = hardware_provider(address, Signature::default());
+
+		// then
+		assert!(ap.unlock_account_temporarily(address, "test".into()).is_err());
+		assert!(ap.sign(address, None, Default::default()).is_err());
+	}
+	fn confirming_provider(hardware: &KeyPair) -> AccountProvider {
+		let wallet = WalletInfo {
+			name: "Nano S".into(),
+			manufacturer: "Ledger".into(),
+			serial: "0001".into(),
+			address: hardware.address(),
+			firmware_version: None,
+			app_version: None,
+			device_type: WalletDeviceType::Ledger,
+		};
+		let mut secrets = HashMap::new();
+		secrets.insert(hardware.address(), hardware.secret().clone());
+		let sstore = Box::new(EthStore::open(Box::new(MemoryDirectory::default())).unwrap());
+		AccountProvider::new_with_hardware(sstore, Some(MockHardwareWalletManager::from_secrets(vec![wallet], secrets)))
+	}
+
+	#[test]
+	fn should_unlock_account_with_hardware_confirmation() {
+		// given
+		let hardware = Random.generate().unwrap();
+		let ap = confirming_provider(&hardware);
+		let address = ap.new_account("test").unwrap();
+
+		// when
+		ap.unlock_account_with_hardware_confirmation(address, "test".into(), hardware.address(), Unlock::Temp).unwrap();
+
+		// then
+		assert!(ap.is_unlocked(address));
+		assert!(ap.sign(address, None, Default::default()).is_ok());
+	}
+
+	#[test]
+	fn should_not_unlock_account_with_invalid_password_or_missing_wallet() {
+		// given
+		let hardware = Random.generate().unwrap();
+		let ap = confirming_provider(&hardware);
+		let address = ap.new_account("test").unwrap();
+
+		// then
+		match ap.unlock_account_with_hardware_confirmation(address, "wrong".into(), hardware.address(), Unlock::Temp) {
+			Err(SignError::SStore(SSError::InvalidPassword)) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match ap.unlock_account_with_hardware_confirmation(address, "test".into(), 5.into(), Unlock::Temp) {
+			Err(SignError::NotFound) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(!ap.is_unlocked(address));
+	}
+
+	#[test]
+	fn should_not_unlock_account_with_invalid_hardware_signature() {
+		// given
+		let hardware: Address = 5.into();
+		let ap = hardware_provider(hardware, Signature::from_rsv(&1.into(), &2.into(), 1));
+		let address = ap.new_account("test").unwrap();
+
+		// when
+		let result = ap.unlock_account_with_hardware_confirmation(address, "test".into(), hardware, Unlock::Temp);
+
+		// then
+		match result {
+			Err(SignError::InvalidConfirmation) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(!ap.is_unlocked(address));
+	}
 }
@@ -30,6 +30,11 @@ use ethjson::misc::{
 };
 use account_provider::DappId;
 
+/// Checks whether lowercase `query` occurs in the name or metadata, ignoring case.
+pub fn meta_matches(meta: &AccountMeta, query: &str) -> bool {
+	meta.name.to_lowercase().contains(query) || meta.meta.to_lowercase().contains(query)
+}
+
 /// Disk-backed map from Address to String. Uses JSON.
 pub struct AddressBook {
 	cache: DiskMap<Address, AccountMeta>,
@@ -57,6 +62,14 @@ impl AddressBook {
 		self.cache.clone()
 	}
 
+	/// Get entries with name or metadata containing given lowercase query.
+	pub fn search(&self, query: &str) -> HashMap<Address, AccountMeta> {
+		self.cache.iter()
+			.filter(|&(_, meta)| meta_matches(meta, query))
+			.map(|(address, meta)| (*address, meta.clone()))
+			.collect()
+	}
+
 	fn save(&self) {
 		self.cache.save(AccountMeta::write)
 	}
@@ -86,19 +99,38 @@ impl AddressBook {
 		self.cache.remove(&a);
 		self.save();
 	}
+
+	/// Adds entries for addresses not present yet. Returns number of added entries.
+	pub fn import(&mut self, entries: Vec<(Address, AccountMeta)>) -> u32 {
+		let mut added = 0;
+		for (a, meta) in entries {
+			if !self.cache.contains_key(&a) {
+				self.cache.insert(a, meta);
+				added += 1;
+			}
+		}
+		self.save();
+		added
+	}
 }
 
 /// Dapps user settings
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct DappsSettings {
-	/// A list of visible accounts
-	pub accounts: Vec<Address>,
+	/// A list of visible accounts. Visibility follows the new dapps policy if not set.
+	pub accounts: Option<Vec<Address>>,
+	/// Default account
+	pub default_address: Option<Address>,
+	/// Unix timestamp after which the settings are ignored
+	pub expiry: Option<u64>,
 }
 
 impl From<JsonSettings> for DappsSettings {
 	fn from(s: JsonSettings) -> Self {
 		DappsSettings {
-			accounts: s.accounts.into_iter().map(Into::into).collect(),
+			accounts: s.accounts.map(|accounts| accounts.into_iter().map(Into::into).collect()),
+			default_address: s.default_address.map(Into::into),
+			expiry: s.expiry,
 		}
 	}
 }
@@ -106,7 +138,9 @@ impl From<JsonSettings> for DappsSettings {
 impl From<DappsSettings> for JsonSettings {
 	fn from(s: DappsSettings) -> Self {
 		JsonSettings {
-			accounts: s.accounts.into_iter().map(Into::into).collect(),
+			accounts: s.accounts.map(|accounts| accounts.into_iter().map(Into::into).collect()),
+			default_address: s.default_address.map(Into::into),
+			expiry: s.expiry,
 		}
 	}
 }
@@ -269,7 +303,31 @@ impl DappsSettingsStore {
 	pub fn set_accounts(&mut self, id: DappId, accounts: Vec<Address>) {
 		{
 			let mut settings = self.settings.entry(id).or_insert_with(DappsSettings::default);
-			settings.accounts = accounts;
+			settings.accounts = Some(accounts);
+		}
+		self.settings.save(JsonSettings::write);
+	}
+
+	/// Sets default account for specific dapp. Visible accounts are left as they are.
+	pub fn set_default_address(&mut self, id: DappId, address: Address) {
+		{
+			let mut settings = self.settings.entry(id).or_insert_with(DappsSettings::default);
+			settings.default_address = Some(address);
+		}
+		self.settings.save(JsonSettings::write);
+	}
+
+	/// Replaces all settings of specific dapp.
+	pub fn set_settings(&mut self, id: DappId, settings: DappsSettings) {
+		self.settings.insert(id, settings);
+		self.settings.save(JsonSettings::write);
+	}
+
+	/// Sets expiry timestamp of the settings for specific dapp. Visible accounts are left as they are.
+	pub fn set_expiry(&mut self, id: DappId, expiry: Option<u64>) {
+		{
+			let mut settings = self.settings.entry(id).or_insert_with(DappsSettings::default);
+			settings.expiry = expiry;
 		}
 		self.settings.save(JsonSettings::write);
 	}
@@ -386,16 +444,52 @@ mod tests {
 
 		// when
 		b.set_accounts("dappOne".into(), vec![1.into(), 2.into()]);
+		b.set_default_address("dappOne".into(), 2.into());
+		b.set_expiry("dappOne".into(), Some(1_500_000_000));
 
 		// then
 		let b = DappsSettingsStore::new(&path);
 		assert_eq!(b.settings(), hash_map![
 			"dappOne".into() => DappsSettings {
-				accounts: vec![1.into(), 2.into()],
+				accounts: Some(vec![1.into(), 2.into()]),
+				default_address: Some(2.into()),
+				expiry: Some(1_500_000_000),
 			}
 		]);
 	}
 
+	#[test]
+	fn should_not_restrict_accounts_when_pinning_default() {
+		// given
+		let path = RandomTempPath::create_dir();
+		let mut b = DappsSettingsStore::new(&path);
+
+		// when
+		b.set_default_address("dappOne".into(), 2.into());
+
+		// then
+		let b = DappsSettingsStore::new(&path);
+		assert_eq!(b.settings(), hash_map![
+			"dappOne".into() => DappsSettings {
+				accounts: None,
+				default_address: Some(2.into()),
+				expiry: None,
+			}
+		]);
+	}
+
+	#[test]
+	fn should_not_restrict_accounts_when_setting_expiry() {
+		// given
+		let mut b = DappsSettingsStore::transient();
+
+		// when
+		b.set_expiry("dappOne".into(), Some(1_500_000_000));
+
+		// then
+		assert_eq!(b.settings()[&DappId::from("dappOne")].accounts, None);
+	}
+
 	#[test]
 	fn should_maintain_a_map_of_recent_dapps() {
 		let mut store = DappsSettingsStore::transient();
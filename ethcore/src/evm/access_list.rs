@@ -0,0 +1,144 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EIP-2930 access lists recovered from VM traces.
+
+use std::collections::{BTreeMap, BTreeSet};
+use util::{Address, H256, U256};
+use trace::VMTrace;
+use super::instructions::{self, INSTRUCTIONS};
+
+/// Accessed accounts with storage keys accessed in each of them.
+pub type AccessList = BTreeMap<Address, BTreeSet<H256>>;
+
+/// Collect accounts and storage keys accessed by traced execution of the code at `address`.
+///
+/// `excluded` accounts (e.g. the transaction sender) are only listed when their storage is accessed.
+pub fn from_vm_trace(trace: &VMTrace, address: Address, excluded: &[Address]) -> AccessList {
+	let mut list = AccessList::new();
+	collect(trace, address, &mut list);
+	list.into_iter()
+		.filter(|&(ref address, ref keys)| !keys.is_empty() || !excluded.contains(address))
+		.collect()
+}
+
+fn to_address(value: U256) -> Address {
+	H256::from(value).into()
+}
+
+/// Item at given position from the top of the stack.
+fn peek(stack: &[U256], position: usize) -> Option<U256> {
+	stack.len().checked_sub(position + 1).map(|index| stack[index])
+}
+
+/// Replays the stack of a single frame, recording accessed accounts and storage keys.
+fn collect(trace: &VMTrace, address: Address, list: &mut AccessList) {
+	list.entry(address).or_insert_with(BTreeSet::new);
+
+	let mut stack: Vec<U256> = Vec::new();
+	let mut subs = trace.subs.iter().peekable();
+	for (step, operation) in trace.operations.iter().enumerate() {
+		let executed = match operation.executed {
+			Some(ref executed) => executed,
+			// execution of the frame failed at this operation
+			None => break,
+		};
+		match operation.instruction {
+			instructions::SLOAD | instructions::SSTORE => if let Some(key) = peek(&stack, 0) {
+				list.entry(address).or_insert_with(BTreeSet::new).insert(key.into());
+			},
+			instructions::BALANCE | instructions::EXTCODESIZE | instructions::EXTCODECOPY | instructions::SUICIDE => if let Some(target) = peek(&stack, 0) {
+				list.entry(to_address(target)).or_insert_with(BTreeSet::new);
+			},
+			instructions::CALL | instructions::CALLCODE | instructions::DELEGATECALL => if let Some(target) = peek(&stack, 1) {
+				list.entry(to_address(target)).or_insert_with(BTreeSet::new);
+			},
+			_ => {},
+		}
+
+		while subs.peek().map_or(false, |sub| sub.parent_step == step) {
+			let sub = subs.next().expect("peeked above; qed");
+			let sub_address = match operation.instruction {
+				instructions::CALL => peek(&stack, 1).map(to_address),
+				// code of another account executed in the context of this one
+				instructions::CALLCODE | instructions::DELEGATECALL => Some(address),
+				// address of the created contract is pushed when creation succeeds
+				instructions::CREATE => executed.stack_push.first().cloned().map(to_address).and_then(|created| if created.is_zero() { None } else { Some(created) }),
+				_ => None,
+			};
+			if let Some(sub_address) = sub_address {
+				collect(sub, sub_address, list);
+			}
+		}
+
+		let info = &INSTRUCTIONS[operation.instruction as usize];
+		let remaining = stack.len().saturating_sub(info.args);
+		stack.truncate(remaining);
+		stack.extend(executed.stack_push.iter().cloned());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, U256};
+	use trace::{VMTrace, VMOperation, VMExecutedOperation};
+	use evm::instructions;
+	use super::from_vm_trace;
+
+	fn operation(instruction: u8, stack_push: Vec<U256>) -> VMOperation {
+		VMOperation {
+			pc: 0,
+			instruction: instruction,
+			gas_cost: 0.into(),
+			executed: Some(VMExecutedOperation {
+				gas_used: 0.into(),
+				stack_push: stack_push,
+				mem_diff: None,
+				store_diff: None,
+			}),
+		}
+	}
+
+	#[test]
+	fn should_collect_storage_keys_and_accounts() {
+		let contract = Address::from(0x10);
+		let sender = Address::from(0x20);
+		let other = Address::from(0x30);
+		let trace = VMTrace {
+			parent_step: 0,
+			code: vec![],
+			operations: vec![
+				operation(instructions::PUSH1, vec![1.into()]),
+				operation(instructions::SLOAD, vec![5.into()]),
+				operation(instructions::PUSH1, vec![2.into()]),
+				operation(instructions::DUP2, vec![5.into(), 2.into(), 5.into()]),
+				operation(instructions::SWAP1, vec![5.into(), 2.into()]),
+				operation(instructions::SSTORE, vec![]),
+				operation(instructions::PUSH1, vec![U256::from(0x30)]),
+				operation(instructions::BALANCE, vec![0.into()]),
+				operation(instructions::PUSH1, vec![U256::from(0x20)]),
+				operation(instructions::BALANCE, vec![0.into()]),
+			],
+			subs: vec![],
+		};
+
+		let list = from_vm_trace(&trace, contract, &[sender]);
+
+		assert_eq!(list.len(), 2);
+		assert_eq!(list[&contract].iter().cloned().collect::<Vec<_>>(), vec![U256::from(1).into(), U256::from(2).into()]);
+		assert!(list[&other].is_empty());
+	}
+}
@@ -16,6 +16,7 @@
 
 //! Ethereum virtual machine.
 
+pub mod access_list;
 pub mod ext;
 pub mod evm;
 pub mod interpreter;
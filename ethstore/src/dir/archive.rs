@@ -0,0 +1,488 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encrypted ZIP backups of key directories.
+//!
+//! A backup is a ZIP archive with every entry encrypted with WinZip AES (AE-2) using a 256-bit key,
+//! so it can be opened by common archivers. Entries are stored without compression.
+
+use rcrypto::aessafe::AesSafe256Encryptor;
+use rcrypto::hmac::Hmac;
+use rcrypto::mac::{Mac, MacResult};
+use rcrypto::pbkdf2::pbkdf2;
+use rcrypto::sha1::Sha1;
+use rcrypto::symmetriccipher::BlockEncryptor;
+use time;
+use random::Random;
+use {SafeAccount, Error};
+use super::disk::{KeyFileManager, DiskKeyFileManager};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_LENGTH: usize = 22;
+/// ZIP specification version needed to extract AES encrypted entries (5.1)
+const VERSION_NEEDED: u16 = 51;
+/// Entry is encrypted and its name is UTF-8
+const ENTRY_FLAGS: u16 = 0x0001 | 0x0800;
+/// Compression method of WinZip AES encrypted entries
+const AES_METHOD: u16 = 99;
+/// Compression method of the data before encryption
+const STORED_METHOD: u16 = 0;
+const AES_EXTRA_FIELD_ID: u16 = 0x9901;
+const AES_EXTRA_FIELD_LENGTH: u16 = 7;
+/// AE-2 entries don't store CRC, their data is authenticated with HMAC only
+const AE_2: u16 = 2;
+/// Key strength marking AES-256
+const AES_256: u8 = 3;
+const SALT_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+const VERIFIER_LENGTH: usize = 2;
+const MAC_LENGTH: usize = 10;
+/// PBKDF2 iterations fixed by WinZip AES
+const KDF_ITERATIONS: u32 = 1000;
+
+fn invalid_archive<T: Into<String>>(reason: T) -> Error {
+	Error::InvalidArchive(reason.into())
+}
+
+/// Keys of a single entry
+struct EntryKeys {
+	encryption_key: Vec<u8>,
+	mac_key: Vec<u8>,
+	/// Stored next to the salt to tell a wrong password from corrupted data
+	verifier: Vec<u8>,
+}
+
+impl EntryKeys {
+	fn derive(password: &str, salt: &[u8]) -> Self {
+		let mut hmac = Hmac::new(Sha1::new(), password.as_bytes());
+		let mut derived = [0u8; 2 * KEY_LENGTH + VERIFIER_LENGTH];
+		pbkdf2(&mut hmac, salt, KDF_ITERATIONS, &mut derived);
+		EntryKeys {
+			encryption_key: derived[..KEY_LENGTH].to_vec(),
+			mac_key: derived[KEY_LENGTH..2 * KEY_LENGTH].to_vec(),
+			verifier: derived[2 * KEY_LENGTH..].to_vec(),
+		}
+	}
+
+	/// AES-256 in CTR mode with a little-endian block counter starting at 1;
+	/// the same operation encrypts and decrypts
+	fn apply_cipher(&self, data: &mut [u8]) {
+		let aes = AesSafe256Encryptor::new(&self.encryption_key);
+		let mut counter = [0u8; 16];
+		let mut keystream = [0u8; 16];
+		for block in data.chunks_mut(16) {
+			for byte in counter.iter_mut() {
+				*byte = byte.wrapping_add(1);
+				if *byte != 0 {
+					break;
+				}
+			}
+			aes.encrypt_block(&counter, &mut keystream);
+			for (byte, key) in block.iter_mut().zip(keystream.iter()) {
+				*byte ^= *key;
+			}
+		}
+	}
+
+	/// Truncated HMAC-SHA1 of the encrypted data
+	fn mac(&self, encrypted: &[u8]) -> MacResult {
+		let mut hmac = Hmac::new(Sha1::new(), &self.mac_key);
+		hmac.input(encrypted);
+		MacResult::new(&hmac.result().code()[..MAC_LENGTH])
+	}
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+	out.push(value as u8);
+	out.push((value >> 8) as u8);
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+	put_u16(out, value as u16);
+	put_u16(out, (value >> 16) as u16);
+}
+
+/// Little-endian reader of archive records
+struct Fields<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Fields<'a> {
+	fn at(data: &'a [u8], position: usize) -> Self {
+		Fields {
+			data: data,
+			position: position,
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.position >= self.data.len()
+	}
+
+	fn bytes(&mut self, length: usize) -> Result<&'a [u8], Error> {
+		if self.position > self.data.len() || self.data.len() - self.position < length {
+			return Err(invalid_archive("unexpected end of data"));
+		}
+		let bytes = &self.data[self.position..self.position + length];
+		self.position += length;
+		Ok(bytes)
+	}
+
+	fn skip(&mut self, length: usize) -> Result<(), Error> {
+		self.bytes(length).map(|_| ())
+	}
+
+	fn u8(&mut self) -> Result<u8, Error> {
+		self.bytes(1).map(|bytes| bytes[0])
+	}
+
+	fn u16(&mut self) -> Result<u16, Error> {
+		self.bytes(2).map(|bytes| bytes[0] as u16 | (bytes[1] as u16) << 8)
+	}
+
+	fn u32(&mut self) -> Result<u32, Error> {
+		let low = self.u16()? as u32;
+		let high = self.u16()? as u32;
+		Ok(low | high << 16)
+	}
+}
+
+/// Current local time in the MS-DOS format used by ZIP headers
+fn dos_date_time() -> (u16, u16) {
+	let now = time::now();
+	let date = (now.tm_year - 80) << 9 | (now.tm_mon + 1) << 5 | now.tm_mday;
+	let time = now.tm_hour << 11 | now.tm_min << 5 | now.tm_sec / 2;
+	(date as u16, time as u16)
+}
+
+/// Fields shared by local and central directory headers, from "version needed" to "extra field length"
+fn put_entry_fields(out: &mut Vec<u8>, date_time: (u16, u16), name: &[u8], compressed_size: u32, size: u32) {
+	put_u16(out, VERSION_NEEDED);
+	put_u16(out, ENTRY_FLAGS);
+	put_u16(out, AES_METHOD);
+	put_u16(out, date_time.1);
+	put_u16(out, date_time.0);
+	// CRC is not stored in AE-2 entries
+	put_u32(out, 0);
+	put_u32(out, compressed_size);
+	put_u32(out, size);
+	put_u16(out, name.len() as u16);
+	put_u16(out, 4 + AES_EXTRA_FIELD_LENGTH);
+}
+
+fn put_aes_extra_field(out: &mut Vec<u8>) {
+	put_u16(out, AES_EXTRA_FIELD_ID);
+	put_u16(out, AES_EXTRA_FIELD_LENGTH);
+	put_u16(out, AE_2);
+	out.extend_from_slice(b"AE");
+	out.push(AES_256);
+	put_u16(out, STORED_METHOD);
+}
+
+/// Checks that `extra` fields mark an entry as encrypted with AES-256 and stored without compression
+fn is_aes_256_stored(extra: &[u8]) -> Result<bool, Error> {
+	let mut fields = Fields::at(extra, 0);
+	while !fields.is_empty() {
+		let id = fields.u16()?;
+		let length = fields.u16()? as usize;
+		let mut field = Fields::at(fields.bytes(length)?, 0);
+		if id == AES_EXTRA_FIELD_ID {
+			// AE-1 entries additionally store CRC, which is redundant with the HMAC
+			let version = field.u16()?;
+			let vendor = field.bytes(2)?;
+			let strength = field.u8()?;
+			let method = field.u16()?;
+			return Ok((version == 1 || version == AE_2) && vendor == b"AE" && strength == AES_256 && method == STORED_METHOD);
+		}
+	}
+	Ok(false)
+}
+
+/// Pack given `(name, contents)` files into a ZIP archive with entries encrypted with `password`.
+pub fn write_zip(files: Vec<(String, Vec<u8>)>, password: &str) -> Result<Vec<u8>, Error> {
+	if files.len() > u16::max_value() as usize {
+		return Err(invalid_archive("too many files"));
+	}
+
+	let date_time = dos_date_time();
+	let mut archive = Vec::new();
+	let mut central_directory = Vec::new();
+	let entries = files.len() as u16;
+	for (name, contents) in files {
+		let salt: [u8; SALT_LENGTH] = Random::random();
+		let keys = EntryKeys::derive(password, &salt);
+		let mut encrypted = contents;
+		let size = encrypted.len();
+		keys.apply_cipher(&mut encrypted);
+		let compressed_size = SALT_LENGTH + VERIFIER_LENGTH + size + MAC_LENGTH;
+		let offset = archive.len();
+		if offset + compressed_size > u32::max_value() as usize {
+			return Err(invalid_archive("archive is too large"));
+		}
+
+		put_u32(&mut archive, LOCAL_HEADER_SIGNATURE);
+		put_entry_fields(&mut archive, date_time, name.as_bytes(), compressed_size as u32, size as u32);
+		archive.extend_from_slice(name.as_bytes());
+		put_aes_extra_field(&mut archive);
+		archive.extend_from_slice(&salt);
+		archive.extend_from_slice(&keys.verifier);
+		archive.extend_from_slice(&encrypted);
+		archive.extend_from_slice(keys.mac(&encrypted).code());
+
+		put_u32(&mut central_directory, CENTRAL_HEADER_SIGNATURE);
+		// version made by
+		put_u16(&mut central_directory, VERSION_NEEDED);
+		put_entry_fields(&mut central_directory, date_time, name.as_bytes(), compressed_size as u32, size as u32);
+		// comment length, disk number, internal and external attributes
+		put_u16(&mut central_directory, 0);
+		put_u16(&mut central_directory, 0);
+		put_u16(&mut central_directory, 0);
+		put_u32(&mut central_directory, 0);
+		put_u32(&mut central_directory, offset as u32);
+		central_directory.extend_from_slice(name.as_bytes());
+		put_aes_extra_field(&mut central_directory);
+	}
+
+	let central_directory_offset = archive.len() as u32;
+	let central_directory_size = central_directory.len() as u32;
+	archive.extend(central_directory);
+	put_u32(&mut archive, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+	// number of this disk and of the disk with central directory
+	put_u16(&mut archive, 0);
+	put_u16(&mut archive, 0);
+	put_u16(&mut archive, entries);
+	put_u16(&mut archive, entries);
+	put_u32(&mut archive, central_directory_size);
+	put_u32(&mut archive, central_directory_offset);
+	// comment length
+	put_u16(&mut archive, 0);
+	Ok(archive)
+}
+
+/// Decrypt data of a single entry.
+/// Fails with `InvalidPassword` if the password doesn't match and `InvalidArchive` if the data is corrupted.
+fn decrypt_entry(name: &str, data: &[u8], size: usize, password: &str) -> Result<Vec<u8>, Error> {
+	if data.len() != SALT_LENGTH + VERIFIER_LENGTH + size + MAC_LENGTH {
+		return Err(invalid_archive(format!("{}: invalid encrypted data length", name)));
+	}
+
+	let (salt, data) = data.split_at(SALT_LENGTH);
+	let (verifier, data) = data.split_at(VERIFIER_LENGTH);
+	let (encrypted, mac) = data.split_at(size);
+	let keys = EntryKeys::derive(password, salt);
+	if &keys.verifier[..] != verifier {
+		return Err(Error::InvalidPassword);
+	}
+	// MacResult comparison takes constant time
+	if keys.mac(encrypted) != MacResult::new(mac) {
+		return Err(invalid_archive(format!("{}: data does not match its authentication code", name)));
+	}
+
+	let mut contents = encrypted.to_vec();
+	keys.apply_cipher(&mut contents);
+	Ok(contents)
+}
+
+/// Decrypt archive created by `write_zip`, or by other archivers using WinZip AES-256 without compression,
+/// and return its `(name, contents)` files.
+pub fn read_zip(data: &[u8], password: &str) -> Result<Vec<(String, Vec<u8>)>, Error> {
+	// backups have no archive comment, so the end of central directory record closes the archive
+	if data.len() < END_OF_CENTRAL_DIRECTORY_LENGTH {
+		return Err(invalid_archive("not a ZIP archive"));
+	}
+	let mut end = Fields::at(data, data.len() - END_OF_CENTRAL_DIRECTORY_LENGTH);
+	if end.u32()? != END_OF_CENTRAL_DIRECTORY_SIGNATURE {
+		return Err(invalid_archive("not a ZIP archive"));
+	}
+	// disk numbers and entries on this disk
+	end.skip(6)?;
+	let entries = end.u16()?;
+	// central directory size
+	end.skip(4)?;
+	let mut central_directory = Fields::at(data, end.u32()? as usize);
+
+	let mut files = Vec::with_capacity(entries as usize);
+	for _ in 0..entries {
+		if central_directory.u32()? != CENTRAL_HEADER_SIGNATURE {
+			return Err(invalid_archive("invalid central directory"));
+		}
+		// versions and flags
+		central_directory.skip(6)?;
+		let method = central_directory.u16()?;
+		// modification time and CRC
+		central_directory.skip(8)?;
+		let compressed_size = central_directory.u32()? as usize;
+		let size = central_directory.u32()? as usize;
+		let name_length = central_directory.u16()? as usize;
+		let extra_length = central_directory.u16()? as usize;
+		let comment_length = central_directory.u16()? as usize;
+		// disk number, internal and external attributes
+		central_directory.skip(8)?;
+		let offset = central_directory.u32()? as usize;
+		let name = String::from_utf8(central_directory.bytes(name_length)?.to_vec())
+			.map_err(|_| invalid_archive("entry name is not UTF-8"))?;
+		let extra = central_directory.bytes(extra_length)?;
+		central_directory.skip(comment_length)?;
+
+		if method != AES_METHOD || !is_aes_256_stored(extra)? {
+			return Err(invalid_archive(format!("{}: entry is not encrypted with AES-256 or is compressed", name)));
+		}
+
+		let mut local = Fields::at(data, offset);
+		if local.u32()? != LOCAL_HEADER_SIGNATURE {
+			return Err(invalid_archive(format!("{}: invalid local header", name)));
+		}
+		// sizes in the local header are zero if they follow the data, so only the central directory is trusted
+		local.skip(22)?;
+		let local_name_length = local.u16()? as usize;
+		let local_extra_length = local.u16()? as usize;
+		local.skip(local_name_length + local_extra_length)?;
+		let contents = decrypt_entry(&name, local.bytes(compressed_size)?, size, password)?;
+		files.push((name, contents));
+	}
+	Ok(files)
+}
+
+/// Read root accounts from archive created by `VaultKeyDirectoryProvider::export_to_zip`.
+/// Nothing is written to disk. Vault accounts are encrypted with their vault keys and can't be read here,
+/// they can be recovered by extracting the archive into a keys directory.
+pub fn import_from_zip(data: &[u8], password: &str) -> Result<Vec<SafeAccount>, Error> {
+	let (vault_files, files): (Vec<_>, Vec<_>) = read_zip(data, password)?
+		.into_iter()
+		.partition(|&(ref name, _)| name.contains('/'));
+	if !vault_files.is_empty() {
+		warn!("Skipping {} vault files of the backup. Extract the backup to a keys directory to recover vaults.", vault_files.len());
+	}
+
+	files.into_iter()
+		.map(|(name, contents)| DiskKeyFileManager.read(Some(name), &contents[..]))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use dir::{KeyDirectory, VaultKeyDirectoryProvider, VaultKey, RootDiskDirectory};
+	use account::SafeAccount;
+	use ethkey::{Random, Generator, Address};
+	use devtools::RandomTempPath;
+	use Error;
+	use super::{import_from_zip, write_zip, read_zip};
+
+	#[test]
+	fn should_export_and_import_accounts() {
+		// given
+		let temp_path = RandomTempPath::create_dir();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let keypairs = vec![Random.generate().unwrap(), Random.generate().unwrap()];
+		for keypair in &keypairs {
+			let account = SafeAccount::create(keypair, [0u8; 16], "password", 1024, "Test".to_owned(), "{}".to_owned());
+			directory.insert(account).unwrap();
+		}
+		let vault = directory.create("vault", VaultKey::new("vault", 1024)).unwrap();
+		let vault_keypair = Random.generate().unwrap();
+		vault.insert(SafeAccount::create(&vault_keypair, [0u8; 16], "password", 1024, "Vault".to_owned(), "{}".to_owned())).unwrap();
+
+		// when
+		let archive = directory.export_to_zip("backup").unwrap();
+		let imported = import_from_zip(&archive, "backup").unwrap();
+
+		// then
+		let mut original = directory.load().unwrap();
+		original.sort_by(|a, b| a.address.cmp(&b.address));
+		let mut imported = imported;
+		imported.sort_by(|a, b| a.address.cmp(&b.address));
+		assert_eq!(imported, original);
+		for keypair in &keypairs {
+			let account = imported.iter().find(|account| account.address == keypair.address()).unwrap();
+			assert_eq!(&account.crypto.secret("password").unwrap(), keypair.secret());
+		}
+		let mut names: Vec<_> = read_zip(&archive, "backup").unwrap().into_iter().map(|(name, _)| name).collect();
+		names.sort();
+		assert!(names.contains(&"vault/vault.json".to_owned()));
+		assert_eq!(names.len(), 4);
+	}
+
+	#[test]
+	fn should_write_winzip_aes_entries() {
+		// when
+		let archive = write_zip(vec![("key".to_owned(), b"contents".to_vec())], "backup").unwrap();
+
+		// then
+		assert_eq!(&archive[..4], b"PK\x03\x04");
+		// encrypted, UTF-8 name, compression method 99
+		assert_eq!(&archive[6..10], &[0x01, 0x08, 99, 0]);
+		// AE-2 extra field with AES-256 over stored data
+		assert_eq!(&archive[33..44], &[0x01, 0x99, 7, 0, 2, 0, b'A', b'E', 3, 0, 0]);
+		assert_eq!(read_zip(&archive, "backup").unwrap(), vec![("key".to_owned(), b"contents".to_vec())]);
+	}
+
+	#[test]
+	fn should_import_archive_created_by_other_archivers() {
+		// given
+		// created with `bsdtar -a -cf aes256_backup.zip --options 'zip:encryption=aes256,zip:compression=store'`
+		let archive = include_bytes!("../../res/aes256_backup.zip");
+
+		// when
+		let imported = import_from_zip(archive, "backup").unwrap();
+
+		// then
+		assert_eq!(imported.len(), 1);
+		assert_eq!(imported[0].address, "3f49624084b67849c7b4e805c5988c21a430f9d9".parse::<Address>().unwrap());
+	}
+
+	#[test]
+	fn should_not_import_with_wrong_password() {
+		// given
+		let temp_path = RandomTempPath::create_dir();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let keypair = Random.generate().unwrap();
+		directory.insert(SafeAccount::create(&keypair, [0u8; 16], "password", 1024, "Test".to_owned(), "{}".to_owned())).unwrap();
+		let archive = directory.export_to_zip("backup").unwrap();
+
+		// when
+		let result = import_from_zip(&archive, "wrong");
+
+		// then
+		match result {
+			Err(Error::InvalidPassword) => (),
+			_ => panic!("expected InvalidPassword"),
+		}
+	}
+
+	#[test]
+	fn should_report_corrupted_archive() {
+		// given
+		let mut archive = write_zip(vec![("key".to_owned(), b"contents".to_vec())], "backup").unwrap();
+		// first byte of encrypted data, after local header, name, extra field, salt and password verifier
+		archive[30 + 3 + 11 + 16 + 2] ^= 1;
+
+		// when
+		let result = read_zip(&archive, "backup");
+
+		// then
+		match result {
+			Err(Error::InvalidArchive(_)) => (),
+			other => panic!("expected InvalidArchive, got {:?}", other),
+		}
+		match read_zip(&archive[..archive.len() - 1], "backup") {
+			Err(Error::InvalidArchive(_)) => (),
+			other => panic!("expected InvalidArchive, got {:?}", other),
+		}
+	}
+}
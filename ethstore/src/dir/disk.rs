@@ -14,14 +14,29 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{fs, io};
+use std::{fs, io, thread};
+use std::io::{Read, Write};
 use std::path::{PathBuf, Path};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, SystemTime};
+use rand::{Rng, OsRng};
+use parking_lot::Mutex;
+use rustc_serialize::hex::{ToHex, FromHex};
+use rcrypto::hmac::Hmac;
+use rcrypto::mac::Mac;
+use rcrypto::sha2::Sha256;
+use serde_json;
 use time;
 use {json, SafeAccount, Error};
 use json::Uuid;
-use super::{KeyDirectory, VaultKeyDirectory, VaultKeyDirectoryProvider, VaultKey};
+use crypto::Keccak256;
+use account::Cipher;
+use random::Random;
+use super::{KeyDirectory, VaultKeyDirectory, VaultKeyDirectoryProvider, VaultKey, DirChangeEvent, IntegrityError};
 use super::vault::{VAULT_FILE_NAME, VaultDiskDirectory};
+use super::archive::write_zip;
 
 const IGNORED_FILES: &'static [&'static str] = &[
 	"thumbs.db",
@@ -32,6 +47,83 @@ const IGNORED_FILES: &'static [&'static str] = &[
 	"vault.json",
 ];
 
+/// Integrity records of key files written by this directory. Hidden, so never treated as a key file.
+const INTEGRITY_FILE_NAME: &'static str = ".integrity.json";
+/// Per-directory key used to authenticate key file contents
+const HMAC_KEY_FILE_NAME: &'static str = ".keystore_integrity";
+/// Integrity record field holding the checksum of the encrypted key
+const CHECKSUM_FIELD: &'static str = "checksum";
+/// Integrity record field holding the HMAC of key file contents, recorded when verification on load is enabled
+const MAC_FIELD: &'static str = "mac";
+
+/// Integrity record of every key file, by file name
+type IntegrityRecords = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Prefix of key files staged before being moved to their final path
+const TEMP_FILE_PREFIX: &'static str = ".tmp_";
+/// Age after which staged key files are considered crash leftovers
+const STALE_TEMP_FILE_SECS: u64 = 5 * 60;
+
+/// Interval between scans of a watched directory
+const WATCH_INTERVAL_MS: u64 = 500;
+
+/// Default name of the file holding given account
+pub fn key_file_name(account: &SafeAccount) -> String {
+	let timestamp = time::strftime("%Y-%m-%dT%H-%M-%S", &time::now_utc()).expect("Time-format string is valid.");
+	format!("UTC--{}Z--{}", timestamp, Uuid::from(account.id))
+}
+
+/// Number of times a colliding key file UUID is regenerated before giving up
+const UUID_ATTEMPTS: usize = 3;
+
+/// Returns `id` if it's not used yet, otherwise a fresh one obtained from `generate`
+fn unique_id<F>(used: &HashSet<[u8; 16]>, id: [u8; 16], mut generate: F) -> Result<[u8; 16], Error> where F: FnMut() -> [u8; 16] {
+	let mut id = id;
+	for _ in 0..UUID_ATTEMPTS {
+		if !used.contains(&id) {
+			return Ok(id);
+		}
+		id = generate();
+	}
+
+	if used.contains(&id) {
+		Err(Error::DuplicateUuid)
+	} else {
+		Ok(id)
+	}
+}
+
+/// Checksum of the encrypted part of the key
+fn key_checksum(account: &SafeAccount) -> String {
+	let Cipher::Aes128Ctr(ref params) = account.crypto.cipher;
+	let mut data = account.crypto.ciphertext.clone();
+	data.extend_from_slice(&params.iv);
+	data.extend_from_slice(&account.crypto.mac);
+	let checksum: [u8; 32] = data.keccak256();
+	checksum.to_hex()
+}
+
+/// Checks whether given directory entry may be a key file
+fn is_key_file(path: &Path) -> bool {
+	let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+	// hidden files
+	!name.is_empty() && !name.starts_with(".") &&
+	// other ignored files
+	!IGNORED_FILES.contains(&&*name)
+}
+
+/// Modification time and length of every key file in given directory
+fn key_file_stamps(path: &Path) -> io::Result<HashMap<PathBuf, (Option<SystemTime>, u64)>> {
+	Ok(fs::read_dir(path)?
+		.flat_map(Result::ok)
+		.filter_map(|entry| match entry.metadata() {
+			Ok(ref metadata) if !metadata.is_dir() && is_key_file(&entry.path()) =>
+				Some((entry.path(), (metadata.modified().ok(), metadata.len()))),
+			_ => None,
+		})
+		.collect())
+}
+
 #[cfg(not(windows))]
 fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
 	use std::ffi;
@@ -50,6 +142,73 @@ fn restrict_permissions_to_owner(_file_path: &Path) -> Result<(), i32> {
 	Ok(())
 }
 
+#[cfg(test)]
+thread_local! {
+	/// Number of directory syncs issued by the current thread.
+	static DIRECTORY_SYNCS: ::std::cell::Cell<usize> = ::std::cell::Cell::new(0);
+}
+
+/// Flush directory entries (created or removed files) to the disk
+fn sync_directory(path: &Path) -> io::Result<()> {
+	#[cfg(test)]
+	DIRECTORY_SYNCS.with(|syncs| syncs.set(syncs.get() + 1));
+
+	#[cfg(unix)]
+	fs::File::open(path)?.sync_all()?;
+	#[cfg(not(unix))]
+	let _ = path;
+	Ok(())
+}
+
+/// Read whole file contents
+fn read_raw(path: &Path) -> Result<Vec<u8>, Error> {
+	let mut contents = Vec::new();
+	fs::File::open(path)?.read_to_end(&mut contents)?;
+	Ok(contents)
+}
+
+/// Write given bytes over the whole file and flush them to the disk
+fn overwrite_pass(path: &Path, data: &[u8]) -> io::Result<()> {
+	let mut file = fs::OpenOptions::new().write(true).open(path)?;
+	file.write_all(data)?;
+	file.sync_all()
+}
+
+/// Overwrite file with zeros, ones and random bytes (DoD 5220.22-M)
+fn overwrite_file(path: &Path) -> io::Result<()> {
+	let len = fs::metadata(path)?.len() as usize;
+	let mut random = vec![0u8; len];
+	OsRng::new()?.fill_bytes(&mut random);
+	for pass in &[vec![0u8; len], vec![0xffu8; len], random] {
+		overwrite_pass(path, pass)?;
+	}
+	Ok(())
+}
+
+/// Replace file contents through a temporary file, so that a crash leaves either old or new contents
+fn replace_file(path: &Path, data: &[u8]) -> io::Result<()> {
+	let filename = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+	let temp_path = path.with_file_name(format!("{}{}", TEMP_FILE_PREFIX, filename));
+	{
+		let mut file = fs::File::create(&temp_path)?;
+		file.write_all(data)?;
+		file.sync_all()?;
+	}
+	fs::rename(&temp_path, path)
+}
+
+/// Overwrite key file contents before unlinking it
+fn secure_remove_file(path: &Path) -> io::Result<()> {
+	if let Err(err) = overwrite_file(path) {
+		warn!("Unable to overwrite key file {:?} ({}), falling back to single zero pass", path, err);
+		let zeroed = fs::metadata(path).and_then(|m| overwrite_pass(path, &vec![0u8; m.len() as usize]));
+		if let Err(err) = zeroed {
+			warn!("Unable to zero key file {:?} ({})", path, err);
+		}
+	}
+	fs::remove_file(path)
+}
+
 /// Root keys directory implementation
 pub type RootDiskDirectory = DiskDirectory<DiskKeyFileManager>;
 
@@ -64,7 +223,12 @@ pub trait KeyFileManager: Send + Sync {
 /// Disk-based keys directory implementation
 pub struct DiskDirectory<T> where T: KeyFileManager {
 	path: PathBuf,
-	key_manager: T,
+	key_manager: Arc<T>,
+	secure_delete: bool,
+	verify_on_load: bool,
+	max_accounts: Option<usize>,
+	/// Guards read-modify-write of the integrity sidecars
+	integrity_lock: Mutex<()>,
 }
 
 /// Keys file manager for root keys directory
@@ -86,7 +250,37 @@ impl<T> DiskDirectory<T> where T: KeyFileManager {
 	pub fn new<P>(path: P, key_manager: T) -> Self where P: AsRef<Path> {
 		DiskDirectory {
 			path: path.as_ref().to_path_buf(),
-			key_manager: key_manager,
+			key_manager: Arc::new(key_manager),
+			secure_delete: false,
+			verify_on_load: false,
+			max_accounts: None,
+			integrity_lock: Mutex::new(()),
+		}
+	}
+
+	/// Overwrite key files before removing them
+	pub fn with_secure_delete(mut self, secure_delete: bool) -> Self {
+		self.secure_delete = secure_delete;
+		self
+	}
+
+	/// Authenticate key file contents when loading accounts
+	pub fn with_verify_on_load(mut self, verify_on_load: bool) -> Self {
+		self.verify_on_load = verify_on_load;
+		self
+	}
+
+	/// Refuse to insert keys once directory holds given number of accounts
+	pub fn with_max_accounts(mut self, max_accounts: Option<usize>) -> Self {
+		self.max_accounts = max_accounts;
+		self
+	}
+
+	/// check that `count` more accounts fit next to `existing` ones
+	fn check_capacity(&self, existing: usize, count: usize) -> Result<(), Error> {
+		match self.max_accounts {
+			Some(max_accounts) if existing + count > max_accounts => Err(Error::DirectoryFull),
+			_ => Ok(()),
 		}
 	}
 
@@ -98,14 +292,8 @@ impl<T> DiskDirectory<T> where T: KeyFileManager {
 			.flat_map(Result::ok)
 			.filter(|entry| {
 				let metadata = entry.metadata().ok();
-				let file_name = entry.file_name();
-				let name = file_name.to_string_lossy();
 				// filter directories
-				metadata.map_or(false, |m| !m.is_dir()) &&
-				// hidden files
-				!name.starts_with(".") &&
-				// other ignored files
-				!IGNORED_FILES.contains(&&*name)
+				metadata.map_or(false, |m| !m.is_dir()) && is_key_file(&entry.path())
 			})
 			.map(|entry| entry.path())
 			.collect::<Vec<PathBuf>>();
@@ -113,60 +301,360 @@ impl<T> DiskDirectory<T> where T: KeyFileManager {
 		Ok(paths
 			.into_iter()
 			.filter_map(|path| {
-				let filename = Some(path.file_name().and_then(|n| n.to_str()).expect("Keys have valid UTF8 names only.").to_owned());
-				fs::File::open(path.clone())
-					.map_err(Into::into)
-					.and_then(|file| self.key_manager.read(filename, file))
-					.map_err(|err| {
-						warn!("Invalid key file: {:?} ({})", path, err);
-						err
-					})
-					.map(|account| (path, account))
-					.ok()
+				Self::read_file(&*self.key_manager, &path).map(|account| (path, account))
 			})
 			.collect()
 		)
 	}
 
+	/// read account from given key file
+	fn read_file(key_manager: &T, path: &Path) -> Option<SafeAccount> {
+		let filename = Some(path.file_name().and_then(|n| n.to_str()).expect("Keys have valid UTF8 names only.").to_owned());
+		fs::File::open(path)
+			.map_err(Into::into)
+			.and_then(|file| key_manager.read(filename, file))
+			.map_err(|err| {
+				warn!("Invalid key file: {:?} ({})", path, err);
+				err
+			})
+			.ok()
+	}
+
+	fn integrity_path(&self) -> PathBuf {
+		self.path.join(INTEGRITY_FILE_NAME)
+	}
+
+	/// recorded integrity of key files
+	fn records(&self) -> IntegrityRecords {
+		fs::File::open(self.integrity_path()).ok()
+			.and_then(|file| serde_json::from_reader(file).ok())
+			.unwrap_or_default()
+	}
+
+	fn save_records(&self, records: &IntegrityRecords) {
+		let saved = serde_json::to_vec(records)
+			.map_err(|e| e.to_string())
+			.and_then(|data| replace_file(&self.integrity_path(), &data).map_err(|e| e.to_string()));
+		if let Err(err) = saved {
+			warn!("Unable to save key checksums in {:?}: {}", self.path, err);
+		}
+	}
+
+	/// integrity record of the key file at `path` holding `account`.
+	/// Contents HMAC is included iff `key` is given.
+	fn record_of(path: &Path, account: &SafeAccount, key: Option<&[u8]>) -> Result<BTreeMap<String, String>, Error> {
+		let mut record = BTreeMap::new();
+		record.insert(CHECKSUM_FIELD.to_owned(), key_checksum(account));
+		if let Some(key) = key {
+			record.insert(MAC_FIELD.to_owned(), Self::file_mac(key, path)?);
+		}
+		Ok(record)
+	}
+
+	/// integrity records of all key files currently in the directory
+	fn existing_records(&self, key: Option<&[u8]>) -> Result<IntegrityRecords, Error> {
+		let mut records = IntegrityRecords::new();
+		for (path, account) in self.files()? {
+			let filename = account.filename.clone().expect("accounts loaded from disk have filenames; qed");
+			records.insert(filename, Self::record_of(&path, &account, key)?);
+		}
+		Ok(records)
+	}
+
+	/// key authenticating file contents if verification is enabled, generated on first use.
+	/// When the key is generated, contents of existing key files are trusted and recorded in `records`.
+	fn contents_key(&self, records: &mut IntegrityRecords) -> Result<Option<Vec<u8>>, Error> {
+		if !self.verify_on_load {
+			return Ok(None);
+		}
+
+		let path = self.path.join(HMAC_KEY_FILE_NAME);
+		if let Ok(key) = read_raw(&path) {
+			return String::from_utf8(key).ok()
+				.and_then(|key| key.trim().from_hex().ok())
+				.map(Some)
+				.ok_or_else(|| Error::Custom(format!("Invalid key file contents HMAC key in {:?}", path)));
+		}
+
+		let key: [u8; 32] = Random::random();
+		for (filename, record) in self.existing_records(Some(&key))? {
+			warn!("Recording hash of key file {:?} without prior verification", self.path.join(&filename));
+			records.insert(filename, record);
+		}
+		// records are saved before the key, so a crash can't leave key files without a recorded hash
+		self.save_records(records);
+
+		fs::File::create(&path)?.write_all(key.to_hex().as_bytes())?;
+		if let Err(_) = restrict_permissions_to_owner(&path) {
+			return Err(Error::Io(io::Error::last_os_error()));
+		}
+		Ok(Some(key.to_vec()))
+	}
+
+	/// HMAC of the key file contents
+	fn file_mac(key: &[u8], path: &Path) -> Result<String, Error> {
+		let mut hmac = Hmac::new(Sha256::new(), key);
+		hmac.input(&read_raw(path)?);
+		Ok(hmac.result().code().to_hex())
+	}
+
+	/// recorded integrity of key files along with the key authenticating their contents.
+	/// When nothing is recorded yet, checksums of existing key files are recorded first.
+	/// Must be called with `integrity_lock` held.
+	fn current_records(&self) -> Result<(IntegrityRecords, Option<Vec<u8>>), Error> {
+		let mut records = match self.integrity_path().exists() {
+			true => self.records(),
+			false => self.existing_records(None)?,
+		};
+		let key = self.contents_key(&mut records)?;
+		Ok((records, key))
+	}
+
+	/// apply `f` to recorded integrity of key files
+	fn update_records<F>(&self, f: F) -> Result<(), Error> where F: FnOnce(&mut IntegrityRecords, Option<&[u8]>) -> Result<(), Error> {
+		let _lock = self.integrity_lock.lock();
+		let (mut records, key) = self.current_records()?;
+		f(&mut records, key.as_ref().map(|key| &key[..]))?;
+		self.save_records(&records);
+		Ok(())
+	}
+
+	/// record integrity of given key files
+	fn record_key_files(&self, accounts: &[SafeAccount]) -> Result<(), Error> {
+		self.update_records(|records, key| {
+			for account in accounts {
+				let filename = account.filename.clone().expect("accounts written to disk have filenames; qed");
+				let record = Self::record_of(&self.path.join(&filename), account, key)?;
+				records.insert(filename, record);
+			}
+			Ok(())
+		})
+	}
+
+	/// drop accounts whose key files were modified or added outside of this directory.
+	fn verified(&self, files: HashMap<PathBuf, SafeAccount>) -> Result<Vec<SafeAccount>, Error> {
+		let (records, key) = {
+			let _lock = self.integrity_lock.lock();
+			self.current_records()?
+		};
+		let key = key.expect("verification is enabled, so the key is always returned; qed");
+
+		let mut accounts = Vec::with_capacity(files.len());
+		for (path, account) in files {
+			let filename = account.filename.clone().expect("accounts loaded from disk have filenames; qed");
+			let mac = Self::file_mac(&key, &path)?;
+			match records.get(&filename).and_then(|record| record.get(MAC_FIELD)) {
+				Some(expected) if *expected == mac => accounts.push(account),
+				Some(_) => warn!("Key file {:?} does not match its recorded hash, ignoring it", path),
+				None => warn!("Key file {:?} has no recorded hash, ignoring it", path),
+			}
+		}
+		Ok(accounts)
+	}
+
+	/// Record integrity of all key files from scratch, e.g. after they have been re-encrypted.
+	pub fn rebuild_integrity(&self) -> Result<(), Error> {
+		let _lock = self.integrity_lock.lock();
+		let key = self.contents_key(&mut IntegrityRecords::new())?;
+		let records = self.existing_records(key.as_ref().map(|key| &key[..]))?;
+		self.save_records(&records);
+		Ok(())
+	}
+
 	/// insert account with given file name
 	pub fn insert_with_filename(&self, account: SafeAccount, filename: String) -> Result<SafeAccount, Error> {
+		let account = self.write_key_file(account, filename)?;
+		self.record_key_files(&[account.clone()])?;
+		Ok(account)
+	}
+
+	/// write key file with given name, without recording its checksum
+	fn write_key_file(&self, account: SafeAccount, filename: String) -> Result<SafeAccount, Error> {
 		// update account filename
 		let original_account = account.clone();
 		let mut account = account;
 		account.filename = Some(filename.clone());
 
-		{
-			// Path to keyfile
-			let mut keyfile_path = self.path.clone();
-			keyfile_path.push(filename.as_str());
-
-			// save the file
-			let mut file = fs::File::create(&keyfile_path)?;
-			if let Err(err) = self.key_manager.write(original_account, &mut file).map_err(|e| Error::Custom(format!("{:?}", e))) {
-				drop(file);
-				fs::remove_file(keyfile_path).expect("Expected to remove recently created file");
-				return Err(err);
-			}
+		// Path to keyfile
+		let mut keyfile_path = self.path.clone();
+		keyfile_path.push(filename.as_str());
 
-			if let Err(_) = restrict_permissions_to_owner(keyfile_path.as_path()) {
-				drop(file);
-				fs::remove_file(keyfile_path).expect("Expected to remove recently created file");
-				return Err(Error::Io(io::Error::last_os_error()));
-			}
+		// a crash can't leave a partially written key file behind
+		let temp_path = self.stage_key_file(original_account)?;
+		if let Err(err) = fs::rename(&temp_path, &keyfile_path) {
+			let _ = fs::remove_file(&temp_path);
+			return Err(err.into());
 		}
 
 		Ok(account)
 	}
 
+	/// write key file to a temporary path, returning that path
+	fn stage_key_file(&self, account: SafeAccount) -> Result<PathBuf, Error> {
+		let temp_path = self.path.join(format!("{}{}", TEMP_FILE_PREFIX, Uuid::from(account.id)));
+
+		let mut file = fs::File::create(&temp_path)?;
+		if let Err(err) = self.key_manager.write(account, &mut file).map_err(|e| Error::Custom(format!("{:?}", e))) {
+			drop(file);
+			fs::remove_file(temp_path).expect("Expected to remove recently created file");
+			return Err(err);
+		}
+
+		if let Err(_) = restrict_permissions_to_owner(temp_path.as_path()) {
+			drop(file);
+			fs::remove_file(temp_path).expect("Expected to remove recently created file");
+			return Err(Error::Io(io::Error::last_os_error()));
+		}
+
+		if let Err(err) = file.sync_all() {
+			drop(file);
+			fs::remove_file(temp_path).expect("Expected to remove recently created file");
+			return Err(err.into());
+		}
+
+		Ok(temp_path)
+	}
+
+	/// remove staged key files left behind by a crash
+	fn remove_stale_temp_files(&self) -> Result<(), Error> {
+		for entry in fs::read_dir(&self.path)?.flat_map(Result::ok) {
+			let is_temp = entry.file_name().to_str().map_or(false, |name| name.starts_with(TEMP_FILE_PREFIX));
+			let is_stale = entry.metadata().ok()
+				.and_then(|metadata| metadata.modified().ok())
+				.and_then(|modified| SystemTime::now().duration_since(modified).ok())
+				.map_or(false, |age| age.as_secs() >= STALE_TEMP_FILE_SECS);
+			if is_temp && is_stale {
+				warn!("Removing key file left behind by an interrupted write: {:?}", entry.path());
+				if let Err(err) = fs::remove_file(entry.path()) {
+					warn!("Unable to remove {:?}: {}", entry.path(), err);
+				}
+			}
+		}
+		Ok(())
+	}
+
 	/// Get key file manager referece
 	pub fn key_manager(&self) -> &T {
 		&self.key_manager
 	}
+
+	/// insert account, picking a new UUID with `generate` if the current one is already used
+	fn insert_unique<F>(&self, account: SafeAccount, generate: F) -> Result<SafeAccount, Error> where F: FnMut() -> [u8; 16] {
+		let files = self.files()?;
+		self.check_capacity(files.len(), 1)?;
+
+		let used = files.values().map(|existing| existing.id).collect();
+		let mut account = account;
+		account.id = unique_id(&used, account.id, generate)?;
+
+		// build file path
+		let filename = account.filename.as_ref().cloned().unwrap_or_else(|| key_file_name(&account));
+
+		self.insert_with_filename(account, filename)
+	}
+
+	/// Assign new UUIDs to key files sharing one with another key file.
+	/// Returns the number of repaired files.
+	pub fn deduplicate_uuids(&self) -> Result<u32, Error> {
+		let mut files: Vec<_> = self.files()?.into_iter().collect();
+		// the oldest file keeps its UUID
+		files.sort_by(|a, b| a.0.cmp(&b.0));
+
+		let mut used = HashSet::new();
+		let mut duplicates = Vec::new();
+		for (_, account) in files {
+			if !used.insert(account.id) {
+				duplicates.push(account);
+			}
+		}
+
+		let mut repaired = 0;
+		for account in duplicates {
+			let mut account = account;
+			account.id = unique_id(&used, account.id, <[u8; 16] as Random>::random)?;
+			used.insert(account.id);
+			let filename = account.filename.clone().expect("accounts loaded from disk have filenames; qed");
+			self.insert_with_filename(account, filename)?;
+			repaired += 1;
+		}
+		Ok(repaired)
+	}
 }
 
-impl<T> KeyDirectory for DiskDirectory<T> where T: KeyFileManager {
+impl<T> DiskDirectory<T> where T: KeyFileManager + 'static {
+	/// Start a thread scanning the directory for key files changed by other processes
+	fn spawn_watcher(&self) -> Result<Receiver<DirChangeEvent>, Error> {
+		let (tx, rx) = mpsc::channel();
+		let path = self.path.clone();
+		let mut stamps = key_file_stamps(&path)?;
+		let mut files = self.files()?;
+		// the thread stops once the directory is dropped
+		let key_manager = Arc::downgrade(&self.key_manager);
+		thread::Builder::new().name("keys_watcher".into()).spawn(move || {
+			loop {
+				thread::sleep(Duration::from_millis(WATCH_INTERVAL_MS));
+				let key_manager = match key_manager.upgrade() {
+					Some(key_manager) => key_manager,
+					None => return,
+				};
+				let current = match key_file_stamps(&path) {
+					Ok(current) => current,
+					Err(err) => {
+						warn!("Unable to scan keys directory {:?}: {}", path, err);
+						continue;
+					},
+				};
+
+				for removed in stamps.keys().filter(|path| !current.contains_key(*path)) {
+					// receiver is gone
+					if Self::file_removed(&mut files, removed, &tx).is_err() {
+						return;
+					}
+				}
+				for (changed, stamp) in &current {
+					if stamps.get(changed) != Some(stamp) && Self::file_changed(&*key_manager, &mut files, changed.clone(), &tx).is_err() {
+						return;
+					}
+				}
+				stamps = current;
+			}
+		})?;
+
+		Ok(rx)
+	}
+
+	fn file_changed(key_manager: &T, files: &mut HashMap<PathBuf, SafeAccount>, path: PathBuf, tx: &Sender<DirChangeEvent>) -> Result<(), mpsc::SendError<DirChangeEvent>> {
+		if !is_key_file(&path) || path.is_dir() {
+			return Ok(());
+		}
+		let account = match Self::read_file(key_manager, &path) {
+			Some(account) => account,
+			None => return Ok(()),
+		};
+		match files.insert(path, account.clone()) {
+			Some(ref old) if *old == account => Ok(()),
+			Some(old) => tx.send(DirChangeEvent::Removed(old)).and_then(|_| tx.send(DirChangeEvent::Inserted(account))),
+			None => tx.send(DirChangeEvent::Inserted(account)),
+		}
+	}
+
+	fn file_removed(files: &mut HashMap<PathBuf, SafeAccount>, path: &Path, tx: &Sender<DirChangeEvent>) -> Result<(), mpsc::SendError<DirChangeEvent>> {
+		match files.remove(path) {
+			Some(account) => tx.send(DirChangeEvent::Removed(account)),
+			None => Ok(()),
+		}
+	}
+}
+
+impl<T> KeyDirectory for DiskDirectory<T> where T: KeyFileManager + 'static {
 	fn load(&self) -> Result<Vec<SafeAccount>, Error> {
-		let accounts = self.files()?
+		self.remove_stale_temp_files()?;
+		let files = self.files()?;
+		if self.verify_on_load {
+			return self.verified(files);
+		}
+
+		let accounts = files
 			.into_iter()
 			.map(|(_, account)| account)
 			.collect();
@@ -175,17 +663,47 @@ impl<T> KeyDirectory for DiskDirectory<T> where T: KeyFileManager {
 
 	fn update(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
 		// Disk store handles updates correctly iff filename is the same
-		self.insert(account)
+		let filename = account.filename.as_ref().cloned().unwrap_or_else(|| key_file_name(&account));
+		self.insert_with_filename(account, filename)
 	}
 
 	fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
-		// build file path
-		let filename = account.filename.as_ref().cloned().unwrap_or_else(|| {
-			let timestamp = time::strftime("%Y-%m-%dT%H-%M-%S", &time::now_utc()).expect("Time-format string is valid.");
-			format!("UTC--{}Z--{}", timestamp, Uuid::from(account.id))
-		});
+		self.insert_unique(account, <[u8; 16] as Random>::random)
+	}
 
-		self.insert_with_filename(account, filename)
+	fn insert_batch(&self, accounts: Vec<SafeAccount>) -> Result<Vec<SafeAccount>, Error> {
+		let files = self.files()?;
+		self.check_capacity(files.len(), accounts.len())?;
+
+		let mut used: HashSet<_> = files.values().map(|existing| existing.id).collect();
+		let mut unique = Vec::with_capacity(accounts.len());
+		for mut account in accounts {
+			account.id = unique_id(&used, account.id, <[u8; 16] as Random>::random)?;
+			used.insert(account.id);
+			unique.push(account);
+		}
+
+		let mut inserted: Vec<SafeAccount> = Vec::with_capacity(unique.len());
+		for account in unique {
+			let filename = account.filename.as_ref().cloned().unwrap_or_else(|| key_file_name(&account));
+			match self.write_key_file(account, filename) {
+				Ok(account) => inserted.push(account),
+				Err(err) => {
+					for account in &inserted {
+						let filename = account.filename.as_ref().expect("filename is set by write_key_file; qed");
+						if let Err(e) = fs::remove_file(self.path.join(filename)) {
+							warn!("Unable to remove key file {} of a failed batch: {}", filename, e);
+						}
+					}
+					return Err(err);
+				},
+			}
+		}
+
+		self.record_key_files(&inserted)?;
+
+		sync_directory(&self.path)?;
+		Ok(inserted)
 	}
 
 	fn remove(&self, account: &SafeAccount) -> Result<(), Error> {
@@ -198,15 +716,77 @@ impl<T> KeyDirectory for DiskDirectory<T> where T: KeyFileManager {
 		// remove it
 		match to_remove {
 			None => Err(Error::InvalidAccount),
-			Some((path, _)) => fs::remove_file(path).map_err(From::from)
+			Some((path, _)) => {
+				if self.secure_delete {
+					secure_remove_file(&path)?;
+				} else {
+					fs::remove_file(&path)?;
+				}
+				match path.file_name().and_then(|n| n.to_str()) {
+					Some(filename) => self.update_records(|records, _| {
+						records.remove(filename);
+						Ok(())
+					}),
+					None => Ok(()),
+				}
+			},
 		}
 	}
 
+	fn verify_integrity(&self) -> Result<Vec<IntegrityError>, Error> {
+		let records = self.records();
+		let mut errors = Vec::new();
+		let mut paths = fs::read_dir(&self.path)?
+			.flat_map(Result::ok)
+			.filter(|entry| entry.metadata().map_or(false, |m| !m.is_dir()) && is_key_file(&entry.path()))
+			.map(|entry| entry.path())
+			.collect::<Vec<_>>();
+		paths.sort();
+
+		for path in paths {
+			let mut contents = Vec::new();
+			if fs::File::open(&path).and_then(|mut file| file.read_to_end(&mut contents)).is_err() {
+				errors.push(IntegrityError::UnreadableFile(path));
+				continue;
+			}
+
+			let filename = path.file_name().and_then(|n| n.to_str()).map(|n| n.to_owned());
+			let account = match self.key_manager.read(filename.clone(), &contents[..]) {
+				Ok(account) => account,
+				Err(_) => {
+					errors.push(IntegrityError::CorruptJson(path));
+					continue;
+				},
+			};
+
+			// files written by other software have no recorded checksum
+			let recorded = filename
+				.and_then(|filename| records.get(&filename))
+				.and_then(|record| record.get(CHECKSUM_FIELD))
+				.cloned();
+			if recorded.map_or(false, |checksum| checksum != key_checksum(&account)) {
+				errors.push(IntegrityError::MacMismatch(account.address));
+			}
+		}
+
+		Ok(errors)
+	}
+
 	fn path(&self) -> Option<&PathBuf> { Some(&self.path) }
 
 	fn as_vault_provider(&self) -> Option<&VaultKeyDirectoryProvider> {
 		Some(self)
 	}
+
+	fn watch(&self) -> Option<Receiver<DirChangeEvent>> {
+		match self.spawn_watcher() {
+			Ok(rx) => Some(rx),
+			Err(err) => {
+				warn!("Unable to watch keys directory {:?}: {}", self.path, err);
+				None
+			},
+		}
+	}
 }
 
 impl<T> VaultKeyDirectoryProvider for DiskDirectory<T> where T: KeyFileManager {
@@ -238,6 +818,41 @@ impl<T> VaultKeyDirectoryProvider for DiskDirectory<T> where T: KeyFileManager {
 	fn vault_meta(&self, name: &str) -> Result<String, Error> {
 		VaultDiskDirectory::meta_at(&self.path, name)
 	}
+
+	fn vault_accounts_count(&self, name: &str) -> Result<usize, Error> {
+		let vault_path = self.path.join(name);
+		if !vault_path.join(VAULT_FILE_NAME).is_file() {
+			return Err(Error::VaultNotFound);
+		}
+
+		Ok(fs::read_dir(&vault_path)?
+			.flat_map(Result::ok)
+			.filter(|entry| entry.metadata().ok().map_or(false, |m| !m.is_dir()) && is_key_file(&entry.path()))
+			.count())
+	}
+
+	fn export_to_zip(&self, password: &str) -> Result<Vec<u8>, Error> {
+		let mut files = Vec::new();
+		for path in self.files()?.keys() {
+			let filename = path.file_name().and_then(|n| n.to_str()).expect("Keys have valid UTF8 names only.");
+			files.push((filename.to_owned(), read_raw(path)?));
+		}
+
+		// vault files are copied as is, encrypted with their vault key
+		for vault in self.list_vaults()? {
+			let vault_path = self.path.join(&vault);
+			for entry in fs::read_dir(&vault_path)?.flat_map(Result::ok) {
+				let path = entry.path();
+				let filename = match path.file_name().and_then(|n| n.to_str()) {
+					Some(filename) if filename == VAULT_FILE_NAME || is_key_file(&path) => filename.to_owned(),
+					_ => continue,
+				};
+				files.push((format!("{}/{}", vault, filename), read_raw(&path)?));
+			}
+		}
+
+		write_zip(files, password)
+	}
 }
 
 impl KeyFileManager for DiskKeyFileManager {
@@ -260,11 +875,15 @@ impl KeyFileManager for DiskKeyFileManager {
 #[cfg(test)]
 mod test {
 	use std::{env, fs};
-	use super::RootDiskDirectory;
-	use dir::{KeyDirectory, VaultKey};
+	use std::io::{Read, Write};
+	use std::time::Duration;
+	use super::{RootDiskDirectory, overwrite_file, DIRECTORY_SYNCS};
+	use dir::{KeyDirectory, VaultKey, DirChangeEvent, IntegrityError};
 	use account::SafeAccount;
+	use Error;
 	use ethkey::{Random, Generator};
 	use devtools::RandomTempPath;
+	use time;
 
 	#[test]
 	fn should_create_new_account() {
@@ -288,6 +907,175 @@ mod test {
 		let _ = fs::remove_dir_all(dir);
 	}
 
+	#[test]
+	fn should_not_insert_account_with_used_uuid() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let account = SafeAccount::create(&Random.generate().unwrap(), [1u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let other = SafeAccount::create(&Random.generate().unwrap(), [1u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		directory.insert(account).unwrap();
+
+		// when
+		let res = directory.insert_unique(other.clone(), || [1u8; 16]);
+
+		// then
+		match res {
+			Err(Error::DuplicateUuid) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(directory.load().unwrap().len(), 1);
+
+		// and when
+		let inserted = directory.insert(other).unwrap();
+
+		// then
+		assert!(inserted.id != [1u8; 16]);
+		assert_eq!(directory.load().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn should_deduplicate_uuids() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		for (i, filename) in ["a", "b", "c"].iter().enumerate() {
+			let id = if i == 2 { [2u8; 16] } else { [1u8; 16] };
+			let account = SafeAccount::create(&Random.generate().unwrap(), id, "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+			directory.insert_with_filename(account, filename.to_string()).unwrap();
+		}
+
+		// when
+		let repaired = directory.deduplicate_uuids().unwrap();
+
+		// then
+		assert_eq!(repaired, 1);
+		let mut ids: Vec<_> = directory.load().unwrap().into_iter().map(|account| account.id).collect();
+		ids.sort();
+		ids.dedup();
+		assert_eq!(ids.len(), 3);
+		assert!(directory.verify_integrity().unwrap().is_empty());
+		assert_eq!(directory.deduplicate_uuids().unwrap(), 0);
+	}
+
+	#[test]
+	fn should_not_insert_accounts_over_limit() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap().with_max_accounts(Some(2));
+		let create = || SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		directory.insert(create()).unwrap();
+		directory.insert(create()).unwrap();
+		let files_before = fs::read_dir(&temp_path).unwrap().count();
+
+		// when
+		let res = directory.insert(create());
+
+		// then
+		match res {
+			Err(Error::DirectoryFull) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(fs::read_dir(&temp_path).unwrap().count(), files_before);
+		assert_eq!(directory.load().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn should_check_accounts_limit_before_inserting_batch() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap().with_max_accounts(Some(2));
+		let create = || SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		directory.insert(create()).unwrap();
+
+		// when
+		let res = directory.insert_batch(vec![create(), create()]);
+
+		// then
+		match res {
+			Err(Error::DirectoryFull) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(directory.load().unwrap().len(), 1);
+	}
+
+	#[test]
+	fn should_insert_batch_with_single_directory_sync() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let accounts: Vec<_> = (0..50)
+			.map(|_| SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned()))
+			.collect();
+		let syncs_before = DIRECTORY_SYNCS.with(|syncs| syncs.get());
+
+		// when
+		let inserted = directory.insert_batch(accounts).unwrap();
+
+		// then
+		assert_eq!(DIRECTORY_SYNCS.with(|syncs| syncs.get()) - syncs_before, 1);
+		assert_eq!(inserted.len(), 50);
+		assert!(inserted.iter().all(|account| account.filename.is_some()));
+		assert_eq!(directory.load().unwrap().len(), 50);
+		assert!(directory.verify_integrity().unwrap().is_empty());
+	}
+
+	#[test]
+	fn should_detect_corrupted_key_files() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let keypair = Random.generate().unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let account = directory.insert(account).unwrap();
+		assert_eq!(directory.verify_integrity().unwrap(), vec![]);
+
+		// when
+		let key_path = temp_path.as_path().join(account.filename.unwrap());
+		let mut contents = String::new();
+		fs::File::open(&key_path).unwrap().read_to_string(&mut contents).unwrap();
+		let position = contents.find("\"ciphertext\":\"").unwrap() + 14;
+		let flipped = if &contents[position..position + 1] == "0" { "1" } else { "0" };
+		let contents = format!("{}{}{}", &contents[..position], flipped, &contents[position + 1..]);
+		fs::File::create(&key_path).unwrap().write_all(contents.as_bytes()).unwrap();
+		fs::File::create(temp_path.as_path().join("broken")).unwrap().write_all(b"{").unwrap();
+
+		// then
+		assert_eq!(directory.verify_integrity().unwrap(), vec![
+			IntegrityError::MacMismatch(keypair.address()),
+			IntegrityError::CorruptJson(temp_path.as_path().join("broken")),
+		]);
+	}
+
+	#[test]
+	fn should_notify_about_external_changes() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let events = directory.watch().unwrap();
+		let keypair = Random.generate().unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+
+		// when
+		let other = RootDiskDirectory::at(&temp_path);
+		let inserted = other.insert(account).unwrap();
+
+		// then
+		match events.recv_timeout(Duration::from_secs(2)) {
+			Ok(DirChangeEvent::Inserted(account)) => assert_eq!(account, inserted),
+			other => panic!("Unexpected event: {:?}", other),
+		}
+
+		// and when
+		other.remove(&inserted).unwrap();
+
+		// then
+		match events.recv_timeout(Duration::from_secs(2)) {
+			Ok(DirChangeEvent::Removed(account)) => assert_eq!(account, inserted),
+			other => panic!("Unexpected event: {:?}", other),
+		}
+	}
+
 	#[test]
 	fn should_manage_vaults() {
 		// given
@@ -336,4 +1124,178 @@ mod test {
 		assert!(vaults.iter().any(|v| &*v == "vault1"));
 		assert!(vaults.iter().any(|v| &*v == "vault2"));
 	}
+
+	#[test]
+	fn should_ignore_modified_key_files_when_verifying() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap().with_verify_on_load(true);
+		let account = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let account = directory.insert(account).unwrap();
+		let other = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		directory.insert(other).unwrap();
+		assert_eq!(directory.load().unwrap().len(), 2);
+		assert!(temp_path.as_path().join(".keystore_integrity").exists());
+		let hidden: Vec<_> = fs::read_dir(&temp_path).unwrap()
+			.map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+			.filter(|name| name.starts_with("."))
+			.collect();
+		assert_eq!(hidden.len(), 2, "Only the integrity records and their key are kept next to key files: {:?}", hidden);
+
+		// when
+		let path = temp_path.as_path().join(account.filename.clone().unwrap());
+		fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b" ").unwrap();
+
+		// then
+		let loaded = directory.load().unwrap();
+		assert_eq!(loaded.len(), 1);
+		assert!(!loaded.contains(&account));
+		assert_eq!(RootDiskDirectory::at(&temp_path).load().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn should_ignore_unrecorded_key_files_when_verifying() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let unverified = RootDiskDirectory::create(&temp_path).unwrap();
+		let existing = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let existing = unverified.insert(existing).unwrap();
+		let directory = RootDiskDirectory::at(&temp_path).with_verify_on_load(true);
+		assert_eq!(directory.load().unwrap(), vec![existing.clone()]);
+
+		// when
+		let planted = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		unverified.insert(planted).unwrap();
+
+		// then
+		assert_eq!(directory.load().unwrap(), vec![existing]);
+		assert_eq!(unverified.load().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn should_keep_existing_key_files_when_inserting_before_first_verified_load() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let unverified = RootDiskDirectory::create(&temp_path).unwrap();
+		let existing = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let existing = unverified.insert(existing).unwrap();
+		let directory = RootDiskDirectory::at(&temp_path).with_verify_on_load(true);
+
+		// when
+		let inserted = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let inserted = directory.insert(inserted).unwrap();
+
+		// then
+		let loaded = directory.load().unwrap();
+		assert_eq!(loaded.len(), 2);
+		assert!(loaded.contains(&existing));
+		assert!(loaded.contains(&inserted));
+	}
+
+	#[test]
+	fn should_record_checksums_of_concurrent_inserts() {
+		use std::sync::Arc;
+		use std::thread;
+		use super::{INTEGRITY_FILE_NAME, CHECKSUM_FIELD, IntegrityRecords, key_checksum};
+
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = Arc::new(RootDiskDirectory::create(&temp_path).unwrap());
+
+		// when
+		let threads: Vec<_> = (0..4u8).map(|i| {
+			let directory = directory.clone();
+			thread::spawn(move || for j in 0..5u8 {
+				// distinct UUIDs, so that no insert is renamed or rejected
+				let id = [i * 5 + j + 1; 16];
+				let account = SafeAccount::create(&Random.generate().unwrap(), id, "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+				directory.insert(account).unwrap();
+			})
+		}).collect();
+		for thread in threads {
+			thread.join().unwrap();
+		}
+
+		// then
+		let records: IntegrityRecords = ::serde_json::from_reader(
+			fs::File::open(temp_path.as_path().join(INTEGRITY_FILE_NAME)).unwrap()
+		).unwrap();
+		let accounts = directory.load().unwrap();
+		assert_eq!(accounts.len(), 20);
+		assert_eq!(records.len(), 20);
+		for account in accounts {
+			assert_eq!(records[account.filename.as_ref().unwrap()][CHECKSUM_FIELD], key_checksum(&account));
+		}
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn should_remove_key_files_left_by_interrupted_writes() {
+		use std::ffi::CString;
+		use std::os::unix::ffi::OsStrExt;
+		use libc;
+
+		// given
+		let temp_path = RandomTempPath::new();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap();
+		let account = SafeAccount::create(&Random.generate().unwrap(), [1u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let account = directory.insert(account).unwrap();
+
+		// when
+		// crash after the key file has been written, but before it has been moved in place
+		let orphan = SafeAccount::create(&Random.generate().unwrap(), [2u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let staged = directory.stage_key_file(orphan).unwrap();
+
+		// then
+		assert_eq!(directory.load().unwrap(), vec![account.clone()]);
+		assert!(staged.exists(), "Staged file might belong to a write in progress.");
+
+		// and when
+		let path = CString::new(staged.as_os_str().as_bytes()).unwrap();
+		let ten_minutes_ago = libc::utimbuf {
+			actime: 0,
+			modtime: (time::get_time().sec - 600) as libc::time_t,
+		};
+		assert_eq!(unsafe { libc::utime(path.as_ptr(), &ten_minutes_ago) }, 0);
+
+		// then
+		assert_eq!(directory.load().unwrap(), vec![account]);
+		assert!(!staged.exists());
+	}
+
+	#[test]
+	fn should_securely_remove_account() {
+		// given
+		let temp_path = RandomTempPath::create_dir();
+		let directory = RootDiskDirectory::create(&temp_path).unwrap().with_secure_delete(true);
+		let keypair = Random.generate().unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], "password", 1024, "Test".to_owned(), "{}".to_owned());
+		let account = directory.insert(account).unwrap();
+		let path = temp_path.as_path().join(account.filename.clone().unwrap());
+
+		// when
+		directory.remove(&account).unwrap();
+
+		// then
+		assert!(!path.exists());
+		assert!(directory.load().unwrap().is_empty());
+	}
+
+	#[test]
+	fn should_overwrite_file_contents() {
+		// given
+		let temp_path = RandomTempPath::create_dir();
+		let path = temp_path.as_path().join("key");
+		let original = vec![0x42u8; 512];
+		fs::File::create(&path).unwrap().write_all(&original).unwrap();
+
+		// when
+		overwrite_file(&path).unwrap();
+
+		// then
+		let mut contents = Vec::new();
+		fs::File::open(&path).unwrap().read_to_end(&mut contents).unwrap();
+		assert_eq!(contents.len(), original.len());
+		assert!(contents != original);
+	}
 }
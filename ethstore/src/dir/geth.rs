@@ -88,6 +88,10 @@ impl KeyDirectory for GethDirectory {
 		self.dir.insert(account)
 	}
 
+	fn insert_batch(&self, accounts: Vec<SafeAccount>) -> Result<Vec<SafeAccount>, Error> {
+		self.dir.insert_batch(accounts)
+	}
+
 	fn update(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
 		self.dir.update(account)
 	}
@@ -15,13 +15,21 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::path::{PathBuf};
+use std::sync::mpsc::Receiver;
+use ethkey::Address;
 use {SafeAccount, Error};
 
+mod archive;
 mod disk;
 mod geth;
 mod memory;
 mod parity;
+mod read_only;
 mod vault;
+#[cfg(feature = "s3")]
+mod s3;
+#[cfg(feature = "hashicorp-vault")]
+mod vault_provider;
 
 pub enum DirectoryType {
 	Testnet,
@@ -39,6 +47,26 @@ pub enum SetKeyError {
 	NonFatalNew(Error),
 }
 
+/// Key file change made outside of the directory (e.g. by another process)
+#[derive(Debug, Clone, PartialEq)]
+pub enum DirChangeEvent {
+	/// Key file has been added
+	Inserted(SafeAccount),
+	/// Key file has been removed
+	Removed(SafeAccount),
+}
+
+/// Problem found by `KeyDirectory::verify_integrity`
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityError {
+	/// Key file is not a valid JSON key file
+	CorruptJson(PathBuf),
+	/// Encrypted key doesn't match the checksum recorded when the file was written
+	MacMismatch(Address),
+	/// Key file can't be read
+	UnreadableFile(PathBuf),
+}
+
 /// Vault key
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VaultKey {
@@ -54,6 +82,10 @@ pub trait KeyDirectory: Send + Sync {
 	fn load(&self) -> Result<Vec<SafeAccount>, Error>;
 	/// Insert new key to directory
 	fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error>;
+	/// Insert multiple keys to directory
+	fn insert_batch(&self, accounts: Vec<SafeAccount>) -> Result<Vec<SafeAccount>, Error> {
+		accounts.into_iter().map(|account| self.insert(account)).collect()
+	}
 	//// Update key in directory
 	fn update(&self, account: SafeAccount) -> Result<SafeAccount, Error>;
 	/// Remove key from directory
@@ -62,6 +94,12 @@ pub trait KeyDirectory: Send + Sync {
 	fn path(&self) -> Option<&PathBuf> { None }
 	/// Return vault provider, if available
 	fn as_vault_provider(&self) -> Option<&VaultKeyDirectoryProvider> { None }
+	/// Watch directory for external changes, if supported
+	fn watch(&self) -> Option<Receiver<DirChangeEvent>> { None }
+	/// Check stored keys for corruption, if supported
+	fn verify_integrity(&self) -> Result<Vec<IntegrityError>, Error> { Ok(Vec::new()) }
+	/// Wrap the directory so that it refuses all modifications
+	fn read_only(self) -> ReadOnlyDirectory where Self: Sized + 'static { ReadOnlyDirectory::new(Box::new(self)) }
 }
 
 /// Vaults provider
@@ -74,6 +112,10 @@ pub trait VaultKeyDirectoryProvider {
 	fn list_vaults(&self) -> Result<Vec<String>, Error>;
 	/// Get vault meta
 	fn vault_meta(&self, name: &str) -> Result<String, Error>;
+	/// Count key files in the vault without opening it
+	fn vault_accounts_count(&self, name: &str) -> Result<usize, Error>;
+	/// Pack all key files, including vaults, into a ZIP backup with WinZip AES-256 encrypted entries
+	fn export_to_zip(&self, password: &str) -> Result<Vec<u8>, Error>;
 }
 
 /// Vault directory
@@ -86,17 +128,25 @@ pub trait VaultKeyDirectory: KeyDirectory {
 	fn key(&self) -> VaultKey;
 	/// Set new key for vault
 	fn set_key(&self, key: VaultKey) -> Result<(), SetKeyError>;
+	/// Re-encrypt all vault accounts with new key, checking that `old_key` is the current one
+	fn rotate_key(&self, old_key: VaultKey, new_key: VaultKey) -> Result<(), SetKeyError>;
 	/// Get vault meta
 	fn meta(&self) -> String;
 	/// Set vault meta
 	fn set_meta(&self, meta: &str) -> Result<(), Error>;
 }
 
+pub use self::archive::import_from_zip;
 pub use self::disk::RootDiskDirectory;
 pub use self::geth::GethDirectory;
 pub use self::memory::MemoryDirectory;
 pub use self::parity::ParityDirectory;
+pub use self::read_only::ReadOnlyDirectory;
 pub use self::vault::VaultDiskDirectory;
+#[cfg(feature = "s3")]
+pub use self::s3::{S3KeyDirectory, S3Credentials};
+#[cfg(feature = "hashicorp-vault")]
+pub use self::vault_provider::{HashiCorpVaultDirectory, VaultAuth};
 
 impl VaultKey {
 	/// Create new vault key
@@ -67,6 +67,10 @@ impl KeyDirectory for ParityDirectory {
 		self.dir.insert(account)
 	}
 
+	fn insert_batch(&self, accounts: Vec<SafeAccount>) -> Result<Vec<SafeAccount>, Error> {
+		self.dir.insert_batch(accounts)
+	}
+
 	fn update(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
 		self.dir.update(account)
 	}
@@ -0,0 +1,111 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use {SafeAccount, Error};
+use super::{KeyDirectory, DirChangeEvent, IntegrityError};
+
+/// Keys directory refusing all modifications
+pub struct ReadOnlyDirectory {
+	inner: Box<KeyDirectory>,
+}
+
+impl ReadOnlyDirectory {
+	/// Wrap given directory
+	pub fn new(inner: Box<KeyDirectory>) -> Self {
+		ReadOnlyDirectory {
+			inner: inner,
+		}
+	}
+}
+
+impl KeyDirectory for ReadOnlyDirectory {
+	fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+		self.inner.load()
+	}
+
+	fn insert(&self, _account: SafeAccount) -> Result<SafeAccount, Error> {
+		Err(Error::PermissionDenied)
+	}
+
+	fn update(&self, _account: SafeAccount) -> Result<SafeAccount, Error> {
+		Err(Error::PermissionDenied)
+	}
+
+	fn remove(&self, _account: &SafeAccount) -> Result<(), Error> {
+		Err(Error::PermissionDenied)
+	}
+
+	fn path(&self) -> Option<&PathBuf> {
+		self.inner.path()
+	}
+
+	fn watch(&self) -> Option<Receiver<DirChangeEvent>> {
+		self.inner.watch()
+	}
+
+	fn verify_integrity(&self) -> Result<Vec<IntegrityError>, Error> {
+		self.inner.verify_integrity()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use dir::{KeyDirectory, MemoryDirectory};
+	use account::SafeAccount;
+	use ethkey::{Random, Generator};
+	use secret_store::{SimpleSecretStore, SecretVaultRef};
+	use {EthStore, Error};
+
+	#[test]
+	fn should_refuse_modifications() {
+		// given
+		let keypair = Random.generate().unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+		let inner = MemoryDirectory::default();
+		inner.insert(account.clone()).unwrap();
+		let directory = inner.read_only();
+
+		// then
+		assert_eq!(directory.load().unwrap(), vec![account.clone()]);
+		match directory.insert(account.clone()) {
+			Err(Error::PermissionDenied) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match directory.update(account.clone()) {
+			Err(Error::PermissionDenied) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match directory.remove(&account) {
+			Err(Error::PermissionDenied) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(directory.as_vault_provider().is_none());
+		assert_eq!(directory.load().unwrap().len(), 1);
+	}
+
+	#[test]
+	fn should_not_create_accounts_in_read_only_store() {
+		let store = EthStore::open(Box::new(MemoryDirectory::default().read_only())).unwrap();
+		let keypair = Random.generate().unwrap();
+		match store.insert_account(SecretVaultRef::Root, keypair.secret().clone(), "test") {
+			Err(Error::PermissionDenied) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(store.accounts().unwrap().is_empty());
+	}
+}
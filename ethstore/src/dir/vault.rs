@@ -15,8 +15,11 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{fs, io};
+use std::io::Read;
 use std::path::{PathBuf, Path};
 use parking_lot::Mutex;
+use serde_json;
+use serde_json::value::Value;
 use {json, SafeAccount, Error};
 use util::sha3::Hashable;
 use super::super::account::Crypto;
@@ -27,6 +30,8 @@ use super::disk::{DiskDirectory, KeyFileManager};
 pub const VAULT_FILE_NAME: &'static str = "vault.json";
 /// Name of temporary vault metadata file
 pub const VAULT_TEMP_FILE_NAME: &'static str = "vault_temp.json";
+/// Current version of vault metadata file format
+pub const VAULT_FILE_VERSION: u32 = 1;
 
 /// Vault directory implementation
 pub type VaultDiskDirectory = DiskDirectory<VaultKeyFileManager>;
@@ -63,7 +68,7 @@ impl VaultDiskDirectory {
 		// check that vault directory exists
 		let vault_dir_path = make_vault_dir_path(root, name, true)?;
 		if !vault_dir_path.is_dir() {
-			return Err(Error::CreationFailed);
+			return Err(Error::VaultNotFound);
 		}
 
 		// check that passed key matches vault file
@@ -160,7 +165,18 @@ impl VaultKeyDirectory for VaultDiskDirectory {
 		target_path.set_file_name(VAULT_FILE_NAME);
 		fs::rename(source_path, target_path).map_err(|err| SetKeyError::Fatal(err.into()))?;
 
-		temp_vault.delete().map_err(|err| SetKeyError::NonFatalNew(err))
+		// checksums recorded for the old key files don't match re-encrypted ones
+		let rebuilt = self.rebuild_integrity();
+		temp_vault.delete().map_err(|err| SetKeyError::NonFatalNew(err))?;
+		rebuilt.map_err(|err| SetKeyError::NonFatalNew(err))
+	}
+
+	fn rotate_key(&self, old_key: VaultKey, new_key: VaultKey) -> Result<(), SetKeyError> {
+		if old_key.password != self.key().password {
+			return Err(SetKeyError::NonFatalOld(Error::InvalidPassword));
+		}
+
+		self.set_key(new_key)
 	}
 
 	fn meta(&self) -> String {
@@ -220,7 +236,7 @@ fn make_vault_dir_path<P>(root: P, name: &str, check_name: bool) -> Result<PathB
 /// Every vault must have unique name => we rely on filesystem to check this
 /// => vault name must not contain any fs-special characters to avoid directory traversal
 /// => we only allow alphanumeric + separator characters in vault name.
-fn check_vault_name(name: &str) -> bool {
+pub fn check_vault_name(name: &str) -> bool {
 	!name.is_empty()
 	&& name.chars()
 		.all(|c| c.is_alphanumeric()
@@ -242,6 +258,7 @@ fn create_vault_file<P>(vault_dir_path: P, key: &VaultKey, meta: &str) -> Result
 	// => write to temporary file first, then rename temporary file to vault file
 	let mut vault_file = fs::File::create(&temp_vault_file_path)?;
 	let vault_file_contents = json::VaultFile {
+		version: VAULT_FILE_VERSION,
 		crypto: crypto.into(),
 		meta: Some(meta.to_owned()),
 	};
@@ -257,8 +274,10 @@ fn read_vault_file<P>(vault_dir_path: P, key: Option<&VaultKey>) -> Result<Strin
 	let mut vault_file_path: PathBuf = vault_dir_path.as_ref().into();
 	vault_file_path.push(VAULT_FILE_NAME);
 
-	let vault_file = fs::File::open(vault_file_path)?;
-	let vault_file_contents = json::VaultFile::load(vault_file).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+	let mut vault_file = String::new();
+	fs::File::open(vault_file_path)?.read_to_string(&mut vault_file)?;
+	let vault_file = migrate_vault_metadata(&vault_file)?;
+	let vault_file_contents = json::VaultFile::load(vault_file.as_bytes()).map_err(|e| Error::Custom(format!("{:?}", e)))?;
 	let vault_file_meta = vault_file_contents.meta.unwrap_or("{}".to_owned());
 	let vault_file_crypto: Crypto = vault_file_contents.crypto.into();
 
@@ -273,13 +292,60 @@ fn read_vault_file<P>(vault_dir_path: P, key: Option<&VaultKey>) -> Result<Strin
 	Ok(vault_file_meta)
 }
 
+/// Upgrade serialized vault metadata file to the current format version.
+/// Files without `version` field are treated as version 0.
+pub fn migrate_vault_metadata(old: &str) -> Result<String, Error> {
+	let mut vault_file: Value = serde_json::from_str(old).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+	{
+		let vault_obj = vault_file.as_object_mut()
+			.ok_or_else(|| Error::Custom("Vault file is expected to be a JSON object".into()))?;
+		let version = match vault_obj.get("version") {
+			None => 0,
+			Some(&Value::U64(version)) => version,
+			Some(_) => return Err(Error::Custom("Invalid vault file version".into())),
+		};
+
+		if version > VAULT_FILE_VERSION as u64 {
+			return Err(Error::Custom(format!("Unsupported vault file version: {}", version)));
+		}
+
+		if version == 0 {
+			// version 0 => 1: iterations may be stored as a string or under `iterations` key
+			if let Some(kdfparams) = vault_obj.get_mut("crypto")
+				.and_then(|crypto| crypto.as_object_mut())
+				.and_then(|crypto| crypto.get_mut("kdfparams"))
+				.and_then(|kdfparams| kdfparams.as_object_mut()) {
+				if let Some(iterations) = kdfparams.remove("iterations") {
+					if !kdfparams.contains_key("c") {
+						kdfparams.insert("c".to_owned(), iterations);
+					}
+				}
+				let iterations = match kdfparams.get("c") {
+					Some(&Value::String(ref c)) => Some(c.parse::<u64>().map_err(|_| Error::Custom(format!("Invalid vault key iterations: {}", c)))?),
+					_ => None,
+				};
+				if let Some(iterations) = iterations {
+					kdfparams.insert("c".to_owned(), Value::U64(iterations));
+				}
+			}
+			vault_obj.insert("version".to_owned(), Value::U64(1));
+		}
+	}
+
+	serde_json::to_string(&vault_file).map_err(|e| Error::Custom(format!("{:?}", e)))
+}
+
 #[cfg(test)]
 mod test {
 	use std::fs;
 	use std::io::Write;
 	use std::path::PathBuf;
-	use dir::VaultKey;
-	use super::{VAULT_FILE_NAME, check_vault_name, make_vault_dir_path, create_vault_file, read_vault_file, VaultDiskDirectory};
+	use dir::{KeyDirectory, VaultKey, VaultKeyDirectory, SetKeyError};
+	use account::SafeAccount;
+	use ethkey::{Random, Generator};
+	use Error;
+	use json;
+	use super::{VAULT_FILE_NAME, check_vault_name, make_vault_dir_path, create_vault_file, read_vault_file, migrate_vault_metadata, VaultDiskDirectory};
 	use devtools::RandomTempPath;
 
 	#[test]
@@ -382,6 +448,52 @@ mod test {
 		assert!(result.is_err());
 	}
 
+	#[test]
+	fn migrate_vault_metadata_from_version_0() {
+		// given
+		let vault_file_contents = r#"{"crypto":{"cipher":"aes-128-ctr","cipherparams":{"iv":"758696c8dc6378ab9b25bb42790da2f5"},"ciphertext":"54eb50683717d41caaeb12ea969f2c159daada5907383f26f327606a37dc7168","kdf":"pbkdf2","kdfparams":{"iterations":"1024","dklen":32,"prf":"hmac-sha256","salt":"3c320fa566a1a7963ac8df68a19548d27c8f40bf92ef87c84594dcd5bbc402b6"},"mac":"9e5c2314c2a0781962db85611417c614bd6756666b6b1e93840f5b6ed895f003"},"meta":"{}"}"#;
+
+		// when
+		let migrated = migrate_vault_metadata(vault_file_contents).unwrap();
+
+		// then
+		let vault_file = json::VaultFile::load(migrated.as_bytes()).unwrap();
+		assert_eq!(vault_file.version, 1);
+		match vault_file.crypto.kdf {
+			json::Kdf::Pbkdf2(ref params) => assert_eq!(params.c, 1024),
+			_ => panic!("Expected pbkdf2 params"),
+		}
+		assert_eq!(migrate_vault_metadata(&migrated).unwrap(), migrated);
+		assert!(migrate_vault_metadata(r#"{"version":2}"#).is_err());
+	}
+
+	#[test]
+	fn vault_directory_with_version_0_metadata_can_be_opened() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let key = VaultKey::new("password", 1024);
+		let dir: PathBuf = temp_path.as_path().into();
+		let mut vault_file_path = dir.clone();
+		vault_file_path.push("vault");
+		fs::create_dir_all(&vault_file_path).unwrap();
+		vault_file_path.push(VAULT_FILE_NAME);
+		let vault_file_contents = r#"{"crypto":{"cipher":"aes-128-ctr","cipherparams":{"iv":"758696c8dc6378ab9b25bb42790da2f5"},"ciphertext":"54eb50683717d41caaeb12ea969f2c159daada5907383f26f327606a37dc7168","kdf":"pbkdf2","kdfparams":{"c":1024,"dklen":32,"prf":"hmac-sha256","salt":"3c320fa566a1a7963ac8df68a19548d27c8f40bf92ef87c84594dcd5bbc402b6"},"mac":"9e5c2314c2a0781962db85611417c614bd6756666b6b1e93840f5b6ed895f003"},"meta":"{\"description\":\"old\"}"}"#;
+		fs::File::create(&vault_file_path).unwrap().write_all(vault_file_contents.as_bytes()).unwrap();
+
+		// when
+		let vault = VaultDiskDirectory::at(&dir, "vault", key.clone()).unwrap();
+
+		// then
+		assert_eq!(vault.meta(), r#"{"description":"old"}"#);
+
+		// and when
+		vault.set_meta("{}").unwrap();
+
+		// then
+		let vault_file = json::VaultFile::load(fs::File::open(&vault_file_path).unwrap()).unwrap();
+		assert_eq!(vault_file.version, 1);
+	}
+
 	#[test]
 	fn vault_directory_can_be_created() {
 		// given
@@ -432,4 +544,73 @@ mod test {
 		// then
 		assert!(vault.is_err());
 	}
+
+	#[test]
+	fn vault_key_can_be_rotated() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let dir: PathBuf = temp_path.as_path().into();
+		let old_key = VaultKey::new("password1", 1024);
+		let new_key = VaultKey::new("password2", 1024);
+		let vault = VaultDiskDirectory::create(&dir, "vault", old_key.clone()).unwrap();
+		let keypairs = vec![Random.generate().unwrap(), Random.generate().unwrap()];
+		for keypair in &keypairs {
+			let account = SafeAccount::create(keypair, [0u8; 16], "account", 1024, "Test".to_owned(), "{}".to_owned());
+			vault.insert(account).unwrap();
+		}
+
+		// when
+		vault.rotate_key(old_key.clone(), new_key.clone()).unwrap();
+
+		// then
+		assert!(VaultDiskDirectory::at(&dir, "vault", old_key).is_err());
+		let vault = VaultDiskDirectory::at(&dir, "vault", new_key).unwrap();
+		let accounts = vault.load().unwrap();
+		assert_eq!(accounts.len(), keypairs.len());
+		for keypair in &keypairs {
+			let account = accounts.iter().find(|account| account.address == keypair.address()).unwrap();
+			assert_eq!(&account.crypto.secret("account").unwrap(), keypair.secret());
+		}
+	}
+
+	#[test]
+	fn vault_key_rotation_keeps_key_files_verifiable() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let dir: PathBuf = temp_path.as_path().into();
+		let old_key = VaultKey::new("password1", 1024);
+		let new_key = VaultKey::new("password2", 1024);
+		let vault = VaultDiskDirectory::create(&dir, "vault", old_key.clone()).unwrap().with_verify_on_load(true);
+		for _ in 0..2 {
+			let account = SafeAccount::create(&Random.generate().unwrap(), [0u8; 16], "account", 1024, "Test".to_owned(), "{}".to_owned());
+			vault.insert(account).unwrap();
+		}
+
+		// when
+		vault.rotate_key(old_key, new_key.clone()).unwrap();
+
+		// then
+		let vault = VaultDiskDirectory::at(&dir, "vault", new_key).unwrap().with_verify_on_load(true);
+		assert_eq!(vault.load().unwrap().len(), 2);
+		assert!(vault.verify_integrity().unwrap().is_empty());
+	}
+
+	#[test]
+	fn vault_key_is_not_rotated_with_wrong_old_key() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let dir: PathBuf = temp_path.as_path().into();
+		let key = VaultKey::new("password1", 1024);
+		let vault = VaultDiskDirectory::create(&dir, "vault", key.clone()).unwrap();
+
+		// when
+		let result = vault.rotate_key(VaultKey::new("wrong", 1024), VaultKey::new("password2", 1024));
+
+		// then
+		match result {
+			Err(SetKeyError::NonFatalOld(Error::InvalidPassword)) => (),
+			_ => panic!("expected InvalidPassword"),
+		}
+		assert!(VaultDiskDirectory::at(&dir, "vault", key).is_ok());
+	}
 }
@@ -0,0 +1,257 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Key directory stored in HashiCorp Vault KV v2 secrets engine.
+
+use std::{fmt, thread};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::Arc;
+use reqwest::{self, Method, StatusCode};
+use reqwest::header::Headers;
+use serde_json::{self, Value};
+use {SafeAccount, Error};
+use json::Uuid;
+use super::KeyDirectory;
+use super::disk::{KeyFileManager, DiskKeyFileManager};
+
+fn vault_error<E: fmt::Display>(e: E) -> Error {
+	Error::Custom(format!("Vault request failed: {}", e))
+}
+
+/// Vault authentication method.
+pub enum VaultAuth {
+	/// Use given token directly.
+	Token(String),
+	/// Log in with AppRole credentials.
+	AppRole {
+		role_id: String,
+		secret_id: Option<String>,
+	},
+}
+
+/// Client of the Vault HTTP API.
+struct VaultClient {
+	client: reqwest::Client,
+	address: String,
+	token: Option<String>,
+}
+
+impl VaultClient {
+	/// Send request to API `path` and parse the JSON response. Returns `None` for 404 responses.
+	fn send(&self, method: Method, path: &str, body: Option<String>) -> Result<Option<Value>, Error> {
+		let mut headers = Headers::new();
+		if let Some(ref token) = self.token {
+			headers.set_raw("X-Vault-Token", vec![token.clone().into_bytes()]);
+		}
+		let request = self.client.request(method, &format!("{}/v1/{}", self.address, path)).headers(headers);
+		let request = match body {
+			Some(body) => request.body(body),
+			None => request,
+		};
+
+		let mut response = request.send().map_err(vault_error)?;
+		let mut body = Vec::new();
+		response.read_to_end(&mut body)?;
+		match *response.status() {
+			StatusCode::NotFound => Ok(None),
+			StatusCode::NoContent => Ok(Some(Value::Null)),
+			status if status.is_success() => serde_json::from_slice(&body).map(Some).map_err(vault_error),
+			status => Err(vault_error(format!("{} {}", status, String::from_utf8_lossy(&body)))),
+		}
+	}
+
+	/// Exchange AppRole credentials for a token.
+	fn app_role_login(&self, role_id: String, secret_id: Option<String>) -> Result<String, Error> {
+		let mut credentials = BTreeMap::new();
+		credentials.insert("role_id", role_id);
+		if let Some(secret_id) = secret_id {
+			credentials.insert("secret_id", secret_id);
+		}
+		let body = serde_json::to_string(&credentials).map_err(vault_error)?;
+		self.send(Method::Post, "auth/approle/login", Some(body))?
+			.as_ref()
+			.and_then(|response| response.find_path(&["auth", "client_token"]))
+			.and_then(Value::as_str)
+			.map(ToOwned::to_owned)
+			.ok_or_else(|| vault_error("login response has no client token"))
+	}
+
+	/// Names of secrets under `prefix`. Vault reports empty prefixes as not found.
+	fn list_secrets(&self, prefix: &str) -> Result<Vec<String>, Error> {
+		let response = self.send(Method::Get, &format!("secret/metadata/{}?list=true", prefix), None)?;
+		Ok(response.as_ref()
+			.and_then(|response| response.find_path(&["data", "keys"]))
+			.and_then(Value::as_array)
+			.map(|keys| keys.iter().filter_map(Value::as_str).map(ToOwned::to_owned).collect())
+			.unwrap_or_else(Vec::new))
+	}
+
+	fn get_secret(&self, key: &str) -> Result<String, Error> {
+		self.send(Method::Get, &format!("secret/data/{}", key), None)?
+			.as_ref()
+			.and_then(|response| response.find_path(&["data", "data", "value"]))
+			.and_then(Value::as_str)
+			.map(ToOwned::to_owned)
+			.ok_or_else(|| vault_error(format!("secret {} not found", key)))
+	}
+
+	fn set_secret(&self, key: &str, value: String) -> Result<(), Error> {
+		let mut data = BTreeMap::new();
+		data.insert("value", value);
+		let mut body = BTreeMap::new();
+		body.insert("data", data);
+		let body = serde_json::to_string(&body).map_err(vault_error)?;
+		self.send(Method::Post, &format!("secret/data/{}", key), Some(body)).map(|_| ())
+	}
+
+	/// Remove all versions of the secret.
+	fn delete_secret(&self, key: &str) -> Result<(), Error> {
+		self.send(Method::Delete, &format!("secret/metadata/{}", key), None).map(|_| ())
+	}
+}
+
+/// Keys directory keeping every key file as a separate secret at `secret/data/<prefix>/<uuid>`.
+pub struct HashiCorpVaultDirectory {
+	client: Arc<VaultClient>,
+	prefix: String,
+	max_concurrent: usize,
+}
+
+impl HashiCorpVaultDirectory {
+	/// Connect to Vault server at given address (e.g. `http://127.0.0.1:8200`).
+	pub fn new(address: &str, auth: VaultAuth, prefix: &str, max_concurrent: usize) -> Result<Self, Error> {
+		let mut client = VaultClient {
+			client: reqwest::Client::new().map_err(vault_error)?,
+			address: address.trim_right_matches('/').to_owned(),
+			token: None,
+		};
+		client.token = Some(match auth {
+			VaultAuth::Token(token) => token,
+			VaultAuth::AppRole { role_id, secret_id } => client.app_role_login(role_id, secret_id)?,
+		});
+
+		Ok(HashiCorpVaultDirectory {
+			client: Arc::new(client),
+			prefix: prefix.trim_matches('/').to_owned(),
+			max_concurrent: ::std::cmp::max(max_concurrent, 1),
+		})
+	}
+
+	fn secret_key(prefix: &str, name: &str) -> String {
+		format!("{}/{}", prefix, name)
+	}
+
+	fn fetch(client: &VaultClient, prefix: &str, name: String) -> Result<SafeAccount, Error> {
+		let secret = client.get_secret(&Self::secret_key(prefix, &name))?;
+		DiskKeyFileManager.read(Some(name), secret.as_bytes())
+	}
+}
+
+impl KeyDirectory for HashiCorpVaultDirectory {
+	fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+		let names = self.client.list_secrets(&self.prefix)?;
+		let chunk_size = (names.len() + self.max_concurrent - 1) / self.max_concurrent;
+		let workers = names.chunks(::std::cmp::max(chunk_size, 1))
+			.map(|chunk| {
+				let chunk = chunk.to_vec();
+				let client = self.client.clone();
+				let prefix = self.prefix.clone();
+				thread::spawn(move || {
+					chunk.into_iter()
+						.filter_map(|name| Self::fetch(&client, &prefix, name.clone())
+							.map_err(|err| warn!("Invalid key file: {} ({})", name, err))
+							.ok())
+						.collect::<Vec<_>>()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		let mut accounts = Vec::new();
+		for worker in workers {
+			accounts.extend(worker.join().map_err(|_| Error::Custom("Vault fetch thread panicked".into()))?);
+		}
+		Ok(accounts)
+	}
+
+	fn update(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+		// secrets are keyed by account uuid
+		self.insert(account)
+	}
+
+	fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+		let name = format!("{}", Uuid::from(account.id));
+		let mut body = Vec::new();
+		DiskKeyFileManager.write(account.clone(), &mut body)?;
+		let body = String::from_utf8(body).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+
+		self.client.set_secret(&Self::secret_key(&self.prefix, &name), body)?;
+
+		let mut account = account;
+		account.filename = Some(name);
+		Ok(account)
+	}
+
+	fn remove(&self, account: &SafeAccount) -> Result<(), Error> {
+		let name = format!("{}", Uuid::from(account.id));
+		self.client.delete_secret(&Self::secret_key(&self.prefix, &name))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+	use dir::KeyDirectory;
+	use account::SafeAccount;
+	use ethkey::{Random, Generator};
+	use super::{HashiCorpVaultDirectory, VaultAuth};
+
+	/// Requires a Vault dev server (`vault server -dev`) configured with
+	/// `ETHSTORE_VAULT_ADDR` and `ETHSTORE_VAULT_TOKEN`.
+	#[test]
+	#[ignore]
+	fn should_insert_update_load_and_remove() {
+		let address = env::var("ETHSTORE_VAULT_ADDR").expect("ETHSTORE_VAULT_ADDR must be set");
+		let token = env::var("ETHSTORE_VAULT_TOKEN").expect("ETHSTORE_VAULT_TOKEN must be set");
+		let directory = HashiCorpVaultDirectory::new(&address, VaultAuth::Token(token), "ethstore-test", 4).unwrap();
+
+		// given
+		let keypair = Random.generate().unwrap();
+		let account = SafeAccount::create(&keypair, [1u8; 16], "hello world", 1024, "Test".to_owned(), "{}".to_owned());
+
+		// when
+		let inserted = directory.insert(account).unwrap();
+
+		// then
+		assert!(directory.load().unwrap().contains(&inserted));
+
+		// and when
+		let mut renamed = inserted.clone();
+		renamed.name = "Renamed".to_owned();
+		let updated = directory.update(renamed).unwrap();
+
+		// then
+		let loaded = directory.load().unwrap();
+		assert!(loaded.contains(&updated));
+		assert!(!loaded.contains(&inserted));
+
+		// and when
+		directory.remove(&updated).unwrap();
+
+		// then
+		assert!(!directory.load().unwrap().contains(&updated));
+	}
+}
@@ -23,11 +23,19 @@ use crypto::Error as EthCryptoError;
 pub enum Error {
 	Io(IoError),
 	InvalidPassword,
+	TemporarilyLocked,
 	InvalidSecret,
 	InvalidCryptoMeta,
 	InvalidAccount,
+	PermissionDenied,
+	AccountAlreadyExists,
+	DuplicateUuid,
+	DirectoryFull,
 	InvalidMessage,
 	InvalidKeyFile(String),
+	InvalidJsonKey(String),
+	InvalidArchive(String),
+	InvalidMnemonic,
 	VaultsAreNotSupported,
 	UnsupportedVault,
 	InvalidVaultName,
@@ -43,11 +51,19 @@ impl fmt::Display for Error {
 		let s = match *self {
 			Error::Io(ref err) => err.to_string(),
 			Error::InvalidPassword => "Invalid password".into(),
+			Error::TemporarilyLocked => "Account is temporarily locked after too many failed unlock attempts".into(),
 			Error::InvalidSecret => "Invalid secret".into(),
 			Error::InvalidCryptoMeta => "Invalid crypted metadata".into(),
 			Error::InvalidAccount => "Invalid account".into(),
+			Error::PermissionDenied => "Keys directory is read-only".into(),
+			Error::AccountAlreadyExists => "Account already exists".into(),
+			Error::DuplicateUuid => "Unable to pick a key file UUID not used by other accounts".into(),
+			Error::DirectoryFull => "Keys directory has reached its accounts limit".into(),
 			Error::InvalidMessage => "Invalid message".into(),
 			Error::InvalidKeyFile(ref reason) => format!("Invalid key file: {}", reason),
+			Error::InvalidJsonKey(ref reason) => format!("Invalid JSON: {}", reason),
+			Error::InvalidArchive(ref reason) => format!("Invalid backup archive: {}", reason),
+			Error::InvalidMnemonic => "Invalid mnemonic".into(),
 			Error::VaultsAreNotSupported => "Vaults are not supported".into(),
 			Error::UnsupportedVault => "Vault is not supported for this operation".into(),
 			Error::InvalidVaultName => "Invalid vault name".into(),
@@ -17,12 +17,13 @@
 use std::collections::{BTreeMap, HashMap};
 use std::mem;
 use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
 use parking_lot::{Mutex, RwLock};
 
 use crypto::KEY_ITERATIONS;
 use random::Random;
 use ethkey::{Signature, Address, Message, Secret, Public, KeyPair};
-use dir::{KeyDirectory, VaultKeyDirectory, VaultKey, SetKeyError};
+use dir::{KeyDirectory, VaultKeyDirectory, VaultKey, SetKeyError, DirChangeEvent};
 use account::SafeAccount;
 use presale::PresaleWallet;
 use json::{self, Uuid};
@@ -43,15 +44,31 @@ impl EthStore {
 		})
 	}
 
+	/// Keep accounts cache in sync with key files changed by other processes.
+	/// Returns `false` if the directory can't be watched.
+	pub fn watch_directory(&self) -> bool {
+		self.store.watch_directory()
+	}
+
 	fn get(&self, account: &StoreAccountRef) -> Result<SafeAccount, Error> {
 		let mut accounts = self.store.get(account)?.into_iter();
 		accounts.next().ok_or(Error::InvalidAccount)
 	}
+
+	/// Unlike `EthMultiStore`, keeps a single key per address.
+	fn ensure_new(&self, account_ref: &StoreAccountRef) -> Result<(), Error> {
+		if self.store.cache.read().contains_key(account_ref) {
+			return Err(Error::AccountAlreadyExists);
+		}
+		Ok(())
+	}
 }
 
 impl SimpleSecretStore for EthStore {
 	fn insert_account(&self, vault: SecretVaultRef, secret: Secret, password: &str) -> Result<StoreAccountRef, Error> {
-		self.store.insert_account(vault, secret, password)
+		let keypair = KeyPair::from_secret(secret).map_err(|_| Error::CreationFailed)?;
+		self.ensure_new(&StoreAccountRef::new(vault.clone(), keypair.address()))?;
+		self.store.insert_account(vault, keypair.secret().clone(), password)
 	}
 
 	fn account_ref(&self, address: &Address) -> Result<StoreAccountRef, Error> {
@@ -112,6 +129,10 @@ impl SimpleSecretStore for EthStore {
 		self.store.get_vault_meta(name)
 	}
 
+	fn vault_accounts_count(&self, name: &str) -> Result<usize, Error> {
+		self.store.vault_accounts_count(name)
+	}
+
 	fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error> {
 		self.store.set_vault_meta(name, meta)
 	}
@@ -130,6 +151,7 @@ impl SecretStore for EthStore {
 		let mut safe_account = SafeAccount::from_file(json_keyfile, None);
 		let secret = safe_account.crypto.secret(password).map_err(|_| Error::InvalidPassword)?;
 		safe_account.address = KeyPair::from_secret(secret)?.address();
+		self.ensure_new(&StoreAccountRef::new(vault.clone(), safe_account.address.clone()))?;
 		self.store.import(vault, safe_account)
 	}
 
@@ -145,6 +167,14 @@ impl SecretStore for EthStore {
 		Ok(())
 	}
 
+	fn export_account(&self, account: &StoreAccountRef, password: &str, new_password: &str) -> Result<Vec<u8>, Error> {
+		let account = self.get(account)?.change_password(password, new_password, self.store.iterations)?;
+		let key_file: json::KeyFile = account.into();
+		let mut exported = Vec::new();
+		key_file.write(&mut exported).map_err(|e| Error::Custom(format!("{}", e)))?;
+		Ok(exported)
+	}
+
 	fn public(&self, account: &StoreAccountRef, password: &str) -> Result<Public, Error> {
 		let account = self.get(account)?;
 		account.public(password)
@@ -215,6 +245,8 @@ pub struct EthMultiStore {
 	// order lock: cache, then vaults
 	cache: RwLock<BTreeMap<StoreAccountRef, Vec<SafeAccount>>>,
 	vaults: Mutex<HashMap<String, Box<VaultKeyDirectory>>>,
+	// changes of the root directory, when watched
+	watcher: Mutex<Option<Receiver<DirChangeEvent>>>,
 }
 
 impl EthMultiStore {
@@ -229,6 +261,7 @@ impl EthMultiStore {
 			vaults: Mutex::new(HashMap::new()),
 			iterations: iterations,
 			cache: Default::default(),
+			watcher: Mutex::new(None),
 		};
 		store.reload_accounts()?;
 		Ok(store)
@@ -259,7 +292,43 @@ impl EthMultiStore {
 		Ok(())
 	}
 
+	/// Keep accounts cache in sync with key files changed by other processes.
+	/// Returns `false` if the directory can't be watched.
+	pub fn watch_directory(&self) -> bool {
+		let watcher = self.dir.watch();
+		let enabled = watcher.is_some();
+		*self.watcher.lock() = watcher;
+		enabled
+	}
+
+	fn apply_directory_changes(&self) {
+		let watcher = self.watcher.lock();
+		let events = match *watcher {
+			Some(ref events) => events,
+			None => return,
+		};
+
+		let mut cache = self.cache.write();
+		for event in events.try_iter() {
+			match event {
+				DirChangeEvent::Inserted(account) => {
+					let accounts = cache.entry(StoreAccountRef::root(account.address.clone())).or_insert_with(Vec::new);
+					if !accounts.contains(&account) {
+						accounts.push(account);
+					}
+				},
+				DirChangeEvent::Removed(account) => {
+					let account_ref = StoreAccountRef::root(account.address.clone());
+					if let Some(accounts) = cache.get_mut(&account_ref) {
+						accounts.retain(|a| a != &account);
+					}
+				},
+			}
+		}
+	}
+
 	fn get(&self, account: &StoreAccountRef) -> Result<Vec<SafeAccount>, Error> {
+		self.apply_directory_changes();
 		{
 			let cache = self.cache.read();
 			if let Some(accounts) = cache.get(account) {
@@ -520,6 +589,11 @@ impl SimpleSecretStore for EthMultiStore {
 			.ok_or(Error::VaultNotFound)
 			.and_then(|v| v.set_meta(meta))
 	}
+
+	fn vault_accounts_count(&self, name: &str) -> Result<usize, Error> {
+		let vault_provider = self.dir.as_vault_provider().ok_or(Error::VaultsAreNotSupported)?;
+		vault_provider.vault_accounts_count(name)
+	}
 }
 
 #[cfg(test)]
@@ -530,6 +604,7 @@ mod tests {
 	use secret_store::{SimpleSecretStore, SecretStore, SecretVaultRef, StoreAccountRef};
 	use super::{EthStore, EthMultiStore};
 	use devtools::RandomTempPath;
+	use Error;
 
 	fn keypair() -> KeyPair {
 		Random.generate().unwrap()
@@ -640,6 +715,45 @@ mod tests {
 		assert_eq!(store.accounts().unwrap().len(), 0);
 	}
 
+	#[test]
+	fn should_not_insert_the_same_account_twice() {
+		// given
+		let dir = RootDiskDirectoryGuard::new();
+		let store = EthStore::open(dir.key_dir.unwrap()).unwrap();
+		let keypair = keypair();
+		store.insert_account(SecretVaultRef::Root, keypair.secret().clone(), "test").unwrap();
+
+		// when
+		let res = store.insert_account(SecretVaultRef::Root, keypair.secret().clone(), "xyz");
+
+		// then
+		match res {
+			Err(Error::AccountAlreadyExists) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(store.accounts().unwrap().len(), 1);
+		assert!(store.test_password(&StoreAccountRef::root(keypair.address()), "test").unwrap());
+	}
+
+	#[test]
+	fn disk_multistore_should_be_able_to_have_the_same_account_twice() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let store = EthMultiStore::open(Box::new(RootDiskDirectory::create(temp_path.as_path()).unwrap())).unwrap();
+		let keypair = keypair();
+		let address = store.insert_account(SecretVaultRef::Root, keypair.secret().clone(), "test").unwrap();
+
+		// when
+		let address2 = store.insert_account(SecretVaultRef::Root, keypair.secret().clone(), "xyz").unwrap();
+
+		// then
+		assert_eq!(address, address2);
+		let reopened = EthMultiStore::open(Box::new(RootDiskDirectory::at(temp_path.as_path()))).unwrap();
+		assert!(reopened.remove_account(&address, "test").is_ok(), "First password should work.");
+		assert!(reopened.remove_account(&address, "xyz").is_ok(), "Second password should work too.");
+		assert_eq!(reopened.accounts().unwrap().len(), 0);
+	}
+
 	#[test]
 	fn should_copy_account() {
 		// given
@@ -898,4 +1012,30 @@ mod tests {
 		assert_eq!(store.get_vault_meta(name1).unwrap(), "Hello, world!!!".to_owned());
 		assert!(store.get_vault_meta("vault2").is_err());
 	}
+
+	#[test]
+	fn should_forget_accounts_removed_by_other_process() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let store = EthStore::open(Box::new(RootDiskDirectory::create(temp_path.as_path()).unwrap())).unwrap();
+		assert!(store.watch_directory());
+		let account_ref = store.insert_account(SecretVaultRef::Root, keypair().secret().clone(), "test").unwrap();
+		assert!(store.test_password(&account_ref, "test").unwrap());
+
+		// when
+		let other = RootDiskDirectory::at(temp_path.as_path());
+		let account = other.load().unwrap().pop().unwrap();
+		other.remove(&account).unwrap();
+
+		// then
+		let mut removed = false;
+		for _ in 0..20 {
+			::std::thread::sleep(::std::time::Duration::from_millis(100));
+			if store.test_password(&account_ref, "test").is_err() {
+				removed = true;
+				break;
+			}
+		}
+		assert!(removed, "Account removed from disk should be dropped from cache.");
+	}
 }
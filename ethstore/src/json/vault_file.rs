@@ -23,6 +23,8 @@ use super::Crypto;
 /// Vault meta file
 #[derive(Debug, PartialEq, Serialize)]
 pub struct VaultFile {
+	/// Vault file format version
+	pub version: u32,
 	/// Vault password, encrypted with vault password
 	pub crypto: Crypto,
 	/// Vault metadata string
@@ -30,6 +32,7 @@ pub struct VaultFile {
 }
 
 enum VaultFileField {
+	Version,
 	Crypto,
 	Meta,
 }
@@ -51,6 +54,7 @@ impl Visitor for VaultFileFieldVisitor {
 		where E: Error
 	{
 		match value {
+			"version" => Ok(VaultFileField::Version),
 			"crypto" => Ok(VaultFileField::Crypto),
 			"meta" => Ok(VaultFileField::Meta),
 			_ => Err(Error::custom(format!("Unknown field: '{}'", value))),
@@ -62,7 +66,7 @@ impl Deserialize for VaultFile {
 	fn deserialize<D>(deserializer: &mut D) -> Result<VaultFile, D::Error>
 		where D: Deserializer
 	{
-		static FIELDS: &'static [&'static str] = &["crypto", "meta"];
+		static FIELDS: &'static [&'static str] = &["version", "crypto", "meta"];
 		deserializer.deserialize_struct("VaultFile", FIELDS, VaultFileVisitor)
 	}
 }
@@ -75,11 +79,13 @@ impl Visitor for VaultFileVisitor {
 	fn visit_map<V>(&mut self, mut visitor: V) -> Result<Self::Value, V::Error>
 		where V: MapVisitor
 	{
+		let mut version = None;
 		let mut crypto = None;
 		let mut meta = None;
 
 		loop {
 			match visitor.visit_key()? {
+				Some(VaultFileField::Version) => { version = Some(visitor.visit_value()?); },
 				Some(VaultFileField::Crypto) => { crypto = Some(visitor.visit_value()?); },
 				Some(VaultFileField::Meta) => { meta = visitor.visit_value().ok(); }, // meta is optional
 				None => { break; },
@@ -94,6 +100,8 @@ impl Visitor for VaultFileVisitor {
 		visitor.end()?;
 
 		let result = VaultFile {
+			// files written before versioning was introduced
+			version: version.unwrap_or(0),
 			crypto: crypto,
 			meta: meta,
 		};
@@ -120,6 +128,7 @@ mod test {
 	#[test]
 	fn to_and_from_json() {
 		let file = VaultFile {
+			version: 1,
 			crypto: Crypto {
 				cipher: Cipher::Aes128Ctr(Aes128Ctr {
 					iv: "0155e3690be19fbfbecabcd440aa284b".into(),
@@ -145,6 +154,7 @@ mod test {
 	#[test]
 	fn to_and_from_json_no_meta() {
 		let file = VaultFile {
+			version: 1,
 			crypto: Crypto {
 				cipher: Cipher::Aes128Ctr(Aes128Ctr {
 					iv: "0155e3690be19fbfbecabcd440aa284b".into(),
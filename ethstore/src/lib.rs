@@ -30,6 +30,11 @@ extern crate tiny_keccak;
 extern crate parking_lot;
 extern crate ethcore_devtools as devtools;
 
+#[cfg(any(feature = "s3", feature = "hashicorp-vault"))]
+extern crate reqwest;
+#[cfg(feature = "s3")]
+extern crate xml;
+
 // reexport it nicely
 extern crate ethkey as _ethkey;
 extern crate ethcrypto as crypto;
@@ -50,6 +55,7 @@ mod json;
 mod error;
 mod ethstore;
 mod import;
+mod mnemonic;
 mod presale;
 mod random;
 mod secret_store;
@@ -58,6 +64,7 @@ pub use self::account::{SafeAccount};
 pub use self::error::Error;
 pub use self::ethstore::{EthStore, EthMultiStore};
 pub use self::import::{import_accounts, read_geth_accounts};
+pub use self::mnemonic::Mnemonic;
 pub use self::presale::PresaleWallet;
 pub use self::secret_store::{SecretVaultRef, StoreAccountRef, SimpleSecretStore, SecretStore};
 pub use self::random::{random_phrase, random_string};
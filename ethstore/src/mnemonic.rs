@@ -0,0 +1,138 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! BIP-39 mnemonic phrases.
+
+use rcrypto::pbkdf2::pbkdf2;
+use rcrypto::sha2::{Sha256, Sha512};
+use rcrypto::hmac::Hmac;
+use rcrypto::digest::Digest;
+use ethkey::{Secret, ExtendedKeyPair};
+use Error;
+
+const SEED_ITERATIONS: u32 = 2048;
+
+/// BIP-44 derivation path of the first Ethereum account: `m/44'/60'/0'/0/0`.
+const ETHEREUM_PATH: [u32; 5] = [0x8000002c, 0x8000003c, 0x80000000, 0, 0];
+
+lazy_static! {
+	static ref WORDS: Vec<&'static str> = ::std::str::from_utf8(include_bytes!("../res/bip39_english.txt"))
+		.expect("Wordlist is valid UTF-8; qed")
+		.lines()
+		.collect();
+}
+
+/// Validated BIP-39 mnemonic phrase.
+pub struct Mnemonic {
+	phrase: String,
+}
+
+impl Mnemonic {
+	/// Parse the phrase, checking word count, words and checksum.
+	pub fn new(phrase: &str) -> Result<Self, Error> {
+		let words: Vec<&str> = phrase.split_whitespace().collect();
+		match words.len() {
+			12 | 15 | 18 | 21 | 24 => {},
+			_ => return Err(Error::InvalidMnemonic),
+		}
+
+		let mut bits = Vec::with_capacity(words.len() * 11);
+		for word in &words {
+			let index = WORDS.binary_search(word).map_err(|_| Error::InvalidMnemonic)?;
+			bits.extend((0..11).rev().map(|i| (index >> i) & 1 == 1));
+		}
+
+		let checksum_len = bits.len() / 33;
+		let (entropy_bits, checksum) = bits.split_at(bits.len() - checksum_len);
+		let entropy: Vec<u8> = entropy_bits.chunks(8)
+			.map(|byte| byte.iter().fold(0u8, |acc, &bit| (acc << 1) | bit as u8))
+			.collect();
+
+		let mut hash = [0u8; 32];
+		let mut sha = Sha256::new();
+		sha.input(&entropy);
+		sha.result(&mut hash);
+
+		let expected = (0..checksum_len).map(|i| (hash[0] >> (7 - i)) & 1 == 1);
+		if !expected.eq(checksum.iter().cloned()) {
+			return Err(Error::InvalidMnemonic);
+		}
+
+		Ok(Mnemonic {
+			phrase: words.join(" "),
+		})
+	}
+
+	/// Compute the 512-bit seed for given passphrase.
+	pub fn seed(&self, passphrase: &str) -> [u8; 64] {
+		let mut h_mac = Hmac::new(Sha512::new(), self.phrase.as_bytes());
+		let salt = format!("mnemonic{}", passphrase);
+		let mut seed = [0u8; 64];
+		pbkdf2(&mut h_mac, salt.as_bytes(), SEED_ITERATIONS, &mut seed);
+		seed
+	}
+
+	/// Derive the secret of the first Ethereum account (`m/44'/60'/0'/0/0`).
+	pub fn secret(&self, passphrase: &str) -> Result<Secret, Error> {
+		let mut pair = ExtendedKeyPair::with_seed(&self.seed(passphrase)).map_err(|_| Error::InvalidMnemonic)?;
+		for index in &ETHEREUM_PATH {
+			pair = pair.derive((*index).into()).map_err(|_| Error::InvalidMnemonic)?;
+		}
+		Ok(pair.secret().secret().clone())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::ToHex;
+	use ethkey::KeyPair;
+	use super::{Mnemonic, WORDS};
+
+	const PHRASE: &'static str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+	#[test]
+	fn should_load_wordlist() {
+		assert_eq!(WORDS.len(), 2048);
+		assert_eq!(WORDS[0], "abandon");
+		assert_eq!(WORDS[2047], "zoo");
+	}
+
+	#[test]
+	fn should_compute_seed() {
+		let mnemonic = Mnemonic::new(PHRASE).unwrap();
+		assert_eq!(mnemonic.seed("TREZOR").to_hex(), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+
+		let mnemonic = Mnemonic::new("legal winner thank year wave sausage worth useful legal winner thank yellow").unwrap();
+		assert_eq!(mnemonic.seed("TREZOR").to_hex(), "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607");
+	}
+
+	#[test]
+	fn should_derive_ethereum_account() {
+		let secret = Mnemonic::new(PHRASE).unwrap().secret("").unwrap();
+		let pair = KeyPair::from_secret(secret).unwrap();
+		assert_eq!(pair.address(), "9858effd232b4033e47d90003d41ec34ecaeda94".into());
+	}
+
+	#[test]
+	fn should_reject_invalid_mnemonics() {
+		// wrong checksum
+		assert!(Mnemonic::new("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon").is_err());
+		// wrong word count
+		assert!(Mnemonic::new("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").is_err());
+		// unknown word
+		assert!(Mnemonic::new("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon parity").is_err());
+	}
+}
@@ -67,6 +67,8 @@ pub trait SimpleSecretStore: Send + Sync {
 	fn change_account_vault(&self, vault: SecretVaultRef, account: StoreAccountRef) -> Result<StoreAccountRef, Error>;
 	/// Get vault metadata string.
 	fn get_vault_meta(&self, name: &str) -> Result<String, Error>;
+	/// Get number of accounts in the vault. The vault doesn't have to be opened.
+	fn vault_accounts_count(&self, name: &str) -> Result<usize, Error>;
 	/// Set vault metadata string.
 	fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error>;
 }
@@ -76,6 +78,8 @@ pub trait SecretStore: SimpleSecretStore {
 	fn import_wallet(&self, vault: SecretVaultRef, json: &[u8], password: &str) -> Result<StoreAccountRef, Error>;
 	fn copy_account(&self, new_store: &SimpleSecretStore, new_vault: SecretVaultRef, account: &StoreAccountRef, password: &str, new_password: &str) -> Result<(), Error>;
 	fn test_password(&self, account: &StoreAccountRef, password: &str) -> Result<bool, Error>;
+	/// Export account as a JSON key file encrypted with `new_password`. Stored account is left untouched.
+	fn export_account(&self, account: &StoreAccountRef, password: &str, new_password: &str) -> Result<Vec<u8>, Error>;
 
 	fn public(&self, account: &StoreAccountRef, password: &str) -> Result<Public, Error>;
 
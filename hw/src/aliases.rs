@@ -0,0 +1,84 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Device nicknames persisted in a JSON file.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use serde_json;
+
+/// Aliases of hardware wallet devices, keyed by device serial number.
+#[derive(Debug, Default)]
+pub struct DeviceAliases {
+	path: Option<PathBuf>,
+	aliases: BTreeMap<String, String>,
+}
+
+impl DeviceAliases {
+	/// Load aliases from the JSON file at `path`. Without a path aliases are kept in memory only.
+	pub fn load(path: Option<PathBuf>) -> DeviceAliases {
+		let aliases = path.as_ref()
+			.and_then(|path| fs::File::open(path).ok())
+			.and_then(|file| serde_json::from_reader::<_, BTreeMap<String, String>>(file).map_err(|e| warn!("Error reading hardware wallet aliases: {}", e)).ok())
+			.unwrap_or_default();
+		DeviceAliases {
+			path: path,
+			aliases: aliases,
+		}
+	}
+
+	/// Alias of the device with given serial number.
+	pub fn get(&self, serial: &str) -> Option<String> {
+		self.aliases.get(serial).cloned()
+	}
+
+	/// Set alias of the device with given serial number and save all aliases.
+	pub fn set(&mut self, serial: &str, alias: String) -> io::Result<()> {
+		self.aliases.insert(serial.to_owned(), alias);
+		match self.path {
+			Some(ref path) => {
+				let mut file = fs::File::create(path)?;
+				serde_json::to_writer(&mut file, &self.aliases).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))
+			},
+			None => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use devtools::RandomTempPath;
+	use super::DeviceAliases;
+
+	#[test]
+	fn should_save_and_load_aliases() {
+		// given
+		let path = RandomTempPath::new();
+		let mut aliases = DeviceAliases::load(Some(path.as_path().clone()));
+		assert_eq!(aliases.get("0001"), None);
+
+		// when
+		aliases.set("0001", "Office".into()).unwrap();
+
+		// then
+		let aliases = DeviceAliases::load(Some(path.as_path().clone()));
+		assert_eq!(aliases.get("0001"), Some("Office".into()));
+		assert_eq!(aliases.get("0002"), None);
+	}
+}
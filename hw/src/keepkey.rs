@@ -0,0 +1,79 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! KeepKey hardware wallet module. KeepKey uses the Trezor HID framing and
+//! message set for Ethereum, so its devices are managed by `trezor::Manager`.
+
+use super::WalletDeviceType;
+use trezor::DeviceFamily;
+
+/// KeepKey devices.
+pub static KEEPKEY: DeviceFamily = DeviceFamily {
+	ids: &[(0x2b24, 0x0001)],
+	device_type: WalletDeviceType::KeepKey,
+};
+
+#[cfg(test)]
+mod tests {
+	use ethkey::Address;
+	use transport::mock::MockDevice;
+	use trezor::{self, proto, message_type, Error};
+	use KeyPath;
+	use super::KEEPKEY;
+
+	fn report(msg_type: u16, data: &[u8]) -> Vec<u8> {
+		assert!(data.len() <= 55);
+		let mut r = vec![b'?', b'#', b'#', (msg_type >> 8) as u8, msg_type as u8, 0, 0, 0, data.len() as u8];
+		r.extend_from_slice(data);
+		r.resize(64, 0);
+		r
+	}
+
+	#[test]
+	fn should_only_enumerate_keepkey_devices() {
+		assert!(KEEPKEY.is_valid_device(0x2b24, 0x0001));
+		assert!(!KEEPKEY.is_valid_device(0x534c, 0x0001));
+		assert!(!KEEPKEY.is_valid_device(0x2b24, 0x0002));
+	}
+
+	#[test]
+	fn should_parse_address_response() {
+		let address: Address = "2f1dfa3e8d73a2c8f4db6c9f3c5b2c1a9e4d7b60".into();
+		let mut address_response = Vec::new();
+		proto::write_bytes(&mut address_response, 1, &address);
+		let device = MockDevice::new(vec![
+			report(message_type::FEATURES, &[]),
+			report(message_type::ETHEREUM_ADDRESS, &address_response),
+		]);
+
+		assert_eq!(trezor::Manager::read_wallet_address(&device, KeyPath::Ethereum).unwrap(), address);
+	}
+
+	#[test]
+	fn should_reject_malformed_address() {
+		let mut address_response = Vec::new();
+		proto::write_bytes(&mut address_response, 1, &[0u8; 19]);
+		let device = MockDevice::new(vec![
+			report(message_type::FEATURES, &[]),
+			report(message_type::ETHEREUM_ADDRESS, &address_response),
+		]);
+
+		match trezor::Manager::read_wallet_address(&device, KeyPath::Ethereum) {
+			Err(Error::Protocol(_)) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+}
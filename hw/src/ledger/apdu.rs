@@ -0,0 +1,234 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ledger APDU framing and command payloads.
+
+use std::cmp::min;
+use transport::HidTransport;
+use ethcore_bigint::hash::{H256, FixedHash};
+use super::Error;
+use SigningProgress;
+
+const APDU_TAG: u8 = 0x05;
+const APDU_CLA: u8 = 0xe0;
+/// Class of commands handled by the device OS rather than the running app.
+const BOLOS_CLA: u8 = 0xb0;
+
+#[cfg(windows)] pub const HID_PREFIX_ZERO: usize = 1;
+#[cfg(not(windows))] pub const HID_PREFIX_ZERO: usize = 0;
+
+pub mod commands {
+	pub const GET_APP_CONFIGURATION: u8 = 0x06;
+	pub const GET_ETH_PUBLIC_ADDRESS: u8 = 0x02;
+	pub const SIGN_ETH_TRANSACTION: u8 = 0x04;
+	pub const SIGN_ETH_PERSONAL_MESSAGE: u8 = 0x08;
+	pub const SIGN_ETH_EIP_712: u8 = 0x0c;
+	/// OS command, must be sent with `send_bolos_apdu`.
+	pub const GET_VERSION: u8 = 0x01;
+}
+
+/// Build the `SIGN_ETH_EIP_712` payload: derivation path followed by the domain separator and message hashes.
+pub fn sign_typed_data_payload(derivation_path: &[u8], domain_separator: &[u8; 32], message_hash: &[u8; 32]) -> Vec<u8> {
+	let mut payload = Vec::with_capacity(derivation_path.len() + 64);
+	payload.extend_from_slice(derivation_path);
+	payload.extend_from_slice(domain_separator);
+	payload.extend_from_slice(message_hash);
+	payload
+}
+
+/// Build the `SIGN_ETH_PERSONAL_MESSAGE` payload: derivation path, big-endian message length and the message.
+/// The device adds the `\x19Ethereum Signed Message:\n` prefix itself.
+pub fn sign_message_payload(derivation_path: &[u8], message: &[u8]) -> Vec<u8> {
+	let len = message.len();
+	let mut payload = Vec::with_capacity(derivation_path.len() + 4 + len);
+	payload.extend_from_slice(derivation_path);
+	payload.extend_from_slice(&[(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]);
+	payload.extend_from_slice(message);
+	payload
+}
+
+/// Parse a `v || r || s` signature response.
+pub fn parse_signature(response: &[u8]) -> Result<(u8, H256, H256), Error> {
+	if response.len() != 65 {
+		return Err(Error::Protocol("Signature packet size mismatch"));
+	}
+	Ok((response[0], H256::from_slice(&response[1..33]), H256::from_slice(&response[33..65])))
+}
+
+/// Parse a `GET_VERSION` response: 4-byte target id followed by a length-prefixed firmware version string.
+pub fn parse_firmware_version(response: &[u8]) -> Result<String, Error> {
+	if response.len() < 5 {
+		return Err(Error::Protocol("Version packet size mismatch"));
+	}
+	let len = response[4] as usize;
+	let version = response.get(5..5 + len).ok_or(Error::Protocol("Version packet size mismatch"))?;
+	::std::str::from_utf8(version)
+		.map(|v| v.to_owned())
+		.map_err(|_| Error::Protocol("Invalid version string"))
+}
+
+/// Send an Ethereum app APDU to the device and read the response. Returns the response data without the status word.
+pub fn send_apdu<T: HidTransport>(handle: &T, command: u8, p1: u8, p2: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+	send_apdu_with_cla(handle, APDU_CLA, command, p1, p2, data)
+}
+
+/// Send a signing request `payload` split into APDUs of at most 255 bytes. Continuation APDUs are marked with `P1 = 0x80`.
+/// The device waits for user confirmation before responding to the last APDU; `progress` is notified
+/// once it has been sent and once the user has made a decision. Returns the response to the last APDU.
+pub fn send_chunked<T: HidTransport>(handle: &T, command: u8, payload: &[u8], progress: &Fn(SigningProgress)) -> Result<Vec<u8>, Error> {
+	const MAX_CHUNK_SIZE: usize = 255;
+	let chunks: Vec<_> = payload.chunks(MAX_CHUNK_SIZE).collect();
+	let (last, init) = chunks.split_last().ok_or(Error::Protocol("Empty request"))?;
+	for (index, chunk) in init.iter().enumerate() {
+		let p1 = if index == 0 { 0x00 } else { 0x80 };
+		send_apdu(handle, command, p1, 0, chunk)?;
+	}
+	let p1 = if init.is_empty() { 0x00 } else { 0x80 };
+	write_apdu(handle, APDU_CLA, command, p1, 0, last)?;
+	progress(SigningProgress::AwaitingConfirmation);
+	let result = read_apdu(handle);
+	match result {
+		Ok(_) => progress(SigningProgress::Signed),
+		Err(Error::UserCancel) | Err(Error::UserRejected) => progress(SigningProgress::Rejected),
+		Err(_) => (),
+	}
+	result
+}
+
+/// Send a device OS APDU to the device and read the response.
+pub fn send_bolos_apdu<T: HidTransport>(handle: &T, command: u8, p1: u8, p2: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+	send_apdu_with_cla(handle, BOLOS_CLA, command, p1, p2, data)
+}
+
+/// Send a complete `CLA INS P1 P2 [Lc data]` APDU and return the response data followed by the status word.
+/// The status word is not interpreted.
+#[cfg(feature = "ledger-debug")]
+pub fn send_raw<T: HidTransport>(handle: &T, apdu: &[u8]) -> Result<Vec<u8>, Error> {
+	if apdu.len() < 4 {
+		return Err(Error::Protocol("APDU too short"));
+	}
+	let data = apdu.get(5..).unwrap_or(&[]);
+	if apdu.len() > 4 && apdu[4] as usize != data.len() {
+		return Err(Error::Protocol("APDU length mismatch"));
+	}
+	write_apdu(handle, apdu[0], apdu[1], apdu[2], apdu[3], data)?;
+	read_response(handle)
+}
+
+fn send_apdu_with_cla<T: HidTransport>(handle: &T, cla: u8, command: u8, p1: u8, p2: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+	write_apdu(handle, cla, command, p1, p2, data)?;
+	read_apdu(handle)
+}
+
+const HID_PACKET_SIZE: usize = 64 + HID_PREFIX_ZERO;
+
+fn write_apdu<T: HidTransport>(handle: &T, cla: u8, command: u8, p1: u8, p2: u8, data: &[u8]) -> Result<(), Error> {
+	let mut offset = 0;
+	let mut chunk_index = 0;
+		loop {
+			let mut hid_chunk: [u8; HID_PACKET_SIZE] = [0; HID_PACKET_SIZE];
+			let mut chunk_size = if chunk_index == 0 { 12 } else { 5 };
+			let size = min(64 - chunk_size, data.len() - offset);
+			{
+				let mut chunk = &mut hid_chunk[HID_PREFIX_ZERO..];
+				&mut chunk[0..5].copy_from_slice(&[0x01, 0x01, APDU_TAG, (chunk_index >> 8) as u8, (chunk_index & 0xff) as u8 ]);
+			
+				if chunk_index == 0 {
+					let data_len = data.len() + 5;
+					&mut chunk[5..12].copy_from_slice(&[ (data_len >> 8) as u8, (data_len & 0xff) as u8, cla, command, p1, p2, data.len() as u8 ]);
+				}
+			
+				&mut chunk[chunk_size..chunk_size + size].copy_from_slice(&data[offset..offset + size]);
+				offset += size;
+				chunk_size += size;
+			}
+			trace!("writing {:?}", &hid_chunk[..]);
+			let n = handle.write(&hid_chunk[0..chunk_size])?;
+			if n < chunk_size {
+				return Err(Error::Protocol("Write data size mismatch"));
+			}
+			if offset == data.len() {
+				break;
+			}
+			chunk_index += 1;
+		}
+	Ok(())
+}
+
+/// Read an APDU response. Returns the response data without the status word.
+fn read_apdu<T: HidTransport>(handle: &T) -> Result<Vec<u8>, Error> {
+	let mut message = read_response(handle)?;
+	let status = (message[message.len() - 2] as usize) << 8  | (message[message.len() - 1] as usize);
+	debug!("Read status {:x}", status);
+	match status {
+		0x6700 => Err(Error::Protocol("Incorrect length")),
+		0x6804 => Err(Error::DeviceLocked),
+		0x6982 => Err(Error::Protocol("Security status not satisfied (Canceled by user)")),
+		0x6985 => Err(Error::UserRejected),
+		0x6a80 => Err(Error::Protocol("Invalid data")),
+		0x6a82 => Err(Error::Protocol("File not found")),
+		0x6a85 => Err(Error::UserCancel),
+		0x6b00 => Err(Error::Protocol("Incorrect parameters")),
+		0x6d00 => Err(Error::Protocol("Not implemented. Make sure Ethereum app is running.")),
+		0x6faa => Err(Error::Protocol("You Ledger need to be unplugged")),
+		0x6f00...0x6fff => Err(Error::Protocol("Internal error")),
+		0x9000 => Ok(()),
+		_ => Err(Error::Protocol("Unknown error")),
+
+	}?;
+	let new_len = message.len() - 2;
+	message.truncate(new_len);
+	Ok(message)
+}
+
+/// Read APDU response data followed by the status word.
+fn read_response<T: HidTransport>(handle: &T) -> Result<Vec<u8>, Error> {
+	let mut chunk_index = 0;
+	let mut message_size = 0;
+	let mut message = Vec::new();
+	loop {
+		let mut chunk: [u8; HID_PACKET_SIZE] = [0; HID_PACKET_SIZE];
+		let chunk_size = handle.read(&mut chunk)?;
+		trace!("read {:?}", &chunk[..]);
+		if chunk_size < 5 || chunk[1] != 0x01 || chunk[1] != 0x01 || chunk[2] != APDU_TAG {
+			return Err(Error::Protocol("Unexpected chunk header"));
+		}
+		let seq = (chunk[3] as usize) << 8 | (chunk[4] as usize);
+		if seq != chunk_index {
+			return Err(Error::Protocol("Unexpected chunk header"));
+		}
+
+		let mut offset = 5;
+		if seq == 0 {
+			// read message size and status word.
+			if chunk_size < 7 {
+				return Err(Error::Protocol("Unexpected chunk header"));
+			}
+			message_size = (chunk[5] as usize) << 8  | (chunk[6] as usize);
+			offset += 2;
+		}
+		message.extend_from_slice(&chunk[offset..chunk_size]);
+		message.truncate(message_size);
+		if message.len() == message_size {
+			break;
+		}
+		chunk_index +=1;
+	}
+	if message.len() < 2 {
+		return Err(Error::Protocol("No status word"));
+	}
+	Ok(message)
+}
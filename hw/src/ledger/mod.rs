@@ -0,0 +1,1522 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ledger hardware wallet module. Supports Ledger Blue and Nano S.
+/// See https://github.com/LedgerHQ/blue-app-eth/blob/master/doc/ethapp.asc for protocol details.
+
+mod apdu;
+pub mod queue;
+
+use hidapi;
+use std::fmt;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::ops::Range;
+use parking_lot::Mutex;
+use super::{WalletInfo, WalletDeviceType, SigningProgress};
+use transport::HidTransport;
+use self::apdu::commands;
+use self::queue::{DeviceQueue, Pending};
+use ethkey::{Address, Public, Signature};
+use ethcore_bigint::hash::FixedHash;
+use rlp::{UntrustedRlp, View};
+
+const LEDGER_VID: u16 = 0x2c97;
+const LEDGER_PIDS: [u16; 2] = [0x0000, 0x0001]; // Nano S and Blue
+const ETH_DERIVATION_PATH_BE: [u8; 17] =  [ 4,  0x80, 0, 0, 44,  0x80, 0, 0, 60,  0x80, 0, 0, 0,  0, 0, 0, 0 ];  // 44'/60'/0'/0
+const ETC_DERIVATION_PATH_BE: [u8; 21] =  [ 5,  0x80, 0, 0, 44,  0x80, 0, 0, 60,  0x80, 0x02, 0x73, 0xd0,  0x80, 0, 0, 0,  0, 0, 0, 0 ];  // 44'/60'/160720'/0'/0
+/// BIP-44 coin type of Ethereum. Addresses for it are derived with the selected `KeyPath`.
+const ETH_COIN_TYPE: u32 = 60;
+
+/// Key derivation paths used on hardware wallets.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyPath {
+	/// Ethereum.
+	Ethereum,
+	/// Ethereum classic. All wallets derive ETC keys on 44'/60'/160720'/0'/0, the path used by the
+	/// Ledger ETC app, so the same device shows the same ETC account whichever wallet it is used with.
+	EthereumClassic,
+}
+
+/// Transaction envelope, determined by the first byte of the signing payload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionType {
+	/// Legacy RLP-encoded transaction.
+	Legacy,
+	/// EIP-2930 access list transaction (type 1).
+	AccessList,
+	/// EIP-1559 dynamic fee transaction (type 2).
+	DynamicFee,
+}
+
+impl TransactionType {
+	/// Detect the type of the transaction `payload`. Legacy transactions start with an RLP list prefix.
+	pub fn from_payload(payload: &[u8]) -> TransactionType {
+		match payload.first() {
+			Some(&0x01) => TransactionType::AccessList,
+			Some(&0x02) => TransactionType::DynamicFee,
+			_ => TransactionType::Legacy,
+		}
+	}
+
+	/// Oldest Ethereum app version able to sign this type of transaction.
+	fn required_app_version(&self) -> (u8, u8, u8) {
+		match *self {
+			TransactionType::Legacy => (1, 0, 3),
+			TransactionType::AccessList => (1, 6, 0),
+			TransactionType::DynamicFee => (1, 7, 0),
+		}
+	}
+}
+
+/// Hardware waller error.
+#[derive(Debug)]
+pub enum Error {
+	/// Ethereum wallet protocol error.
+	Protocol(&'static str),
+	/// Hidapi error.
+	Usb(hidapi::HidError),
+	/// Device with request key is not available.
+	KeyNotFound,
+	/// Signing has been cancelled by user.
+	UserCancel,
+	/// Device did not respond in time.
+	Timeout,
+	/// User has rejected the request on the device.
+	UserRejected,
+	/// Ethereum app on the device is too old to sign this type of transaction.
+	FirmwareTooOld(TransactionType),
+	/// Device is locked with a PIN.
+	DeviceLocked,
+	/// Too many requests are waiting for the device.
+	DeviceBusy,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Error::Protocol(ref s) => write!(f, "Ledger protocol error: {}", s),
+			Error::Usb(ref e) => write!(f, "USB communication error: {}", e),
+			Error::KeyNotFound => write!(f, "Key not found"),
+			Error::UserCancel => write!(f, "Operation has been cancelled"),
+			Error::Timeout => write!(f, "Operation timed out"),
+			Error::UserRejected => write!(f, "Operation has been rejected on the device"),
+			Error::DeviceLocked => write!(f, "Device is locked. Unlock it with the PIN"),
+			Error::DeviceBusy => write!(f, "Too many requests are waiting for the device"),
+			Error::FirmwareTooOld(ref t) => {
+				let (major, minor, patch) = t.required_app_version();
+				write!(f, "{:?} transactions require Ethereum app {}.{}.{} or newer", t, major, minor, patch)
+			},
+		}
+	}
+}
+
+impl From<hidapi::HidError> for Error {
+	fn from(err: hidapi::HidError) -> Error {
+		Error::Usb(err)
+	}
+}
+
+/// Shared signing progress callback.
+pub type ProgressCallback = Arc<Mutex<Option<Box<Fn(SigningProgress) + Send>>>>;
+
+/// Default delay between consecutive address requests sent to a single device.
+const DEFAULT_ENUMERATION_DELAY_MS: u64 = 50;
+/// Default time to wait for the user to confirm an operation on the device.
+const DEFAULT_SIGNING_TIMEOUT_SECS: u64 = 30;
+/// Default number of re-enumeration attempts after a USB arrival event.
+const DEFAULT_RECONNECT_RETRIES: u32 = 5;
+/// Number of consecutive failed address requests ending an address scan.
+const ADDRESS_GAP_LIMIT: u32 = 3;
+/// Time to wait for the device to return an address during an address scan. No user confirmation is involved.
+const ADDRESS_REQUEST_TIMEOUT_MS: u64 = 2000;
+/// Delay before the first re-enumeration attempt. Doubled on each subsequent attempt.
+const RECONNECT_BASE_DELAY_MS: u64 = 100;
+/// Upper bound for the delay between re-enumeration attempts.
+const RECONNECT_MAX_DELAY_MS: u64 = 2000;
+
+/// Device re-enumeration state after a USB arrival event.
+/// The OS might not have finished setting up the HID interface when the event fires,
+/// so enumeration is retried with exponential backoff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RetryState {
+	/// No retry pending.
+	Idle,
+	/// Retry number `attempt` (zero-based) is due at `at`.
+	Pending {
+		attempt: u32,
+		at: Instant,
+	},
+}
+
+impl RetryState {
+	fn backoff(attempt: u32) -> Duration {
+		let delay = RECONNECT_BASE_DELAY_MS.saturating_mul(1u64 << min(attempt, 16));
+		Duration::from_millis(min(delay, RECONNECT_MAX_DELAY_MS))
+	}
+
+	/// Schedule the first retry.
+	fn start(now: Instant) -> RetryState {
+		RetryState::Pending { attempt: 0, at: now + Self::backoff(0) }
+	}
+
+	/// Whether a retry is due at `now`.
+	fn is_due(&self, now: Instant) -> bool {
+		match *self {
+			RetryState::Pending { at, .. } => now >= at,
+			RetryState::Idle => false,
+		}
+	}
+
+	/// Run `update` if a retry is due. `update` returns `true` once the device has been found.
+	fn poll<F: FnMut() -> bool>(self, now: Instant, max_retries: u32, mut update: F) -> RetryState {
+		match self {
+			RetryState::Pending { attempt, at } if now >= at => {
+				if update() {
+					RetryState::Idle
+				} else if attempt + 1 >= max_retries {
+					debug!("Giving up on Ledger enumeration after {} retries", max_retries);
+					RetryState::Idle
+				} else {
+					RetryState::Pending { attempt: attempt + 1, at: now + Self::backoff(attempt + 1) }
+				}
+			},
+			state => state,
+		}
+	}
+}
+
+/// Ledger device manager.
+pub struct Manager {
+	usb: Arc<Mutex<hidapi::HidApi>>,
+	devices: Vec<Device>,
+	key_path: KeyPath,
+	account_index_range: Range<u32>,
+	enumeration_delay: Duration,
+	retry_state: RetryState,
+	reconnect_retries: u32,
+	signing_timeout: Duration,
+	progress: ProgressCallback,
+	device_queues: Mutex<HashMap<String, DeviceQueue<hidapi::HidDevice>>>,
+}
+
+/// Public key of a device account along with its BIP-32 chain code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletPublicKey {
+	/// Account public key.
+	pub public: Public,
+	/// Chain code for deriving child keys.
+	pub chain_code: [u8; 32],
+}
+
+/// A single account on a connected device.
+#[derive(Debug)]
+struct Device {
+	path: String,
+	index: u32,
+	info: WalletInfo,
+}
+
+impl Manager {
+	/// Create a new instance.
+	pub fn new(hidapi: Arc<Mutex<hidapi::HidApi>>) -> Manager {
+		Manager {
+			usb: hidapi,
+			devices: Vec::new(),
+			key_path: KeyPath::Ethereum,
+			account_index_range: 0..1,
+			enumeration_delay: Duration::from_millis(DEFAULT_ENUMERATION_DELAY_MS),
+			retry_state: RetryState::Idle,
+			reconnect_retries: DEFAULT_RECONNECT_RETRIES,
+			signing_timeout: Duration::from_secs(DEFAULT_SIGNING_TIMEOUT_SECS),
+			progress: Arc::new(Mutex::new(None)),
+			device_queues: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Set a callback notified while signing requests wait for user confirmation.
+	pub fn set_progress_callback(&mut self, callback: Box<Fn(SigningProgress) + Send>) {
+		*self.progress.lock() = Some(callback);
+	}
+
+	/// Set the time to wait for a device to complete a signing operation.
+	pub fn set_signing_timeout(&mut self, timeout: Duration) {
+		self.signing_timeout = timeout;
+	}
+
+	/// Time to wait for a device to complete a signing operation.
+	pub fn signing_timeout(&self) -> Duration {
+		self.signing_timeout
+	}
+
+	/// Handle a USB arrival event. Enumerates devices right away and schedules
+	/// retries with exponential backoff if no new device shows up.
+	pub fn device_arrived(&mut self) {
+		let found = self.update_devices().unwrap_or_else(|e| {
+			debug!("Error enumerating Ledger devices: {}", e);
+			0
+		});
+		self.retry_state = if found > 0 || self.reconnect_retries == 0 {
+			RetryState::Idle
+		} else {
+			RetryState::start(Instant::now())
+		};
+	}
+
+	/// Run a scheduled re-enumeration attempt if one is due.
+	pub fn handle_retries(&mut self) {
+		let max_retries = self.reconnect_retries;
+		let state = self.retry_state.poll(Instant::now(), max_retries, || {
+			self.update_devices().unwrap_or_else(|e| {
+				debug!("Error enumerating Ledger devices: {}", e);
+				0
+			}) > 0
+		});
+		self.retry_state = state;
+	}
+
+	/// Whether a scheduled re-enumeration attempt is due.
+	pub fn retry_due(&self) -> bool {
+		self.retry_state.is_due(Instant::now())
+	}
+
+	/// Time left until the next scheduled re-enumeration attempt, if any.
+	pub fn next_retry_in(&self) -> Option<Duration> {
+		match self.retry_state {
+			RetryState::Idle => None,
+			RetryState::Pending { at, .. } => {
+				let now = Instant::now();
+				Some(if at > now { at - now } else { Duration::from_millis(0) })
+			},
+		}
+	}
+
+	/// Set the maximum number of re-enumeration attempts after a USB arrival event.
+	pub fn set_reconnect_retries(&mut self, retries: u32) {
+		self.reconnect_retries = retries;
+	}
+
+	/// Maximum number of re-enumeration attempts after a USB arrival event.
+	pub fn reconnect_retries(&self) -> u32 {
+		self.reconnect_retries
+	}
+
+	/// Re-populate device list. Only those devices that have Ethereum app open will be added.
+	pub fn update_devices(&mut self) -> Result<usize, Error> {
+		let devices = {
+			let mut usb = self.usb.lock();
+			usb.refresh_devices();
+			usb.devices()
+		};
+		let mut new_devices = Vec::new();
+		let mut num_new_devices = 0;
+		for device in devices {
+			trace!("Checking device: {:?}", device);
+			if device.vendor_id != LEDGER_VID || !LEDGER_PIDS.contains(&device.product_id) {
+				continue;
+			}
+			match self.read_device_info(&device) {
+				Ok(accounts) => {
+					for info in accounts {
+						debug!("Found device: {:?}", info);
+						if !self.devices.iter().any(|d| d.path == info.path && d.info.address == info.info.address) {
+							num_new_devices += 1;
+						}
+						new_devices.push(info);
+					}
+				},
+				Err(e) => debug!("Error reading device info: {}", e),
+			};
+		}
+		self.device_queues.lock().retain(|path, _| new_devices.iter().any(|d| &d.path == path));
+		self.devices = new_devices;
+		Ok(num_new_devices)
+	}
+
+	/// Select key derivation path for a known chain.
+	pub fn set_key_path(&mut self, key_path: KeyPath) {
+		self.key_path = key_path;
+	}
+
+	/// Select the range of account indices exposed for each device. The end of the range is excluded.
+	pub fn set_account_index_range(&mut self, range: Range<u32>) {
+		self.account_index_range = range;
+	}
+
+	/// Set the delay between consecutive address requests sent to a device.
+	pub fn set_enumeration_delay(&mut self, delay: Duration) {
+		self.enumeration_delay = delay;
+	}
+
+	/// Delay between consecutive address requests sent to a device.
+	pub fn enumeration_delay(&self) -> Duration {
+		self.enumeration_delay
+	}
+
+	fn read_device_info(&self, dev_info: &hidapi::HidDeviceInfo) -> Result<Vec<Device>, Error> {
+		let (key_path, range, delay) = (self.key_path, self.account_index_range.clone(), self.enumeration_delay);
+		let pending = self.enqueue_at(&dev_info.path, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::read_accounts(handle, key_path, range.clone(), delay)
+		}), self.signing_timeout)?;
+		let (app_version, firmware_version, accounts) = pending.wait()?;
+		let manufacturer = dev_info.manufacturer_string.clone().unwrap_or("Unknown".to_owned());
+		let name = dev_info.product_string.clone().unwrap_or("Unknown".to_owned());
+		let serial = dev_info.serial_number.clone().unwrap_or("Unknown".to_owned());
+		Ok(accounts.into_iter().map(|(index, address)| Device {
+			path: dev_info.path.clone(),
+			index: index,
+			info: WalletInfo {
+				name: name.clone(),
+				manufacturer: manufacturer.clone(),
+				serial: serial.clone(),
+				address: address,
+				firmware_version: firmware_version.clone(),
+				app_version: Some(app_version.clone()),
+				device_type: WalletDeviceType::Ledger,
+			},
+		}).collect())
+	}
+
+	/// Read the app version, the firmware version if supported and the addresses of accounts in `range`.
+	fn read_accounts<T: HidTransport>(handle: &T, key_path: KeyPath, range: Range<u32>, delay: Duration) -> Result<(String, Option<String>, Vec<(u32, Address)>), Error> {
+		let app_version = Self::check_app_version(handle)?;
+		let accounts = Self::read_wallet_addresses(handle, key_path, range, delay)?;
+		// Older firmware does not support the version command.
+		let firmware_version = apdu::send_bolos_apdu(handle, commands::GET_VERSION, 0, 0, &[])
+			.and_then(|r| apdu::parse_firmware_version(&r))
+			.map_err(|e| debug!("Error reading firmware version: {}", e))
+			.ok();
+		Ok((app_version, firmware_version, accounts))
+	}
+
+	/// Derivation path for the account with given `index`. The index replaces the last path component.
+	fn derivation_path(key_path: KeyPath, index: u32) -> Vec<u8> {
+		let mut path = match key_path {
+			KeyPath::Ethereum => ETH_DERIVATION_PATH_BE.to_vec(),
+			KeyPath::EthereumClassic => ETC_DERIVATION_PATH_BE.to_vec(),
+		};
+		let len = path.len();
+		&mut path[len - 4..].copy_from_slice(&[(index >> 24) as u8, (index >> 16) as u8, (index >> 8) as u8, index as u8]);
+		path
+	}
+
+	/// Derivation path of account `index` for a BIP-44 `coin_type`: `m/44'/<coin_type>'/0'/0/<index>`.
+	fn chain_derivation_path(key_path: KeyPath, coin_type: u32, index: u32) -> Vec<u8> {
+		if coin_type == ETH_COIN_TYPE {
+			return Self::derivation_path(key_path, index);
+		}
+		let mut path = vec![5];
+		for component in &[0x8000_002c, 0x8000_0000 | coin_type, 0x8000_0000, 0, index] {
+			path.extend_from_slice(&[(component >> 24) as u8, (component >> 16) as u8, (component >> 8) as u8, *component as u8]);
+		}
+		path
+	}
+
+	/// Check that the Ethereum app is recent enough. Returns the app version.
+	fn check_app_version<T: HidTransport>(handle: &T) -> Result<String, Error> {
+		let ver = apdu::send_apdu(handle, commands::GET_APP_CONFIGURATION, 0, 0, &[])?;
+		if ver.len() != 4 {
+			return Err(Error::Protocol("Version packet size mismatch"));
+		}
+
+		let (major, minor, patch) = (ver[1], ver[2], ver[3]);
+		if major < 1 || (major == 1 && minor == 0 && patch < 3) {
+			return Err(Error::Protocol("App version 1.0.3 is required."));
+		}
+		Ok(format!("{}.{}.{}", major, minor, patch))
+	}
+
+	/// Read addresses for all indices in `range`. Requests are sent one at a time, `delay` apart.
+	fn read_wallet_addresses<T: HidTransport>(handle: &T, key_path: KeyPath, range: Range<u32>, delay: Duration) -> Result<Vec<(u32, Address)>, Error> {
+		let mut addresses = Vec::new();
+		for index in range {
+			if !addresses.is_empty() {
+				thread::sleep(delay);
+			}
+			addresses.push((index, Self::read_wallet_address(handle, key_path, index)?));
+		}
+		Ok(addresses)
+	}
+
+	/// Queue a request for the address with `index` on the first connected device.
+	/// The result is only waited for `ADDRESS_REQUEST_TIMEOUT_MS`, so an unresponsive device doesn't stall a scan.
+	pub fn request_wallet_address(&self, key_path: KeyPath, index: u32) -> Result<Pending<Address>, Error> {
+		let device = self.devices.first().ok_or(Error::KeyNotFound)?;
+		self.enqueue_at(&device.path, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::read_wallet_address(handle, key_path, index)
+		}), Duration::from_millis(ADDRESS_REQUEST_TIMEOUT_MS))
+	}
+
+	/// Read addresses for indices `0..=max_index` with `request`, `delay` apart.
+	/// Stops early after `ADDRESS_GAP_LIMIT` consecutive failures.
+	pub fn scan_addresses<F>(max_index: u32, delay: Duration, mut request: F) -> Vec<(u32, Address)>
+		where F: FnMut(u32) -> Result<Address, Error>
+	{
+		let mut addresses = Vec::new();
+		let mut failures = 0;
+		for index in 0..max_index.saturating_add(1) {
+			if index > 0 {
+				thread::sleep(delay);
+			}
+			match request(index) {
+				Ok(address) => {
+					addresses.push((index, address));
+					failures = 0;
+				},
+				Err(e) => {
+					debug!("Error reading address {}: {}", index, e);
+					failures += 1;
+					if failures >= ADDRESS_GAP_LIMIT {
+						break;
+					}
+				},
+			}
+		}
+		addresses
+	}
+
+	fn read_wallet_address<T: HidTransport>(handle: &T, key_path: KeyPath, index: u32) -> Result<Address, Error> {
+		Self::request_address(handle, &Self::derivation_path(key_path, index), false)
+	}
+
+	/// Request address for `derivation_path`. With `display` set the device shows the address
+	/// and only responds once the user has confirmed it.
+	fn request_address<T: HidTransport>(handle: &T, derivation_path: &[u8], display: bool) -> Result<Address, Error> {
+		let p1 = if display { 0x01 } else { 0x00 };
+		let key_and_address = apdu::send_apdu(handle, commands::GET_ETH_PUBLIC_ADDRESS, p1, 0, derivation_path)?;
+		if key_and_address.len() != 107 { // 1 + 65 PK + 1 + 40 Addr (ascii-hex)
+			return Err(Error::Protocol("Key packet size mismatch"));
+		}
+		let address_string = ::std::str::from_utf8(&key_and_address[67..107])
+			.map_err(|_| Error::Protocol("Invalid address string"))?;
+
+		let address = Address::from_str(&address_string)
+			.map_err(|_| Error::Protocol("Invalid address string"))?;
+
+		Ok(address)
+	}
+
+	/// List connected wallets. This only returns wallets that are ready to be used.
+	pub fn list_devices(&self) -> Vec<WalletInfo> {
+		self.devices.iter().map(|d| d.info.clone()).collect()
+	}
+
+	/// Get wallet info.
+	pub fn device_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.devices.iter().find(|d| &d.info.address == address).map(|d| d.info.clone())
+	}
+
+	/// Queue signing of transaction data with wallet managing `address`.
+	/// Typed transactions (EIP-2930, EIP-1559) are sent with their type byte and require a recent Ethereum app.
+	/// The device expects the derivation path followed by the whole envelope, type byte included.
+	pub fn sign_transaction(&self, address: &Address, data: &[u8]) -> Result<Pending<Signature>, Error> {
+		let device = self.device(address)?;
+		let transaction_type = Self::check_transaction_type(device.info.app_version.as_ref().map(|v| v.as_str()), data)?;
+		Self::check_typed_transaction(transaction_type, data)?;
+		self.queue_transaction(device, data)
+	}
+
+	/// Check that a typed transaction carries exactly the fields of its type:
+	/// EIP-2930: `0x01 || rlp([chain_id, nonce, gas_price, gas, to, value, data, access_list])`,
+	/// EIP-1559: `0x02 || rlp([chain_id, nonce, max_priority_fee, max_fee, gas, to, value, data, access_list])`.
+	fn check_typed_transaction(transaction_type: TransactionType, data: &[u8]) -> Result<(), Error> {
+		let (field_count, error) = match transaction_type {
+			TransactionType::Legacy => return Ok(()),
+			TransactionType::AccessList => (8, "Invalid EIP-2930 transaction"),
+			TransactionType::DynamicFee => (9, "Invalid EIP-1559 transaction"),
+		};
+		if TransactionType::from_payload(data) != transaction_type || !Self::has_fields(&data[1..], field_count) {
+			return Err(Error::Protocol(error));
+		}
+		Ok(())
+	}
+
+	/// Check that `payload` is a single RLP list of `field_count` transaction fields ending with an access list.
+	fn has_fields(payload: &[u8], field_count: usize) -> bool {
+		let transaction = UntrustedRlp::new(payload);
+		let complete = transaction.payload_info().map_or(false, |info| info.total() == payload.len());
+		if !complete || !transaction.is_list() || transaction.item_count() != field_count {
+			return false;
+		}
+
+		// `to` is empty for contract creation, `data` is arbitrary and the remaining scalars are integers
+		let (to, input, access_list) = (field_count - 4, field_count - 2, field_count - 1);
+		let scalars_valid = (0..access_list).all(|index| match transaction.at(index) {
+			Ok(ref field) if index == to => field.is_data() && (field.size() == 0 || field.size() == 20),
+			Ok(ref field) if index == input => field.is_data(),
+			Ok(ref field) => field.is_data() && field.size() <= 32,
+			Err(_) => false,
+		});
+		scalars_valid && transaction.at(access_list).map_or(false, |list| Self::is_access_list(&list))
+	}
+
+	/// Check that `list` is an access list: `[[address, [storage_key, ...]], ...]`.
+	fn is_access_list(list: &UntrustedRlp) -> bool {
+		list.is_list() && list.iter().all(|entry| {
+			entry.is_list() && entry.item_count() == 2
+				&& entry.at(0).map_or(false, |address| address.is_data() && address.size() == 20)
+				&& entry.at(1).map_or(false, |keys| keys.is_list() && keys.iter().all(|key| key.is_data() && key.size() == 32))
+		})
+	}
+
+	fn queue_transaction(&self, device: &Device, data: &[u8]) -> Result<Pending<Signature>, Error> {
+		let derivation_path = Self::derivation_path(self.key_path, device.index);
+		let data = data.to_vec();
+		let progress = self.progress.clone();
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::sign_with_handle(handle, &derivation_path, &data, &|p| Self::report(&progress, p))
+		}))
+	}
+
+	/// Queue `task` on the worker of `device`, starting the worker if needed.
+	fn enqueue<R: Send + 'static>(&self, device: &Device, task: queue::Task<hidapi::HidDevice, R>) -> Result<Pending<R>, Error> {
+		self.enqueue_at(&device.path, task, self.signing_timeout)
+	}
+
+	/// Queue `task` on the worker of the device at `path`, starting the worker if needed.
+	/// The result may be waited for up to `timeout`.
+	fn enqueue_at<R: Send + 'static>(&self, path: &str, task: queue::Task<hidapi::HidDevice, R>, timeout: Duration) -> Result<Pending<R>, Error> {
+		let mut queues = self.device_queues.lock();
+		let queue = queues.entry(path.to_owned()).or_insert_with(|| {
+			let usb = self.usb.clone();
+			let path = path.to_owned();
+			DeviceQueue::start(move || Self::open(&usb, &path))
+		});
+		queue.enqueue(task, timeout)
+	}
+
+	/// Check that the app `version` is able to sign a transaction with given payload.
+	fn check_transaction_type(version: Option<&str>, data: &[u8]) -> Result<TransactionType, Error> {
+		let transaction_type = TransactionType::from_payload(data);
+		if transaction_type == TransactionType::Legacy {
+			return Ok(transaction_type);
+		}
+
+		let version = version.and_then(|v| {
+			let parts = v.split('.').map(|p| p.parse::<u8>()).collect::<Result<Vec<_>, _>>().ok();
+			match parts {
+				Some(ref p) if p.len() == 3 => Some((p[0], p[1], p[2])),
+				_ => None,
+			}
+		});
+		match version {
+			Some(version) if version >= transaction_type.required_app_version() => Ok(transaction_type),
+			_ => Err(Error::FirmwareTooOld(transaction_type)),
+		}
+	}
+
+	fn sign_with_handle<T: HidTransport>(handle: &T, derivation_path: &[u8], data: &[u8], progress: &Fn(SigningProgress)) -> Result<Signature, Error> {
+		let mut payload = derivation_path.to_vec();
+		payload.extend_from_slice(data);
+		let result = apdu::send_chunked(handle, commands::SIGN_ETH_TRANSACTION, &payload, progress)?;
+		let (v, r, s) = apdu::parse_signature(&result)?;
+		let v = match TransactionType::from_payload(data) {
+			TransactionType::Legacy => v,
+			// typed transactions carry the bare y-parity; some app versions still add 27
+			_ => v & 1,
+		};
+		Ok(Signature::from_rsv(&r, &s, v))
+	}
+
+	/// Queue signing of EIP-712 typed data with wallet managing `address`.
+	/// The device computes the final digest from the domain separator and message hashes.
+	pub fn sign_typed_data(&self, address: &Address, domain_separator: &[u8; 32], message_hash: &[u8; 32]) -> Result<Pending<Signature>, Error> {
+		let device = self.device(address)?;
+		let derivation_path = Self::derivation_path(self.key_path, device.index);
+		let (domain_separator, message_hash) = (*domain_separator, *message_hash);
+		let progress = self.progress.clone();
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::sign_typed_data_with_handle(handle, &derivation_path, &domain_separator, &message_hash, &|p| Self::report(&progress, p))
+		}))
+	}
+
+	fn sign_typed_data_with_handle<T: HidTransport>(handle: &T, derivation_path: &[u8], domain_separator: &[u8; 32], message_hash: &[u8; 32], progress: &Fn(SigningProgress)) -> Result<Signature, Error> {
+		let payload = apdu::sign_typed_data_payload(derivation_path, domain_separator, message_hash);
+		let result = apdu::send_chunked(handle, commands::SIGN_ETH_EIP_712, &payload, progress)?;
+		let (v, r, s) = apdu::parse_signature(&result)?;
+		// Message signatures are returned with `v` in the 27/28 form.
+		Ok(Signature::from_rsv(&r, &s, v.saturating_sub(27)))
+	}
+
+	/// Queue a request showing `address` on the device screen. The request completes once the user has confirmed it.
+	pub fn confirm_address(&self, address: &Address) -> Result<Pending<()>, Error> {
+		let device = self.device(address)?;
+		let derivation_path = Self::derivation_path(self.key_path, device.index);
+		let address = *address;
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::confirm_address_with_handle(handle, &derivation_path, &address)
+		}))
+	}
+
+	fn confirm_address_with_handle<T: HidTransport>(handle: &T, derivation_path: &[u8], address: &Address) -> Result<(), Error> {
+		if &Self::request_address(handle, derivation_path, true)? != address {
+			return Err(Error::Protocol("Device reported a different address"));
+		}
+		Ok(())
+	}
+
+	/// Queue a request for the address the account managing `address` has on the chain with BIP-44 `coin_type`.
+	pub fn get_address_for_chain(&self, address: &Address, coin_type: u32) -> Result<Pending<Address>, Error> {
+		let device = self.device(address)?;
+		let derivation_path = Self::chain_derivation_path(self.key_path, coin_type, device.index);
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::request_address(handle, &derivation_path, false)
+		}))
+	}
+
+	/// PIN entry stub. Ledger devices only accept the PIN on the device itself, so this
+	/// does nothing and returns `Ok(())` for any known `address`.
+	pub fn enter_pin(&self, address: &Address, _pin: &str) -> Result<(), Error> {
+		self.device(address).map(|_| ())
+	}
+
+	/// Queue a request for the public key and chain code of the account managing `address`.
+	pub fn get_public_key(&self, address: &Address) -> Result<Pending<WalletPublicKey>, Error> {
+		let device = self.device(address)?;
+		let derivation_path = Self::derivation_path(self.key_path, device.index);
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::read_public_key(handle, &derivation_path)
+		}))
+	}
+
+	fn read_public_key<T: HidTransport>(handle: &T, derivation_path: &[u8]) -> Result<WalletPublicKey, Error> {
+		// P1 = 0: no on-device confirmation, P2 = 1: return chain code
+		let response = apdu::send_apdu(handle, commands::GET_ETH_PUBLIC_ADDRESS, 0, 1, derivation_path)?;
+		if response.len() != 139 { // 1 + 65 PK + 1 + 40 Addr (ascii-hex) + 32 chain code
+			return Err(Error::Protocol("Key packet size mismatch"));
+		}
+		if response[0] != 65 || response[1] != 0x04 {
+			return Err(Error::Protocol("Invalid public key"));
+		}
+		let mut chain_code = [0u8; 32];
+		chain_code.copy_from_slice(&response[107..139]);
+		Ok(WalletPublicKey {
+			public: Public::from_slice(&response[2..66]),
+			chain_code: chain_code,
+		})
+	}
+
+	/// Queue signing of a message with wallet managing `address`, as in `personal_sign`.
+	/// The device hashes the message with the `\x19Ethereum Signed Message:\n` prefix.
+	pub fn sign_message(&self, address: &Address, message: &[u8]) -> Result<Pending<Signature>, Error> {
+		let device = self.device(address)?;
+		let derivation_path = Self::derivation_path(self.key_path, device.index);
+		let message = message.to_vec();
+		let progress = self.progress.clone();
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| {
+			Self::sign_message_with_handle(handle, &derivation_path, &message, &|p| Self::report(&progress, p))
+		}))
+	}
+
+	fn sign_message_with_handle<T: HidTransport>(handle: &T, derivation_path: &[u8], message: &[u8], progress: &Fn(SigningProgress)) -> Result<Signature, Error> {
+		let payload = apdu::sign_message_payload(derivation_path, message);
+		let result = apdu::send_chunked(handle, commands::SIGN_ETH_PERSONAL_MESSAGE, &payload, progress)?;
+		let (v, r, s) = apdu::parse_signature(&result)?;
+		if v != 27 && v != 28 {
+			return Err(Error::Protocol("Invalid signature v"));
+		}
+		Ok(Signature::from_rsv(&r, &s, v - 27))
+	}
+
+	fn report(progress: &ProgressCallback, event: SigningProgress) {
+		if let Some(ref callback) = *progress.lock() {
+			callback(event);
+		}
+	}
+
+	/// Queue a check whether the device holding `address` is locked with a PIN.
+	pub fn is_locked(&self, address: &Address) -> Result<Pending<bool>, Error> {
+		let device = self.device(address)?;
+		self.enqueue(device, Box::new(|handle: &hidapi::HidDevice| Ok(Self::is_locked_with_handle(handle))))
+	}
+
+	fn is_locked_with_handle<T: HidTransport>(handle: &T) -> bool {
+		match apdu::send_apdu(handle, commands::GET_APP_CONFIGURATION, 0, 0, &[]) {
+			Err(Error::DeviceLocked) => true,
+			_ => false,
+		}
+	}
+
+	/// Queue a keep-alive `GET_VERSION` request on each connected device.
+	pub fn ping_devices(&self) -> Vec<Result<Pending<()>, Error>> {
+		let mut paths: Vec<&str> = Vec::new();
+		let mut pings = Vec::new();
+		for device in &self.devices {
+			if paths.contains(&device.path.as_str()) {
+				continue;
+			}
+			paths.push(&device.path);
+			pings.push(self.enqueue(device, Box::new(|handle: &hidapi::HidDevice| Self::ping(handle))));
+		}
+		pings
+	}
+
+	fn ping<T: HidTransport>(handle: &T) -> Result<(), Error> {
+		apdu::send_bolos_apdu(handle, commands::GET_VERSION, 0, 0, &[]).map(|_| ())
+	}
+
+	/// Queue a raw APDU for the device with given serial number. The result is the response data followed by the status word.
+	/// For testing and firmware development only; never use it in production.
+	#[cfg(feature = "ledger-debug")]
+	pub fn send_raw(&self, serial: &str, apdu: &[u8]) -> Result<Pending<Vec<u8>>, Error> {
+		let device = self.devices.iter().find(|d| d.info.serial == serial).ok_or(Error::KeyNotFound)?;
+		let apdu = apdu.to_vec();
+		self.enqueue(device, Box::new(move |handle: &hidapi::HidDevice| apdu::send_raw(handle, &apdu)))
+	}
+
+	fn device(&self, address: &Address) -> Result<&Device, Error> {
+		self.devices.iter().find(|d| &d.info.address == address).ok_or(Error::KeyNotFound)
+	}
+
+	fn open(usb: &Mutex<hidapi::HidApi>, path: &str) -> Result<hidapi::HidDevice, Error> {
+		let mut err = Error::KeyNotFound;
+		/// Try to open device a few times.
+		for _ in 0..10 {
+			match usb.lock().open_path(&path) {
+				Ok(handle) => return Ok(handle),
+				Err(e) => err = From::from(e),
+			}
+			::std::thread::sleep(Duration::from_millis(200));
+		}
+		Err(err)
+	}
+}
+
+#[test]
+fn smoke() {
+	use rustc_serialize::hex::FromHex;
+	fn assert_signed(pending: Result<Pending<Signature>, Error>) {
+		let signature = pending.and_then(|p| p.wait()).expect("Transaction should be signed");
+		assert!(signature.r().iter().any(|b| *b != 0));
+		assert!(signature.s().iter().any(|b| *b != 0));
+	}
+	let hidapi = Arc::new(Mutex::new(hidapi::HidApi::new().unwrap()));
+	let mut manager = Manager::new(hidapi);
+	manager.update_devices().unwrap();
+
+	if let Some(address) = manager.list_devices().first().map(|d| d.address.clone()) {
+		let tx = FromHex::from_hex("eb018504a817c80082520894a6ca2e6707f2cc189794a9dd459d5b05ed1bcd1c8703f26fcfb7a22480018080").unwrap();
+		assert_signed(manager.sign_transaction(&address, &tx));
+		let large_tx = FromHex::from_hex("f8cb81968504e3b2920083024f279475b02a3c39710d6a3f2870d0d788299d48e790f180b8a4b61d27f6000000000000000000000000e1af840a5a1cb1efdf608a97aa632f4aa39ed199000000000000000000000000000000000000000000000000105ff43f46a9a800000000000000000000000000000000000000000000000000000000000000006000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000018080").unwrap();
+		assert_signed(manager.sign_transaction(&address, &large_tx));
+		let huge_tx = FromHex::from_hex("f935e98201048505d21dba00833b82608080b935946103e86003908155620d2f00600455601460055560a060405260608190527f2e2e2e00000000000000000000000000000000000000000000000000000000006080908152600d805460008290527f2e2e2e00000000000000000000000000000000000000000000000000000000068255909260008051602062003474833981519152602060026001851615610100026000190190941693909304601f0192909204820192909190620000dc565b82800160010185558215620000dc579182015b82811115620000dc578251825591602001919060010190620000bf565b5b50620001009291505b80821115620000fc5760008155600101620000e6565b5090565b5050600e8054600360ff199182168117909255604080518082019091528281527f2e2e2e00000000000000000000000000000000000000000000000000000000006020918201908152600f80546000829052825160069516949094178155937f8d1108e10bcb7c27dddfc02ed9d693a074039d026cf4ea4240b40f7d581ac80260026101006001871615026000190190951694909404601f019290920483019290620001d7565b82800160010185558215620001d7579182015b82811115620001d7578251825591602001919060010190620001ba565b5b50620001fb9291505b80821115620000fc5760008155600101620000e6565b5090565b50506010805460ff19166001179055346200000057604051620034943803806200349483398101604090815281516020830151918301516060840151919390810191015b5b5b60068054600160a060020a0319166c01000000000000000000000000338102041790558151600d80546000829052909160008051602062003474833981519152602060026101006001861615026000190190941693909304601f908101849004820193870190839010620002c157805160ff1916838001178555620002f1565b82800160010185558215620002f1579182015b82811115620002f1578251825591602001919060010190620002d4565b5b50620003159291505b80821115620000fc5760008155600101620000e6565b5090565b505080600f9080519060200190828054600181600116156101000203166002900490600052602060002090601f016020900481019282601f106200036557805160ff191683800117855562000395565b8280016001018555821562000395579182015b828111156200039557825182559160200191906001019062000378565b5b50620003b99291505b80821115620000fc5760008155600101620000e6565b5090565b5050600980546c01000000000000000000000000808602819004600160a060020a0319928316179092556007805487840293909304929091169190911790555b505050505b613066806200040e6000396000f300606060405236156102035760e060020a600035046306fdde03811461034b578063095ea7b3146103c65780630b0b6d5b146103ed5780631b1ccc47146103fc57806320e870931461047757806323b872dd1461049657806325b29d84146104c057806327187991146104df578063277ccde2146104f15780632e1fbfcd14610510578063308b2fdc14610532578063313ce5671461055457806338cc48311461057757806340eddc4e146105a057806341f4793a146105bf578063467ed261146105de578063471ad963146105fd5780634e860ebb1461060f5780634efbe9331461061e57806354786b4e1461064257806354e35ba2146106bd57806358793ad4146106d25780635abedab21461073f5780635af2f8211461074e57806360483a3f1461076d57806360d12fa0146107da578063698f2e84146108035780636a749986146108155780636d5f66391461082a5780636e9c36831461083c57806370a082311461085e5780637a290fe5146108805780637e7541461461088f57806394c41bdb1461090a57806395d89b4114610929578063962a64cd146109a4578063a0b6533214610a09578063a9059cbb14610a2b578063ab62438f14610a52578063b63ca98114610aa9578063b7c54c6f14610abb578063c4e41b2214610ada578063ca7c4dba14610af9578063cb79e31b14610b18578063dd62ed3e14610b3a575b6103495b60006000600c546000141561021b57610000565b600354600c54670de0b6b3a764000091349091020204915060009050816001600030600160a060020a031681526020019081526020016000205410156102c557600160a060020a033016600090815260016020526040902054600c54909250828115610000570466038d7ea4c68000023403905033600160a060020a03166108fc829081150290604051809050600060405180830381858888f1935050505015156102c557610000565b5b600160a060020a03338116600081815260016020908152604080832080548801905530909416825283822080548790039055601380543487900301908190559154600c548551908152918201879052845190949293927f5a0391f2a67f11ed0034b68f8cf14e7e41d6f86e0a7622f2af5ea8f07b488396928290030190a45b5050565b005b3461000057610358610b5f565b60405180806020018281038252838181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f1680156103b85780820380516001836020036101000a031916815260200191505b509250505060405180910390f35b34610000576103d9600435602435610bed565b604080519115158252519081900360200190f35b3461000057610349610c58565b005b3461000057610358610dbc565b60405180806020018281038252838181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f1680156103b85780820380516001836020036101000a031916815260200191505b509250505060405180910390f35b3461000057610484610e5a565b60408051918252519081900360200190f35b34610000576103d9600435602435604435610ef9565b604080519115158252519081900360200190f35b3461000057610484610ff3565b60408051918252519081900360200190f35b3461000057610349600435611002565b005b346100005761048461105a565b60408051918252519081900360200190f35b3461000057610484600435611061565b60408051918252519081900360200190f35b346100005761048460043561108d565b60408051918252519081900360200190f35b34610000576105616110b9565b6040805160ff9092168252519081900360200190f35b34610000576105846110c2565b60408051600160a060020a039092168252519081900360200190f35b34610000576104846110c7565b60408051918252519081900360200190f35b34610000576104846110ce565b60408051918252519081900360200190f35b34610000576104846110d5565b60408051918252519081900360200190f35b3461000057610349600435611174565b005b34610000576103496113b5565b005b34610000576103d9600435611407565b604080519115158252519081900360200190f35b3461000057610358611549565b60405180806020018281038252838181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f1680156103b85780820380516001836020036101000a031916815260200191505b509250505060405180910390f35b34610000576103496004356024356115e7565b005b346100005760408051602060046024803582810135601f8101859004850286018501909652858552610726958335959394604494939290920191819084018382808284375094965061167e95505050505050565b6040805192835290151560208301528051918290030190f35b3461000057610349611c13565b005b3461000057610484611d46565b60408051918252519081900360200190f35b346100005760408051602060046024803582810135601f81018590048502860185019096528585526107269583359593946044949392909201918190840183828082843750949650611d4d95505050505050565b6040805192835290151560208301528051918290030190f35b3461000057610584612303565b60408051600160a060020a039092168252519081900360200190f35b3461000057610349600435612313565b005b3461000057610349600435602435612347565b005b346100005761034960043561252f565b005b3461000057610484600435612941565b60408051918252519081900360200190f35b346100005761048460043561298d565b60408051918252519081900360200190f35b34610000576103496129ac565b005b3461000057610358612a13565b60405180806020018281038252838181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f1680156103b85780820380516001836020036101000a031916815260200191505b509250505060405180910390f35b3461000057610484612ab1565b60408051918252519081900360200190f35b3461000057610358612ab8565b60405180806020018281038252838181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f1680156103b85780820380516001836020036101000a031916815260200191505b509250505060405180910390f35b3461000057610484600480803590602001908201803590602001908080601f01602080910402602001604051908101604052809392919081815260200183838082843750949650612b4695505050505050565b60408051918252519081900360200190f35b3461000057610484600435612b63565b60408051918252519081900360200190f35b34610000576103d9600435602435612b8c565b604080519115158252519081900360200190f35b3461000057610349600480803590602001908201803590602001908080601f016020809104026020016040519081016040528093929190818152602001838380828437509496505093359350612c3c92505050565b005b3461000057610349600435612f38565b005b3461000057610484612f90565b60408051918252519081900360200190f35b346100005761048461300c565b60408051918252519081900360200190f35b3461000057610484613013565b60408051918252519081900360200190f35b346100005761048460043561301a565b60408051918252519081900360200190f35b3461000057610484600435602435613039565b60408051918252519081900360200190f35b600d805460408051602060026001851615610100026000190190941693909304601f81018490048402820184019092528181529291830182828015610be55780601f10610bba57610100808354040283529160200191610be5565b820191906000526020600020905b815481529060010190602001808311610bc857829003601f168201915b505050505081565b600160a060020a03338116600081815260026020908152604080832094871680845294825280832086905580518681529051929493927f8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925929181900390910190a35060015b92915050565b601a54600090600160a060020a03161515610c7257610000565b600160a060020a0333166000908152600a60205260409020541515610c9657610000565b600160a060020a0333166000908152601d602052604090205460ff1615610cbc57610000565b601b54426212750090910111610cd157610000565b600160a060020a0333166000818152601d60209081526040808320805460ff19166001179055600a8252918290208054601c805490910190555482519384529083015280517f475c7605c08471fdc551a58d2c318b163628c5852f69323a1b91c34eb0bb09339281900390910190a150601154601c54606490910490604682029010610db857601a5460068054600160a060020a031916606060020a600160a060020a0393841681020417908190556040805191909216815290517f6b8184e23a898262087be50aa3ea5de648451e63f94413e810586c25282d58c2916020908290030190a15b5b50565b604080516020808201835260008252600d8054845160026001831615610100026000190190921691909104601f810184900484028201840190955284815292939091830182828015610e4f5780601f10610e2457610100808354040283529160200191610e4f565b820191906000526020600020905b815481529060010190602001808311610e3257829003601f168201915b505050505090505b90565b600f805460408051602060026001851615610100026000190190941693909304601f8101849004840282018401909252818152600093610ef39391929091830182828015610ee95780601f10610ebe57610100808354040283529160200191610ee9565b820191906000526020600020905b815481529060010190602001808311610ecc57829003601f168201915b5050505050612b46565b90505b90565b600160a060020a038316600090815260016020526040812054829010801590610f495750600160a060020a0380851660009081526002602090815260408083203390941683529290522054829010155b8015610f555750600082115b15610fe757600160a060020a03808516600081815260016020908152604080832080548890039055878516808452818420805489019055848452600283528184203390961684529482529182902080548790039055815186815291517fddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef9281900390910190a3506001610feb56610feb565b5060005b5b9392505050565b600160a060020a033016315b90565b60065433600160a060020a0390811691161461101d57610000565b600c8190556040805182815290517f0bbd501ef336990995d82b5e3fd82a15abe1ff10c982757a1698ac5d1c3e79579181900360200190a15b5b50565b600b545b90565b6000601882815481101561000057906000526020600020906007020160005b506004015490505b919050565b6000601882815481101561000057906000526020600020906007020160005b506001015490505b919050565b600e5460ff1681565b305b90565b6013545b90565b601c545b90565b600d805460408051602060026001851615610100026000190190941693909304601f8101849004840282018401909252818152600093610ef39391929091830182828015610ee95780601f10610ebe57610100808354040283529160200191610ee9565b820191906000526020600020905b815481529060010190602001808311610ecc57829003601f168201915b5050505050612b46565b90505b90565b600654600090819033600160a060020a0390811691161461119457610000565b60008381526014602052604090205415156111ae57610000565b60008381526014602052604090206005015433600160a060020a039081169116146111d857610000565b60008381526014602052604090206005015460a060020a900460ff16156111fe57610000565b601154600084815260146020526040902060040154606490910460370292508290111561122a57610000565b60008381526014602052604081206005015460a860020a900460ff16600181116100005714156112eb57600954600084815260146020908152604080832060058101546001909101548251840185905282517fa9059cbb000000000000000000000000000000000000000000000000000000008152600160a060020a0392831660048201526024810191909152915194169363a9059cbb93604480840194938390030190829087803b156100005760325a03f1156100005750611389915050565b60008381526014602052604080822060058101546001909101549151600160a060020a039091169282156108fc02929190818181858888f160008881526014602090815260409182902060058101546001909101548351600160a060020a0390921682529181019190915281519297507f2648a7e2f9c34700b91370233666e5118fa8be3e0c21fed4f7402b941df8efdd9650829003019350915050a15b6000838152601460205260409020600501805460a060020a60ff02191660a060020a1790555b5b505050565b60065433600160a060020a039081169116146113d057610000565b6010805460ff191690556040517fb48c7f694f0a3b9b22d7e61c60ff8aebbb107314b6b698fc489ff3f017cb57e090600090a15b5b565b600060006000600760009054906101000a9004600160a060020a0316600160a060020a031663d4884b566000604051602001526040518160e060020a028152600401809050602060405180830381600087803b156100005760325a03f115610000575050604051514210905061147c57610000565b60085433600160a060020a0390811691161461149757610000565b5050600b54600160a060020a03328181166000908152600a6020526040902080549386029384019055601180548401905560128054860190556008549092916114e39130911683610ef9565b506114ee8282612b8c565b50600054601154600b5460408051918252602082018590528051600160a060020a038716927fb4d6befef2def3d17bcb13c2b882ec4fa047f33157446d3e0e6094b2a21609ac92908290030190a4600192505b5b5050919050565b604080516020808201835260008252600f8054845160026001831615610100026000190190921691909104601f810184900484028201840190955284815292939091830182828015610e4f5780601f10610e2457610100808354040283529160200191610e4f565b820191906000526020600020905b815481529060010190602001808311610e3257829003601f168201915b505050505090505b90565b60065433600160a060020a0390811691161461160257610000565b60105460ff16151561161357610000565b600160a060020a0330166000908152600160209081526040808320805485019055600c859055825484019283905580518481529051839286927f10cb430288a1696de11938bc5362c6f8c60e58808237bce4436b93a8573e00c3929081900390910190a45b5b5b5050565b6040805161010081018252600080825260208083018290528351908101845281815292820192909252606081018290526080810182905260a0810182905260c0810182905260e08101829052600654829182918291829133600160a060020a039081169116146116ed57610000565b60115460649004935060056016541115801561170c5750836005540288115b1561171657610000565b61171e612f90565b8811156117305761172d612f90565b97505b60003642604051808484808284378201915050828152602001935050505060405180910390209250600454420191506101006040519081016040528084815260200189815260200188815260200183815260200160008152602001338152602001600081526020016000815260200150905080601460008560001916815260200190815260200160002060008201518160000155602082015181600101556040820151816002019080519060200190828054600181600116156101000203166002900490600052602060002090601f016020900481019282601f1061182057805160ff191683800117855561184d565b8280016001018555821561184d579182015b8281111561184d578251825591602001919060010190611832565b5b5061186e9291505b8082111561186a5760008155600101611856565b5090565b5050606082015160038201556080820151600482015560a08201516005909101805460c084015160e09094015160f860020a90810281900460a860020a0260a860020a60ff02199582029190910460a060020a0260a060020a60ff0219606060020a95860295909504600160a060020a031990931692909217939093161792909216179055601880546001810180835582818380158290116119c9576007028160070283600052602060002091820191016119c991905b8082111561186a5760006000820160009055600182016000905560028201805460018160011615610100020316600290046000825580601f10611968575061199a565b601f01602090049060005260206000209081019061199a91905b8082111561186a5760008155600101611856565b5090565b5b50506000600382018190556004820155600581018054600160b060020a0319169055600701611925565b5090565b5b505050916000526020600020906007020160005b83909190915060008201518160000155602082015181600101556040820151816002019080519060200190828054600181600116156101000203166002900490600052602060002090601f016020900481019282601f10611a4a57805160ff1916838001178555611a77565b82800160010185558215611a77579182015b82811115611a77578251825591602001919060010190611a5c565b5b50611a989291505b8082111561186a5760008155600101611856565b5090565b5050606082015181600301556080820151816004015560a08201518160050160006101000a815481600160a060020a030219169083606060020a90810204021790555060c08201518160050160146101000a81548160ff021916908360f860020a90810204021790555060e08201518160050160156101000a81548160ff021916908360f860020a90810204021790555050505060166000815460010191905081905550426017819055507f1a1eea7d2a0f099c2f19efb4e101fcf220558c9f4fbc6961b33fbe02d3a7be908389848a3360405180866000191681526020018581526020018481526020018060200183600160a060020a031681526020018281038252848181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f168015611bee5780820380516001836020036101000a031916815260200191505b50965050505050505060405180910390a1826001955095505b5b505050509250929050565b60065460009033600160a060020a03908116911614611c3157610000565b600760009054906101000a9004600160a060020a0316600160a060020a031663d4884b566000604051602001526040518160e060020a028152600401809050602060405180830381600087803b156100005760325a03f1156100005750506040515162dd7c00014210159050611ca657610000565b604051600160a060020a0333811691309091163180156108fc02916000818181858888f1600954909550600160a060020a0316935063a9059cbb9250339150611cef9050612f90565b6000604051602001526040518360e060020a0281526004018083600160a060020a0316815260200182815260200192505050602060405180830381600087803b156100005760325a03f115610000575050505b5b50565b6016545b90565b6040805161010081018252600080825260208083018290528351908101845281815292820192909252606081018290526080810182905260a0810182905260c0810182905260e08101829052600654829182918291829133600160a060020a03908116911614611dbc57610000565b60105460ff1615611dcc57610000565b6000611dd73061298d565b1115611de257610000565b6017546212750001421015611df657610000565b6013546064900493508360055402881115611e1057610000565b30600160a060020a031631881115611e305730600160a060020a03163197505b60003642604051808484808284378201915050828152602001935050505060405180910390209250600454420191506101006040519081016040528084815260200189815260200188815260200183815260200160008152602001338152602001600081526020016001815260200150905080601460008560001916815260200190815260200160002060008201518160000155602082015181600101556040820151816002019080519060200190828054600181600116156101000203166002900490600052602060002090601f016020900481019282601f10611f2057805160ff1916838001178555611f4d565b82800160010185558215611f4d579182015b82811115611f4d578251825591602001919060010190611f32565b5b50611f6e9291505b8082111561186a5760008155600101611856565b5090565b5050606082015160038201556080820151600482015560a08201516005909101805460c084015160e09094015160f860020a90810281900460a860020a0260a860020a60ff02199582029190910460a060020a0260a060020a60ff0219606060020a95860295909504600160a060020a031990931692909217939093161792909216179055601880546001810180835582818380158290116120c9576007028160070283600052602060002091820191016120c991905b8082111561186a5760006000820160009055600182016000905560028201805460018160011615610100020316600290046000825580601f10612068575061209a565b601f01602090049060005260206000209081019061209a91905b8082111561186a5760008155600101611856565b5090565b5b50506000600382018190556004820155600581018054600160b060020a0319169055600701612025565b5090565b5b505050916000526020600020906007020160005b83909190915060008201518160000155602082015181600101556040820151816002019080519060200190828054600181600116156101000203166002900490600052602060002090601f016020900481019282601f1061214a57805160ff1916838001178555612177565b82800160010185558215612177579182015b8281111561217757825182559160200191906001019061215c565b5b506121989291505b8082111561186a5760008155600101611856565b5090565b5050606082015181600301556080820151816004015560a08201518160050160006101000a815481600160a060020a030219169083606060020a90810204021790555060c08201518160050160146101000a81548160ff021916908360f860020a90810204021790555060e08201518160050160156101000a81548160ff021916908360f860020a908102040217905550505050426017819055507f1a1eea7d2a0f099c2f19efb4e101fcf220558c9f4fbc6961b33fbe02d3a7be908389848a3360405180866000191681526020018581526020018481526020018060200183600160a060020a031681526020018281038252848181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f168015611bee5780820380516001836020036101000a031916815260200191505b50965050505050505060405180910390a1826001955095505b5b505050509250929050565b600654600160a060020a03165b90565b600854600160a060020a03161561232957610000565b60088054600160a060020a031916606060020a838102041790555b50565b60065433600160a060020a0390811691161461236257610000565b60105460ff16151561237357610000565b600760009054906101000a9004600160a060020a0316600160a060020a031663d4884b566000604051602001526040518160e060020a028152600401809050602060405180830381600087803b156100005760325a03f11561000057505060405151421090506123e257610000565b600760009054906101000a9004600160a060020a0316600160a060020a031663cdd933326000604051602001526040518160e060020a028152600401809050602060405180830381600087803b156100005760325a03f11561000057505060405151421015905061245257610000565b600854600160a060020a0316151561246957610000565b6000805482018155600160a060020a03308116808352600160209081526040808520805487019055600b8790556002825280852060088054861687529083529481902080548701905593548451868152945193169391927f8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b9259281900390910190a3600054601154600b546040805185815290517f10cb430288a1696de11938bc5362c6f8c60e58808237bce4436b93a8573e00c39181900360200190a45b5b5b5b5b5050565b604080516101008082018352600080835260208084018290528451808201865282815284860152606084018290526080840182905260a0840182905260c0840182905260e0840182905285825260148152848220855180850187528154815260018083015482850152600280840180548a51600019948216159099029390930190921604601f8101859004850287018501895280875296979496879692959394938601938301828280156126245780601f106125f957610100808354040283529160200191612624565b820191906000526020600020905b81548152906001019060200180831161260757829003601f168201915b505050918352505060038201546020820152600482015460408201526005820154600160a060020a038116606083015260ff60a060020a820481161515608084015260a09092019160a860020a909104166001811161000057905250600085815260146020526040902054909350151561269d57610000565b60008481526014602052604090206005015460a060020a900460ff16156126c357610000565b60008481526014602052604090206003015442106126e057610000565b6000848152601460209081526040808320600160a060020a033316845260060190915290205460ff161561271357610000565b600160a060020a0333166000818152600a6020908152604080832054888452601483528184206004810180548301905594845260069094019091529020805460ff19166001179055915061276684612941565b6000858152601460205260409020601880549293509091839081101561000057906000526020600020906007020160005b50600082015481600001556001820154816001015560028201816002019080546001816001161561010002031660029004828054600181600116156101000203166002900490600052602060002090601f016020900481019282601f10612801578054855561283d565b8280016001018555821561283d57600052602060002091601f016020900482015b8281111561283d578254825591600101919060010190612822565b5b5061285e9291505b8082111561186a5760008155600101611856565b5090565b5050600382810154908201556004808301549082015560059182018054929091018054600160a060020a031916606060020a600160a060020a0394851681020417808255825460a060020a60ff021990911660f860020a60a060020a9283900460ff908116820282900490930291909117808455935460a860020a60ff021990941660a860020a9485900490921681020490920291909117905560408051868152339092166020830152818101849052517f8f8bbb8c1937f844f6a094cd4c6eeab8ed5e36f83952e6306ffb2c11fffe5bce916060908290030190a15b50505050565b6000805b60185481101561298657601881815481101561000057906000526020600020906007020160005b505483141561297d57809150612986565b5b600101612945565b5b50919050565b600160a060020a0381166000908152600160205260409020545b919050565b60065433600160a060020a039081169116146129c757610000565b600160a060020a03301660009081526001602052604080822080548354038355829055517fe0987873419fe09d3c9a3e0267f4daf163e812d512f867abaf6bf9822f141a8b9190a15b5b565b60408051602080820183526000825260198054845160026001831615610100026000190190921691909104601f810184900484028201840190955284815292939091830182828015610e4f5780601f10610e2457610100808354040283529160200191610e4f565b820191906000526020600020905b815481529060010190602001808311610e3257829003601f168201915b505050505090505b90565b6011545b90565b600f805460408051602060026001851615610100026000190190941693909304601f81018490048402820184019092528181529291830182828015610be55780601f10610bba57610100808354040283529160200191610be5565b820191906000526020600020905b815481529060010190602001808311610bc857829003601f168201915b505050505081565b6000602082511115612b5757610000565b5060208101515b919050565b6000601882815481101561000057906000526020600020906007020160005b505490505b919050565b600160a060020a033316600090815260016020526040812054829010801590612bb55750600082115b15612c2d57600160a060020a03338116600081815260016020908152604080832080548890039055938716808352918490208054870190558351868152935191937fddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef929081900390910190a3506001610c5256610c52565b506000610c52565b5b92915050565b600160a060020a0333166000908152600a60205260409020541515612c6057610000565b600760009054906101000a9004600160a060020a0316600160a060020a031663d4884b566000604051602001526040518160e060020a028152600401809050602060405180830381600087803b156100005760325a03f11561000057505060405151626ebe00014210159050612cd557610000565b601b5415801590612cef5750426019600201546212750001115b15612cf957610000565b6040805160808101825283815260208082018490524262127500018284015233600160a060020a03166000908152600a8252928320546060830152815180516019805495819052939484937f944998273e477b495144fb8794c914197f3ccb46be2900f4698fd0ef743c969560026001841615610100026000190190931692909204601f90810182900483019490910190839010612da257805160ff1916838001178555612dcf565b82800160010185558215612dcf579182015b82811115612dcf578251825591602001919060010190612db4565b5b50612df09291505b8082111561186a5760008155600101611856565b5090565b505060208201518160010160006101000a815481600160a060020a030219169083606060020a908102040217905550604082015181600201556060820151816003015590505060016019600401600033600160a060020a0316815260200190815260200160002060006101000a81548160ff021916908360f860020a9081020402179055507f854a9cc4d907d23cd8dcc72af48dc0e6a87e6f76376a309a0ffa3231ce8e13363383426212750001846040518085600160a060020a031681526020018060200184815260200183600160a060020a031681526020018281038252858181518152602001915080519060200190808383829060006004602084601f0104600302600f01f150905090810190601f168015612f235780820380516001836020036101000a031916815260200191505b509550505050505060405180910390a15b5050565b60065433600160a060020a03908116911614612f5357610000565b600b819055600080546011546040519192909184917f17a7f53ef43da32c3936b4ac2b060caff5c4b03cd24b1c8e96a191eb1ec48d1591a45b5b50565b6000600960009054906101000a9004600160a060020a0316600160a060020a03166370a08231306000604051602001526040518260e060020a0281526004018082600160a060020a03168152602001915050602060405180830381600087803b156100005760325a03f115610000575050604051519150505b90565b6000545b90565b600c545b90565b600160a060020a0381166000908152600a60205260409020545b919050565b600160a060020a038083166000908152600260209081526040808320938516835292905220545b9291505056d7b6990105719101dabeb77144f2a3385c8033acd3af97e9423a695e81ad1eb500000000000000000000000069381683bde924cef65f1c97f7c8fb769a20409300000000000000000000000014f37b574242d366558db61f3335289a5035c506000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000c00000000000000000000000000000000000000000000000000000000000000018546573742074657374657220746573746573742063616d700000000000000000000000000000000000000000000000000000000000000000000000000000000354455300000000000000000000000000000000000000000000000000000000001ba033230fce515bea9de982fe85e0ec8fe892984bc3070ad633daab20eb370864d5a05deb41870e2117197b84cb71110fc0508fa7457165cb8cb82cb8d4d801e6e3f1").unwrap();
+		assert_signed(manager.sign_transaction(&address, &huge_tx));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use tiny_keccak::Keccak;
+	use ethkey::{Address, Generator, Random, sign, recover, public_to_address};
+	use ethcore_bigint::hash::H256;
+	use transport::mock::MockDevice;
+	use rlp::{RlpStream, Stream};
+	use super::{Manager, KeyPath, TransactionType, Error};
+	use super::apdu::{self, HID_PREFIX_ZERO};
+
+	/// Frame an APDU response (with a success status word) into HID reports.
+	fn apdu_response(data: &[u8]) -> Vec<Vec<u8>> {
+		apdu_response_with_status(data, 0x9000)
+	}
+
+	fn apdu_response_with_status(data: &[u8], status: u16) -> Vec<Vec<u8>> {
+		let mut message = data.to_vec();
+		message.extend_from_slice(&[(status >> 8) as u8, status as u8]);
+		let mut reports = Vec::new();
+		let mut offset = 0;
+		let mut seq = 0;
+		loop {
+			let mut report = vec![0x01, 0x01, 0x05, (seq >> 8) as u8, seq as u8];
+			if seq == 0 {
+				report.extend_from_slice(&[(message.len() >> 8) as u8, message.len() as u8]);
+			}
+			let size = ::std::cmp::min(64 - report.len(), message.len() - offset);
+			report.extend_from_slice(&message[offset..offset + size]);
+			report.resize(64, 0);
+			reports.push(report);
+			offset += size;
+			seq += 1;
+			if offset == message.len() {
+				return reports;
+			}
+		}
+	}
+
+	fn version_response() -> Vec<Vec<u8>> {
+		apdu_response(&[0x00, 1, 0, 3])
+	}
+
+	fn address_response(address: &Address) -> Vec<Vec<u8>> {
+		let mut data = vec![65];
+		data.extend_from_slice(&[4u8; 65]);
+		data.push(40);
+		data.extend_from_slice(format!("{:?}", address).as_bytes());
+		apdu_response(&data)
+	}
+
+	/// Account index encoded at the end of the derivation path in the first written report.
+	fn written_index(report: &[u8]) -> u32 {
+		let path_end = HID_PREFIX_ZERO + 12 + 17;
+		let index = &report[path_end - 4..path_end];
+		(index[0] as u32) << 24 | (index[1] as u32) << 16 | (index[2] as u32) << 8 | index[3] as u32
+	}
+
+	#[test]
+	fn should_enumerate_multiple_accounts() {
+		let addresses: Vec<Address> = vec![10.into(), 11.into(), 12.into()];
+		let mut responses = version_response();
+		for address in &addresses {
+			responses.extend(address_response(address));
+		}
+		let device = MockDevice::new(responses);
+
+		let app_version = Manager::check_app_version(&device).unwrap();
+		let accounts = Manager::read_wallet_addresses(&device, KeyPath::Ethereum, 0..3, Duration::from_millis(0)).unwrap();
+
+		assert_eq!(app_version, "1.0.3");
+		assert_eq!(accounts, vec![(0, addresses[0]), (1, addresses[1]), (2, addresses[2])]);
+		let written = device.written.borrow();
+		// one version request followed by one address request per index
+		assert_eq!(written.len(), 4);
+		assert_eq!(written[1..].iter().map(|r| written_index(r)).collect::<Vec<_>>(), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn should_stop_address_scan_at_gap_limit() {
+		// given
+		let addresses: Vec<Address> = (0..5).map(|i| Address::from(100 + i)).collect();
+		let mut responses = Vec::new();
+		for address in &addresses {
+			responses.extend(address_response(address));
+		}
+		for _ in 5..20 {
+			responses.extend(apdu_response_with_status(&[], 0x6a80));
+		}
+		let device = MockDevice::new(responses);
+
+		// when
+		let scanned = Manager::scan_addresses(19, Duration::from_millis(0), |index| Manager::read_wallet_address(&device, KeyPath::Ethereum, index));
+
+		// then
+		assert_eq!(scanned, addresses.into_iter().enumerate().map(|(i, a)| (i as u32, a)).collect::<Vec<_>>());
+		let written = device.written.borrow();
+		// indices 5, 6 and 7 fail
+		assert_eq!(written.len(), 8);
+		assert_eq!(written_index(&written[7]), 7);
+	}
+
+	#[test]
+	fn should_end_address_scan_on_unresponsive_device() {
+		use std::thread;
+		use std::time::Instant;
+		use super::queue::DeviceQueue;
+
+		// given
+		let queue = DeviceQueue::start(|| Ok(MockDevice::new(Vec::new())));
+		let start = Instant::now();
+
+		// when
+		let scanned = Manager::scan_addresses(19, Duration::from_millis(0), |_| {
+			// the device never answers in time
+			queue.enqueue(Box::new(|_: &MockDevice| {
+				thread::sleep(Duration::from_secs(1));
+				Ok(Address::default())
+			}), Duration::from_millis(20)).and_then(|p| p.wait())
+		});
+
+		// then
+		assert!(scanned.is_empty());
+		assert!(start.elapsed() < Duration::from_millis(500));
+	}
+
+	#[test]
+	fn should_sign_with_account_derivation_index() {
+		let mut signature = vec![1u8];
+		signature.extend_from_slice(&[2u8; 64]);
+		let device = MockDevice::new(apdu_response(&signature));
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 7);
+		let result = Manager::sign_with_handle(&device, &path, &[0xc0], &|_| ()).unwrap();
+
+		assert_eq!(result.v(), 1);
+		assert_eq!(written_index(&device.written.borrow()[0]), 7);
+	}
+
+	/// Unsigned EIP-1559 transaction: `0x02 || rlp([chain_id, nonce, max_priority_fee, max_fee, gas, to, value, data, access_list])`.
+	fn dynamic_fee_transaction() -> Vec<u8> {
+		let mut stream = RlpStream::new_list(9);
+		stream.append(&1u64).append(&0u64).append(&1_000_000_000u64).append(&20_000_000_000u64).append(&21000u64);
+		stream.append(&Address::from(5)).append(&1u64).append_empty_data().begin_list(0);
+		let mut transaction = vec![0x02];
+		transaction.extend(stream.out());
+		transaction
+	}
+
+	#[test]
+	fn should_sign_dynamic_fee_transaction() {
+		// given
+		let transaction = dynamic_fee_transaction();
+		let mut signature = vec![28u8];
+		signature.extend_from_slice(&[2u8; 64]);
+		let device = MockDevice::new(apdu_response(&signature));
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+
+		// when
+		assert_eq!(Manager::check_transaction_type(Some("1.7.0"), &transaction).unwrap(), TransactionType::DynamicFee);
+		let result = Manager::sign_with_handle(&device, &path, &transaction, &|_| ()).unwrap();
+
+		// then
+		assert!(result.v() == 0 || result.v() == 1);
+		assert_eq!(result.v(), 1);
+		// the type byte directly follows the derivation path
+		assert_eq!(device.written.borrow()[0][HID_PREFIX_ZERO + 12 + path.len()], 0x02);
+	}
+
+	/// Unsigned EIP-2930 transaction: `0x01 || rlp([chain_id, nonce, gas_price, gas, to, value, data, access_list])`.
+	fn access_list_transaction(fields: usize) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(fields);
+		stream.append(&1u64).append(&0u64).append(&20_000_000_000u64).append(&21000u64);
+		stream.append(&Address::from(5)).append(&1u64).append_empty_data();
+		if fields > 7 {
+			stream.begin_list(1).begin_list(2).append(&Address::from(6)).begin_list(1).append(&H256::from(7));
+		}
+		if fields > 8 {
+			stream.begin_list(0);
+		}
+		let mut transaction = vec![0x01];
+		transaction.extend(stream.out());
+		transaction
+	}
+
+	#[test]
+	fn should_check_type2_transaction_fields() {
+		let transaction = dynamic_fee_transaction();
+		assert!(Manager::check_typed_transaction(TransactionType::DynamicFee, &transaction).is_ok());
+
+		// legacy field layout behind the type byte
+		let mut stream = RlpStream::new_list(6);
+		stream.append(&0u64).append(&1u64).append(&21000u64).append(&Address::from(5)).append(&1u64).append_empty_data();
+		let mut invalid = vec![0x02];
+		invalid.extend(stream.out());
+		assert!(Manager::check_typed_transaction(TransactionType::DynamicFee, &invalid).is_err());
+
+		// EIP-2930 fields behind the EIP-1559 type byte
+		let mut invalid = access_list_transaction(8);
+		invalid[0] = 0x02;
+		assert!(Manager::check_typed_transaction(TransactionType::DynamicFee, &invalid).is_err());
+	}
+
+	#[test]
+	fn should_check_type1_transaction_fields() {
+		assert!(Manager::check_typed_transaction(TransactionType::AccessList, &access_list_transaction(8)).is_ok());
+		// missing access list
+		assert!(Manager::check_typed_transaction(TransactionType::AccessList, &access_list_transaction(7)).is_err());
+		// trailing list after the access list
+		assert!(Manager::check_typed_transaction(TransactionType::AccessList, &access_list_transaction(9)).is_err());
+	}
+
+	#[test]
+	fn should_reject_malformed_access_list() {
+		// given
+		let mut stream = RlpStream::new_list(8);
+		stream.append(&1u64).append(&0u64).append(&20_000_000_000u64).append(&21000u64);
+		stream.append(&Address::from(5)).append(&1u64).append_empty_data();
+		stream.begin_list(1).begin_list(1).append(&Address::from(6));
+		let mut transaction = vec![0x01];
+		transaction.extend(stream.out());
+
+		// then
+		assert!(Manager::check_typed_transaction(TransactionType::AccessList, &transaction).is_err());
+	}
+
+	#[test]
+	fn should_reject_typed_transaction_with_trailing_bytes() {
+		// given
+		let mut transaction = dynamic_fee_transaction();
+		transaction.push(0xc0);
+
+		// then
+		assert!(Manager::check_typed_transaction(TransactionType::DynamicFee, &transaction).is_err());
+	}
+
+	#[test]
+	fn should_reject_typed_transactions_on_old_app() {
+		let transaction = dynamic_fee_transaction();
+		let mut access_list_transaction = transaction.clone();
+		access_list_transaction[0] = 0x01;
+
+		assert_eq!(Manager::check_transaction_type(Some("1.0.3"), &[0xc0]).unwrap(), TransactionType::Legacy);
+		assert_eq!(Manager::check_transaction_type(Some("1.6.0"), &access_list_transaction).unwrap(), TransactionType::AccessList);
+		match Manager::check_transaction_type(Some("1.6.9"), &transaction) {
+			Err(Error::FirmwareTooOld(TransactionType::DynamicFee)) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match Manager::check_transaction_type(None, &access_list_transaction) {
+			Err(Error::FirmwareTooOld(TransactionType::AccessList)) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_sign_typed_data() {
+		let domain_separator = [0x11u8; 32];
+		let message_hash = [0x22u8; 32];
+		let key = Random.generate().unwrap();
+		// The device signs keccak256(0x19 0x01 || domain_separator || message_hash).
+		let mut digest = [0u8; 32];
+		let mut keccak = Keccak::new_keccak256();
+		keccak.update(&[0x19, 0x01]);
+		keccak.update(&domain_separator);
+		keccak.update(&message_hash);
+		keccak.finalize(&mut digest);
+		let expected = sign(key.secret(), &digest.into()).unwrap();
+		let mut response = vec![expected.v() + 27];
+		response.extend_from_slice(expected.r());
+		response.extend_from_slice(expected.s());
+		let device = MockDevice::new(apdu_response(&response));
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		let signature = Manager::sign_typed_data_with_handle(&device, &path, &domain_separator, &message_hash, &|_| ()).unwrap();
+
+		let mut expected_apdu = vec![0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 0x56, 0xe0, 0x0c, 0x00, 0x00, 0x51];
+		expected_apdu.extend_from_slice(&path);
+		expected_apdu.extend_from_slice(&domain_separator[0..64 - 12 - path.len()]);
+		assert_eq!(&device.written.borrow()[0][HID_PREFIX_ZERO..], &expected_apdu[..]);
+		assert_eq!(public_to_address(&recover(&signature, &digest.into()).unwrap()), key.address());
+	}
+
+	#[test]
+	fn should_sign_personal_message() {
+		// long enough to be split into two APDUs
+		let message = vec![0x42u8; 300];
+		let key = Random.generate().unwrap();
+		let mut digest = [0u8; 32];
+		let mut keccak = Keccak::new_keccak256();
+		keccak.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+		keccak.update(&message);
+		keccak.finalize(&mut digest);
+		let expected = sign(key.secret(), &digest.into()).unwrap();
+		let mut response = vec![expected.v() + 27];
+		response.extend_from_slice(expected.r());
+		response.extend_from_slice(expected.s());
+		// the first APDU is acknowledged with an empty response
+		let mut responses = apdu_response(&[]);
+		responses.extend(apdu_response(&response));
+		let device = MockDevice::new(responses);
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		let signature = Manager::sign_message_with_handle(&device, &path, &message, &|_| ()).unwrap();
+
+		// the device reports `v` in the 27/28 form
+		assert!(response[0] == 27 || response[0] == 28);
+		assert_eq!(signature.v(), response[0] - 27);
+		assert_eq!(public_to_address(&recover(&signature, &digest.into()).unwrap()), key.address());
+		let written = device.written.borrow();
+		// first APDU: path followed by the big-endian message length
+		assert_eq!(&written[0][HID_PREFIX_ZERO + 7..HID_PREFIX_ZERO + 12], &[0xe0, 0x08, 0x00, 0x00, 0xff]);
+		assert_eq!(&written[0][HID_PREFIX_ZERO + 12 + path.len()..][0..4], &[0x00, 0x00, 0x01, 0x2c]);
+		// P1 of each APDU, taken from the first report of every APDU
+		let p1: Vec<u8> = written.iter()
+			.filter(|r| r[HID_PREFIX_ZERO + 3] == 0 && r[HID_PREFIX_ZERO + 4] == 0)
+			.map(|r| r[HID_PREFIX_ZERO + 9])
+			.collect();
+		assert_eq!(p1, vec![0x00, 0x80]);
+	}
+
+	#[test]
+	fn should_read_public_key_with_chain_code() {
+		let key = Random.generate().unwrap();
+		let chain_code = [0x33u8; 32];
+		let mut data = vec![65, 0x04];
+		data.extend_from_slice(&**key.public());
+		data.push(40);
+		data.extend_from_slice(format!("{:?}", key.address()).as_bytes());
+		data.extend_from_slice(&chain_code);
+		let device = MockDevice::new(apdu_response(&data));
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		let public_key = Manager::read_public_key(&device, &path).unwrap();
+
+		assert_eq!(public_to_address(&public_key.public), key.address());
+		assert_eq!(public_key.chain_code, chain_code);
+		// P2 requests the chain code
+		assert_eq!(&device.written.borrow()[0][HID_PREFIX_ZERO + 7..HID_PREFIX_ZERO + 11], &[0xe0, 0x02, 0x00, 0x01]);
+	}
+
+	#[test]
+	fn should_report_signing_progress() {
+		use std::cell::RefCell;
+		use SigningProgress;
+
+		let mut response = vec![27];
+		response.extend_from_slice(&[1u8; 64]);
+		let device = MockDevice::new(apdu_response(&response));
+		let events = RefCell::new(Vec::new());
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		Manager::sign_with_handle(&device, &path, &[0xc0], &|p| events.borrow_mut().push(p)).unwrap();
+		assert_eq!(*events.borrow(), vec![SigningProgress::AwaitingConfirmation, SigningProgress::Signed]);
+
+		let device = MockDevice::new(apdu_response_with_status(&[], 0x6985));
+		events.borrow_mut().clear();
+		assert!(Manager::sign_with_handle(&device, &path, &[0xc0], &|p| events.borrow_mut().push(p)).is_err());
+		assert_eq!(*events.borrow(), vec![SigningProgress::AwaitingConfirmation, SigningProgress::Rejected]);
+	}
+
+	#[test]
+	fn should_accept_pin_for_known_device_only() {
+		use std::sync::Arc;
+		use parking_lot::Mutex;
+		use hidapi;
+		use super::{Device, Error};
+		use {WalletInfo, WalletDeviceType};
+
+		let address: Address = 10.into();
+		let mut manager = Manager::new(Arc::new(Mutex::new(hidapi::HidApi::new().unwrap())));
+		manager.devices.push(Device {
+			path: "test".into(),
+			index: 0,
+			info: WalletInfo {
+				name: "Nano S".into(),
+				manufacturer: "Ledger".into(),
+				serial: "0001".into(),
+				address: address,
+				firmware_version: None,
+				app_version: None,
+				device_type: WalletDeviceType::Ledger,
+			},
+		});
+
+		assert!(manager.enter_pin(&address, "1234").is_ok());
+		match manager.enter_pin(&11.into(), "1234") {
+			Err(Error::KeyNotFound) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_request_address_confirmation() {
+		let address: Address = 10.into();
+		let device = MockDevice::new(address_response(&address));
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		Manager::confirm_address_with_handle(&device, &path, &address).unwrap();
+
+		// P1 = 1 asks the device to display the address
+		assert_eq!(&device.written.borrow()[0][HID_PREFIX_ZERO + 7..HID_PREFIX_ZERO + 11], &[0xe0, 0x02, 0x01, 0x00]);
+	}
+
+	#[test]
+	fn should_report_rejected_address() {
+		use super::Error;
+		use ::Error as HwError;
+
+		let device = MockDevice::new(apdu_response_with_status(&[], 0x6985));
+
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		match Manager::confirm_address_with_handle(&device, &path, &10.into()).map_err(HwError::from) {
+			Err(HwError::UserRejected) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_retry_with_exponential_backoff() {
+		use std::time::Instant;
+		use super::RetryState;
+
+		let start = Instant::now();
+		let ms = |n| start + Duration::from_millis(n);
+		// device fails to open twice and succeeds on the third attempt
+		let mut opens = 0;
+		let mut open = || { opens += 1; opens == 3 };
+
+		assert!(!RetryState::Idle.is_due(start));
+		let state = RetryState::start(start);
+		assert_eq!(state, RetryState::Pending { attempt: 0, at: ms(100) });
+		// not due yet
+		assert!(!state.is_due(ms(50)));
+		assert!(state.is_due(ms(100)));
+		let state = state.poll(ms(50), 5, &mut open);
+		assert_eq!(state, RetryState::Pending { attempt: 0, at: ms(100) });
+		let state = state.poll(ms(100), 5, &mut open);
+		assert_eq!(state, RetryState::Pending { attempt: 1, at: ms(300) });
+		let state = state.poll(ms(300), 5, &mut open);
+		assert_eq!(state, RetryState::Pending { attempt: 2, at: ms(700) });
+		let state = state.poll(ms(700), 5, &mut open);
+		assert_eq!(state, RetryState::Idle);
+		assert_eq!(opens, 3);
+	}
+
+	#[test]
+	fn should_cap_backoff_and_give_up() {
+		use std::time::Instant;
+		use super::RetryState;
+
+		assert_eq!(RetryState::backoff(3), Duration::from_millis(800));
+		assert_eq!(RetryState::backoff(5), Duration::from_millis(2000));
+		assert_eq!(RetryState::backoff(40), Duration::from_millis(2000));
+
+		let start = Instant::now();
+		let mut state = RetryState::start(start);
+		let mut attempts = 0;
+		while let RetryState::Pending { at, .. } = state {
+			state = state.poll(at, 3, || { attempts += 1; false });
+		}
+		assert_eq!(attempts, 3);
+	}
+
+	#[test]
+	fn should_time_out_blocking_request() {
+		use std::thread;
+		use std::time::Instant;
+		use super::Error;
+		use super::queue::DeviceQueue;
+		use ::Error as HwError;
+
+		let queue = DeviceQueue::start(|| Ok(MockDevice::new(Vec::new())));
+		let start = Instant::now();
+		// device handler that never gets a user confirmation in time
+		let result: Result<(), Error> = queue.enqueue(Box::new(|_: &MockDevice| {
+			thread::sleep(Duration::from_secs(1));
+			Ok(())
+		}), Duration::from_millis(10)).and_then(|p| p.wait());
+
+		assert!(start.elapsed() < Duration::from_millis(60));
+		match result.map_err(HwError::from) {
+			Err(HwError::LedgerDevice(Error::Timeout)) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_read_firmware_version() {
+		// GET_VERSION response captured from a Nano S running firmware 1.6.0:
+		// target id, SE version, flags, MCU version
+		let response = apdu_response(&[
+			0x31, 0x10, 0x00, 0x04,
+			0x05, 0x31, 0x2e, 0x36, 0x2e, 0x30,
+			0x04, 0xa6, 0x00, 0x00, 0x00,
+			0x04, 0x31, 0x2e, 0x31, 0x31,
+		]);
+		let device = MockDevice::new(response);
+
+		let data = apdu::send_bolos_apdu(&device, apdu::commands::GET_VERSION, 0, 0, &[]).unwrap();
+
+		assert_eq!(apdu::parse_firmware_version(&data).unwrap(), "1.6.0");
+		// sent with the OS class byte
+		assert_eq!(&device.written.borrow()[0][HID_PREFIX_ZERO + 7..HID_PREFIX_ZERO + 9], &[0xb0, 0x01]);
+	}
+
+	#[cfg(feature = "ledger-debug")]
+	#[test]
+	fn should_send_raw_apdu() {
+		// given
+		let device = MockDevice::new(apdu_response(&[0x31, 0x10, 0x00, 0x04, 0x05, 0x31, 0x2e, 0x36, 0x2e, 0x30]));
+
+		// when
+		let response = apdu::send_raw(&device, &[0xb0, apdu::commands::GET_VERSION, 0x00, 0x00, 0x00]).unwrap();
+
+		// then
+		assert_eq!(&response[response.len() - 2..], &[0x90, 0x00]);
+		assert_eq!(&response[..4], &[0x31, 0x10, 0x00, 0x04]);
+		assert_eq!(&device.written.borrow()[0][HID_PREFIX_ZERO + 7..HID_PREFIX_ZERO + 12], &[0xb0, 0x01, 0x00, 0x00, 0x00]);
+		assert!(apdu::send_raw(&device, &[0xb0, 0x01, 0x00]).is_err());
+		assert!(apdu::send_raw(&device, &[0xb0, 0x01, 0x00, 0x00, 0x02, 0xff]).is_err());
+	}
+
+	#[test]
+	fn should_serialize_concurrent_signing_requests() {
+		use std::sync::{Arc, Mutex};
+		use std::thread;
+		use super::queue::DeviceQueue;
+
+		// given
+		let mut responses = Vec::new();
+		for i in 0..3u8 {
+			let mut signature = vec![i];
+			signature.extend_from_slice(&[i + 1; 64]);
+			responses.extend(apdu_response(&signature));
+		}
+		let queue = Arc::new(Mutex::new(DeviceQueue::start(move || Ok(MockDevice::new(responses.clone())))));
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+
+		// when
+		let requests = (0..3).map(|_| {
+			let queue = queue.clone();
+			let path = path.clone();
+			thread::spawn(move || {
+				let pending = queue.lock().unwrap().enqueue(Box::new(move |handle: &MockDevice| {
+					// long payload, so the request takes several HID reports
+					Manager::sign_with_handle(handle, &path, &[0xc0; 300], &|_| ())
+				}), Duration::from_secs(5)).unwrap();
+				pending.wait().unwrap()
+			})
+		}).collect::<Vec<_>>();
+
+		// then
+		let mut v = requests.into_iter().map(|r| r.join().unwrap().v()).collect::<Vec<_>>();
+		v.sort();
+		assert_eq!(v, vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn should_serialize_requests_of_different_callers() {
+		use std::sync::{Arc, Mutex, mpsc};
+		use std::thread;
+		use super::queue::DeviceQueue;
+
+		// given
+		let address: Address = 10.into();
+		let key = Random.generate().unwrap();
+		let mut public_key = vec![65, 0x04];
+		public_key.extend_from_slice(&**key.public());
+		public_key.push(40);
+		public_key.extend_from_slice(format!("{:?}", key.address()).as_bytes());
+		public_key.extend_from_slice(&[0x33u8; 32]);
+		let mut responses = address_response(&address);
+		responses.extend(apdu_response(&public_key));
+		let queue = Arc::new(Mutex::new(DeviceQueue::start(move || Ok(MockDevice::new(responses.clone())))));
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+		let (started, confirming) = mpsc::channel();
+		let (confirm, confirmed) = mpsc::channel::<()>();
+		let confirmed = Arc::new(Mutex::new(confirmed));
+
+		// when
+		let first = {
+			let queue = queue.clone();
+			let path = path.clone();
+			thread::spawn(move || {
+				let pending = queue.lock().unwrap().enqueue(Box::new(move |handle: &MockDevice| {
+					// the user takes a while to confirm the address on the device
+					started.send(()).unwrap();
+					let _ = confirmed.lock().unwrap().recv();
+					Manager::confirm_address_with_handle(handle, &path, &address)
+				}), Duration::from_secs(5)).unwrap();
+				pending.wait()
+			})
+		};
+		confirming.recv().unwrap();
+		let second = {
+			let queue = queue.clone();
+			thread::spawn(move || {
+				let pending = queue.lock().unwrap().enqueue(Box::new(move |handle: &MockDevice| {
+					Manager::read_public_key(handle, &path)
+				}), Duration::from_secs(5)).unwrap();
+				pending.wait()
+			})
+		};
+		confirm.send(()).unwrap();
+
+		// then
+		assert!(first.join().unwrap().is_ok());
+		assert_eq!(public_to_address(&second.join().unwrap().unwrap().public), key.address());
+	}
+
+	#[test]
+	fn should_refuse_requests_when_queue_is_full() {
+		use std::sync::{Arc, Mutex, mpsc};
+		use ethkey::Signature;
+		use super::queue::{DeviceQueue, QUEUE_SIZE};
+
+		// given
+		let (release, blocked) = mpsc::channel::<()>();
+		let blocked = Arc::new(Mutex::new(blocked));
+		let queue = DeviceQueue::start(|| Ok(MockDevice::new(Vec::new())));
+
+		// when
+		let mut pending = Vec::new();
+		let mut busy = None;
+		while busy.is_none() {
+			let blocked = blocked.clone();
+			match queue.enqueue(Box::new(move |_: &MockDevice| {
+				let _ = blocked.lock().unwrap().recv();
+				Ok(Signature::default())
+			}), Duration::from_secs(5)) {
+				Ok(p) => pending.push(p),
+				Err(e) => busy = Some(e),
+			}
+			assert!(pending.len() <= QUEUE_SIZE + 1);
+		}
+
+		// then
+		match busy {
+			Some(Error::DeviceBusy) => (),
+			other => panic!("Unexpected error: {:?}", other),
+		}
+		assert!(pending.len() >= QUEUE_SIZE);
+		for _ in 0..pending.len() {
+			release.send(()).unwrap();
+		}
+		for p in pending {
+			assert!(p.wait().is_ok());
+		}
+	}
+
+	#[test]
+	fn should_detect_locked_device() {
+		// given
+		let locked = || MockDevice::new(apdu_response_with_status(&[], 0x6804));
+		let path = Manager::derivation_path(KeyPath::Ethereum, 0);
+
+		// then
+		assert!(Manager::is_locked_with_handle(&locked()));
+		assert!(!Manager::is_locked_with_handle(&MockDevice::new(version_response())));
+		match Manager::sign_with_handle(&locked(), &path, &[0xc0], &|_| ()) {
+			Err(Error::DeviceLocked) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match ::Error::from(Error::DeviceLocked) {
+			::Error::DeviceLocked => (),
+			other => panic!("Unexpected error: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_reject_truncated_firmware_version() {
+		assert!(apdu::parse_firmware_version(&[0x31, 0x10, 0x00, 0x04, 0x05, 0x31, 0x2e]).is_err());
+	}
+
+	#[test]
+	fn should_derive_chain_specific_addresses() {
+		// given
+		let ethereum: Address = 10.into();
+		let xdai: Address = 11.into();
+		let mut responses = address_response(&ethereum);
+		responses.extend(address_response(&xdai));
+		let device = MockDevice::new(responses);
+		let ethereum_path = Manager::chain_derivation_path(KeyPath::Ethereum, 60, 0);
+		let xdai_path = Manager::chain_derivation_path(KeyPath::Ethereum, 700, 0);
+
+		// when
+		let first = Manager::request_address(&device, &ethereum_path, false).unwrap();
+		let second = Manager::request_address(&device, &xdai_path, false).unwrap();
+
+		// then
+		assert_eq!(ethereum_path, Manager::derivation_path(KeyPath::Ethereum, 0));
+		assert_eq!(xdai_path, vec![5, 0x80, 0, 0, 44, 0x80, 0, 0x02, 0xbc, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+		assert_eq!((first, second), (ethereum, xdai));
+		assert!(first != second);
+		let written = device.written.borrow();
+		assert_eq!(&written[0][HID_PREFIX_ZERO + 12..HID_PREFIX_ZERO + 12 + 17], &ethereum_path[..]);
+		assert_eq!(&written[1][HID_PREFIX_ZERO + 12..HID_PREFIX_ZERO + 12 + 21], &xdai_path[..]);
+	}
+}
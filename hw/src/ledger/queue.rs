@@ -0,0 +1,112 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Per-device request queue. A worker thread owns the device connection exclusively,
+//! so APDU exchanges of concurrent requests can't interleave.
+
+use std::sync::mpsc::{self, TrySendError, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use transport::HidTransport;
+use super::Error;
+
+/// Maximal number of requests waiting for a device.
+pub const QUEUE_SIZE: usize = 8;
+
+/// Operation run on the device connection.
+pub type Task<T, R> = Box<Fn(&T) -> Result<R, Error> + Send>;
+
+/// Queued operation along with its reply channel. Gets the device connection or the error opening it.
+/// Returns `true` if the connection should be reopened.
+type Job<T> = Box<Fn(Result<&T, Error>) -> bool + Send>;
+
+/// Result of a request queued for the worker thread.
+pub struct Pending<R> {
+	result: mpsc::Receiver<Result<R, Error>>,
+	timeout: Duration,
+}
+
+impl<R> Pending<R> {
+	/// Wait for the result. Fails with `Error::Timeout` if the request is not completed in time.
+	pub fn wait(self) -> Result<R, Error> {
+		match self.result.recv_timeout(self.timeout) {
+			Ok(result) => result,
+			Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+			Err(RecvTimeoutError::Disconnected) => Err(Error::Protocol("Device worker has stopped")),
+		}
+	}
+}
+
+/// Requests waiting for a single device.
+pub struct DeviceQueue<T> {
+	jobs: mpsc::SyncSender<Job<T>>,
+}
+
+impl<T: HidTransport + 'static> DeviceQueue<T> {
+	/// Start the worker thread. The device is opened with `open` on the worker thread
+	/// and kept open until the queue is dropped or a USB error occurs.
+	pub fn start<F>(open: F) -> DeviceQueue<T> where F: Fn() -> Result<T, Error> + Send + 'static {
+		let (tx, rx) = mpsc::sync_channel::<Job<T>>(QUEUE_SIZE);
+		let worker = thread::Builder::new().name("ledger_worker".to_owned()).spawn(move || {
+			let mut handle = None;
+			for job in rx {
+				if handle.is_none() {
+					match open() {
+						Ok(h) => handle = Some(h),
+						Err(e) => {
+							job(Err(e));
+							continue;
+						},
+					}
+				}
+				if job(Ok(handle.as_ref().expect("device opened above; qed"))) {
+					handle = None;
+				}
+			}
+		});
+		if let Err(e) = worker {
+			warn!("Error starting Ledger worker thread: {}", e);
+		}
+		DeviceQueue {
+			jobs: tx,
+		}
+	}
+
+	/// Queue `task`. The returned result may be waited for up to `timeout`.
+	/// Fails with `Error::DeviceBusy` if the queue is full.
+	pub fn enqueue<R: Send + 'static>(&self, task: Task<T, R>, timeout: Duration) -> Result<Pending<R>, Error> {
+		let (reply, result) = mpsc::channel();
+		let job: Job<T> = Box::new(move |handle| {
+			let result = handle.and_then(|handle| task(handle));
+			let reopen = match result {
+				Err(Error::Usb(_)) => true,
+				_ => false,
+			};
+			// The receiver is gone if the request has timed out.
+			let _ = reply.send(result);
+			reopen
+		});
+		match self.jobs.try_send(job) {
+			Ok(()) => Ok(Pending {
+				result: result,
+				timeout: timeout,
+			}),
+			Err(TrySendError::Full(_)) => Err(Error::DeviceBusy),
+			Err(TrySendError::Disconnected(_)) => Err(Error::Protocol("Device worker has stopped")),
+		}
+	}
+}
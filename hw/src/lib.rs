@@ -21,31 +21,227 @@ extern crate hidapi;
 extern crate libusb;
 extern crate ethkey;
 extern crate ethcore_bigint;
+extern crate rlp;
+extern crate serde_json;
 #[macro_use] extern crate log;
 #[cfg(test)] extern crate rustc_serialize;
+#[cfg(test)] extern crate ethcore_devtools as devtools;
+#[cfg(any(test, feature = "test-support"))] extern crate tiny_keccak;
 
+mod aliases;
 mod ledger;
+mod trezor;
+mod keepkey;
+mod transport;
+#[cfg(any(test, feature = "test-support"))]
+pub mod mock;
 
-use std::fmt;
+use std::{fmt, io};
+use std::cmp::min;
+use std::path::PathBuf;
 use std::thread;
 use std::sync::atomic;
-use std::sync::{Arc, Weak};
-use std::sync::atomic::AtomicBool;
-use std::time::Duration;
+use std::sync::{Arc, Weak, mpsc};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::time::{Duration, Instant};
+use std::ops::Range;
 use parking_lot::Mutex;
-use ethkey::{Address, Signature};
+use ethkey::{Address, Public, Signature};
+use aliases::DeviceAliases;
 
-pub use ledger::KeyPath;
+pub use ledger::{KeyPath, TransactionType};
 
 /// Hardware waller error.
 #[derive(Debug)]
 pub enum Error {
 	/// Ledger device error.
 	LedgerDevice(ledger::Error),
+	/// Trezor or KeepKey device error.
+	TrezorDevice(trezor::Error),
+	/// HID error.
+	Hid(hidapi::HidError),
 	/// USB error.
 	Usb(libusb::Error),
+	/// USB library failed to initialize.
+	UsbContextInit(String),
+	/// USB hotplug callback could not be registered.
+	CallbackRegistration(String),
 	/// Hardware wallet not found for specified key.
 	KeyNotFound,
+	/// User has rejected the request on the device.
+	UserRejected,
+	/// Wallet does not support entering the PIN from the host.
+	PinNotSupported,
+	/// Device is locked with a PIN.
+	DeviceLocked,
+	/// Too many requests are waiting for the device.
+	DeviceBusy,
+	/// Invalid manager configuration.
+	Config(ConfigError),
+	/// Error reading or writing manager files.
+	Io(io::Error),
+}
+
+/// Hardware wallet configuration error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+	/// USB event polling interval is out of the supported range.
+	PollingInterval(Duration),
+	/// Signing timeout must be non-zero.
+	SigningTimeout,
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			ConfigError::PollingInterval(ref d) => write!(f, "Polling interval of {}ms is out of range. Expected between {}ms and {}ms.",
+				d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000, MIN_POLLING_INTERVAL_MS, MAX_POLLING_INTERVAL_MS),
+			ConfigError::SigningTimeout => write!(f, "Signing timeout must be greater than zero."),
+		}
+	}
+}
+
+const MIN_POLLING_INTERVAL_MS: u64 = 10;
+const MAX_POLLING_INTERVAL_MS: u64 = 5000;
+const DEFAULT_POLLING_INTERVAL_MS: u64 = 500;
+/// USB events arriving within this time after an accepted event are discarded.
+const DEFAULT_DEBOUNCE_MS: u64 = 100;
+/// Delay between an accepted USB event and the device enumeration it triggers.
+const UPDATE_DELAY_MS: u64 = 50;
+
+/// Hardware wallet manager configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardwareWalletConfig {
+	/// Maximal time the update thread waits for USB events before checking for other work.
+	pub polling_interval: Duration,
+	/// Interval between keep-alive requests sent to idle devices. Disabled if `None`.
+	pub keepalive_interval: Option<Duration>,
+}
+
+impl Default for HardwareWalletConfig {
+	fn default() -> Self {
+		HardwareWalletConfig {
+			polling_interval: Duration::from_millis(DEFAULT_POLLING_INTERVAL_MS),
+			keepalive_interval: None,
+		}
+	}
+}
+
+impl HardwareWalletConfig {
+	/// Set USB event polling interval.
+	pub fn polling_interval(mut self, interval: Duration) -> Self {
+		self.polling_interval = interval;
+		self
+	}
+
+	/// Send keep-alive requests to connected devices every `interval`.
+	pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+		self.keepalive_interval = Some(interval);
+		self
+	}
+
+	/// Check that all values are within supported bounds.
+	pub fn validate(&self) -> Result<(), ConfigError> {
+		if self.polling_interval < Duration::from_millis(MIN_POLLING_INTERVAL_MS) || self.polling_interval > Duration::from_millis(MAX_POLLING_INTERVAL_MS) {
+			return Err(ConfigError::PollingInterval(self.polling_interval));
+		}
+		Ok(())
+	}
+}
+
+/// Builds a `HardwareWalletManager` with non-default settings.
+#[derive(Default)]
+pub struct HardwareWalletManagerBuilder {
+	config: HardwareWalletConfig,
+	signing_timeout: Option<Duration>,
+	reconnect_retries: Option<u32>,
+	event_channel: Option<mpsc::Sender<DeviceEvent>>,
+	without_ledger: bool,
+	debounce: Option<Duration>,
+	aliases_path: Option<PathBuf>,
+}
+
+impl HardwareWalletManagerBuilder {
+	/// Set USB event polling interval.
+	pub fn polling_interval(&mut self, interval: Duration) -> &mut Self {
+		self.config.polling_interval = interval;
+		self
+	}
+
+	/// Send keep-alive requests to connected devices every `interval`. Disabled by default.
+	pub fn keepalive_interval(&mut self, interval: Duration) -> &mut Self {
+		self.config.keepalive_interval = Some(interval);
+		self
+	}
+
+	/// Set the time to wait for the user to confirm a signing request on a Ledger device.
+	pub fn signing_timeout(&mut self, timeout: Duration) -> &mut Self {
+		self.signing_timeout = Some(timeout);
+		self
+	}
+
+	/// Publish wallet arrival and departure events to `channel` from the start.
+	pub fn event_channel(&mut self, channel: mpsc::Sender<DeviceEvent>) -> &mut Self {
+		self.event_channel = Some(channel);
+		self
+	}
+
+	/// Set the maximum number of Ledger re-enumeration attempts after a USB device arrives.
+	pub fn reconnect_retries(&mut self, retries: u32) -> &mut Self {
+		self.reconnect_retries = Some(retries);
+		self
+	}
+
+	/// Set the time after a USB event during which further events are discarded. Defaults to 100ms.
+	pub fn debounce(&mut self, window: Duration) -> &mut Self {
+		self.debounce = Some(window);
+		self
+	}
+
+	/// Keep device aliases in the JSON file at `path`. Without it aliases are not persisted.
+	pub fn aliases_path(&mut self, path: PathBuf) -> &mut Self {
+		self.aliases_path = Some(path);
+		self
+	}
+
+	/// Enable or disable Ledger support. Enabled by default.
+	pub fn with_ledger(&mut self, enabled: bool) -> &mut Self {
+		self.without_ledger = !enabled;
+		self
+	}
+
+	/// Check the configuration and start the manager.
+	pub fn build(&self) -> Result<HardwareWalletManager, Error> {
+		self.config.validate()?;
+		if self.signing_timeout == Some(Duration::from_secs(0)) {
+			return Err(ConfigError::SigningTimeout.into());
+		}
+		HardwareWalletManager::start(self)
+	}
+}
+
+/// Kind of hardware wallet device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WalletDeviceType {
+	/// Ledger Nano S or Blue.
+	Ledger,
+	/// Trezor.
+	Trezor,
+	/// KeepKey.
+	KeepKey,
+	/// Device of unknown make.
+	Unknown,
+}
+
+impl fmt::Display for WalletDeviceType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			WalletDeviceType::Ledger => write!(f, "Ledger"),
+			WalletDeviceType::Trezor => write!(f, "Trezor"),
+			WalletDeviceType::KeepKey => write!(f, "KeepKey"),
+			WalletDeviceType::Unknown => write!(f, "Unknown"),
+		}
+	}
 }
 
 /// Hardware waller information.
@@ -59,14 +255,30 @@ pub struct WalletInfo {
 	pub serial: String,
 	/// Ethereum address.
 	pub address: Address,
+	/// Device firmware version, if reported by the device.
+	pub firmware_version: Option<String>,
+	/// Version of the Ethereum app running on the device, if any.
+	pub app_version: Option<String>,
+	/// Kind of device.
+	pub device_type: WalletDeviceType,
 }
 
 impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
 		match *self {
 			Error::KeyNotFound => write!(f, "Key not found for given address."),
+			Error::UserRejected => write!(f, "Request rejected on the device."),
+			Error::PinNotSupported => write!(f, "PIN entry is not supported by the wallet."),
+			Error::DeviceLocked => write!(f, "Device is locked."),
+			Error::DeviceBusy => write!(f, "Device is busy."),
 			Error::LedgerDevice(ref e) => write!(f, "{}", e),
+			Error::TrezorDevice(ref e) => write!(f, "{}", e),
+			Error::Hid(ref e) => write!(f, "{}", e),
 			Error::Usb(ref e) => write!(f, "{}", e),
+			Error::UsbContextInit(ref e) => write!(f, "Error initializing USB: {}", e),
+			Error::CallbackRegistration(ref e) => write!(f, "Error registering USB hotplug callback: {}", e),
+			Error::Config(ref e) => write!(f, "{}", e),
+			Error::Io(ref e) => write!(f, "{}", e),
 		}
 	}
 }
@@ -75,38 +287,288 @@ impl From<ledger::Error> for Error {
 	fn from(err: ledger::Error) -> Error {
 		match err {
 			ledger::Error::KeyNotFound => Error::KeyNotFound,
+			ledger::Error::UserRejected => Error::UserRejected,
+			ledger::Error::DeviceLocked => Error::DeviceLocked,
+			ledger::Error::DeviceBusy => Error::DeviceBusy,
 			_ => Error::LedgerDevice(err),
 		}
 	}
 }
 
+impl From<trezor::Error> for Error {
+	fn from(err: trezor::Error) -> Error {
+		match err {
+			trezor::Error::KeyNotFound => Error::KeyNotFound,
+			_ => Error::TrezorDevice(err),
+		}
+	}
+}
+
+impl From<hidapi::HidError> for Error {
+	fn from(err: hidapi::HidError) -> Error {
+		Error::Hid(err)
+	}
+}
+
 impl From<libusb::Error> for Error {
 	fn from(err: libusb::Error) -> Error {
 		Error::Usb(err)
 	}
 }
 
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Error {
+		Error::Io(err)
+	}
+}
+
+impl From<ConfigError> for Error {
+	fn from(err: ConfigError) -> Error {
+		Error::Config(err)
+	}
+}
+
+/// Progress of a signing request waiting for the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SigningProgress {
+	/// Request has been sent and is waiting for confirmation on the device.
+	AwaitingConfirmation,
+	/// User has confirmed the request.
+	Signed,
+	/// User has rejected the request.
+	Rejected,
+}
+
+/// Hardware wallet connection event.
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+	/// A wallet has become available.
+	Arrived(WalletInfo),
+	/// Wallet with given address is no longer available.
+	Left(Address),
+}
+
+/// Send events for wallets that appear in `after` but not in `before` and vice versa.
+/// Returns `false` if the receiver has been dropped.
+fn publish_changes(tx: &mpsc::Sender<DeviceEvent>, before: &[WalletInfo], after: &[WalletInfo]) -> bool {
+	let arrived = after.iter()
+		.filter(|w| !before.iter().any(|b| b.address == w.address))
+		.map(|w| DeviceEvent::Arrived(w.clone()));
+	let left = before.iter()
+		.filter(|b| !after.iter().any(|w| w.address == b.address))
+		.map(|b| DeviceEvent::Left(b.address));
+	arrived.chain(left).all(|event| tx.send(event).is_ok())
+}
+
 /// Hardware wallet management interface.
 pub struct HardwareWalletManager {
 	update_thread: Option<thread::JoinHandle<()>>,
 	exiting: Arc<AtomicBool>,
-	ledger: Arc<Mutex<ledger::Manager>>,
+	ledger: Option<Arc<Mutex<ledger::Manager>>>,
+	trezor: Option<Arc<Mutex<trezor::Manager>>>,
+	keepkey: Option<Arc<Mutex<trezor::Manager>>>,
+	device_event_tx: Arc<Mutex<Option<mpsc::Sender<DeviceEvent>>>>,
+	aliases: Mutex<DeviceAliases>,
+	keepalive_stats: Arc<KeepaliveStats>,
+	/// Backends requests for a wallet are dispatched to.
+	wallets: Vec<Arc<Wallet>>,
+}
+
+/// Wallet backend of `HardwareWalletManager`. Requests for an address go to the backend listing a wallet
+/// with that address. Requests a backend does not support fail with `Error::KeyNotFound`.
+pub trait Wallet: Send + Sync {
+	/// List connected wallets.
+	fn list_devices(&self) -> Vec<WalletInfo>;
+	/// Get connected wallet info.
+	fn device_info(&self, address: &Address) -> Option<WalletInfo>;
+	/// Sign RLP encoded transaction `data`.
+	fn sign_transaction(&self, address: &Address, data: &[u8]) -> Result<Signature, Error>;
+	/// Sign a message, as in `personal_sign`.
+	fn sign_message(&self, address: &Address, message: &[u8]) -> Result<Signature, Error>;
+	/// Sign EIP-712 typed data.
+	fn sign_typed_data(&self, address: &Address, domain_separator: &[u8; 32], message_hash: &[u8; 32]) -> Result<Signature, Error>;
+	/// Show `address` on the device and wait for the user to confirm it.
+	fn confirm_address(&self, address: &Address) -> Result<(), Error>;
+	/// Unlock the wallet with `pin`.
+	fn enter_pin(&self, address: &Address, pin: &str) -> Result<(), Error>;
+	/// Check whether the device is locked with a PIN.
+	fn is_locked(&self, address: &Address) -> bool;
+}
+
+impl Wallet for Mutex<ledger::Manager> {
+	fn list_devices(&self) -> Vec<WalletInfo> {
+		self.lock().list_devices()
+	}
+
+	fn device_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.lock().device_info(address)
+	}
+
+	// Requests are queued for the device. Wait for them without holding the lock, so other requests can be queued.
+
+	fn sign_transaction(&self, address: &Address, data: &[u8]) -> Result<Signature, Error> {
+		let pending = self.lock().sign_transaction(address, data)?;
+		Ok(pending.wait()?)
+	}
+
+	fn sign_message(&self, address: &Address, message: &[u8]) -> Result<Signature, Error> {
+		let pending = self.lock().sign_message(address, message)?;
+		Ok(pending.wait()?)
+	}
+
+	fn sign_typed_data(&self, address: &Address, domain_separator: &[u8; 32], message_hash: &[u8; 32]) -> Result<Signature, Error> {
+		let pending = self.lock().sign_typed_data(address, domain_separator, message_hash)?;
+		Ok(pending.wait()?)
+	}
+
+	fn confirm_address(&self, address: &Address) -> Result<(), Error> {
+		let pending = self.lock().confirm_address(address)?;
+		Ok(pending.wait()?)
+	}
+
+	fn enter_pin(&self, address: &Address, pin: &str) -> Result<(), Error> {
+		Ok(self.lock().enter_pin(address, pin)?)
+	}
+
+	fn is_locked(&self, address: &Address) -> bool {
+		let pending = self.lock().is_locked(address);
+		pending.and_then(|p| p.wait()).unwrap_or(false)
+	}
+}
+
+impl Wallet for Mutex<trezor::Manager> {
+	fn list_devices(&self) -> Vec<WalletInfo> {
+		self.lock().list_devices()
+	}
+
+	fn device_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.lock().device_info(address)
+	}
+
+	fn sign_transaction(&self, address: &Address, data: &[u8]) -> Result<Signature, Error> {
+		// The user confirms on the device without the manager locked, so enumeration isn't blocked meanwhile.
+		let request = self.lock().signing_request(address)?;
+		Ok(request.sign(data)?)
+	}
+
+	fn sign_message(&self, _address: &Address, _message: &[u8]) -> Result<Signature, Error> {
+		Err(Error::KeyNotFound)
+	}
+
+	fn sign_typed_data(&self, _address: &Address, _domain_separator: &[u8; 32], _message_hash: &[u8; 32]) -> Result<Signature, Error> {
+		Err(Error::KeyNotFound)
+	}
+
+	fn confirm_address(&self, _address: &Address) -> Result<(), Error> {
+		Err(Error::KeyNotFound)
+	}
+
+	fn enter_pin(&self, address: &Address, pin: &str) -> Result<(), Error> {
+		Ok(self.lock().enter_pin(address, pin)?)
+	}
+
+	fn is_locked(&self, _address: &Address) -> bool {
+		false
+	}
+}
+
+/// Keep-alive request counters.
+#[derive(Default)]
+struct KeepaliveStats {
+	/// Number of keep-alive requests sent.
+	sent: AtomicUsize,
+	/// Number of keep-alive requests that failed or timed out.
+	failed: AtomicUsize,
+}
+
+/// Wait for queued keep-alive requests and count them in `stats`. Returns `true` if any of them failed.
+fn send_keepalives(pings: Vec<Result<ledger::queue::Pending<()>, ledger::Error>>, stats: &KeepaliveStats) -> bool {
+	let mut failed = false;
+	for ping in pings {
+		stats.sent.fetch_add(1, atomic::Ordering::Relaxed);
+		if let Err(e) = ping.and_then(|p| p.wait()) {
+			debug!("Keep-alive request failed: {}", e);
+			stats.failed.fetch_add(1, atomic::Ordering::Relaxed);
+			failed = true;
+		}
+	}
+	failed
+}
+
+/// Device enumeration scheduled after a USB event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PendingUpdate {
+	/// Time the enumeration is due.
+	at: Instant,
+	/// Whether any of the events was a device arrival.
+	arrived: bool,
 }
 
 struct EventHandler {
-	ledger: Weak<Mutex<ledger::Manager>>,
+	ledger: Option<Weak<Mutex<ledger::Manager>>>,
+	trezor: Option<Weak<Mutex<trezor::Manager>>>,
+	keepkey: Option<Weak<Mutex<trezor::Manager>>>,
+	device_event_tx: Arc<Mutex<Option<mpsc::Sender<DeviceEvent>>>>,
+	/// Minimal time between two accepted USB events.
+	debounce: Duration,
+	/// Time of the last accepted USB event.
+	last_event_time: Option<Instant>,
+	/// Enumeration to be run by the update thread. Shared by all handlers of a manager.
+	pending_update: Arc<Mutex<Option<PendingUpdate>>>,
 }
 
-impl libusb::Hotplug for EventHandler {
-	fn device_arrived(&mut self, _device: libusb::Device) {
-		debug!("USB Device arrived");
-		if let Some(l) = self.ledger.upgrade() {
+impl EventHandler {
+	/// Record a USB event. Events following an accepted one within the debounce window don't reschedule
+	/// the enumeration, but arrivals are still merged into the pending one; accepted events schedule device
+	/// enumeration on the update thread. Returns `false` for discarded events.
+	fn on_usb_event(&mut self, now: Instant, arrived: bool) -> bool {
+		match self.last_event_time {
+			Some(last) if now < last + self.debounce => {
+				trace!("Discarding USB event within debounce window");
+				if let Some(ref mut update) = *self.pending_update.lock() {
+					update.arrived = update.arrived || arrived;
+				}
+				return false;
+			},
+			_ => self.last_event_time = Some(now),
+		}
+		let mut pending = self.pending_update.lock();
+		let arrived = arrived || pending.map_or(false, |p| p.arrived);
+		*pending = Some(PendingUpdate { at: now + Duration::from_millis(UPDATE_DELAY_MS), arrived: arrived });
+		true
+	}
+
+	/// Time left until the scheduled enumeration, if any.
+	fn next_update_in(&self, now: Instant) -> Option<Duration> {
+		self.pending_update.lock().map(|p| if p.at > now { p.at - now } else { Duration::from_millis(0) })
+	}
+
+	/// Run the scheduled enumeration if it is due. Returns `true` if it has been run.
+	fn run_pending_update(&self, now: Instant) -> bool {
+		let update = {
+			let mut pending = self.pending_update.lock();
+			match *pending {
+				Some(update) if update.at <= now => pending.take(),
+				_ => None,
+			}
+		};
+		match update {
+			Some(update) => {
+				self.publishing(|h| if update.arrived { h.devices_arrived() } else { h.devices_left() });
+				true
+			},
+			None => false,
+		}
+	}
+
+	fn devices_arrived(&self) {
+		if let Some(l) = self.ledger.as_ref().and_then(|l| l.upgrade()) {
+			l.lock().device_arrived();
+		}
+		if self.trezor.is_some() || self.keepkey.is_some() {
 			for _ in 0..10 {
 				// The device might not be visible right away. Try a few times.
-				if l.lock().update_devices().unwrap_or_else(|e| {
-					debug!("Error enumerating Ledger devices: {}", e);
-					0
-				}) > 0 {
+				if self.update_trezor() + self.update_keepkey() > 0 {
 					break;
 				}
 				thread::sleep(Duration::from_millis(200));
@@ -114,61 +576,418 @@ impl libusb::Hotplug for EventHandler {
 		}
 	}
 
-	fn device_left(&mut self, _device: libusb::Device) {
-		debug!("USB Device lost");
-		if let Some(l) = self.ledger.upgrade() {
+	fn devices_left(&self) {
+		if let Some(l) = self.ledger.as_ref().and_then(|l| l.upgrade()) {
 			if let Err(e) = l.lock().update_devices() {
 				debug!("Error enumerating Ledger devices: {}", e);
 			}
 		}
+		self.update_trezor();
+		self.update_keepkey();
+	}
+
+	fn wallets(&self) -> Vec<WalletInfo> {
+		let mut wallets = self.ledger.as_ref().and_then(|l| l.upgrade()).map_or_else(Vec::new, |l| l.lock().list_devices());
+		if let Some(t) = self.trezor.as_ref().and_then(|t| t.upgrade()) {
+			wallets.extend(t.lock().list_devices());
+		}
+		if let Some(k) = self.keepkey.as_ref().and_then(|k| k.upgrade()) {
+			wallets.extend(k.lock().list_devices());
+		}
+		wallets
+	}
+
+	/// Run `update` and notify the subscriber, if any, of wallets that came or went.
+	fn publishing<F: FnOnce(&Self)>(&self, update: F) {
+		if self.device_event_tx.lock().is_none() {
+			return update(self);
+		}
+		let before = self.wallets();
+		update(self);
+		let after = self.wallets();
+		let mut tx = self.device_event_tx.lock();
+		let subscribed = tx.as_ref().map_or(false, |tx| publish_changes(tx, &before, &after));
+		if !subscribed {
+			*tx = None;
+		}
+	}
+
+	fn update_trezor(&self) -> usize {
+		self.trezor.as_ref().and_then(|t| t.upgrade()).map_or(0, |t| t.lock().update_devices().unwrap_or_else(|e| {
+			debug!("Error enumerating Trezor devices: {}", e);
+			0
+		}))
+	}
+
+	fn update_keepkey(&self) -> usize {
+		self.keepkey.as_ref().and_then(|k| k.upgrade()).map_or(0, |k| k.lock().update_devices().unwrap_or_else(|e| {
+			debug!("Error enumerating KeepKey devices: {}", e);
+			0
+		}))
+	}
+}
+
+impl libusb::Hotplug for EventHandler {
+	fn device_arrived(&mut self, _device: libusb::Device) {
+		debug!("USB Device arrived");
+		self.on_usb_event(Instant::now(), true);
+	}
+
+	fn device_left(&mut self, _device: libusb::Device) {
+		debug!("USB Device lost");
+		self.on_usb_event(Instant::now(), false);
 	}
 }
 
 impl HardwareWalletManager {
+	/// Create a new manager with default configuration. Same as `try_new`.
 	pub fn new() -> Result<HardwareWalletManager, Error> {
-		let usb_context = Arc::new(libusb::Context::new()?);
-		let ledger = Arc::new(Mutex::new(ledger::Manager::new()?));
-		usb_context.register_callback(None, None, None, Box::new(EventHandler { ledger: Arc::downgrade(&ledger) }))?;
-		let exiting = Arc::new(AtomicBool::new(false));
-		let thread_exiting = exiting.clone();
-		let l = ledger.clone();
-		let thread = thread::Builder::new().name("hw_wallet".to_string()).spawn(move || {
-			if let Err(e) = l.lock().update_devices() {
+		Self::try_new()
+	}
+
+	/// Create a new manager with default configuration. Fails with `Error::UsbContextInit` or
+	/// `Error::CallbackRegistration` if USB events can't be handled.
+	pub fn try_new() -> Result<HardwareWalletManager, Error> {
+		Self::try_new_with_context(libusb::Context::new)
+	}
+
+	/// Create a new manager with default configuration and the USB context created by `new_context`.
+	fn try_new_with_context<F>(new_context: F) -> Result<HardwareWalletManager, Error>
+		where F: FnOnce() -> Result<libusb::Context, libusb::Error>
+	{
+		let builder = HardwareWalletManagerBuilder::default();
+		builder.config.validate()?;
+		Self::start_with_context(&builder, new_context)
+	}
+
+	/// Create a new manager with given configuration.
+	pub fn new_with_config(config: HardwareWalletConfig) -> Result<HardwareWalletManager, Error> {
+		let mut builder = HardwareWalletManagerBuilder::default();
+		builder.polling_interval(config.polling_interval);
+		if let Some(interval) = config.keepalive_interval {
+			builder.keepalive_interval(interval);
+		}
+		builder.build()
+	}
+
+	fn start(builder: &HardwareWalletManagerBuilder) -> Result<HardwareWalletManager, Error> {
+		Self::start_with_context(builder, libusb::Context::new)
+	}
+
+	/// Start the manager with the USB context created by `new_context`.
+	fn start_with_context<F>(builder: &HardwareWalletManagerBuilder, new_context: F) -> Result<HardwareWalletManager, Error>
+		where F: FnOnce() -> Result<libusb::Context, libusb::Error>
+	{
+		let usb_context = Arc::new(new_context().map_err(|e| Error::UsbContextInit(format!("{}", e)))?);
+		let hidapi = Arc::new(Mutex::new(hidapi::HidApi::new()?));
+		let mut manager = Self::with_backends(builder, hidapi);
+		let (usb_handler, retry_handler) = {
+			let pending_update = Arc::new(Mutex::new(None));
+			let new_handler = || EventHandler {
+				ledger: manager.ledger.as_ref().map(Arc::downgrade),
+				trezor: manager.trezor.as_ref().map(Arc::downgrade),
+				keepkey: manager.keepkey.as_ref().map(Arc::downgrade),
+				device_event_tx: manager.device_event_tx.clone(),
+				debounce: builder.debounce.unwrap_or(Duration::from_millis(DEFAULT_DEBOUNCE_MS)),
+				last_event_time: None,
+				pending_update: pending_update.clone(),
+			};
+			(new_handler(), new_handler())
+		};
+		usb_context.register_callback(None, None, None, Box::new(usb_handler))
+			.map_err(|e| Error::CallbackRegistration(format!("{}", e)))?;
+		// Enumeration after USB events and Ledger re-enumeration retries run in the update thread.
+		let ledger = manager.ledger.clone();
+		manager.update_thread = manager.spawn_update_thread(&builder.config, retry_handler, move |timeout| {
+			usb_context.handle_events(Some(timeout)).unwrap_or_else(|e| debug!("Error processing USB events: {}", e));
+		}, move || ledger.as_ref().map_or_else(Vec::new, |l| l.lock().ping_devices()));
+		Ok(manager)
+	}
+
+	/// Create the manager with wallet backends configured by `builder`. USB events are not handled.
+	fn with_backends(builder: &HardwareWalletManagerBuilder, hidapi: Arc<Mutex<hidapi::HidApi>>) -> HardwareWalletManager {
+		let ledger = if builder.without_ledger {
+			None
+		} else {
+			let mut manager = ledger::Manager::new(hidapi.clone());
+			if let Some(timeout) = builder.signing_timeout {
+				manager.set_signing_timeout(timeout);
+			}
+			if let Some(retries) = builder.reconnect_retries {
+				manager.set_reconnect_retries(retries);
+			}
+			Some(Arc::new(Mutex::new(manager)))
+		};
+		let trezor = Arc::new(Mutex::new(trezor::Manager::new(&trezor::TREZOR, hidapi.clone())));
+		let keepkey = Arc::new(Mutex::new(trezor::Manager::new(&keepkey::KEEPKEY, hidapi)));
+		let mut wallets: Vec<Arc<Wallet>> = Vec::new();
+		if let Some(ref l) = ledger {
+			wallets.push(l.clone());
+		}
+		wallets.push(trezor.clone());
+		wallets.push(keepkey.clone());
+		let mut manager = Self::with_wallets(wallets);
+		manager.ledger = ledger;
+		manager.trezor = Some(trezor);
+		manager.keepkey = Some(keepkey);
+		manager.device_event_tx = Arc::new(Mutex::new(builder.event_channel.clone()));
+		manager.aliases = Mutex::new(DeviceAliases::load(builder.aliases_path.clone()));
+		manager
+	}
+
+	/// Create a manager dispatching requests to `wallets`. No USB events are handled and
+	/// no backend specific requests are supported.
+	fn with_wallets(wallets: Vec<Arc<Wallet>>) -> HardwareWalletManager {
+		HardwareWalletManager {
+			update_thread: None,
+			exiting: Arc::new(AtomicBool::new(false)),
+			ledger: None,
+			trezor: None,
+			keepkey: None,
+			device_event_tx: Arc::new(Mutex::new(None)),
+			aliases: Mutex::new(DeviceAliases::load(None)),
+			keepalive_stats: Arc::new(KeepaliveStats::default()),
+			wallets: wallets,
+		}
+	}
+
+	/// Start the update thread. `wait_for_events` processes USB events, blocking for at most the given time.
+	/// `ping_devices` queues keep-alive requests for connected devices.
+	fn spawn_update_thread<W, P>(&self, config: &HardwareWalletConfig, retry_handler: EventHandler, wait_for_events: W, ping_devices: P) -> Option<thread::JoinHandle<()>>
+		where W: Fn(Duration) + Send + 'static, P: Fn() -> Vec<Result<ledger::queue::Pending<()>, ledger::Error>> + Send + 'static
+	{
+		let thread_exiting = self.exiting.clone();
+		let l = self.ledger.clone();
+		let t = self.trezor.clone();
+		let k = self.keepkey.clone();
+		let poll_interval = config.polling_interval;
+		let keepalive_interval = config.keepalive_interval;
+		let thread_keepalive_stats = self.keepalive_stats.clone();
+		thread::Builder::new().name("hw_wallet".to_string()).spawn(move || {
+			if let Some(Err(e)) = l.as_ref().map(|l| l.lock().update_devices()) {
 				debug!("Error updating ledger devices: {}", e);
 			}
+			if let Some(Err(e)) = t.as_ref().map(|t| t.lock().update_devices()) {
+				debug!("Error updating trezor devices: {}", e);
+			}
+			if let Some(Err(e)) = k.as_ref().map(|k| k.lock().update_devices()) {
+				debug!("Error updating keepkey devices: {}", e);
+			}
+			let mut next_keepalive = keepalive_interval.map(|interval| Instant::now() + interval);
 			loop {
-				usb_context.handle_events(Some(Duration::from_millis(500))).unwrap_or_else(|e| debug!("Error processing USB events: {}", e));
+				let timeout = l.as_ref().and_then(|l| l.lock().next_retry_in()).map_or(poll_interval, |t| min(t, poll_interval));
+				let timeout = retry_handler.next_update_in(Instant::now()).map_or(timeout, |t| min(t, timeout));
+				let now = Instant::now();
+				let timeout = next_keepalive.map_or(timeout, |at| if at > now { min(at - now, timeout) } else { Duration::from_millis(0) });
+				wait_for_events(timeout);
+				retry_handler.run_pending_update(Instant::now());
+				// Re-enumerate only when a backoff attempt is due; USB events are handled by `run_pending_update`.
+				if let Some(ref l) = l {
+					let retry_due = l.lock().retry_due();
+					if retry_due {
+						retry_handler.publishing(|_| l.lock().handle_retries());
+					}
+				}
+				if let Some(interval) = keepalive_interval {
+					if next_keepalive.map_or(false, |at| at <= Instant::now()) {
+						// Wait for the replies without holding the lock; requests are answered by device workers.
+						if send_keepalives(ping_devices(), &thread_keepalive_stats) {
+							if let Some(ref l) = l {
+								retry_handler.publishing(|_| if let Err(e) = l.lock().update_devices() {
+									debug!("Error enumerating Ledger devices: {}", e);
+								});
+							}
+						}
+						next_keepalive = Some(Instant::now() + interval);
+					}
+				}
 				if thread_exiting.load(atomic::Ordering::Acquire) {
 					break;
 				}
 			}
-		}).ok();
-		Ok(HardwareWalletManager {
-			update_thread: thread,
-			exiting: exiting,
-			ledger: ledger,
-		})
+		}).ok()
+	}
+
+	/// Number of keep-alive requests sent and failed so far.
+	pub fn keepalive_stats(&self) -> (u64, u64) {
+		let sent = self.keepalive_stats.sent.load(atomic::Ordering::Relaxed);
+		let failed = self.keepalive_stats.failed.load(atomic::Ordering::Relaxed);
+		(sent as u64, failed as u64)
+	}
+
+	/// Subscribe to wallet arrival and departure events. Replaces any previous subscription.
+	pub fn subscribe_events(&self) -> mpsc::Receiver<DeviceEvent> {
+		let (tx, rx) = mpsc::channel();
+		*self.device_event_tx.lock() = Some(tx);
+		rx
 	}
 
 	/// Select key derivation path for a chain.
 	pub fn set_key_path(&self, key_path: KeyPath) {
-		self.ledger.lock().set_key_path(key_path);
+		if let Some(ref l) = self.ledger {
+			l.lock().set_key_path(key_path);
+		}
+		if let Some(ref t) = self.trezor {
+			t.lock().set_key_path(key_path);
+		}
+		if let Some(ref k) = self.keepkey {
+			k.lock().set_key_path(key_path);
+		}
+	}
+
+	/// Select the range of account indices exposed for each Ledger device.
+	/// The end of the range is excluded. Takes effect on the next device enumeration.
+	pub fn set_account_index_range(&self, range: Range<u32>) {
+		if let Some(ref l) = self.ledger {
+			l.lock().set_account_index_range(range);
+		}
 	}
 
+	/// Set the time to wait for the user to confirm a signing request on a Ledger device.
+	pub fn set_signing_timeout(&self, timeout: Duration) {
+		if let Some(ref l) = self.ledger {
+			l.lock().set_signing_timeout(timeout);
+		}
+	}
+
+	/// Set a callback notified about the progress of Ledger signing requests.
+	/// The callback is invoked from the thread talking to the device.
+	pub fn set_signing_progress_callback(&self, callback: Box<Fn(SigningProgress) + Send>) {
+		if let Some(ref l) = self.ledger {
+			l.lock().set_progress_callback(callback);
+		}
+	}
+
+	/// Set the maximum number of Ledger re-enumeration attempts after a USB device arrives.
+	/// Attempts are spaced with exponential backoff starting at 100ms.
+	pub fn set_reconnect_retries(&self, retries: u32) {
+		if let Some(ref l) = self.ledger {
+			l.lock().set_reconnect_retries(retries);
+		}
+	}
+
+	/// Set the delay between consecutive address requests sent to a Ledger device during enumeration.
+	pub fn set_enumeration_delay(&self, delay: Duration) {
+		if let Some(ref l) = self.ledger {
+			l.lock().set_enumeration_delay(delay);
+		}
+	}
+
+	/// Scan addresses with indices `0..=max_index` on the connected Ledger device. The scan ends early after
+	/// three consecutive failures. Requests are queued with other requests for the device.
+	pub fn enumerate_addresses(&self, key_path: KeyPath, max_index: u32) -> Result<Vec<(u32, Address)>, Error> {
+		let ledger = self.ledger()?;
+		let delay = {
+			let ledger = ledger.lock();
+			if ledger.list_devices().is_empty() {
+				return Err(Error::KeyNotFound);
+			}
+			ledger.enumeration_delay()
+		};
+		// Each address is queued separately, waiting without the lock held.
+		Ok(ledger::Manager::scan_addresses(max_index, delay, |index| {
+			let pending = ledger.lock().request_wallet_address(key_path, index);
+			pending.and_then(|p| p.wait())
+		}))
+	}
+
+	/// Check whether the device holding `address` is locked with a PIN. Only supported on Ledger devices.
+	pub fn is_device_locked(&self, address: &Address) -> bool {
+		self.wallet(address).map_or(false, |w| w.is_locked(address))
+	}
+
+	/// Send a raw APDU to the Ledger device with `serial` number. Returns response data followed by the status word.
+	/// Meant for testing and firmware development only; it must never be called in production.
+	#[cfg(feature = "ledger-debug")]
+	pub fn send_raw_apdu(&self, serial: &str, apdu: &[u8]) -> Result<Vec<u8>, Error> {
+		let pending = self.ledger()?.lock().send_raw(serial, apdu)?;
+		Ok(pending.wait()?)
+	}
+
+	/// Give the device with `serial` number a nickname, reported as the name of its wallets.
+	pub fn set_device_alias(&self, serial: &str, alias: String) -> Result<(), Error> {
+		Ok(self.aliases.lock().set(serial, alias)?)
+	}
+
+	/// Nickname of the device with `serial` number.
+	pub fn device_alias(&self, serial: &str) -> Option<String> {
+		self.aliases.lock().get(serial)
+	}
+
+	fn with_alias(&self, mut wallet: WalletInfo) -> WalletInfo {
+		if let Some(alias) = self.device_alias(&wallet.serial) {
+			wallet.name = alias;
+		}
+		wallet
+	}
 
 	/// List connected wallets. This only returns wallets that are ready to be used.
+	/// Wallets on devices with an alias are named after the alias.
 	pub fn list_wallets(&self) -> Vec<WalletInfo> {
-		self.ledger.lock().list_devices()
+		self.connected_wallets().into_iter().map(|w| self.with_alias(w)).collect()
+	}
+
+	fn connected_wallets(&self) -> Vec<WalletInfo> {
+		self.wallets.iter().flat_map(|w| w.list_devices()).collect()
 	}
 
 	/// Get connected wallet info.
 	pub fn wallet_info(&self, address: &Address) -> Option<WalletInfo> {
-		self.ledger.lock().device_info(address)
+		self.connected_wallet_info(address).map(|w| self.with_alias(w))
+	}
+
+	fn connected_wallet_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.wallets.iter().filter_map(|w| w.device_info(address)).next()
+	}
+
+	/// Backend managing the wallet with `address`.
+	fn wallet(&self, address: &Address) -> Result<&Arc<Wallet>, Error> {
+		self.wallets.iter().find(|w| w.device_info(address).is_some()).ok_or(Error::KeyNotFound)
 	}
 
 	/// Sign transaction data with wallet managing `address`.
 	pub fn sign_transaction(&self, address: &Address, data: &[u8]) -> Result<Signature, Error> {
-		Ok(self.ledger.lock().sign_transaction(address, data)?)
+		self.wallet(address)?.sign_transaction(address, data)
+	}
+
+	/// Show `address` on the device and wait for the user to confirm it. Only supported on Ledger devices.
+	/// Fails with `Error::UserRejected` if the user rejects the address.
+	pub fn confirm_address(&self, address: &Address) -> Result<(), Error> {
+		self.wallet(address)?.confirm_address(address)
+	}
+
+	/// Unlock the wallet managing `address` with `pin`. For Trezor and KeepKey the PIN is given as
+	/// positions in the matrix shown on the device. Ledger devices take the PIN on-device only;
+	/// for them this does nothing.
+	pub fn enter_pin(&self, address: &Address, pin: &str) -> Result<(), Error> {
+		self.wallet(address)?.enter_pin(address, pin)
+	}
+
+	/// Get public key of the account managing `address` without signing anything. Only supported on Ledger devices.
+	pub fn get_public_key(&self, address: &Address) -> Result<Public, Error> {
+		let pending = self.ledger()?.lock().get_public_key(address)?;
+		Ok(pending.wait()?.public)
+	}
+
+	/// Get the address the account managing `address` has on the chain with BIP-44 `coin_type`,
+	/// e.g. 700 for xDAI. Coin type 60 gives the address for the selected key path. Only supported on Ledger devices.
+	pub fn get_address_for_chain(&self, address: &Address, coin_type: u32) -> Result<Address, Error> {
+		let pending = self.ledger()?.lock().get_address_for_chain(address, coin_type)?;
+		Ok(pending.wait()?)
+	}
+
+	/// Sign a message with wallet managing `address`, as in `personal_sign`. Only supported on Ledger devices.
+	pub fn sign_message(&self, address: &Address, message: &[u8]) -> Result<Signature, Error> {
+		self.wallet(address)?.sign_message(address, message)
+	}
+
+	/// Sign EIP-712 typed data with wallet managing `address`. Only supported on Ledger devices.
+	pub fn sign_typed_data(&self, address: &Address, domain_separator: &[u8; 32], message_hash: &[u8; 32]) -> Result<Signature, Error> {
+		self.wallet(address)?.sign_typed_data(address, domain_separator, message_hash)
+	}
+
+	fn ledger(&self) -> Result<&Arc<Mutex<ledger::Manager>>, Error> {
+		self.ledger.as_ref().ok_or(Error::KeyNotFound)
 	}
 }
 
@@ -181,3 +1000,382 @@ impl Drop for HardwareWalletManager {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::thread;
+	use std::sync::{Arc, Mutex, Condvar, mpsc};
+	use std::time::{Duration, Instant};
+	use parking_lot;
+	use super::{HardwareWalletManager, HardwareWalletManagerBuilder, HardwareWalletConfig, ConfigError, Error, WalletInfo, WalletDeviceType,
+		DeviceEvent, EventHandler, Wallet, publish_changes, send_keepalives};
+
+	#[test]
+	fn should_validate_polling_interval() {
+		let config = HardwareWalletConfig::default();
+		assert_eq!(config.polling_interval, Duration::from_millis(500));
+		assert!(config.validate().is_ok());
+		let config = HardwareWalletConfig::default().polling_interval(Duration::from_millis(5));
+		assert_eq!(config.validate(), Err(ConfigError::PollingInterval(Duration::from_millis(5))));
+		let config = HardwareWalletConfig::default().polling_interval(Duration::from_secs(6));
+		assert_eq!(config.validate(), Err(ConfigError::PollingInterval(Duration::from_secs(6))));
+	}
+
+	/// Handler without any wallet backends.
+	fn test_handler() -> EventHandler {
+		EventHandler {
+			ledger: None,
+			trezor: None,
+			keepkey: None,
+			device_event_tx: Arc::new(parking_lot::Mutex::new(None)),
+			debounce: Duration::from_millis(100),
+			last_event_time: None,
+			pending_update: Arc::new(parking_lot::Mutex::new(None)),
+		}
+	}
+
+	#[test]
+	fn should_name_wallets_after_persisted_alias() {
+		use std::collections::HashMap;
+		use devtools::RandomTempPath;
+		use mock::MockHardwareWalletManager;
+
+		// given
+		let path = RandomTempPath::new();
+		let wallet = WalletInfo {
+			name: "Nano S".into(),
+			manufacturer: "Ledger".into(),
+			serial: "0001".into(),
+			address: 10.into(),
+			firmware_version: None,
+			app_version: None,
+			device_type: WalletDeviceType::Ledger,
+		};
+		let manager = MockHardwareWalletManager::from_fixtures_with_aliases(vec![wallet.clone()], HashMap::new(), path.as_path().clone());
+		manager.set_device_alias("0001", "Cold storage".into()).unwrap();
+		drop(manager);
+
+		// when
+		let manager = MockHardwareWalletManager::from_fixtures_with_aliases(vec![wallet], HashMap::new(), path.as_path().clone());
+
+		// then
+		assert_eq!(manager.device_alias("0001"), Some("Cold storage".into()));
+		assert_eq!(manager.device_alias("0002"), None);
+		assert_eq!(manager.list_wallets()[0].name, "Cold storage");
+		assert_eq!(manager.wallet_info(&10.into()).unwrap().name, "Cold storage");
+	}
+
+	#[test]
+	fn should_debounce_usb_events() {
+		// given
+		let mut handler = test_handler();
+		let start = Instant::now();
+		let mut updates = 0;
+
+		// when
+		for i in 0..20 {
+			let now = start + Duration::from_millis(i * 10);
+			handler.on_usb_event(now, true);
+			if handler.run_pending_update(now) {
+				updates += 1;
+			}
+		}
+		if handler.run_pending_update(start + Duration::from_secs(1)) {
+			updates += 1;
+		}
+
+		// then
+		assert!(updates >= 1 && updates <= 2);
+		assert!(handler.next_update_in(start + Duration::from_secs(1)).is_none());
+	}
+
+	#[test]
+	fn should_schedule_update_after_delay() {
+		let mut handler = test_handler();
+		let now = Instant::now();
+
+		assert!(handler.on_usb_event(now, false));
+		assert!(!handler.on_usb_event(now + Duration::from_millis(20), true));
+		assert_eq!(handler.next_update_in(now), Some(Duration::from_millis(50)));
+		assert!(!handler.run_pending_update(now + Duration::from_millis(49)));
+		assert!(handler.run_pending_update(now + Duration::from_millis(50)));
+	}
+
+	#[test]
+	fn should_keep_arrivals_of_debounced_events() {
+		// given
+		let mut handler = test_handler();
+		let now = Instant::now();
+		assert!(handler.on_usb_event(now, false));
+
+		// when
+		let accepted = handler.on_usb_event(now + Duration::from_millis(20), true);
+
+		// then
+		assert!(!accepted);
+		assert_eq!(handler.pending_update.lock().map(|update| update.arrived), Some(true));
+		assert_eq!(handler.next_update_in(now), Some(Duration::from_millis(50)));
+	}
+
+	#[test]
+	fn should_build_manager_with_custom_settings() {
+		use hidapi;
+
+		// given
+		let (tx, _rx) = mpsc::channel();
+		let mut builder = HardwareWalletManagerBuilder::default();
+		builder.polling_interval(Duration::from_millis(100))
+			.signing_timeout(Duration::from_secs(5))
+			.reconnect_retries(2)
+			.event_channel(tx);
+
+		let hidapi = Arc::new(parking_lot::Mutex::new(hidapi::HidApi::new().unwrap()));
+
+		// when
+		let manager = HardwareWalletManager::with_backends(&builder, hidapi.clone());
+		let without_ledger = HardwareWalletManager::with_backends(HardwareWalletManagerBuilder::default().with_ledger(false), hidapi);
+
+		// then
+		{
+			let ledger = manager.ledger.as_ref().unwrap().lock();
+			assert_eq!(ledger.signing_timeout(), Duration::from_secs(5));
+			assert_eq!(ledger.reconnect_retries(), 2);
+		}
+		assert!(manager.device_event_tx.lock().is_some());
+		assert!(without_ledger.ledger.is_none());
+		assert!(without_ledger.trezor.is_some());
+	}
+
+	#[test]
+	fn should_reject_invalid_builder_settings() {
+		match HardwareWalletManagerBuilder::default().polling_interval(Duration::from_millis(1)).build() {
+			Err(Error::Config(ConfigError::PollingInterval(_))) => (),
+			other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+		}
+		match HardwareWalletManagerBuilder::default().signing_timeout(Duration::from_secs(0)).build() {
+			Err(Error::Config(ConfigError::SigningTimeout)) => (),
+			other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+		}
+	}
+
+	#[test]
+	fn should_wake_up_within_polling_interval() {
+		use std::collections::HashMap;
+		use mock::MockHardwareWalletManager;
+
+		let interval = Duration::from_millis(50);
+		let mut manager = MockHardwareWalletManager::from_fixtures(vec![], HashMap::new());
+		// no USB events ever arrive
+		manager.update_thread = manager.spawn_update_thread(&HardwareWalletConfig::default().polling_interval(interval), test_handler(), thread::sleep, Vec::new);
+		// let the update thread settle in the event loop
+		thread::sleep(interval);
+
+		// Shutting down requires the update thread to wake up and notice the exit flag.
+		let stopped = Arc::new((Mutex::new(false), Condvar::new()));
+		let s = stopped.clone();
+		thread::spawn(move || {
+			drop(manager);
+			let &(ref lock, ref cvar) = &*s;
+			*lock.lock().unwrap() = true;
+			cvar.notify_one();
+		});
+
+		let &(ref lock, ref cvar) = &*stopped;
+		let (stopped, _) = cvar.wait_timeout(lock.lock().unwrap(), interval * 2).unwrap();
+		assert!(*stopped);
+	}
+
+	#[test]
+	fn should_publish_device_events() {
+		use std::collections::HashMap;
+		use mock::MockHardwareWalletManager;
+
+		let manager = MockHardwareWalletManager::from_fixtures(vec![], HashMap::new());
+		let rx = manager.subscribe_events();
+		let wallet = WalletInfo {
+			name: "Nano S".into(),
+			manufacturer: "Ledger".into(),
+			serial: "0001".into(),
+			address: 10.into(),
+			firmware_version: None,
+			app_version: None,
+			device_type: WalletDeviceType::Ledger,
+		};
+		let tx = manager.device_event_tx.lock().clone().unwrap();
+
+		// wallet enumerated after a USB arrival
+		assert!(publish_changes(&tx, &[], &[wallet.clone()]));
+		match rx.recv_timeout(Duration::from_secs(1)) {
+			Ok(DeviceEvent::Arrived(ref w)) if w.address == wallet.address => (),
+			other => panic!("Unexpected event: {:?}", other),
+		}
+		// nothing changed
+		assert!(publish_changes(&tx, &[wallet.clone()], &[wallet.clone()]));
+		assert!(rx.try_recv().is_err());
+		// wallet gone after a USB departure
+		assert!(publish_changes(&tx, &[wallet.clone()], &[]));
+		match rx.recv_timeout(Duration::from_secs(1)) {
+			Ok(DeviceEvent::Left(address)) if address == wallet.address => (),
+			other => panic!("Unexpected event: {:?}", other),
+		}
+
+		drop(rx);
+		assert!(!publish_changes(&tx, &[], &[wallet]));
+	}
+
+	#[test]
+	fn should_count_keepalive_requests() {
+		use ledger;
+		use ledger::queue::DeviceQueue;
+		use transport::mock::MockDevice;
+		use std::collections::HashMap;
+		use mock::MockHardwareWalletManager;
+
+		// given
+		let manager = MockHardwareWalletManager::from_fixtures(vec![], HashMap::new());
+		assert_eq!(manager.keepalive_stats(), (0, 0));
+		let responsive = DeviceQueue::start(|| Ok(MockDevice::new(vec![])));
+		let unplugged = DeviceQueue::<MockDevice>::start(|| Err(ledger::Error::KeyNotFound));
+		let timeout = Duration::from_millis(100);
+
+		// when
+		let pings = vec![
+			responsive.enqueue(Box::new(|_: &MockDevice| Ok(())), timeout),
+			unplugged.enqueue(Box::new(|_: &MockDevice| Ok(())), timeout),
+		];
+		let failed = send_keepalives(pings, &manager.keepalive_stats);
+
+		// then
+		assert!(failed);
+		assert_eq!(manager.keepalive_stats(), (2, 1));
+		assert!(!send_keepalives(vec![responsive.enqueue(Box::new(|_: &MockDevice| Ok(())), timeout)], &manager.keepalive_stats));
+		assert_eq!(manager.keepalive_stats(), (3, 1));
+	}
+
+	#[test]
+	fn should_send_keepalives_from_update_thread() {
+		use std::sync::atomic;
+		use ledger;
+		use ledger::queue::DeviceQueue;
+		use transport::mock::MockDevice;
+		use std::collections::HashMap;
+		use mock::MockHardwareWalletManager;
+
+		// given
+		let interval = Duration::from_millis(20);
+		let config = HardwareWalletConfig::default().polling_interval(interval).keepalive_interval(interval);
+		let mut manager = MockHardwareWalletManager::from_fixtures(vec![], HashMap::new());
+		let responsive = DeviceQueue::start(|| Ok(MockDevice::new(vec![])));
+		let unplugged = DeviceQueue::<MockDevice>::start(|| Err(ledger::Error::KeyNotFound));
+
+		// when
+		manager.update_thread = manager.spawn_update_thread(&config, test_handler(), thread::sleep, move || vec![
+			responsive.enqueue(Box::new(|_: &MockDevice| Ok(())), Duration::from_millis(100)),
+			unplugged.enqueue(Box::new(|_: &MockDevice| Ok(())), Duration::from_millis(100)),
+		]);
+		let deadline = Instant::now() + Duration::from_secs(5);
+		while manager.keepalive_stats().0 < 2 && Instant::now() < deadline {
+			thread::sleep(interval);
+		}
+		// stop the update thread, so the counters don't change while being read
+		let stats = manager.keepalive_stats.clone();
+		drop(manager);
+
+		// then
+		let (sent, failed) = (stats.sent.load(atomic::Ordering::Relaxed), stats.failed.load(atomic::Ordering::Relaxed));
+		assert!(sent >= 2);
+		// only the unplugged device fails
+		assert_eq!(sent, failed * 2);
+	}
+
+	#[test]
+	fn should_dispatch_requests_to_wallet_listing_the_address() {
+		use ethkey::Signature;
+		use mock::MockManager;
+
+		// given
+		let first: Arc<Wallet> = Arc::new(MockManager::new(vec![(1.into(), Signature::from_rsv(&1.into(), &1.into(), 0))]));
+		let second: Arc<Wallet> = Arc::new(MockManager::new(vec![(2.into(), Signature::from_rsv(&2.into(), &2.into(), 0))]));
+		let manager = HardwareWalletManager::with_wallets(vec![first, second]);
+
+		// when
+		let signature = manager.sign_transaction(&2.into(), &[]);
+
+		// then
+		assert_eq!(signature.unwrap(), Signature::from_rsv(&2.into(), &2.into(), 0));
+		assert_eq!(manager.list_wallets().len(), 2);
+		assert_eq!(manager.confirm_address(&1.into()).unwrap(), ());
+		match manager.sign_message(&3.into(), b"hello") {
+			Err(Error::KeyNotFound) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_sign_with_trezor_backend_for_trezor_account() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		use hidapi;
+		use rustc_serialize::hex::FromHex;
+		use ethkey::Address;
+		use trezor::{self, proto, message_type};
+		use transport::HidTransport;
+		use transport::mock::MockDevice;
+
+		// given
+		let address: Address = "8f5a9e1c5c8ad04ce27c6ae58f2f5d0af3f3d3a7".into();
+		let wallet = WalletInfo {
+			name: "TREZOR".into(),
+			manufacturer: "SatoshiLabs".into(),
+			serial: "0001".into(),
+			address: address,
+			firmware_version: None,
+			app_version: None,
+			device_type: WalletDeviceType::Trezor,
+		};
+		let mut signature = Vec::new();
+		proto::write_uint(&mut signature, 2, 37);
+		proto::write_bytes(&mut signature, 3, &[1u8; 32]);
+		proto::write_bytes(&mut signature, 4, &[2u8; 32]);
+		let mut response = vec![b'?', b'#', b'#', 0, message_type::ETHEREUM_TX_REQUEST as u8, 0, 0, 0, signature.len() as u8];
+		response.extend_from_slice(&signature);
+		response.resize(64, 0);
+		let opened = Arc::new(AtomicUsize::new(0));
+		let open_count = opened.clone();
+		let open: trezor::Opener = Arc::new(move |path: &str| -> Result<Box<HidTransport>, trezor::Error> {
+			assert_eq!(path, "trezor-path");
+			open_count.fetch_add(1, Ordering::SeqCst);
+			Ok(Box::new(MockDevice::new(vec![response.clone()])) as Box<HidTransport>)
+		});
+		let hidapi = Arc::new(parking_lot::Mutex::new(hidapi::HidApi::new().unwrap()));
+		let trezor = trezor::Manager::with_devices(&trezor::TREZOR, hidapi, open, vec![("trezor-path".into(), wallet)]);
+		let trezor: Arc<Wallet> = Arc::new(parking_lot::Mutex::new(trezor));
+		let manager = HardwareWalletManager::with_wallets(vec![trezor]);
+		// nonce 1, gas price, gas, to, value, no data, chain id 1
+		let tx = FromHex::from_hex("eb018504a817c80082520894a6ca2e6707f2cc189794a9dd459d5b05ed1bcd1c8703f26fcfb7a22480018080").unwrap();
+
+		// when
+		let signature = manager.sign_transaction(&address, &tx).unwrap();
+
+		// then
+		assert_eq!(opened.load(Ordering::SeqCst), 1);
+		assert_eq!(signature.r(), &[1u8; 32]);
+		assert_eq!(signature.s(), &[2u8; 32]);
+		assert!(manager.sign_transaction(&Address::default(), &tx).is_err());
+		assert_eq!(opened.load(Ordering::SeqCst), 1);
+	}
+
+	#[test]
+	fn should_fail_to_start_without_usb_context() {
+		use libusb;
+
+		// when
+		let result = HardwareWalletManager::try_new_with_context(|| Err(libusb::Error::Access));
+
+		// then
+		match result {
+			Err(Error::UsbContextInit(ref e)) => assert_eq!(e, &format!("{}", libusb::Error::Access)),
+			Err(e) => panic!("Unexpected error: {}", e),
+			Ok(_) => panic!("Manager started without a USB context"),
+		}
+	}
+}
@@ -0,0 +1,215 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hardware wallet backend replaying fixtures. Allows testing code that depends on
+//! `HardwareWalletManager` without USB devices.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use parking_lot::Mutex;
+use tiny_keccak::Keccak;
+use ethkey::{self, Address, Secret, Signature};
+use aliases::DeviceAliases;
+use super::{Error, HardwareWalletManager, Wallet, WalletInfo, WalletDeviceType};
+
+/// Wallet backend returning predefined signatures.
+/// Every signing request for an address is answered with that address' fixture signature.
+/// Wallets with a known secret sign personal messages for real.
+/// Public keys are not available.
+pub struct MockManager {
+	wallets: Arc<Mutex<Vec<WalletInfo>>>,
+	signatures: HashMap<Address, Signature>,
+	secrets: HashMap<Address, Secret>,
+}
+
+impl MockManager {
+	/// Create a backend exposing a wallet for each fixture address.
+	pub fn new(fixtures: Vec<(Address, Signature)>) -> MockManager {
+		let wallets = fixtures.iter().map(|&(ref address, _)| WalletInfo {
+			name: "Mock".into(),
+			manufacturer: "Parity".into(),
+			serial: format!("{:?}", address),
+			address: address.clone(),
+			firmware_version: None,
+			app_version: None,
+			device_type: WalletDeviceType::Unknown,
+		}).collect();
+		MockManager::with_wallets(wallets, fixtures.into_iter().collect())
+	}
+
+	/// Create a backend exposing `wallets`. Wallets without an entry in `signatures` refuse to sign.
+	pub fn with_wallets(wallets: Vec<WalletInfo>, signatures: HashMap<Address, Signature>) -> MockManager {
+		MockManager::with_shared_wallets(Arc::new(Mutex::new(wallets)), signatures)
+	}
+
+	/// Create a backend exposing `wallets`. Changes to the list simulate plugging and unplugging devices.
+	pub fn with_shared_wallets(wallets: Arc<Mutex<Vec<WalletInfo>>>, signatures: HashMap<Address, Signature>) -> MockManager {
+		MockManager {
+			wallets: wallets,
+			signatures: signatures,
+			secrets: HashMap::new(),
+		}
+	}
+
+	/// List fixture wallets.
+	pub fn list_devices(&self) -> Vec<WalletInfo> {
+		self.wallets.lock().clone()
+	}
+
+	/// Get fixture wallet info.
+	pub fn device_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.wallets.lock().iter().find(|w| &w.address == address).cloned()
+	}
+
+	/// Get fixture signature for `address`.
+	pub fn sign(&self, address: &Address) -> Result<Signature, Error> {
+		self.signatures.get(address).cloned().ok_or(Error::KeyNotFound)
+	}
+
+	/// Sign a personal message with the secret for `address`, falling back to the fixture signature.
+	pub fn sign_message(&self, address: &Address, message: &[u8]) -> Result<Signature, Error> {
+		let secret = match self.secrets.get(address) {
+			Some(secret) => secret,
+			None => return self.sign(address),
+		};
+
+		let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+		prefixed.extend_from_slice(message);
+		let mut hash = [0u8; 32];
+		let mut keccak = Keccak::new_keccak256();
+		keccak.update(&prefixed);
+		keccak.finalize(&mut hash);
+		ethkey::sign(secret, &hash.into()).map_err(|_| Error::KeyNotFound)
+	}
+}
+
+impl Wallet for MockManager {
+	fn list_devices(&self) -> Vec<WalletInfo> {
+		MockManager::list_devices(self)
+	}
+
+	fn device_info(&self, address: &Address) -> Option<WalletInfo> {
+		MockManager::device_info(self, address)
+	}
+
+	fn sign_transaction(&self, address: &Address, _data: &[u8]) -> Result<Signature, Error> {
+		self.sign(address)
+	}
+
+	fn sign_message(&self, address: &Address, message: &[u8]) -> Result<Signature, Error> {
+		MockManager::sign_message(self, address, message)
+	}
+
+	fn sign_typed_data(&self, address: &Address, _domain_separator: &[u8; 32], _message_hash: &[u8; 32]) -> Result<Signature, Error> {
+		self.sign(address)
+	}
+
+	fn confirm_address(&self, address: &Address) -> Result<(), Error> {
+		MockManager::device_info(self, address).map(|_| ()).ok_or(Error::KeyNotFound)
+	}
+
+	fn enter_pin(&self, _address: &Address, _pin: &str) -> Result<(), Error> {
+		Err(Error::PinNotSupported)
+	}
+
+	fn is_locked(&self, _address: &Address) -> bool {
+		false
+	}
+}
+
+/// Builds `HardwareWalletManager` instances backed by fixtures.
+pub struct MockHardwareWalletManager;
+
+impl MockHardwareWalletManager {
+	/// Create a manager exposing `wallets` and answering signing requests with `signatures`.
+	/// No USB context or update thread is created.
+	pub fn from_fixtures(wallets: Vec<WalletInfo>, signatures: HashMap<Address, Signature>) -> HardwareWalletManager {
+		Self::from_shared_fixtures(Arc::new(Mutex::new(wallets)), signatures)
+	}
+
+	/// Like `from_fixtures`, but the wallet list stays shared with the caller.
+	pub fn from_shared_fixtures(wallets: Arc<Mutex<Vec<WalletInfo>>>, signatures: HashMap<Address, Signature>) -> HardwareWalletManager {
+		Self::from_mock(MockManager::with_shared_wallets(wallets, signatures))
+	}
+
+	/// Create a manager with `mock` as its only wallet backend.
+	pub fn from_mock(mock: MockManager) -> HardwareWalletManager {
+		let mock: Arc<Wallet> = Arc::new(mock);
+		HardwareWalletManager::with_wallets(vec![mock])
+	}
+
+	/// Like `from_fixtures`, but device aliases are kept in the JSON file at `aliases_path`.
+	pub fn from_fixtures_with_aliases(wallets: Vec<WalletInfo>, signatures: HashMap<Address, Signature>, aliases_path: PathBuf) -> HardwareWalletManager {
+		let mut manager = Self::from_fixtures(wallets, signatures);
+		manager.aliases = Mutex::new(DeviceAliases::load(Some(aliases_path)));
+		manager
+	}
+
+	/// Create a manager exposing `wallets` and signing personal messages with `secrets`.
+	pub fn from_secrets(wallets: Vec<WalletInfo>, secrets: HashMap<Address, Secret>) -> HardwareWalletManager {
+		let mut mock = MockManager::with_wallets(wallets, HashMap::new());
+		mock.secrets = secrets;
+		Self::from_mock(mock)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use ethkey::{Address, Signature};
+	use super::{MockManager, MockHardwareWalletManager};
+	use {WalletInfo, WalletDeviceType};
+
+	#[test]
+	fn should_sign_with_fixture_signature() {
+		let address: Address = 1.into();
+		let signature = Signature::from_rsv(&1.into(), &2.into(), 0);
+		let mock = MockManager::new(vec![(address, signature.clone())]);
+		let mut signatures = HashMap::new();
+		signatures.insert(address, signature.clone());
+		let manager = MockHardwareWalletManager::from_fixtures(mock.list_devices(), signatures);
+
+		assert_eq!(manager.list_wallets().len(), 1);
+		assert!(manager.wallet_info(&address).is_some());
+		assert_eq!(manager.sign_transaction(&address, &[]).unwrap(), signature);
+		assert!(manager.sign_transaction(&2.into(), &[]).is_err());
+	}
+
+	#[test]
+	fn should_list_wallets_of_each_device_type() {
+		// given
+		let device_types = vec![WalletDeviceType::Ledger, WalletDeviceType::Trezor, WalletDeviceType::KeepKey];
+		let wallets = device_types.iter().enumerate().map(|(i, device_type)| WalletInfo {
+			name: format!("{}", device_type),
+			manufacturer: format!("{}", device_type),
+			serial: format!("{}", i),
+			address: (i as u64 + 1).into(),
+			firmware_version: None,
+			app_version: None,
+			device_type: *device_type,
+		}).collect();
+
+		// when
+		let manager = MockHardwareWalletManager::from_fixtures(wallets, HashMap::new());
+
+		// then
+		let listed = manager.list_wallets().into_iter().map(|w| w.device_type).collect::<Vec<_>>();
+		assert_eq!(listed, device_types);
+		assert_eq!(manager.wallet_info(&2.into()).unwrap().device_type, WalletDeviceType::Trezor);
+		assert_eq!(MockManager::new(vec![(5.into(), Signature::default())]).list_devices()[0].device_type, WalletDeviceType::Unknown);
+	}
+}
@@ -0,0 +1,89 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HID report transport shared by the wallet backends.
+
+use hidapi;
+
+/// Raw HID report I/O. Implemented for `hidapi::HidDevice` and by mock devices in tests.
+pub trait HidTransport {
+	/// Write a single report. Returns the number of bytes written.
+	fn write(&self, data: &[u8]) -> Result<usize, hidapi::HidError>;
+	/// Read a single report into `buf`. Returns the number of bytes read.
+	fn read(&self, buf: &mut [u8]) -> Result<usize, hidapi::HidError>;
+}
+
+impl HidTransport for hidapi::HidDevice {
+	fn write(&self, data: &[u8]) -> Result<usize, hidapi::HidError> {
+		hidapi::HidDevice::write(self, data)
+	}
+
+	fn read(&self, buf: &mut [u8]) -> Result<usize, hidapi::HidError> {
+		hidapi::HidDevice::read(self, buf)
+	}
+}
+
+impl<T: HidTransport + ?Sized> HidTransport for Box<T> {
+	fn write(&self, data: &[u8]) -> Result<usize, hidapi::HidError> {
+		(**self).write(data)
+	}
+
+	fn read(&self, buf: &mut [u8]) -> Result<usize, hidapi::HidError> {
+		(**self).read(buf)
+	}
+}
+
+#[cfg(test)]
+pub mod mock {
+	use std::collections::VecDeque;
+	use std::cell::RefCell;
+	use hidapi;
+	use super::HidTransport;
+
+	/// HID device replaying canned reports and recording everything written to it.
+	#[derive(Default)]
+	pub struct MockDevice {
+		pub written: RefCell<Vec<Vec<u8>>>,
+		pub responses: RefCell<VecDeque<Vec<u8>>>,
+	}
+
+	impl MockDevice {
+		pub fn new(responses: Vec<Vec<u8>>) -> Self {
+			MockDevice {
+				written: RefCell::new(Vec::new()),
+				responses: RefCell::new(responses.into_iter().collect()),
+			}
+		}
+	}
+
+	impl HidTransport for MockDevice {
+		fn write(&self, data: &[u8]) -> Result<usize, hidapi::HidError> {
+			self.written.borrow_mut().push(data.to_vec());
+			Ok(data.len())
+		}
+
+		fn read(&self, buf: &mut [u8]) -> Result<usize, hidapi::HidError> {
+			match self.responses.borrow_mut().pop_front() {
+				Some(report) => {
+					let len = ::std::cmp::min(buf.len(), report.len());
+					buf[0..len].copy_from_slice(&report[0..len]);
+					Ok(len)
+				},
+				None => Ok(0),
+			}
+		}
+	}
+}
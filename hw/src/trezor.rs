@@ -0,0 +1,707 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Trezor protocol wallets. Supports Trezor One and Trezor Model T, as well as KeepKey,
+//! which uses the same HID framing and Ethereum message set.
+/// See https://github.com/trezor/trezor-common/blob/master/protob/messages.proto for protocol details.
+
+use hidapi;
+use std::fmt;
+use std::cmp::min;
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::Mutex;
+use rlp::{UntrustedRlp, View};
+use super::{WalletInfo, WalletDeviceType, KeyPath};
+use transport::HidTransport;
+use ethkey::{Address, Signature};
+use ethcore_bigint::hash::{H256, FixedHash};
+
+static ETH_DERIVATION_PATH: [u32; 5] = [0x8000002c, 0x8000003c, 0x80000000, 0, 0]; // m/44'/60'/0'/0/0
+static ETC_DERIVATION_PATH: [u32; 5] = [0x8000002c, 0x8000003c, 0x800273d0, 0x80000000, 0]; // m/44'/60'/160720'/0'/0
+
+/// USB ids and kind of the devices of one make speaking the Trezor protocol.
+pub struct DeviceFamily {
+	/// Vendor and product id pairs of the devices.
+	pub ids: &'static [(u16, u16)],
+	/// Kind of the devices.
+	pub device_type: WalletDeviceType,
+}
+
+impl DeviceFamily {
+	/// Check if USB vendor and product ids belong to a device of this family.
+	pub fn is_valid_device(&self, vendor_id: u16, product_id: u16) -> bool {
+		self.ids.iter().any(|&(vid, pid)| vid == vendor_id && pid == product_id)
+	}
+}
+
+/// Trezor One and Trezor Model T.
+pub static TREZOR: DeviceFamily = DeviceFamily {
+	ids: &[(0x534c, 0x0001), (0x1209, 0x53c1)],
+	device_type: WalletDeviceType::Trezor,
+};
+
+/// Maximum amount of transaction data sent along with the initial `EthereumSignTx` message.
+const MAX_INITIAL_CHUNK: usize = 1024;
+
+#[cfg(windows)] const HID_PREFIX_ZERO: usize = 1;
+#[cfg(not(windows))] const HID_PREFIX_ZERO: usize = 0;
+const HID_REPORT_SIZE: usize = 64;
+
+/// Protobuf message type identifiers.
+pub mod message_type {
+	pub const INITIALIZE: u16 = 0;
+	pub const FAILURE: u16 = 3;
+	pub const FEATURES: u16 = 17;
+	pub const PIN_MATRIX_REQUEST: u16 = 18;
+	pub const PIN_MATRIX_ACK: u16 = 19;
+	pub const BUTTON_REQUEST: u16 = 26;
+	pub const BUTTON_ACK: u16 = 27;
+	pub const PASSPHRASE_REQUEST: u16 = 41;
+	pub const ETHEREUM_GET_ADDRESS: u16 = 56;
+	pub const ETHEREUM_ADDRESS: u16 = 57;
+	pub const ETHEREUM_SIGN_TX: u16 = 58;
+	pub const ETHEREUM_TX_REQUEST: u16 = 59;
+	pub const ETHEREUM_TX_ACK: u16 = 60;
+}
+
+/// `Failure.code` value reported when the user cancels an action on the device.
+const FAILURE_ACTION_CANCELLED: u64 = 4;
+
+/// Hardware wallet error.
+#[derive(Debug)]
+pub enum Error {
+	/// Ethereum wallet protocol error.
+	Protocol(&'static str),
+	/// Hidapi error.
+	Usb(hidapi::HidError),
+	/// Device with request key is not available.
+	KeyNotFound,
+	/// Signing has been cancelled by user.
+	UserCancel,
+	/// Device has reported a failure.
+	Failure(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Error::Protocol(ref s) => write!(f, "Trezor protocol error: {}", s),
+			Error::Usb(ref e) => write!(f, "USB communication error: {}", e),
+			Error::KeyNotFound => write!(f, "Key not found"),
+			Error::UserCancel => write!(f, "Operation has been cancelled"),
+			Error::Failure(ref s) => write!(f, "Trezor failure: {}", s),
+		}
+	}
+}
+
+impl From<hidapi::HidError> for Error {
+	fn from(err: hidapi::HidError) -> Error {
+		Error::Usb(err)
+	}
+}
+
+/// Minimal protobuf wire format support for the handful of messages we exchange.
+pub mod proto {
+	use super::Error;
+
+	/// Decoded protobuf field value.
+	#[derive(Debug, PartialEq)]
+	pub enum Value<'a> {
+		/// Varint encoded scalar (wire type 0).
+		Varint(u64),
+		/// Length-delimited payload (wire type 2).
+		Bytes(&'a [u8]),
+	}
+
+	fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+		while value >= 0x80 {
+			out.push((value as u8 & 0x7f) | 0x80);
+			value >>= 7;
+		}
+		out.push(value as u8);
+	}
+
+	/// Append a varint field.
+	pub fn write_uint(out: &mut Vec<u8>, field: u32, value: u64) {
+		write_varint(out, (field as u64) << 3);
+		write_varint(out, value);
+	}
+
+	/// Append a length-delimited field.
+	pub fn write_bytes(out: &mut Vec<u8>, field: u32, data: &[u8]) {
+		write_varint(out, (field as u64) << 3 | 2);
+		write_varint(out, data.len() as u64);
+		out.extend_from_slice(data);
+	}
+
+	fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64, Error> {
+		let mut value = 0u64;
+		let mut shift = 0;
+		loop {
+			let byte = *data.get(*pos).ok_or(Error::Protocol("Truncated varint"))?;
+			*pos += 1;
+			if shift >= 64 {
+				return Err(Error::Protocol("Varint overflow"));
+			}
+			value |= ((byte & 0x7f) as u64) << shift;
+			if byte & 0x80 == 0 {
+				return Ok(value);
+			}
+			shift += 7;
+		}
+	}
+
+	/// Split a message into its `(field number, value)` pairs.
+	pub fn parse(data: &[u8]) -> Result<Vec<(u32, Value)>, Error> {
+		let mut fields = Vec::new();
+		let mut pos = 0;
+		while pos < data.len() {
+			let key = read_varint(data, &mut pos)?;
+			let field = (key >> 3) as u32;
+			match key & 0x07 {
+				0 => fields.push((field, Value::Varint(read_varint(data, &mut pos)?))),
+				2 => {
+					let len = read_varint(data, &mut pos)? as usize;
+					if data.len() - pos < len {
+						return Err(Error::Protocol("Truncated field"));
+					}
+					fields.push((field, Value::Bytes(&data[pos..pos + len])));
+					pos += len;
+				},
+				_ => return Err(Error::Protocol("Unsupported wire type")),
+			}
+		}
+		Ok(fields)
+	}
+
+	/// Find the first varint value of `field`.
+	pub fn uint(fields: &[(u32, Value)], field: u32) -> Option<u64> {
+		fields.iter().filter_map(|&(f, ref v)| match *v {
+			Value::Varint(v) if f == field => Some(v),
+			_ => None,
+		}).next()
+	}
+
+	/// Find the first length-delimited value of `field`.
+	pub fn bytes<'a>(fields: &[(u32, Value<'a>)], field: u32) -> Option<&'a [u8]> {
+		fields.iter().filter_map(|&(f, ref v)| match *v {
+			Value::Bytes(b) if f == field => Some(b),
+			_ => None,
+		}).next()
+	}
+}
+
+/// Opens the device with given HID path.
+pub type Opener = Arc<Fn(&str) -> Result<Box<HidTransport>, Error> + Send + Sync>;
+
+/// Manager of the devices of a single family.
+pub struct Manager {
+	family: &'static DeviceFamily,
+	usb: Arc<Mutex<hidapi::HidApi>>,
+	open: Opener,
+	devices: Vec<Device>,
+	key_path: KeyPath,
+}
+
+/// Signing request for a single device. Runs without access to the manager, so the manager
+/// does not have to stay locked while the user confirms the transaction on the device.
+pub struct SigningRequest {
+	open: Opener,
+	path: String,
+	key_path: KeyPath,
+}
+
+impl SigningRequest {
+	/// Sign RLP encoded transaction `data`. Blocks until the user confirms or rejects the transaction.
+	pub fn sign(&self, data: &[u8]) -> Result<Signature, Error> {
+		let handle = (self.open)(&self.path)?;
+		Manager::sign_with_handle(&handle, self.key_path, data)
+	}
+}
+
+#[derive(Debug)]
+struct Device {
+	path: String,
+	info: WalletInfo,
+}
+
+fn open_path(usb: &Mutex<hidapi::HidApi>, path: &str) -> Result<hidapi::HidDevice, Error> {
+	let mut err = Error::KeyNotFound;
+	/// Try to open device a few times.
+	for _ in 0..10 {
+		match usb.lock().open_path(&path) {
+			Ok(handle) => return Ok(handle),
+			Err(e) => err = From::from(e),
+		}
+		::std::thread::sleep(Duration::from_millis(200));
+	}
+	Err(err)
+}
+
+impl Manager {
+	/// Create a new instance managing devices of `family`.
+	pub fn new(family: &'static DeviceFamily, hidapi: Arc<Mutex<hidapi::HidApi>>) -> Manager {
+		let usb = hidapi.clone();
+		let open: Opener = Arc::new(move |path: &str| open_path(&usb, path).map(|handle| Box::new(handle) as Box<HidTransport>));
+		Manager {
+			family: family,
+			usb: hidapi,
+			open: open,
+			devices: Vec::new(),
+			key_path: KeyPath::Ethereum,
+		}
+	}
+
+	/// Create a manager opening devices with `open`, with `devices` listed as connected.
+	#[cfg(test)]
+	pub fn with_devices(family: &'static DeviceFamily, hidapi: Arc<Mutex<hidapi::HidApi>>, open: Opener, devices: Vec<(String, WalletInfo)>) -> Manager {
+		Manager {
+			family: family,
+			usb: hidapi,
+			open: open,
+			devices: devices.into_iter().map(|(path, info)| Device { path: path, info: info }).collect(),
+			key_path: KeyPath::Ethereum,
+		}
+	}
+
+	/// Re-populate device list. Only those devices that respond with an address will be added.
+	pub fn update_devices(&mut self) -> Result<usize, Error> {
+		let devices = {
+			let mut usb = self.usb.lock();
+			usb.refresh_devices();
+			usb.devices()
+		};
+		let mut new_devices = Vec::new();
+		let mut num_new_devices = 0;
+		for device in devices {
+			trace!("Checking device: {:?}", device);
+			if !self.family.is_valid_device(device.vendor_id, device.product_id) {
+				continue;
+			}
+			match self.read_device_info(&device) {
+				Ok(info) => {
+					debug!("Found device: {:?}", info);
+					if !self.devices.iter().any(|d| d.path == info.path) {
+						num_new_devices += 1;
+					}
+					new_devices.push(info);
+				},
+				Err(e) => debug!("Error reading device info: {}", e),
+			};
+		}
+		self.devices = new_devices;
+		Ok(num_new_devices)
+	}
+
+	/// Select key derivation path for a known chain.
+	pub fn set_key_path(&mut self, key_path: KeyPath) {
+		self.key_path = key_path;
+	}
+
+	fn read_device_info(&self, dev_info: &hidapi::HidDeviceInfo) -> Result<Device, Error> {
+		let handle = (self.open)(&dev_info.path)?;
+		let address = Self::read_wallet_address(&handle, self.key_path)?;
+		let manufacturer = dev_info.manufacturer_string.clone().unwrap_or("Unknown".to_owned());
+		let name = dev_info.product_string.clone().unwrap_or("Unknown".to_owned());
+		let serial = dev_info.serial_number.clone().unwrap_or("Unknown".to_owned());
+		Ok(Device {
+			path: dev_info.path.clone(),
+			info: WalletInfo {
+				name: name,
+				manufacturer: manufacturer,
+				serial: serial,
+				address: address,
+				firmware_version: None,
+				app_version: None,
+				device_type: self.family.device_type,
+			},
+		})
+	}
+
+	fn derivation_path(key_path: KeyPath) -> &'static [u32] {
+		match key_path {
+			KeyPath::Ethereum => &ETH_DERIVATION_PATH,
+			KeyPath::EthereumClassic => &ETC_DERIVATION_PATH,
+		}
+	}
+
+	fn address_request(key_path: KeyPath) -> Vec<u8> {
+		let mut request = Vec::new();
+		for index in Self::derivation_path(key_path) {
+			proto::write_uint(&mut request, 1, *index as u64);
+		}
+		request
+	}
+
+	/// Request the address for `key_path`.
+	pub fn read_wallet_address<T: HidTransport>(handle: &T, key_path: KeyPath) -> Result<Address, Error> {
+		let (msg_type, _) = Self::call(handle, message_type::INITIALIZE, &[])?;
+		if msg_type != message_type::FEATURES {
+			return Err(Error::Protocol("Unexpected response to Initialize"));
+		}
+
+		let (msg_type, response) = Self::call(handle, message_type::ETHEREUM_GET_ADDRESS, &Self::address_request(key_path))?;
+		if msg_type != message_type::ETHEREUM_ADDRESS {
+			return Err(Error::Protocol("Unexpected response to EthereumGetAddress"));
+		}
+		let fields = proto::parse(&response)?;
+		match proto::bytes(&fields, 1) {
+			Some(address) if address.len() == 20 => Ok(Address::from_slice(address)),
+			_ => Err(Error::Protocol("Invalid address")),
+		}
+	}
+
+	/// List connected wallets. This only returns wallets that are ready to be used.
+	pub fn list_devices(&self) -> Vec<WalletInfo> {
+		self.devices.iter().map(|d| d.info.clone()).collect()
+	}
+
+	/// Get wallet info.
+	pub fn device_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.devices.iter().find(|d| &d.info.address == address).map(|d| d.info.clone())
+	}
+
+	/// Sign transaction data with wallet managing `address`.
+	/// `data` is the RLP of the unsigned transaction, optionally including the EIP-155 chain id.
+	pub fn sign_transaction(&self, address: &Address, data: &[u8]) -> Result<Signature, Error> {
+		self.signing_request(address)?.sign(data)
+	}
+
+	/// Prepare signing with wallet managing `address`. The request may be run after the manager is unlocked.
+	pub fn signing_request(&self, address: &Address) -> Result<SigningRequest, Error> {
+		let device = self.devices.iter().find(|d| &d.info.address == address)
+			.ok_or(Error::KeyNotFound)?;
+
+		Ok(SigningRequest {
+			open: self.open.clone(),
+			path: device.path.clone(),
+			key_path: self.key_path,
+		})
+	}
+
+	/// Sign RLP encoded transaction `data`.
+	pub fn sign_with_handle<T: HidTransport>(handle: &T, key_path: KeyPath, data: &[u8]) -> Result<Signature, Error> {
+		let tx = UntrustedRlp::new(data);
+		let item_count = tx.item_count();
+		if item_count != 6 && item_count != 9 {
+			return Err(Error::Protocol("Invalid transaction RLP"));
+		}
+		let field = |index: usize| tx.at(index).and_then(|item| item.data())
+			.map_err(|_| Error::Protocol("Invalid transaction RLP"));
+		let tx_data = field(5)?;
+
+		let mut request = Vec::new();
+		for index in Self::derivation_path(key_path) {
+			proto::write_uint(&mut request, 1, *index as u64);
+		}
+		proto::write_bytes(&mut request, 2, field(0)?);
+		proto::write_bytes(&mut request, 3, field(1)?);
+		proto::write_bytes(&mut request, 4, field(2)?);
+		proto::write_bytes(&mut request, 5, field(3)?);
+		proto::write_bytes(&mut request, 6, field(4)?);
+		let initial_chunk = min(tx_data.len(), MAX_INITIAL_CHUNK);
+		proto::write_bytes(&mut request, 7, &tx_data[0..initial_chunk]);
+		proto::write_uint(&mut request, 8, tx_data.len() as u64);
+		if item_count == 9 {
+			let chain_id: u64 = tx.val_at(6).map_err(|_| Error::Protocol("Invalid transaction RLP"))?;
+			proto::write_uint(&mut request, 9, chain_id);
+		}
+
+		let mut data_pos = initial_chunk;
+		let (mut msg_type, mut response) = Self::call(handle, message_type::ETHEREUM_SIGN_TX, &request)?;
+		loop {
+			if msg_type != message_type::ETHEREUM_TX_REQUEST {
+				return Err(Error::Protocol("Unexpected response to EthereumSignTx"));
+			}
+			let fields = proto::parse(&response)?;
+			match proto::uint(&fields, 1) {
+				Some(len) if len > 0 => {
+					let len = len as usize;
+					if data_pos + len > tx_data.len() {
+						return Err(Error::Protocol("Device requested data beyond transaction end"));
+					}
+					let mut ack = Vec::new();
+					proto::write_bytes(&mut ack, 1, &tx_data[data_pos..data_pos + len]);
+					data_pos += len;
+					let (t, r) = Self::call(handle, message_type::ETHEREUM_TX_ACK, &ack)?;
+					msg_type = t;
+					response = r;
+				},
+				_ => {
+					let v = proto::uint(&fields, 2).ok_or(Error::Protocol("Missing signature"))?;
+					let r = proto::bytes(&fields, 3).ok_or(Error::Protocol("Missing signature"))?;
+					let s = proto::bytes(&fields, 4).ok_or(Error::Protocol("Missing signature"))?;
+					if r.len() > 32 || s.len() > 32 {
+						return Err(Error::Protocol("Signature packet size mismatch"));
+					}
+					// Firmware reports either 27/28 or the EIP-155 adjusted value; we need the recovery id.
+					let v = match v {
+						27 | 28 => v - 27,
+						v if v >= 35 => (v - 35) % 2,
+						_ => return Err(Error::Protocol("Invalid signature v")),
+					};
+					let mut r_bytes = [0u8; 32];
+					let mut s_bytes = [0u8; 32];
+					r_bytes[32 - r.len()..].copy_from_slice(r);
+					s_bytes[32 - s.len()..].copy_from_slice(s);
+					return Ok(Signature::from_rsv(&H256::from(r_bytes), &H256::from(s_bytes), v as u8));
+				},
+			}
+		}
+	}
+
+	/// Unlock the wallet managing `address` with `pin`.
+	/// The PIN is entered as positions in the scrambled matrix shown on the device, digits 1 to 9.
+	pub fn enter_pin(&self, address: &Address, pin: &str) -> Result<(), Error> {
+		let device = self.devices.iter().find(|d| &d.info.address == address)
+			.ok_or(Error::KeyNotFound)?;
+
+		let handle = (self.open)(&device.path)?;
+		Self::unlock_with_pin(&handle, self.key_path, pin)
+	}
+
+	/// Send a request requiring the PIN and answer the PIN prompt, if any, with `pin`.
+	pub fn unlock_with_pin<T: HidTransport>(handle: &T, key_path: KeyPath, pin: &str) -> Result<(), Error> {
+		if pin.is_empty() || pin.len() > 9 || !pin.chars().all(|c| c >= '1' && c <= '9') {
+			return Err(Error::Protocol("PIN must consist of matrix positions 1 to 9"));
+		}
+		let (msg_type, _) = Self::call_with_pin(handle, message_type::ETHEREUM_GET_ADDRESS, &Self::address_request(key_path), Some(pin))?;
+		if msg_type != message_type::ETHEREUM_ADDRESS {
+			return Err(Error::Protocol("Unexpected response to EthereumGetAddress"));
+		}
+		Ok(())
+	}
+
+	/// Send a message and wait for the final response, acknowledging any button requests on the way.
+	pub fn call<T: HidTransport>(handle: &T, msg_type: u16, data: &[u8]) -> Result<(u16, Vec<u8>), Error> {
+		Self::call_with_pin(handle, msg_type, data, None)
+	}
+
+	/// Same as `call`, answering a PIN prompt with `pin` if given.
+	fn call_with_pin<T: HidTransport>(handle: &T, msg_type: u16, data: &[u8], pin: Option<&str>) -> Result<(u16, Vec<u8>), Error> {
+		Self::write_message(handle, msg_type, data)?;
+		loop {
+			let (response_type, response) = Self::read_message(handle)?;
+			match response_type {
+				message_type::BUTTON_REQUEST => {
+					trace!("Waiting for user confirmation");
+					Self::write_message(handle, message_type::BUTTON_ACK, &[])?;
+				},
+				message_type::PIN_MATRIX_REQUEST => match pin {
+					Some(pin) => {
+						let mut ack = Vec::new();
+						proto::write_bytes(&mut ack, 1, pin.as_bytes());
+						Self::write_message(handle, message_type::PIN_MATRIX_ACK, &ack)?;
+					},
+					None => return Err(Error::Protocol("Device is locked with a PIN")),
+				},
+				message_type::PASSPHRASE_REQUEST => return Err(Error::Protocol("Passphrase protected devices are not supported")),
+				message_type::FAILURE => {
+					let fields = proto::parse(&response)?;
+					if proto::uint(&fields, 1) == Some(FAILURE_ACTION_CANCELLED) {
+						return Err(Error::UserCancel);
+					}
+					let message = proto::bytes(&fields, 2)
+						.map(|m| String::from_utf8_lossy(m).into_owned())
+						.unwrap_or_else(|| "Unknown error".to_owned());
+					return Err(Error::Failure(message));
+				},
+				_ => return Ok((response_type, response)),
+			}
+		}
+	}
+
+	fn write_message<T: HidTransport>(handle: &T, msg_type: u16, data: &[u8]) -> Result<(), Error> {
+		let mut message = Vec::with_capacity(data.len() + 8);
+		message.extend_from_slice(&[b'#', b'#', (msg_type >> 8) as u8, (msg_type & 0xff) as u8]);
+		let len = data.len();
+		message.extend_from_slice(&[(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, (len & 0xff) as u8]);
+		message.extend_from_slice(data);
+
+		for chunk in message.chunks(HID_REPORT_SIZE - 1) {
+			let mut report = [0u8; HID_REPORT_SIZE + HID_PREFIX_ZERO];
+			report[HID_PREFIX_ZERO] = b'?';
+			&mut report[HID_PREFIX_ZERO + 1..][0..chunk.len()].copy_from_slice(chunk);
+			trace!("writing {:?}", &report[..]);
+			let n = handle.write(&report)?;
+			if n < report.len() {
+				return Err(Error::Protocol("Write data size mismatch"));
+			}
+		}
+		Ok(())
+	}
+
+	fn read_message<T: HidTransport>(handle: &T) -> Result<(u16, Vec<u8>), Error> {
+		let mut report = [0u8; HID_REPORT_SIZE];
+		let n = handle.read(&mut report)?;
+		trace!("read {:?}", &report[..]);
+		if n < 9 || report[0] != b'?' || report[1] != b'#' || report[2] != b'#' {
+			return Err(Error::Protocol("Unexpected report header"));
+		}
+		let msg_type = (report[3] as u16) << 8 | report[4] as u16;
+		let len = (report[5] as usize) << 24 | (report[6] as usize) << 16 | (report[7] as usize) << 8 | report[8] as usize;
+		let mut message = Vec::with_capacity(len);
+		message.extend_from_slice(&report[9..min(n, 9 + len)]);
+		while message.len() < len {
+			let n = handle.read(&mut report)?;
+			trace!("read {:?}", &report[..]);
+			if n < 2 || report[0] != b'?' {
+				return Err(Error::Protocol("Unexpected report header"));
+			}
+			let left = len - message.len();
+			message.extend_from_slice(&report[1..min(n, 1 + left)]);
+		}
+		Ok((msg_type, message))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use parking_lot::Mutex;
+	use hidapi;
+	use rustc_serialize::hex::FromHex;
+	use ethkey::Address;
+	use transport::mock::MockDevice;
+	use KeyPath;
+	use super::{Manager, Error, proto, message_type, TREZOR};
+
+	fn report(msg_type: u16, data: &[u8]) -> Vec<u8> {
+		assert!(data.len() <= 55);
+		let mut r = vec![b'?', b'#', b'#', (msg_type >> 8) as u8, msg_type as u8, 0, 0, 0, data.len() as u8];
+		r.extend_from_slice(data);
+		r.resize(64, 0);
+		r
+	}
+
+	fn written_types(device: &MockDevice) -> Vec<u16> {
+		device.written.borrow().iter()
+			.filter(|r| r[1] == b'#' && r[2] == b'#')
+			.map(|r| (r[3] as u16) << 8 | r[4] as u16)
+			.collect()
+	}
+
+	#[test]
+	fn should_only_enumerate_trezor_devices() {
+		assert!(TREZOR.is_valid_device(0x534c, 0x0001));
+		assert!(TREZOR.is_valid_device(0x1209, 0x53c1));
+		assert!(!TREZOR.is_valid_device(0x2c97, 0x0001));
+		assert!(!TREZOR.is_valid_device(0x534c, 0x0002));
+	}
+
+	#[test]
+	fn should_read_address_from_device() {
+		let address: Address = "8f5a9e1c5c8ad04ce27c6ae58f2f5d0af3f3d3a7".into();
+		let mut address_response = Vec::new();
+		proto::write_bytes(&mut address_response, 1, &address);
+		let device = MockDevice::new(vec![
+			report(message_type::FEATURES, &[]),
+			report(message_type::ETHEREUM_ADDRESS, &address_response),
+		]);
+
+		assert_eq!(Manager::read_wallet_address(&device, KeyPath::Ethereum).unwrap(), address);
+		assert_eq!(written_types(&device), vec![message_type::INITIALIZE, message_type::ETHEREUM_GET_ADDRESS]);
+	}
+
+	#[test]
+	fn should_derive_etc_addresses_on_ledger_path() {
+		// given
+		let mut address_response = Vec::new();
+		proto::write_bytes(&mut address_response, 1, &[1u8; 20]);
+		let device = MockDevice::new(vec![
+			report(message_type::FEATURES, &[]),
+			report(message_type::ETHEREUM_ADDRESS, &address_response),
+		]);
+
+		// when
+		Manager::read_wallet_address(&device, KeyPath::EthereumClassic).unwrap();
+
+		// then
+		let mut request = Vec::new();
+		for index in &[0x8000002c, 0x8000003c, 0x800273d0, 0x80000000, 0] {
+			proto::write_uint(&mut request, 1, *index);
+		}
+		assert_eq!(&device.written.borrow()[1][9..9 + request.len()], &request[..]);
+	}
+
+	#[test]
+	fn should_sign_transaction_after_button_confirmation() {
+		let mut signature_response = Vec::new();
+		proto::write_uint(&mut signature_response, 2, 37);
+		proto::write_bytes(&mut signature_response, 3, &[1u8; 32]);
+		proto::write_bytes(&mut signature_response, 4, &[2u8; 32]);
+		let device = MockDevice::new(vec![
+			report(message_type::BUTTON_REQUEST, &[]),
+			report(message_type::ETHEREUM_TX_REQUEST, &signature_response),
+		]);
+		// nonce 1, gas price, gas, to, value, no data, chain id 1
+		let tx = FromHex::from_hex("eb018504a817c80082520894a6ca2e6707f2cc189794a9dd459d5b05ed1bcd1c8703f26fcfb7a22480018080").unwrap();
+
+		let signature = Manager::sign_with_handle(&device, KeyPath::Ethereum, &tx).unwrap();
+		assert_eq!(signature.v(), 0);
+		assert_eq!(signature.r(), &[1u8; 32]);
+		assert_eq!(signature.s(), &[2u8; 32]);
+		assert_eq!(written_types(&device), vec![message_type::ETHEREUM_SIGN_TX, message_type::BUTTON_ACK]);
+	}
+
+	#[test]
+	fn should_report_cancelled_signing() {
+		let mut failure = Vec::new();
+		proto::write_uint(&mut failure, 1, 4);
+		let device = MockDevice::new(vec![report(message_type::FAILURE, &failure)]);
+		let tx = FromHex::from_hex("eb018504a817c80082520894a6ca2e6707f2cc189794a9dd459d5b05ed1bcd1c8703f26fcfb7a22480018080").unwrap();
+
+		match Manager::sign_with_handle(&device, KeyPath::Ethereum, &tx) {
+			Err(Error::UserCancel) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_answer_pin_request() {
+		let mut address_response = Vec::new();
+		proto::write_bytes(&mut address_response, 1, &[1u8; 20]);
+		let device = MockDevice::new(vec![
+			report(message_type::PIN_MATRIX_REQUEST, &[]),
+			report(message_type::ETHEREUM_ADDRESS, &address_response),
+		]);
+
+		Manager::unlock_with_pin(&device, KeyPath::Ethereum, "1379").unwrap();
+
+		assert_eq!(written_types(&device), vec![message_type::ETHEREUM_GET_ADDRESS, message_type::PIN_MATRIX_ACK]);
+		let mut ack = Vec::new();
+		proto::write_bytes(&mut ack, 1, b"1379");
+		assert_eq!(&device.written.borrow()[1][9..9 + ack.len()], &ack[..]);
+	}
+
+	#[test]
+	fn should_reject_invalid_pin() {
+		let device = MockDevice::new(vec![]);
+		assert!(Manager::unlock_with_pin(&device, KeyPath::Ethereum, "1230").is_err());
+		assert!(Manager::unlock_with_pin(&device, KeyPath::Ethereum, "").is_err());
+		assert!(device.written.borrow().is_empty());
+	}
+
+	#[test]
+	fn should_not_find_unknown_device() {
+		let manager = Manager::new(&TREZOR, Arc::new(Mutex::new(hidapi::HidApi::new().unwrap())));
+		match manager.sign_transaction(&Address::default(), &[]) {
+			Err(Error::KeyNotFound) => (),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+}
@@ -20,6 +20,8 @@ export const ERROR_CODES = {
   UNSUPPORTED_REQUEST: -32000,
   NO_WORK: -32001,
   NO_AUTHOR: -32002,
+  RESOURCE_NOT_FOUND: -32001,
+  RESOURCE_UNAVAILABLE: -32002,
   NO_NEW_WORK: -32003,
   NOT_ENOUGH_DATA: -32006,
   UNKNOWN_ERROR: -32009,
@@ -23,9 +23,11 @@ export default withPreamble(`
 
 The following methods have an optional extra \`defaultBlock\` parameter:
 
+- [eth_createAccessList](#eth_createaccesslist)
 - [eth_estimateGas](#eth_estimategas)
 - [eth_getBalance](#eth_getbalance)
 - [eth_getCode](#eth_getcode)
+- [eth_getProof](#eth_getproof)
 - [eth_getTransactionCount](#eth_gettransactioncount)
 - [eth_getStorageAt](#eth_getstorageat)
 - [eth_call](#eth_call)
@@ -145,6 +147,28 @@ The following options are possible for the \`defaultBlock\` parameter:
     }
   },
 
+  createAccessList: {
+    desc: 'Makes a call, which won\'t be added to the blockchain, and returns the accounts and storage keys it accessed as an EIP-2930 access list.',
+    params: [
+      {
+        type: CallRequest,
+        desc: 'Same as [eth_call](#eth_call) parameters, except that all properties are optional.',
+        format: 'inputCallFormatter',
+        example: new Dummy('{ ... }')
+      },
+      {
+        type: BlockNumber,
+        desc: 'Integer block number, or the string `\'latest\'`, `\'earliest\'` or `\'pending\'`, see the [default block parameter](#the-default-block-parameter).',
+        format: 'inputDefaultBlockNumberFormatter',
+        optional: true
+      }
+    ],
+    returns: {
+      type: Object,
+      desc: 'Access list result: `accessList` (array of `address` and `storageKeys`), `gasUsed` and `error` (only if the call failed).'
+    }
+  },
+
   estimateGas: {
     desc: 'Makes a call or transaction, which won\'t be added to the blockchain and returns the used gas, which can be used for estimating the used gas.',
     params: [
@@ -191,6 +215,33 @@ The following options are possible for the \`defaultBlock\` parameter:
     }
   },
 
+  feeHistory: {
+    desc: 'Returns base fees, gas usage ratios and priority fees at given percentiles for a range of blocks. At most 1024 blocks are returned.',
+    params: [
+      {
+        type: Quantity,
+        desc: 'Number of blocks in the range.',
+        format: 'utils.toHex',
+        example: fromDecimal(4)
+      },
+      {
+        type: BlockNumber,
+        desc: 'Newest block of the range: integer block number, or the string `\'latest\'`, `\'earliest\'` or `\'pending\'`.',
+        example: 'latest'
+      },
+      {
+        type: Array,
+        desc: 'Increasing percentiles (0-100) of gas used to sample priority fees at.',
+        example: [25, 75],
+        optional: true
+      }
+    ],
+    returns: {
+      type: Object,
+      desc: 'Fee history: `oldestBlock`, `baseFeePerGas`, `gasUsedRatio` and `reward` (only if percentiles were given).'
+    }
+  },
+
   gasPrice: {
     desc: 'Returns the current price per gas in wei.',
     params: [],
@@ -515,6 +566,33 @@ The following options are possible for the \`defaultBlock\` parameter:
     }
   },
 
+  getProof: {
+    desc: 'Returns the Merkle proof of an account and some of its storage slots (EIP-1186).',
+    params: [
+      {
+        type: Address,
+        desc: '20 Bytes - address of the account.',
+        example: '0x407d73d8a49eeb85d32cf465507dd71d507100c1'
+      },
+      {
+        type: Array,
+        desc: 'Storage keys to prove.',
+        example: ['0x0000000000000000000000000000000000000000000000000000000000000000']
+      },
+      {
+        type: BlockNumber,
+        desc: 'integer block number, or the string `\'latest\'`, `\'earliest\'` or `\'pending\'`, see the [default block parameter](#the-default-block-parameter).',
+        format: 'inputDefaultBlockNumberFormatter',
+        example: fromDecimal(2),
+        optional: true
+      }
+    ],
+    returns: {
+      type: Object,
+      desc: 'Account proof: `address`, `accountProof`, `balance`, `codeHash`, `nonce`, `storageHash` and `storageProof` (a list of `key`, `value` and `proof` objects).'
+    }
+  },
+
   getStorageAt: {
     desc: 'Returns the value from a storage position at a given address.',
     params: [
@@ -1048,6 +1126,28 @@ The following options are possible for the \`defaultBlock\` parameter:
     }
   },
 
+  signTypedData: {
+    desc: 'Signs EIP-712 typed structured data with a given address. Requires the account to be unlocked and Trusted Signer to be disabled.',
+    params: [
+      {
+        type: Address,
+        desc: '20 Bytes - address.',
+        format: 'inputAddressFormatter',
+        example: '0xd1ade25ccd3d550a7eb532ac759cac7be09c2719'
+      },
+      {
+        type: Object,
+        desc: 'Typed data object with `types`, `primaryType`, `domain` and `message` fields.',
+        example: new Dummy('{ ... }')
+      }
+    ],
+    returns: {
+      type: Data,
+      desc: 'Signature.',
+      example: '0x2ac19db245478a06032e69cdbd2b54e648b78431d0a47bd1fbab18f79f820ba407466e37adbe9e84541cab97ab7d290f4a64a5825c876d22109f3bf813254e8628'
+    }
+  },
+
   signTransaction: {
     desc: 'Signs transactions without dispatching it to the network. It can be later submitted using [eth_sendRawTransaction](#eth_sendrawtransaction).',
     params: [
@@ -1147,6 +1147,27 @@ export default {
     }
   },
 
+  exportAccount: {
+    subdoc: SUBDOC_ACCOUNTS,
+    desc: 'Exports an account as keystore JSON encrypted with the account password. Limited to 5 calls per minute.',
+    params: [
+      {
+        type: Address,
+        desc: 'The account to export.',
+        example: '0x407d73d8a49eeb85d32cf465507dd71d507100c1'
+      },
+      {
+        type: String,
+        desc: 'Account password.',
+        example: 'hunter2'
+      }
+    ],
+    returns: {
+      type: Data,
+      desc: 'Keystore JSON file contents.'
+    }
+  },
+
   killAccount: {
     subdoc: SUBDOC_ACCOUNTS,
     desc: 'Deletes an account.',
@@ -1225,6 +1246,78 @@ export default {
     }
   },
 
+  clearUnlockedAccounts: {
+    subdoc: SUBDOC_ACCOUNTS,
+    desc: 'Locks all unlocked accounts immediately.',
+    params: [],
+    returns: {
+      type: Boolean,
+      desc: '`true` if the call was successful.',
+      example: true
+    }
+  },
+
+  getDappPermissions: {
+    subdoc: SUBDOC_ACCOUNTS,
+    desc: 'Returns the accounts, default account and expiry of the permissions of a specific dapp.',
+    params: [
+      {
+        type: String,
+        desc: 'Dapp Id.',
+        example: 'web'
+      }
+    ],
+    returns: {
+      type: Object,
+      desc: 'Dapp permissions.',
+      details: {
+        allowedAccounts: {
+          type: Array,
+          desc: 'Accounts available to the dapp.'
+        },
+        defaultAccount: {
+          type: Address,
+          desc: 'Account used by default or `null` if not pinned.'
+        },
+        expiryTimestamp: {
+          type: Quantity,
+          desc: 'Unix timestamp after which the permissions are ignored or `null` if they never expire.'
+        }
+      },
+      example: {
+        allowedAccounts: ['0x407d73d8a49eeb85d32cf465507dd71d507100c1'],
+        defaultAccount: '0x407d73d8a49eeb85d32cf465507dd71d507100c1',
+        expiryTimestamp: null
+      }
+    }
+  },
+
+  setDappPermissions: {
+    subdoc: SUBDOC_ACCOUNTS,
+    desc: 'Sets the accounts, default account and expiry of the permissions of a specific dapp. Default account is left unchanged if `null`.',
+    params: [
+      {
+        type: String,
+        desc: 'Dapp Id.',
+        example: 'web'
+      },
+      {
+        type: Object,
+        desc: 'Dapp permissions, see `parity_getDappPermissions`.',
+        example: {
+          allowedAccounts: ['0x407d73d8a49eeb85d32cf465507dd71d507100c1'],
+          defaultAccount: '0x407d73d8a49eeb85d32cf465507dd71d507100c1',
+          expiryTimestamp: 1500000000
+        }
+      }
+    ],
+    returns: {
+      type: Boolean,
+      desc: '`true` if the call was successful.',
+      example: true
+    }
+  },
+
   setNewDappsWhitelist: {
     subdoc: SUBDOC_ACCOUNTS,
     desc: 'Sets the list of accounts available to new dapps.',
@@ -1289,6 +1382,26 @@ export default {
     }
   },
 
+  listHardwareWallets: {
+    subdoc: SUBDOC_ACCOUNTS,
+    desc: 'Returns hardware wallets seen since startup, including the ones that are no longer connected.',
+    params: [],
+    returns: {
+      type: Array,
+      desc: 'Wallet details (`name`, `manufacturer`, `serial`, `address`, `isHardware`, `connected`).',
+      example: [
+        {
+          name: 'Nano S',
+          manufacturer: 'Ledger',
+          serial: '0001',
+          address: '0x0000000000000000000000000000000000000001',
+          isHardware: true,
+          connected: true
+        }
+      ]
+    }
+  },
+
   /*
    * `parity_set` module methods
    * ===========================
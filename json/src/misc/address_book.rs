@@ -0,0 +1,44 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Address book export format.
+
+use std::io::{Read, Write};
+use serde_json;
+use hash;
+
+/// Single exported address book entry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddressBookEntry {
+	/// The address.
+	pub address: hash::Address,
+	/// The name of the address.
+	pub name: String,
+	/// The rest of the metadata of the address.
+	pub meta: String,
+}
+
+impl AddressBookEntry {
+	/// Read a list of entries.
+	pub fn read_list<R>(reader: R) -> Result<Vec<Self>, serde_json::Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+
+	/// Write a list of entries.
+	pub fn write_list<W>(entries: &[Self], writer: &mut W) -> Result<(), serde_json::Error> where W: Write {
+		serde_json::to_writer(writer, &entries)
+	}
+}
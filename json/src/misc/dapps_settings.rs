@@ -21,8 +21,12 @@ use hash;
 /// Settings for specific dapp.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DappsSettings {
-	/// A list of accounts this Dapp can see.
-	pub accounts: Vec<hash::Address>,
+	/// A list of accounts this Dapp can see. The new dapps policy applies if not set.
+	pub accounts: Option<Vec<hash::Address>>,
+	/// Account pinned as default for this Dapp.
+	pub default_address: Option<hash::Address>,
+	/// Unix timestamp after which the settings are ignored.
+	pub expiry: Option<u64>,
 }
 
 impl_serialization!(String => DappsSettings);
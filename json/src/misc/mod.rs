@@ -48,7 +48,9 @@ macro_rules! impl_serialization {
 }
 
 mod account_meta;
+mod address_book;
 mod dapps_settings;
 
 pub use self::dapps_settings::{DappsSettings, DappsHistory, NewDappsPolicy};
 pub use self::account_meta::AccountMeta;
+pub use self::address_book::AddressBookEntry;
@@ -163,6 +163,8 @@ usage! {
 			or |c: &Config| otry!(c.rpc).apis.clone().map(|vec| vec.join(",")),
 		flag_jsonrpc_hosts: String = "none",
 			or |c: &Config| otry!(c.rpc).hosts.clone().map(|vec| vec.join(",")),
+		flag_jsonrpc_rate_limits: String = "none",
+			or |c: &Config| otry!(c.rpc).rate_limits.clone().map(|vec| vec.join(",")),
 
 		// IPC
 		flag_no_ipc: bool = false,
@@ -389,6 +391,7 @@ struct Rpc {
 	cors: Option<String>,
 	apis: Option<Vec<String>>,
 	hosts: Option<Vec<String>>,
+	rate_limits: Option<Vec<String>>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -621,6 +624,7 @@ mod tests {
 			flag_jsonrpc_cors: Some("null".into()),
 			flag_jsonrpc_apis: "web3,eth,net,parity,traces,rpc".into(),
 			flag_jsonrpc_hosts: "none".into(),
+			flag_jsonrpc_rate_limits: "none".into(),
 
 			// IPC
 			flag_no_ipc: false,
@@ -807,6 +811,7 @@ mod tests {
 				cors: None,
 				apis: None,
 				hosts: None,
+				rate_limits: None,
 			}),
 			ipc: Some(Ipc {
 				disable: None,
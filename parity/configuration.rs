@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::time::Duration;
+use std::collections::HashMap;
 use std::io::Read;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
@@ -30,6 +31,7 @@ use ethcore::verification::queue::VerifierSettings;
 
 use rpc::{IpcConfiguration, HttpConfiguration};
 use ethcore_rpc::NetworkSettings;
+use ethcore_rpc::rate_limit::RateLimitConfig;
 use cache::CacheConfig;
 use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, replace_home, replace_home_for_db,
 geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address, to_gas_limit, to_queue_strategy};
@@ -351,6 +353,7 @@ impl Configuration {
 				check_seal: !self.args.flag_no_seal_check,
 				download_old_blocks: !self.args.flag_no_ancient_blocks,
 				verifier_settings: verifier_settings,
+				rpc_rate_limits: self.rpc_rate_limits()?,
 			};
 			Cmd::Run(run_cmd)
 		};
@@ -686,6 +689,30 @@ impl Configuration {
 		Some(hosts)
 	}
 
+	fn rpc_rate_limits(&self) -> Result<HashMap<String, RateLimitConfig>, String> {
+		let mut limits = HashMap::new();
+		if self.args.flag_jsonrpc_rate_limits == "none" {
+			return Ok(limits);
+		}
+
+		for entry in self.args.flag_jsonrpc_rate_limits.split(',') {
+			let invalid = || format!("Invalid RPC rate limit: {}. Expected METHOD:CALLS/SECONDS.", entry);
+			let mut parts = entry.splitn(2, ':');
+			let method = parts.next().unwrap_or("");
+			let mut limit = parts.next().ok_or_else(&invalid)?.splitn(2, '/');
+			let max_requests = limit.next().and_then(|calls| calls.parse().ok()).ok_or_else(&invalid)?;
+			let window_secs = limit.next().and_then(|secs| secs.parse().ok()).ok_or_else(&invalid)?;
+			if method.is_empty() || max_requests == 0 || window_secs == 0 {
+				return Err(invalid());
+			}
+			limits.insert(method.to_owned(), RateLimitConfig {
+				max_requests: max_requests,
+				window_secs: window_secs,
+			});
+		}
+		Ok(limits)
+	}
+
 	fn dapps_hosts(&self) -> Option<Vec<String>> {
 		match self.args.flag_dapps_hosts.as_ref() {
 			"none" => return Some(Vec::new()),
@@ -1112,6 +1139,7 @@ mod tests {
 			check_seal: true,
 			download_old_blocks: true,
 			verifier_settings: Default::default(),
+			rpc_rate_limits: Default::default(),
 		}));
 	}
 
@@ -1217,6 +1245,26 @@ mod tests {
 		assert_eq!(conf3.rpc_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
 	}
 
+	#[test]
+	fn should_parse_rpc_rate_limits() {
+		// given
+		let mut expected = HashMap::new();
+		expected.insert("eth_getLogs".to_owned(), RateLimitConfig { max_requests: 10, window_secs: 1 });
+		expected.insert("eth_call".to_owned(), RateLimitConfig { max_requests: 100, window_secs: 60 });
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-rate-limits", "eth_getLogs:10/1,eth_call:100/60"]);
+		let conf2 = parse(&["parity", "--jsonrpc-rate-limits", "eth_getLogs:10"]);
+		let conf3 = parse(&["parity", "--jsonrpc-rate-limits", "eth_getLogs:0/1"]);
+
+		// then
+		assert_eq!(conf0.rpc_rate_limits(), Ok(HashMap::new()));
+		assert_eq!(conf1.rpc_rate_limits(), Ok(expected));
+		assert!(conf2.rpc_rate_limits().is_err());
+		assert!(conf3.rpc_rate_limits().is_err());
+	}
+
 	#[test]
 	fn should_parse_dapps_hosts() {
 		// given
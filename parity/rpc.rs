@@ -22,14 +22,14 @@ use io::PanicHandler;
 
 use dir::default_data_path;
 use ethcore_rpc::{self as rpc, RpcServerError, IpcServerError, Metadata};
-use ethcore_rpc::informant::{RpcStats, Middleware};
+use ethcore_rpc::informant::RpcStats;
 use helpers::parity_ipc_path;
 use jsonrpc_core::MetaIoHandler;
 use jsonrpc_core::reactor::{RpcHandler, Remote};
 use rpc_apis;
-use rpc_apis::ApiSet;
+use rpc_apis::{ApiSet, Middleware};
 
-pub use ethcore_rpc::{IpcServer, Server as HttpServer};
+pub use ethcore_rpc::{IpcServer, HttpServer};
 
 #[derive(Debug, PartialEq)]
 pub struct HttpConfiguration {
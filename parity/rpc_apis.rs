@@ -16,7 +16,7 @@
 
 use std::cmp::PartialEq;
 use std::collections::BTreeMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -27,7 +27,9 @@ use ethcore::client::Client;
 use ethcore::miner::{Miner, ExternalMiner};
 use ethcore::snapshot::SnapshotService;
 use ethcore_rpc::{Metadata, NetworkSettings};
-use ethcore_rpc::informant::{Middleware, RpcStats, ClientNotifier};
+use ethcore_rpc::informant::{Middleware as StatsMiddleware, RpcStats, ClientNotifier};
+use ethcore_rpc::rate_limit::{RpcRateLimiter, RateLimitConfig};
+use ethcore_rpc::request_id::RequestTracer;
 use ethcore_rpc::dispatch::FullDispatcher;
 use ethsync::{ManageNetwork, SyncProvider};
 use hash_fetch::fetch::Client as FetchClient;
@@ -57,6 +59,8 @@ pub enum Api {
 	Traces,
 	/// Rpc (Safe)
 	Rpc,
+	/// Debug (UNSAFE: Exposes internal data, never enabled by default)
+	Debug,
 }
 
 impl FromStr for Api {
@@ -76,6 +80,7 @@ impl FromStr for Api {
 			"parity_set" => Ok(ParitySet),
 			"traces" => Ok(Traces),
 			"rpc" => Ok(Rpc),
+			"debug" => Ok(Debug),
 			api => Err(format!("Unknown api: {}", api))
 		}
 	}
@@ -129,8 +134,12 @@ pub struct Dependencies {
 	pub dapps_interface: Option<String>,
 	pub dapps_port: Option<u16>,
 	pub fetch: FetchClient,
+	pub rate_limits: HashMap<String, RateLimitConfig>,
 }
 
+/// Middleware of the RPC handlers.
+pub type Middleware = RequestTracer<RpcRateLimiter<StatsMiddleware>>;
+
 fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 	let mut modules = BTreeMap::new();
 	for api in apis {
@@ -145,6 +154,7 @@ fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 			Api::ParitySet => ("parity_set", "1.0"),
 			Api::Traces => ("traces", "1.0"),
 			Api::Rpc => ("rpc", "1.0"),
+			Api::Debug => ("debug", "1.0"),
 		};
 		modules.insert(name.into(), version.into());
 	}
@@ -190,9 +200,11 @@ macro_rules! add_signing_methods {
 pub fn setup_rpc(stats: Arc<RpcStats>, deps: Arc<Dependencies>, apis: ApiSet) -> MetaIoHandler<Metadata, Middleware> {
 	use ethcore_rpc::v1::*;
 
-	let mut handler = MetaIoHandler::with_middleware(Middleware::new(stats, ClientNotifier {
+	let stats_middleware = StatsMiddleware::new(stats, ClientNotifier {
 		client: deps.client.clone(),
-	}));
+	});
+	let rate_limiter = RpcRateLimiter::new(stats_middleware, deps.rate_limits.clone());
+	let mut handler = MetaIoHandler::with_middleware(RequestTracer::new(rate_limiter));
 
 	// it's turned into vector, cause ont of the cases requires &[]
 	let apis = apis.list_apis().into_iter().collect::<Vec<_>>();
@@ -272,6 +284,9 @@ pub fn setup_rpc(stats: Arc<RpcStats>, deps: Arc<Dependencies>, apis: ApiSet) ->
 			Api::Rpc => {
 				let modules = to_modules(&apis);
 				handler.extend_with(RpcClient::new(modules).to_delegate());
+			},
+			Api::Debug => {
+				handler.extend_with(DebugClient::new(&deps.client).to_delegate());
 			}
 		}
 	}
@@ -294,6 +309,7 @@ mod test {
 		assert_eq!(Api::ParitySet, "parity_set".parse().unwrap());
 		assert_eq!(Api::Traces, "traces".parse().unwrap());
 		assert_eq!(Api::Rpc, "rpc".parse().unwrap());
+		assert_eq!(Api::Debug, "debug".parse().unwrap());
 		assert!("rp".parse::<Api>().is_err());
 	}
 
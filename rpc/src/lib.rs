@@ -25,12 +25,14 @@ extern crate serde;
 extern crate serde_json;
 extern crate jsonrpc_core;
 extern crate jsonrpc_http_server;
+extern crate hyper;
 
 extern crate ethcore_io as io;
 extern crate ethcore;
 extern crate ethkey;
 extern crate ethcrypto as crypto;
 extern crate ethstore;
+extern crate hardware_wallet;
 extern crate ethsync;
 extern crate ethash;
 extern crate ethcore_light as light;
@@ -57,43 +59,114 @@ extern crate ethjson;
 #[cfg(test)]
 extern crate ethcore_devtools as devtools;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::net::SocketAddr;
+use std::thread;
 use io::PanicHandler;
 use jsonrpc_core::reactor::RpcHandler;
+use jsonrpc_http_server::{Rpc, ServerHandler, AccessControlAllowOrigin};
 
 pub use ipc::{Server as IpcServer, Error as IpcServerError};
-pub use jsonrpc_http_server::{ServerBuilder, Server, RpcServerError};
+pub use jsonrpc_http_server::RpcServerError;
 pub mod v1;
-pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings, Metadata, Origin, informant, dispatch};
+pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings, Metadata, Origin, informant, rate_limit, request_id, dispatch};
 pub use v1::block_import::is_major_importing;
 
-/// Start http server asynchronously and returns result with `Server` handle on success or an error.
-pub fn start_http<M: jsonrpc_core::Metadata, S: jsonrpc_core::Middleware<M>>(
+struct HttpMetaExtractor {
+	request_id: Arc<Mutex<Option<String>>>,
+}
+
+impl jsonrpc_http_server::HttpMetaExtractor<Metadata> for HttpMetaExtractor {
+	fn read_metadata(&self, request: &hyper::server::Request<hyper::net::HttpStream>) -> Metadata {
+		let request_id = v1::request_id::read_request_id(request.headers());
+		*self.request_id.lock().expect("Lock is never poisoned; qed") = Some(request_id.clone());
+		Metadata {
+			request_id: Some(request_id),
+			remote_ip: request.remote_addr().map(|addr| addr.ip()),
+			..Default::default()
+		}
+	}
+}
+
+/// Running JSON-RPC HTTP server. Stops listening when dropped.
+pub struct HttpServer {
+	server: Option<hyper::server::Listening>,
+}
+
+impl HttpServer {
+	/// Returns address that this server is bound to.
+	pub fn addr(&self) -> &SocketAddr {
+		self.server.as_ref()
+			.expect("server is always Some at the start; it's consumed only when object is dropped; qed")
+			.addrs()
+			.first()
+			.expect("You cannot start the server without binding to at least one address; qed")
+	}
+}
+
+impl Drop for HttpServer {
+	fn drop(&mut self) {
+		self.server.take().unwrap().close()
+	}
+}
+
+/// Start http server asynchronously and returns result with `HttpServer` handle on success or an error.
+/// Responses carry the `X-Request-ID` of the request they answer.
+pub fn start_http<S: jsonrpc_core::Middleware<Metadata>>(
 	addr: &SocketAddr,
 	cors_domains: Option<Vec<String>>,
 	allowed_hosts: Option<Vec<String>>,
 	panic_handler: Arc<PanicHandler>,
-	handler: RpcHandler<M, S>,
-) -> Result<Server, RpcServerError> {
+	handler: RpcHandler<Metadata, S>,
+) -> Result<HttpServer, RpcServerError> {
 
-	let cors_domains = cors_domains.map(|domains| {
+	let cors_domains: Option<Vec<_>> = cors_domains.map(|domains| {
 		domains.into_iter()
 			.map(|v| match v.as_str() {
-				"*" => jsonrpc_http_server::AccessControlAllowOrigin::Any,
-				"null" => jsonrpc_http_server::AccessControlAllowOrigin::Null,
-				v => jsonrpc_http_server::AccessControlAllowOrigin::Value(v.into()),
+				"*" => AccessControlAllowOrigin::Any,
+				"null" => AccessControlAllowOrigin::Null,
+				v => AccessControlAllowOrigin::Value(v.into()),
 			})
 			.collect()
 	});
+	let on_panic: Box<Fn() -> () + Send> = Box::new(move || {
+		panic_handler.notify_all("Panic in RPC thread.".to_owned());
+	});
+	let on_panic = Arc::new(Mutex::new(Some(on_panic)));
+	// Addresses the server is bound to are allowed as well; they are known only after binding.
+	let allowed_hosts = Arc::new(Mutex::new(allowed_hosts));
+	let bound_hosts = allowed_hosts.clone();
+
+	let (listening, server) = hyper::Server::http(addr)?
+		.handle(move |control| {
+			let allowed_hosts = allowed_hosts.lock().expect("Lock is never poisoned; qed").clone();
+			let request_id = Arc::new(Mutex::new(None));
+			let meta_extractor = HttpMetaExtractor {
+				request_id: request_id.clone(),
+			};
+			v1::request_id::RequestIdHandler::new(
+				ServerHandler::new(
+					Rpc::new(handler.clone(), Arc::new(meta_extractor)),
+					cors_domains.clone(),
+					allowed_hosts,
+					jsonrpc_http_server::PanicHandler { handler: on_panic.clone() },
+					control,
+				),
+				request_id,
+			)
+		})?;
+
+	if let Some(ref mut hosts) = *bound_hosts.lock().expect("Lock is never poisoned; qed") {
+		hosts.extend(listening.addrs().iter().map(|addr| format!("{}", addr)));
+	}
 
-	ServerBuilder::with_rpc_handler(handler)
-		.cors(cors_domains.into())
-		.allowed_hosts(allowed_hosts.into())
-		.panic_handler(move || {
-			panic_handler.notify_all("Panic in RPC thread.".to_owned());
-		})
-		.start_http(addr)
+	thread::spawn(move || {
+		server.run();
+	});
+
+	Ok(HttpServer {
+		server: Some(listening),
+	})
 }
 
 /// Start ipc server asynchronously and returns result with `Server` handle on success or an error.
@@ -105,3 +178,54 @@ pub fn start_ipc<M: jsonrpc_core::Metadata, S: jsonrpc_core::Middleware<M>>(
 	server.run_async()?;
 	Ok(server)
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use devtools::http_client;
+	use futures::{future, Future};
+	use io::PanicHandler;
+	use jsonrpc_core::{MetaIoHandler, Value};
+	use jsonrpc_core::reactor::RpcEventLoop;
+	use super::{start_http, Metadata};
+
+	#[test]
+	fn should_echo_request_id() {
+		// given
+		let mut io = MetaIoHandler::default();
+		io.add_method_with_meta("rpc_test", |_params, meta: Metadata| {
+			future::ok(Value::String(meta.request_id.unwrap_or_else(String::new))).boxed()
+		});
+		let event_loop = RpcEventLoop::spawn();
+		let server = start_http(
+			&"127.0.0.1:0".parse().unwrap(),
+			None,
+			None,
+			PanicHandler::new_in_arc(),
+			event_loop.handler(Arc::new(io)),
+		).unwrap();
+
+		// when
+		let req = r#"{"jsonrpc":"2.0","id":1,"method":"rpc_test","params":[]}"#;
+		let response = http_client::request(server.addr(), &format!(
+			"\
+				POST / HTTP/1.1\r\n\
+				Host: {}\r\n\
+				Connection: close\r\n\
+				X-Request-ID: test-123\r\n\
+				Content-Type: application/json\r\n\
+				Content-Length: {}\r\n\
+				\r\n\
+				{}\r\n\
+			",
+			server.addr(),
+			req.as_bytes().len(),
+			req,
+		));
+
+		// then
+		response.assert_status("HTTP/1.1 200 OK");
+		response.assert_header("X-Request-ID", "test-123");
+		assert!(response.body.contains(r#""result":"test-123""#), "Unexpected body: {}", response.body);
+	}
+}
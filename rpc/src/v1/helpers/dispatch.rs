@@ -40,6 +40,8 @@ use v1::types::{
 	ConfirmationResponse,
 	SignRequest as RpcSignRequest,
 	DecryptRequest as RpcDecryptRequest,
+	TypedDataRequest as RpcTypedDataRequest,
+	TypedData as RpcTypedData,
 };
 
 /// Has the capability to dispatch, sign, and decrypt.
@@ -283,15 +285,7 @@ pub fn execute<D: Dispatcher + 'static>(
 			message_data.append(&mut data);
 			let res = signature(accounts, address, message_data.sha3(), pass)
 				.map(|result| result
-					.map(|rsv| {
-						let mut vrs = [0u8; 65];
-						let rsv = rsv.as_ref();
-						vrs[0] = rsv[64] + 27;
-						vrs[1..33].copy_from_slice(&rsv[0..32]);
-						vrs[33..65].copy_from_slice(&rsv[32..64]);
-						H520(vrs)
-					})
-					.map(RpcH520::from)
+					.map(vrs_signature)
 					.map(ConfirmationResponse::Signature)
 				);
 			future::done(res).boxed()
@@ -304,9 +298,35 @@ pub fn execute<D: Dispatcher + 'static>(
 				);
 			future::done(res).boxed()
 		},
+		ConfirmationPayload::SignTypedData(address, domain_separator, message_hash) => {
+			let res = if accounts.is_hardware_address(address) {
+				accounts.sign_typed_data_with_hardware(address, &domain_separator.0, &message_hash.0)
+					.map(WithToken::No)
+					.map_err(|e| {
+						debug!(target: "miner", "Error signing typed data with hardware wallet: {}", e);
+						errors::account("Error signing typed data with hardware wallet", e)
+					})
+			} else {
+				signature(accounts, address, RpcTypedData::signing_hash(&domain_separator, &message_hash), pass)
+			};
+			future::done(res.map(|result| result
+				.map(vrs_signature)
+				.map(ConfirmationResponse::Signature)
+			)).boxed()
+		},
 	}
 }
 
+/// Convert signature to `v ‖ r ‖ s` form returned by signing methods.
+pub fn vrs_signature(signature: Signature) -> RpcH520 {
+	let mut vrs = [0u8; 65];
+	let rsv = signature.as_ref();
+	vrs[0] = rsv[64] + 27;
+	vrs[1..33].copy_from_slice(&rsv[0..32]);
+	vrs[33..65].copy_from_slice(&rsv[32..64]);
+	H520(vrs).into()
+}
+
 fn signature(accounts: &AccountProvider, address: Address, hash: H256, password: SignWith) -> Result<WithToken<Signature>, Error> {
 	match password.clone() {
 		SignWith::Nothing => accounts.sign(address, None, hash).map(WithToken::No),
@@ -358,5 +378,8 @@ pub fn from_rpc<D>(payload: RpcConfirmationPayload, default_account: Address, di
 		RpcConfirmationPayload::Signature(RpcSignRequest { address, data }) => {
 			future::ok(ConfirmationPayload::Signature(address.into(), data.into())).boxed()
 		},
+		RpcConfirmationPayload::SignTypedData(RpcTypedDataRequest { address, domain_separator, message_hash }) => {
+			future::ok(ConfirmationPayload::SignTypedData(address.into(), domain_separator.into(), message_hash.into())).boxed()
+		},
 	}
 }
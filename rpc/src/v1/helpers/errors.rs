@@ -21,18 +21,32 @@ macro_rules! rpc_unimplemented {
 }
 
 use std::fmt;
+use std::collections::BTreeMap;
 use rlp::DecoderError;
+use rustc_serialize::hex::ToHex;
+use util::{U256, Uint};
 use ethcore::error::{Error as EthcoreError, CallError, TransactionError};
 use ethcore::account_provider::{SignError as AccountError};
+use hardware_wallet::Error as HardwareError;
 use jsonrpc_core::{Error, ErrorCode, Value};
 
 mod codes {
 	// NOTE [ToDr] Codes from [-32099, -32000]
+	//
+	// EIP-1474 migration:
+	// - `NO_WORK` (-32001) and `NO_AUTHOR` (-32002) are kept as aliases of `EIP1474_RESOURCE_NOT_FOUND`
+	//   and `EIP1474_RESOURCE_UNAVAILABLE`. Codes sent over the wire are unchanged.
+	// - Standard codes outside of the server range (-32700, -32600, -32601, -32602, -32603)
+	//   are provided by `jsonrpc_core::ErrorCode` and must not be redefined here.
+	// Every value apart from the aliases must be unique, see `tests::codes_are_unique`.
 	pub const UNSUPPORTED_REQUEST: i64 = -32000;
-	pub const NO_WORK: i64 = -32001;
-	pub const NO_AUTHOR: i64 = -32002;
+	pub const EIP1474_RESOURCE_NOT_FOUND: i64 = -32001;
+	pub const EIP1474_RESOURCE_UNAVAILABLE: i64 = -32002;
+	pub const NO_WORK: i64 = EIP1474_RESOURCE_NOT_FOUND;
+	pub const NO_AUTHOR: i64 = EIP1474_RESOURCE_UNAVAILABLE;
 	pub const NO_NEW_WORK: i64 = -32003;
 	pub const NOT_ENOUGH_DATA: i64 = -32006;
+	pub const RATE_LIMITED: i64 = -32007;
 	pub const UNKNOWN_ERROR: i64 = -32009;
 	pub const TRANSACTION_ERROR: i64 = -32010;
 	pub const EXECUTION_ERROR: i64 = -32015;
@@ -40,6 +54,8 @@ mod codes {
 	pub const ACCOUNT_LOCKED: i64 = -32020;
 	pub const PASSWORD_INVALID: i64 = -32021;
 	pub const ACCOUNT_ERROR: i64 = -32023;
+	pub const VAULT_ERROR: i64 = -32024;
+	pub const HARDWARE_WALLET_ERROR: i64 = -32025;
 	pub const SIGNER_DISABLED: i64 = -32030;
 	pub const DAPPS_DISABLED: i64 = -32031;
 	pub const NETWORK_DISABLED: i64 = -32035;
@@ -84,6 +100,14 @@ pub fn request_rejected_limit() -> Error {
 	}
 }
 
+pub fn rate_limited(retry_after_ms: u64) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::RATE_LIMITED),
+		message: "Too many requests. Retry after given number of milliseconds.".into(),
+		data: Some(Value::U64(retry_after_ms)),
+	}
+}
+
 pub fn account<T: fmt::Debug>(error: &str, details: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::ACCOUNT_ERROR),
@@ -92,6 +116,30 @@ pub fn account<T: fmt::Debug>(error: &str, details: T) -> Error {
 	}
 }
 
+pub fn vault_not_found(name: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::VAULT_ERROR),
+		message: "Vault not found.".into(),
+		data: Some(Value::String(name.into())),
+	}
+}
+
+pub fn vault_wrong_password() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::VAULT_ERROR),
+		message: "Invalid vault password.".into(),
+		data: None,
+	}
+}
+
+pub fn vault_already_exists(name: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::VAULT_ERROR),
+		message: "Vault already exists.".into(),
+		data: Some(Value::String(name.into())),
+	}
+}
+
 pub fn compilation<T: fmt::Debug>(error: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::COMPILATION_ERROR),
@@ -100,6 +148,36 @@ pub fn compilation<T: fmt::Debug>(error: T) -> Error {
 	}
 }
 
+/// Attaches the request correlation id to the error data.
+/// The data becomes an object with the original data under `details`.
+pub fn with_request_id(mut error: Error, request_id: &str) -> Error {
+	let mut data = BTreeMap::new();
+	if let Some(details) = error.data.take() {
+		data.insert("details".to_owned(), details);
+	}
+	data.insert("requestId".to_owned(), Value::String(request_id.into()));
+	error.data = Some(Value::Object(data));
+	error
+}
+
+/// Attaches the request correlation id to internal and execution errors which don't carry it yet.
+pub fn trace_request(error: Error, request_id: &str) -> Error {
+	let traceable = match error.code {
+		ErrorCode::InternalError | ErrorCode::ServerError(codes::EXECUTION_ERROR) => true,
+		_ => false,
+	};
+	let traced = match error.data {
+		Some(Value::Object(ref data)) => data.contains_key("requestId"),
+		_ => false,
+	};
+
+	if traceable && !traced {
+		with_request_id(error, request_id)
+	} else {
+		error
+	}
+}
+
 pub fn internal<T: fmt::Debug>(error: &str, data: T) -> Error {
 	Error {
 		code: ErrorCode::InternalError,
@@ -108,6 +186,10 @@ pub fn internal<T: fmt::Debug>(error: &str, data: T) -> Error {
 	}
 }
 
+pub fn internal_with_id<T: fmt::Debug>(error: &str, data: T, request_id: &str) -> Error {
+	with_request_id(internal(error, data), request_id)
+}
+
 pub fn invalid_params<T: fmt::Debug>(param: &str, details: T) -> Error {
 	Error {
 		code: ErrorCode::InvalidParams,
@@ -124,6 +206,62 @@ pub fn execution<T: fmt::Debug>(data: T) -> Error {
 	}
 }
 
+pub fn execution_with_id<T: fmt::Debug>(data: T, request_id: &str) -> Error {
+	with_request_id(execution(data), request_id)
+}
+
+/// Selector of `Error(string)`, the call Solidity encodes `revert("reason")` as.
+const REVERT_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Decodes the reason of a Solidity `revert("reason")` from the output of a reverted call.
+pub fn decode_revert_reason(bytes: &[u8]) -> Option<String> {
+	if bytes.len() < REVERT_SELECTOR.len() || &bytes[..REVERT_SELECTOR.len()] != &REVERT_SELECTOR[..] {
+		return None;
+	}
+
+	let data = &bytes[REVERT_SELECTOR.len()..];
+	// ABI word at given position, if it fits in usize and doesn't point past the data
+	let word = |position: usize| -> Option<usize> {
+		if data.len() < position + 32 || data[position..position + 24].iter().any(|byte| *byte != 0) {
+			return None;
+		}
+		let value = data[position + 24..position + 32].iter().fold(0u64, |value, byte| value << 8 | *byte as u64);
+		if value > data.len() as u64 {
+			None
+		} else {
+			Some(value as usize)
+		}
+	};
+
+	let offset = match word(0) {
+		Some(offset) => offset,
+		None => return None,
+	};
+	let length = match word(offset) {
+		Some(length) => length,
+		None => return None,
+	};
+	let start = offset + 32;
+	if data.len() < start + length {
+		return None;
+	}
+	String::from_utf8(data[start..start + length].to_vec()).ok()
+}
+
+/// Execution error of a call reverted with given output, if the output carries a revert reason.
+pub fn execution_reverted(output: &[u8]) -> Option<Error> {
+	decode_revert_reason(output).map(|reason| {
+		let mut data = BTreeMap::new();
+		data.insert("revert".to_owned(), Value::String(reason));
+		data.insert("raw".to_owned(), Value::String(format!("0x{}", output.to_hex())));
+		Error {
+			code: ErrorCode::ServerError(codes::EXECUTION_ERROR),
+			message: "Transaction execution error.".into(),
+			data: Some(Value::Object(data)),
+		}
+	})
+}
+
 pub fn state_pruned() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -220,11 +358,38 @@ pub fn from_fetch_error<T: fmt::Debug>(error: T) -> Error {
 	}
 }
 
-pub fn from_signing_error(error: AccountError) -> Error {
+pub fn hardware_wallet_error(error: &HardwareError) -> Error {
+	let message = match *error {
+		HardwareError::KeyNotFound => "Hardware wallet holding the account is not connected.".into(),
+		HardwareError::UserRejected => "Request has been rejected on the hardware wallet.".into(),
+		HardwareError::PinNotSupported => "Hardware wallet does not support entering the PIN from the host.".into(),
+		HardwareError::DeviceLocked => "Hardware wallet is locked. Unlock it with the PIN.".into(),
+		HardwareError::DeviceBusy => "Hardware wallet is busy with other requests. Try again later.".into(),
+		HardwareError::LedgerDevice(ref e) => format!("Ledger device error: {}", e),
+		HardwareError::TrezorDevice(ref e) => format!("Trezor device error: {}", e),
+		HardwareError::Hid(ref e) => format!("Unable to communicate with the hardware wallet: {}", e),
+		HardwareError::Usb(ref e) => format!("USB error: {}", e),
+		HardwareError::UsbContextInit(ref e) => format!("Unable to initialize USB: {}", e),
+		HardwareError::CallbackRegistration(ref e) => format!("Unable to watch for USB devices: {}", e),
+		HardwareError::Config(ref e) => format!("Invalid hardware wallet configuration: {}", e),
+		HardwareError::Io(ref e) => format!("Hardware wallet file error: {}", e),
+	};
+
 	Error {
-		code: ErrorCode::ServerError(codes::ACCOUNT_LOCKED),
-		message: "Your account is locked. Unlock the account via CLI, personal_unlockAccount or use Trusted Signer.".into(),
-		data: Some(Value::String(format!("{:?}", error))),
+		code: ErrorCode::ServerError(codes::HARDWARE_WALLET_ERROR),
+		message: message,
+		data: None,
+	}
+}
+
+pub fn from_signing_error(error: AccountError) -> Error {
+	match error {
+		AccountError::Hardware(ref e) => hardware_wallet_error(e),
+		error => Error {
+			code: ErrorCode::ServerError(codes::ACCOUNT_LOCKED),
+			message: "Your account is locked. Unlock the account via CLI, personal_unlockAccount or use Trusted Signer.".into(),
+			data: Some(Value::String(format!("{:?}", error))),
+		},
 	}
 }
 
@@ -268,13 +433,46 @@ pub fn transaction_message(error: TransactionError) -> String {
 	}
 }
 
+fn transaction_error_data(error: TransactionError) -> Value {
+	use ethcore::error::TransactionError::*;
+
+	fn number(value: U256) -> Value {
+		if value <= U256::from(u64::max_value()) {
+			Value::U64(value.low_u64())
+		} else {
+			Value::String(format!("{}", value))
+		}
+	}
+
+	let (kind, fields) = match error {
+		AlreadyImported => ("AlreadyImported", vec![]),
+		Old => ("Old", vec![]),
+		TooCheapToReplace => ("TooCheapToReplace", vec![]),
+		LimitReached => ("LimitReached", vec![]),
+		InsufficientGas { minimal, got } => ("InsufficientGas", vec![("minimal", number(minimal)), ("got", number(got))]),
+		InsufficientGasPrice { minimal, got } => ("InsufficientGasPrice", vec![("minimal", number(minimal)), ("got", number(got))]),
+		InsufficientBalance { balance, cost } => ("InsufficientBalance", vec![("balance", number(balance)), ("cost", number(cost))]),
+		GasLimitExceeded { limit, got } => ("GasLimitExceeded", vec![("limit", number(limit)), ("got", number(got))]),
+		InvalidNetworkId => ("InvalidNetworkId", vec![]),
+		InvalidGasLimit(_) => ("InvalidGasLimit", vec![]),
+		SenderBanned => ("SenderBanned", vec![]),
+		RecipientBanned => ("RecipientBanned", vec![]),
+		CodeBanned => ("CodeBanned", vec![]),
+	};
+
+	Value::Object(Some(("kind", Value::String(kind.into()))).into_iter()
+		.chain(fields)
+		.map(|(key, value)| (key.to_owned(), value))
+		.collect())
+}
+
 pub fn from_transaction_error(error: EthcoreError) -> Error {
 
 	if let EthcoreError::Transaction(e) = error {
 		Error {
 			code: ErrorCode::ServerError(codes::TRANSACTION_ERROR),
 			message: transaction_message(e),
-			data: None,
+			data: Some(transaction_error_data(e)),
 		}
 	} else {
 		Error {
@@ -317,3 +515,173 @@ pub fn no_filter_error() -> Error {
 		data: None
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use jsonrpc_core::{ErrorCode, Value};
+	use ethcore::account_provider::SignError;
+	use hardware_wallet::{Error as HardwareError, ConfigError};
+	use ethcore::error::{Error as EthcoreError, TransactionError};
+	use std::collections::HashSet;
+	use super::codes::*;
+	use super::{vault_not_found, vault_wrong_password, vault_already_exists};
+	use super::{rate_limited, hardware_wallet_error, from_signing_error, from_transaction_error};
+	use super::{internal_with_id, execution, decode_revert_reason, execution_reverted};
+
+	/// Output of a call reverted with `revert(reason)`
+	fn revert_output(reason: &str) -> Vec<u8> {
+		fn word(output: &mut Vec<u8>, value: usize) {
+			output.extend_from_slice(&[0u8; 24]);
+			output.extend((0..8).rev().map(|i| (value >> (i * 8)) as u8));
+		}
+
+		let mut output = vec![0x08, 0xc3, 0x79, 0xa0];
+		word(&mut output, 32);
+		word(&mut output, reason.len());
+		output.extend_from_slice(reason.as_bytes());
+		let padding = (32 - reason.len() % 32) % 32;
+		output.extend(::std::iter::repeat(0u8).take(padding));
+		output
+	}
+
+	#[test]
+	fn should_decode_revert_reason() {
+		// given
+		let output = revert_output("Insufficient funds");
+
+		// then
+		assert_eq!(decode_revert_reason(&output), Some("Insufficient funds".to_owned()));
+		assert_eq!(decode_revert_reason(&revert_output("")), Some("".to_owned()));
+	}
+
+	#[test]
+	fn should_not_decode_other_outputs() {
+		let output = revert_output("Insufficient funds");
+		let mut other_selector = output.clone();
+		other_selector[0] = 0x4e;
+		let mut long_length = output.clone();
+		long_length[4 + 32 + 31] = 0xff;
+
+		assert_eq!(decode_revert_reason(&[]), None);
+		assert_eq!(decode_revert_reason(&output[..4]), None);
+		assert_eq!(decode_revert_reason(&output[..output.len() - 32]), None);
+		assert_eq!(decode_revert_reason(&other_selector), None);
+		assert_eq!(decode_revert_reason(&long_length), None);
+	}
+
+	#[test]
+	fn should_include_revert_reason_in_execution_error() {
+		// given
+		let output = revert_output("Not owner");
+
+		// when
+		let error = execution_reverted(&output).unwrap();
+
+		// then
+		assert_eq!(error.code, ErrorCode::ServerError(EXECUTION_ERROR));
+		let data = match error.data {
+			Some(Value::Object(data)) => data,
+			other => panic!("Unexpected data: {:?}", other),
+		};
+		assert_eq!(data.get("revert"), Some(&Value::String("Not owner".into())));
+		assert_eq!(data.get("raw"), Some(&Value::String(format!("0x{}", ::rustc_serialize::hex::ToHex::to_hex(&output[..])))));
+		assert!(execution_reverted(&[]).is_none());
+	}
+
+	#[test]
+	fn codes_are_unique() {
+		let codes = [
+			UNSUPPORTED_REQUEST, EIP1474_RESOURCE_NOT_FOUND, EIP1474_RESOURCE_UNAVAILABLE, NO_NEW_WORK,
+			NOT_ENOUGH_DATA, RATE_LIMITED, UNKNOWN_ERROR, TRANSACTION_ERROR, EXECUTION_ERROR, EXCEPTION_ERROR,
+			ACCOUNT_LOCKED, PASSWORD_INVALID, ACCOUNT_ERROR, VAULT_ERROR, HARDWARE_WALLET_ERROR, SIGNER_DISABLED,
+			DAPPS_DISABLED, NETWORK_DISABLED, REQUEST_REJECTED, REQUEST_REJECTED_LIMIT, REQUEST_NOT_FOUND,
+			COMPILATION_ERROR, ENCRYPTION_ERROR, FETCH_ERROR, NO_FILTER_ERROR,
+		];
+
+		let unique: HashSet<_> = codes.iter().collect();
+		assert_eq!(unique.len(), codes.len());
+		assert!(codes.iter().all(|code| *code <= -32000 && *code >= -32099));
+	}
+
+	#[test]
+	fn should_return_rate_limited_error() {
+		let error = rate_limited(1500);
+
+		assert_eq!(error.code, ErrorCode::ServerError(-32007));
+		assert_eq!(error.message, "Too many requests. Retry after given number of milliseconds.");
+		assert_eq!(error.data, Some(Value::U64(1500)));
+	}
+
+	#[test]
+	fn should_include_request_id_in_error_data() {
+		let error = internal_with_id("Unexpected result.", "details", "test-123");
+		let data = match error.data {
+			Some(Value::Object(data)) => data,
+			other => panic!("Unexpected data: {:?}", other),
+		};
+		assert_eq!(data.get("requestId"), Some(&Value::String("test-123".into())));
+		assert_eq!(data.get("details"), Some(&Value::String("\"details\"".into())));
+
+		let error = execution("reverted");
+		assert_eq!(error.data, Some(Value::String("\"reverted\"".into())));
+	}
+
+	#[test]
+	fn should_describe_hardware_wallet_errors() {
+		let cases = vec![
+			(HardwareError::KeyNotFound, "Hardware wallet holding the account is not connected."),
+			(HardwareError::UserRejected, "Request has been rejected on the hardware wallet."),
+			(HardwareError::PinNotSupported, "Hardware wallet does not support entering the PIN from the host."),
+			(HardwareError::Config(ConfigError::PollingInterval(Duration::from_millis(1))),
+				"Invalid hardware wallet configuration: Polling interval of 1ms is out of range. Expected between 10ms and 5000ms."),
+		];
+
+		for (hardware_error, message) in cases {
+			let error = hardware_wallet_error(&hardware_error);
+			assert_eq!(error.code, ErrorCode::ServerError(-32025));
+			assert_eq!(error.message, message);
+		}
+	}
+
+	#[test]
+	fn should_map_hardware_signing_errors() {
+		let error = from_signing_error(SignError::Hardware(HardwareError::UserRejected));
+		assert_eq!(error.code, ErrorCode::ServerError(-32025));
+
+		let error = from_signing_error(SignError::NotUnlocked);
+		assert_eq!(error.code, ErrorCode::ServerError(-32020));
+	}
+
+	#[test]
+	fn should_include_transaction_error_details() {
+		let error = from_transaction_error(EthcoreError::Transaction(TransactionError::InsufficientBalance {
+			balance: 10.into(),
+			cost: 21000.into(),
+		}));
+
+		assert_eq!(error.code, ErrorCode::ServerError(-32010));
+		let data = match error.data {
+			Some(Value::Object(data)) => data,
+			other => panic!("Unexpected data: {:?}", other),
+		};
+		assert_eq!(data.get("kind"), Some(&Value::String("InsufficientBalance".into())));
+		assert_eq!(data.get("balance"), Some(&Value::U64(10)));
+		assert_eq!(data.get("cost"), Some(&Value::U64(21000)));
+	}
+
+	#[test]
+	fn should_return_vault_errors() {
+		let error = vault_not_found("vault1");
+		assert_eq!(error.code, ErrorCode::ServerError(-32024));
+		assert_eq!(error.data, Some(Value::String("vault1".into())));
+
+		let error = vault_already_exists("vault2");
+		assert_eq!(error.code, ErrorCode::ServerError(-32024));
+		assert_eq!(error.data, Some(Value::String("vault2".into())));
+
+		let error = vault_wrong_password();
+		assert_eq!(error.code, ErrorCode::ServerError(-32024));
+		assert_eq!(error.message, "Invalid vault password.");
+	}
+}
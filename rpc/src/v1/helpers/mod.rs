@@ -20,6 +20,8 @@ pub mod errors;
 pub mod block_import;
 pub mod dispatch;
 pub mod informant;
+pub mod rate_limit;
+pub mod request_id;
 
 mod network_settings;
 mod poll_manager;
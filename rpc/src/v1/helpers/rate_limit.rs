@@ -0,0 +1,250 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-method RPC rate limiting
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use jsonrpc_core as rpc;
+use util::Mutex;
+use v1::helpers::errors;
+use v1::metadata::Metadata;
+
+/// Rate limit of a single method.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+	/// Maximal number of calls within the window.
+	pub max_requests: u32,
+	/// Length of the sliding window in seconds.
+	pub window_secs: u32,
+}
+
+/// Middleware rejecting calls of rate-limited methods made too often by the same caller.
+/// Callers are told apart by their IP address, or by the dapp id and origin of the request
+/// when the transport doesn't know the remote address.
+pub struct RpcRateLimiter<T> {
+	inner: T,
+	limits: HashMap<String, RateLimitConfig>,
+	calls: Mutex<HashMap<(String, String), VecDeque<Instant>>>,
+}
+
+impl<T> RpcRateLimiter<T> {
+	/// Create new rate limiter with limits for given methods, wrapping `inner` middleware.
+	pub fn new(inner: T, limits: HashMap<String, RateLimitConfig>) -> Self {
+		RpcRateLimiter {
+			inner: inner,
+			limits: limits,
+			calls: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Records a call. Returns number of milliseconds to wait if the caller exceeded the limit.
+	fn check(&self, caller: &str, method: &str) -> Option<u64> {
+		let limit = match self.limits.get(method) {
+			Some(limit) => limit,
+			None => return None,
+		};
+
+		let window = Duration::from_secs(limit.window_secs as u64);
+		let now = Instant::now();
+		let mut calls = self.calls.lock();
+		let made = calls.entry((caller.to_owned(), method.to_owned())).or_insert_with(VecDeque::new);
+		while made.front().map_or(false, |made_at| now.duration_since(*made_at) >= window) {
+			made.pop_front();
+		}
+
+		if made.len() >= limit.max_requests as usize {
+			let oldest = made.front().cloned().unwrap_or(now);
+			let wait = window - now.duration_since(oldest);
+			return Some(wait.as_secs() * 1000 + (wait.subsec_nanos() / 1_000_000) as u64);
+		}
+
+		made.push_back(now);
+		None
+	}
+
+	/// Returns `Some` if the call is rejected, with the failure to respond with (notifications get no response).
+	fn reject(&self, call: &rpc::Call, caller: &str) -> Option<Option<rpc::Output>> {
+		match *call {
+			rpc::Call::MethodCall(ref call) => self.check(caller, &call.method).map(|retry_after| Some(rpc::Output::Failure(rpc::Failure {
+				jsonrpc: call.jsonrpc.clone(),
+				error: errors::rate_limited(retry_after),
+				id: call.id.clone(),
+			}))),
+			rpc::Call::Notification(ref notification) => self.check(caller, &notification.method).map(|_| None),
+			rpc::Call::Invalid(_) => None,
+		}
+	}
+}
+
+fn caller(meta: &Metadata) -> String {
+	match meta.remote_ip {
+		Some(ref ip) => ip.to_string(),
+		None => format!("{:?}:{}", meta.origin, meta.dapp_id.as_ref().map_or("", |dapp| dapp.as_str())),
+	}
+}
+
+impl<T: rpc::Middleware<Metadata>> rpc::Middleware<Metadata> for RpcRateLimiter<T> {
+	fn on_request<F>(&self, request: rpc::Request, meta: Metadata, process: F) -> rpc::FutureResponse where
+		F: FnOnce(rpc::Request, Metadata) -> rpc::FutureResponse,
+	{
+		let caller = caller(&meta);
+		match request {
+			rpc::Request::Single(call) => match self.reject(&call, &caller) {
+				Some(failure) => future::ok(failure.map(rpc::Response::Single)).boxed(),
+				None => self.inner.on_request(rpc::Request::Single(call), meta, process),
+			},
+			rpc::Request::Batch(calls) => {
+				let mut rejected = Vec::new();
+				let allowed = calls.into_iter()
+					.filter(|call| match self.reject(call, &caller) {
+						Some(failure) => {
+							rejected.extend(failure);
+							false
+						},
+						None => true,
+					})
+					.collect::<Vec<_>>();
+
+				let batch = |outputs: Vec<rpc::Output>| if outputs.is_empty() { None } else { Some(rpc::Response::Batch(outputs)) };
+				if allowed.is_empty() {
+					return future::ok(batch(rejected)).boxed();
+				}
+
+				self.inner.on_request(rpc::Request::Batch(allowed), meta, process).map(move |response| {
+					let mut outputs = match response {
+						Some(rpc::Response::Batch(outputs)) => outputs,
+						Some(rpc::Response::Single(output)) => vec![output],
+						None => Vec::new(),
+					};
+					outputs.extend(rejected);
+					batch(outputs)
+				}).boxed()
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use jsonrpc_core::{MetaIoHandler, NoopMiddleware, Value};
+	use serde_json;
+	use v1::metadata::{Metadata, Origin};
+	use super::{RpcRateLimiter, RateLimitConfig};
+
+	fn io() -> MetaIoHandler<Metadata, RpcRateLimiter<NoopMiddleware>> {
+		let mut limits = HashMap::new();
+		limits.insert("eth_call".to_owned(), RateLimitConfig { max_requests: 3, window_secs: 1 });
+		let mut io = MetaIoHandler::with_middleware(RpcRateLimiter::new(NoopMiddleware, limits));
+		io.add_method("eth_call", |_| Ok(Value::String("0x".into())));
+		io.add_method("eth_blockNumber", |_| Ok(Value::String("0x1".into())));
+		io
+	}
+
+	fn error_code(response: &str) -> Option<i64> {
+		let response: Value = serde_json::from_str(response).unwrap();
+		response.as_object().unwrap().get("error").map(|error| error.as_object().unwrap().get("code").unwrap().as_i64().unwrap())
+	}
+
+	#[test]
+	fn should_reject_calls_over_the_limit() {
+		// given
+		let io = io();
+		let request = r#"{"jsonrpc":"2.0","method":"eth_call","params":[],"id":1}"#;
+
+		// when
+		let codes = (0..10)
+			.map(|_| error_code(&io.handle_request_sync(request, Metadata::default()).unwrap()))
+			.collect::<Vec<_>>();
+
+		// then
+		assert_eq!(&codes[0..3], &[None, None, None]);
+		assert!(codes[3..].iter().all(|code| *code == Some(-32007)));
+	}
+
+	#[test]
+	fn should_limit_methods_and_callers_separately() {
+		// given
+		let io = io();
+		let call = r#"{"jsonrpc":"2.0","method":"eth_call","params":[],"id":1}"#;
+		let block_number = r#"{"jsonrpc":"2.0","method":"eth_blockNumber","params":[],"id":1}"#;
+		let dapp = Metadata {
+			dapp_id: Some("dapp".into()),
+			origin: Origin::Dapps,
+			..Default::default()
+		};
+		for _ in 0..3 {
+			io.handle_request_sync(call, Metadata::default()).unwrap();
+		}
+
+		// when
+		let limited = io.handle_request_sync(call, Metadata::default()).unwrap();
+		let other_method = io.handle_request_sync(block_number, Metadata::default()).unwrap();
+		let other_caller = io.handle_request_sync(call, dapp).unwrap();
+
+		// then
+		assert_eq!(error_code(&limited), Some(-32007));
+		assert_eq!(error_code(&other_method), None);
+		assert_eq!(error_code(&other_caller), None);
+	}
+
+	#[test]
+	fn should_limit_remote_addresses_separately() {
+		// given
+		let io = io();
+		let call = r#"{"jsonrpc":"2.0","method":"eth_call","params":[],"id":1}"#;
+		let from = |ip: &str| Metadata {
+			origin: Origin::Rpc,
+			remote_ip: Some(ip.parse().unwrap()),
+			..Default::default()
+		};
+		for _ in 0..3 {
+			io.handle_request_sync(call, from("10.0.0.1")).unwrap();
+		}
+
+		// when
+		let limited = io.handle_request_sync(call, from("10.0.0.1")).unwrap();
+		let other_address = io.handle_request_sync(call, from("10.0.0.2")).unwrap();
+
+		// then
+		assert_eq!(error_code(&limited), Some(-32007));
+		assert_eq!(error_code(&other_address), None);
+	}
+
+	#[test]
+	fn should_reject_limited_calls_within_batch() {
+		// given
+		let io = io();
+		let request = r#"[
+			{"jsonrpc":"2.0","method":"eth_call","params":[],"id":1},
+			{"jsonrpc":"2.0","method":"eth_call","params":[],"id":2},
+			{"jsonrpc":"2.0","method":"eth_call","params":[],"id":3},
+			{"jsonrpc":"2.0","method":"eth_call","params":[],"id":4},
+			{"jsonrpc":"2.0","method":"eth_blockNumber","params":[],"id":5}
+		]"#;
+
+		// when
+		let response: Value = serde_json::from_str(&io.handle_request_sync(request, Metadata::default()).unwrap()).unwrap();
+
+		// then
+		let outputs = response.as_array().unwrap();
+		assert_eq!(outputs.len(), 5);
+		let failed = outputs.iter().filter(|output| output.as_object().unwrap().get("error").is_some()).count();
+		assert_eq!(failed, 1);
+	}
+}
@@ -0,0 +1,218 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Request correlation ids
+
+use std::sync::{Arc, Mutex};
+use futures::Future;
+use hyper::{server, Decoder, Encoder, Next};
+use hyper::header::Headers;
+use hyper::net::HttpStream;
+use jsonrpc_core as rpc;
+use rustc_serialize::hex::ToHex;
+use util::{H128, FixedHash};
+use v1::helpers::errors;
+use v1::metadata::Metadata;
+
+/// HTTP header carrying the request correlation id.
+pub const REQUEST_ID_HEADER: &'static str = "X-Request-ID";
+
+/// Generates new random (version 4) UUID to be used as request id.
+pub fn new_request_id() -> String {
+	let mut bytes = H128::random().0;
+	bytes[6] = (bytes[6] & 0x0f) | 0x40;
+	bytes[8] = (bytes[8] & 0x3f) | 0x80;
+	let hex = bytes.to_hex();
+	format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Reads the request id from given HTTP headers. Generates new one if it's missing.
+pub fn read_request_id(headers: &Headers) -> String {
+	headers.get_raw(REQUEST_ID_HEADER)
+		.and_then(|raw| raw.one())
+		.map(|raw| String::from_utf8_lossy(raw).trim().to_owned())
+		.and_then(|id| if id.is_empty() { None } else { Some(id) })
+		.unwrap_or_else(new_request_id)
+}
+
+/// HTTP handler echoing the request id back in the response headers.
+/// The id is put into `request_id` by the metadata extractor of the wrapped handler.
+pub struct RequestIdHandler<H> {
+	handler: H,
+	request_id: Arc<Mutex<Option<String>>>,
+}
+
+impl<H> RequestIdHandler<H> {
+	/// Create new handler wrapping `handler`, echoing ids stored in `request_id`.
+	pub fn new(handler: H, request_id: Arc<Mutex<Option<String>>>) -> Self {
+		RequestIdHandler {
+			handler: handler,
+			request_id: request_id,
+		}
+	}
+}
+
+impl<H: server::Handler<HttpStream>> server::Handler<HttpStream> for RequestIdHandler<H> {
+	fn on_request(&mut self, request: server::Request<HttpStream>) -> Next {
+		self.handler.on_request(request)
+	}
+
+	fn on_request_readable(&mut self, decoder: &mut Decoder<HttpStream>) -> Next {
+		self.handler.on_request_readable(decoder)
+	}
+
+	fn on_response(&mut self, response: &mut server::Response) -> Next {
+		if let Some(request_id) = self.request_id.lock().expect("Lock is never poisoned; qed").take() {
+			response.headers_mut().set_raw(REQUEST_ID_HEADER, vec![request_id.into_bytes()]);
+		}
+		self.handler.on_response(response)
+	}
+
+	fn on_response_writable(&mut self, encoder: &mut Encoder<HttpStream>) -> Next {
+		self.handler.on_response_writable(encoder)
+	}
+}
+
+/// Middleware logging requests together with their correlation id
+/// and attaching the id to internal and execution errors.
+pub struct RequestTracer<T> {
+	inner: T,
+}
+
+impl<T> RequestTracer<T> {
+	/// Create new tracer wrapping `inner` middleware.
+	pub fn new(inner: T) -> Self {
+		RequestTracer {
+			inner: inner,
+		}
+	}
+}
+
+fn method(call: &rpc::Call) -> &str {
+	match *call {
+		rpc::Call::MethodCall(ref call) => &call.method,
+		rpc::Call::Notification(ref notification) => &notification.method,
+		rpc::Call::Invalid(_) => "<invalid>",
+	}
+}
+
+fn trace_output(output: rpc::Output, request_id: &str) -> rpc::Output {
+	match output {
+		rpc::Output::Failure(failure) => rpc::Output::Failure(rpc::Failure {
+			error: errors::trace_request(failure.error, request_id),
+			..failure
+		}),
+		output => output,
+	}
+}
+
+impl<T: rpc::Middleware<Metadata>> rpc::Middleware<Metadata> for RequestTracer<T> {
+	fn on_request<F>(&self, request: rpc::Request, meta: Metadata, process: F) -> rpc::FutureResponse where
+		F: FnOnce(rpc::Request, Metadata) -> rpc::FutureResponse,
+	{
+		let request_id = match meta.request_id.clone() {
+			Some(request_id) => request_id,
+			None => return self.inner.on_request(request, meta, process),
+		};
+
+		match request {
+			rpc::Request::Single(ref call) => debug!(target: "rpc", "[{}] Request: {}", request_id, method(call)),
+			rpc::Request::Batch(ref calls) => debug!(target: "rpc", "[{}] Batch request: {:?}", request_id, calls.iter().map(method).collect::<Vec<_>>()),
+		}
+
+		self.inner.on_request(request, meta, process).map(move |response| {
+			trace!(target: "rpc", "[{}] Response ready", request_id);
+			response.map(|response| match response {
+				rpc::Response::Single(output) => rpc::Response::Single(trace_output(output, &request_id)),
+				rpc::Response::Batch(outputs) => rpc::Response::Batch(outputs.into_iter().map(|output| trace_output(output, &request_id)).collect()),
+			})
+		}).boxed()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jsonrpc_core::{MetaIoHandler, NoopMiddleware, Value};
+	use serde_json;
+	use v1::helpers::errors;
+	use v1::metadata::Metadata;
+	use hyper::header::Headers;
+	use super::{RequestTracer, new_request_id, read_request_id, REQUEST_ID_HEADER};
+
+	fn io() -> MetaIoHandler<Metadata, RequestTracer<NoopMiddleware>> {
+		let mut io = MetaIoHandler::with_middleware(RequestTracer::new(NoopMiddleware));
+		io.add_method("eth_call", |_| Err(errors::execution("Reverted")));
+		io.add_method("eth_chainId", |_| Err(errors::unimplemented(None)));
+		io
+	}
+
+	fn meta(request_id: &str) -> Metadata {
+		Metadata {
+			request_id: Some(request_id.into()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn should_generate_uuid_v4() {
+		let id = new_request_id();
+
+		assert_eq!(id.len(), 36);
+		assert_eq!(id.split('-').map(str::len).collect::<Vec<_>>(), vec![8, 4, 4, 4, 12]);
+		assert_eq!(&id[14..15], "4");
+		assert!(new_request_id() != id);
+	}
+
+	#[test]
+	fn should_read_request_id_from_headers() {
+		let mut headers = Headers::new();
+		assert_eq!(read_request_id(&headers).len(), 36);
+
+		headers.set_raw(REQUEST_ID_HEADER, vec![b"test-123".to_vec()]);
+		assert_eq!(read_request_id(&headers), "test-123");
+	}
+
+	#[test]
+	fn should_attach_request_id_to_execution_errors() {
+		// given
+		let io = io();
+		let request = r#"{"jsonrpc":"2.0","method":"eth_call","params":[],"id":1}"#;
+
+		// when
+		let response = io.handle_request_sync(request, meta("test-123")).unwrap();
+
+		// then
+		let response: Value = serde_json::from_str(&response).unwrap();
+		let error = response.as_object().unwrap().get("error").unwrap().as_object().unwrap();
+		let data = error.get("data").unwrap().as_object().unwrap();
+		assert_eq!(data.get("requestId"), Some(&Value::String("test-123".into())));
+		assert_eq!(data.get("details"), Some(&Value::String("\"Reverted\"".into())));
+	}
+
+	#[test]
+	fn should_leave_other_errors_intact() {
+		// given
+		let io = io();
+		let request = r#"{"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":1}"#;
+
+		// when
+		let traced = io.handle_request_sync(request, meta("test-123")).unwrap();
+		let untraced = io.handle_request_sync(request, Metadata::default()).unwrap();
+
+		// then
+		assert_eq!(traced, untraced);
+	}
+}
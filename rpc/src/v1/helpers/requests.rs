@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use util::{Address, U256, Bytes};
+use util::{Address, U256, H256, Bytes};
 use v1::types::TransactionCondition;
 
 /// Transaction request coming from RPC
@@ -115,6 +115,8 @@ pub enum ConfirmationPayload {
 	Signature(Address, Bytes),
 	/// Decrypt request
 	Decrypt(Address, Bytes),
+	/// Sign EIP-712 typed data (domain separator and message hash)
+	SignTypedData(Address, H256, H256),
 }
 
 impl ConfirmationPayload {
@@ -124,6 +126,7 @@ impl ConfirmationPayload {
 			ConfirmationPayload::SignTransaction(ref request) => request.from,
 			ConfirmationPayload::Signature(ref address, _) => *address,
 			ConfirmationPayload::Decrypt(ref address, _) => *address,
+			ConfirmationPayload::SignTypedData(ref address, _, _) => *address,
 		}
 	}
 }
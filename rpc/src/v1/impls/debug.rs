@@ -0,0 +1,50 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Debug api implementation.
+
+use std::sync::{Weak, Arc};
+
+use ethcore::client::BlockChainClient;
+
+use jsonrpc_core::Error;
+use v1::traits::Debug;
+use v1::helpers::errors;
+use v1::types::{BlockNumber, Bytes};
+
+/// Debug api implementation.
+pub struct DebugClient<C> where C: BlockChainClient {
+	client: Weak<C>,
+}
+
+impl<C> DebugClient<C> where C: BlockChainClient {
+	/// Creates new Debug client.
+	pub fn new(client: &Arc<C>) -> Self {
+		DebugClient {
+			client: Arc::downgrade(client),
+		}
+	}
+}
+
+impl<C> Debug for DebugClient<C> where C: BlockChainClient + 'static {
+	fn raw_block(&self, number: BlockNumber) -> Result<Bytes, Error> {
+		let client = take_weak!(self.client);
+
+		client.block(number.into())
+			.map(|block| Bytes::new(block.into_inner()))
+			.ok_or_else(errors::unknown_block)
+	}
+}
@@ -26,19 +26,22 @@ use futures::{self, future, BoxFuture, Future};
 use rlp::{self, UntrustedRlp, View};
 use time::get_time;
 use util::{H160, H256, Address, FixedHash, U256, H64, Uint};
-use util::sha3::Hashable;
+use util::sha3::{Hashable, SHA3_EMPTY, SHA3_NULL_RLP};
 use util::{FromHex, Mutex};
 
 use ethash::SeedHashCompute;
 use ethcore::account_provider::{AccountProvider, DappId};
 use ethcore::block::IsBlock;
-use ethcore::client::{MiningBlockChainClient, BlockId, TransactionId, UncleId};
+use ethcore::client::{MiningBlockChainClient, ProvingBlockChainClient, BlockId, TransactionId, UncleId, CallAnalytics};
+use ethcore::contract_address;
+use ethcore::evm::access_list;
 use ethcore::ethereum::Ethash;
 use ethcore::filter::Filter as EthcoreFilter;
 use ethcore::header::{Header as BlockHeader, BlockNumber as EthBlockNumber};
 use ethcore::log_entry::LogEntry;
 use ethcore::miner::{MinerService, ExternalMinerService};
-use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, Action};
+use ethcore::receipt::Receipt as EthReceipt;
+use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, UnverifiedTransaction, Action};
 use ethcore::snapshot::SnapshotService;
 use ethsync::{SyncProvider};
 
@@ -51,13 +54,17 @@ use v1::helpers::block_import::is_major_importing;
 use v1::traits::Eth;
 use v1::types::{
 	RichBlock, Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo,
-	Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
+	Transaction, CallRequest, Index, Filter, Log, Receipt, Work, EIP1186ProofResponse, StorageProof, FeeHistoryResult,
+	AccessListEntry, AccessListResult,
 	H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256,
 };
 use v1::metadata::Metadata;
 
 const EXTRA_INFO_PROOF: &'static str = "Object exists in in blockchain (fetched earlier), extra_info is always available if object exists; qed";
 
+/// Maximal number of blocks covered by a single `eth_feeHistory` call.
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
 /// Eth RPC options
 pub struct EthClientOptions {
 	/// Returns receipt from pending blocks
@@ -219,6 +226,47 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> EthClient<C, SN, S, M, EM> where
 		}.fake_sign(from))
 	}
 
+	fn fee_history_at(&self, block_count: u64, newest: BlockId, percentiles: &[f64]) -> Result<FeeHistoryResult, Error> {
+		let client = take_weak!(self.client);
+		let mut block = client.block(newest).ok_or_else(errors::unknown_block)?;
+
+		let mut gas_used_ratio = Vec::new();
+		let mut reward = Vec::new();
+		let mut oldest_block = block.number();
+		for _ in 0..block_count {
+			let parent_hash = {
+				let header = block.header_view();
+				oldest_block = header.number();
+				gas_used_ratio.push(if header.gas_limit().is_zero() {
+					0.0
+				} else {
+					header.gas_used().low_u64() as f64 / header.gas_limit().low_u64() as f64
+				});
+				if !percentiles.is_empty() {
+					let receipts = client.block_receipts(&header.sha3())
+						.and_then(|receipts| UntrustedRlp::new(&receipts).as_val::<Vec<EthReceipt>>().ok());
+					reward.push(block_rewards(&block.transactions(), receipts, percentiles));
+				}
+				header.parent_hash()
+			};
+
+			if oldest_block == 0 {
+				break;
+			}
+			block = client.block(BlockId::Hash(parent_hash)).ok_or_else(errors::unknown_block)?;
+		}
+
+		gas_used_ratio.reverse();
+		reward.reverse();
+		// Blocks do not carry a base fee yet, so it's reported as zero like for any block preceding EIP-1559.
+		Ok(FeeHistoryResult {
+			oldest_block: oldest_block.into(),
+			base_fee_per_gas: vec![0.into(); gas_used_ratio.len() + 1],
+			gas_used_ratio: gas_used_ratio,
+			reward: if percentiles.is_empty() { None } else { Some(reward) },
+		})
+	}
+
 	fn dapp_accounts(&self, dapp: DappId) -> Result<Vec<H160>, Error> {
 		let store = take_weak!(self.accounts);
 		store
@@ -247,6 +295,37 @@ pub fn pending_logs<M>(miner: &M, best_block: EthBlockNumber, filter: &EthcoreFi
 	result
 }
 
+/// Priority fees paid at given percentiles of the block's gas.
+/// Transactions are weighted by gas used according to `receipts` or by their gas limit if receipts are unavailable.
+fn block_rewards(transactions: &[UnverifiedTransaction], receipts: Option<Vec<EthReceipt>>, percentiles: &[f64]) -> Vec<RpcU256> {
+	let gas_used: Vec<U256> = match receipts {
+		Some(ref receipts) if receipts.len() == transactions.len() => receipts.iter()
+			.scan(U256::zero(), |cumulative, receipt| {
+				let used = receipt.gas_used - *cumulative;
+				*cumulative = receipt.gas_used;
+				Some(used)
+			})
+			.collect(),
+		_ => transactions.iter().map(|t| t.gas).collect(),
+	};
+
+	let mut sorted: Vec<(U256, U256)> = transactions.iter().map(|t| t.gas_price).zip(gas_used).collect();
+	sorted.sort_by(|a, b| a.0.cmp(&b.0));
+	let total = sorted.iter().fold(U256::zero(), |acc, &(_, gas)| acc + gas);
+
+	percentiles.iter().map(|percentile| {
+		let threshold = (total.low_u64() as f64 * percentile / 100.0) as u64;
+		let mut cumulative = 0u64;
+		sorted.iter()
+			.find(|&&(_, gas)| {
+				cumulative += gas.low_u64();
+				cumulative >= threshold
+			})
+			.or_else(|| sorted.last())
+			.map_or_else(RpcU256::default, |&(price, _)| price.into())
+	}).collect()
+}
+
 fn check_known<C>(client: &C, number: BlockNumber) -> Result<(), Error> where C: MiningBlockChainClient {
 	use ethcore::block_status::BlockStatus;
 
@@ -265,7 +344,7 @@ static SOLC: &'static str = "solc.exe";
 static SOLC: &'static str = "solc";
 
 impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
-	C: MiningBlockChainClient + 'static,
+	C: MiningBlockChainClient + ProvingBlockChainClient + 'static,
 	SN: SnapshotService + 'static,
 	S: SyncProvider + 'static,
 	M: MinerService + 'static,
@@ -341,6 +420,23 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		Ok(RpcU256::from(default_gas_price(&*client, &*miner)))
 	}
 
+	fn fee_history(&self, block_count: RpcU256, newest_block: BlockNumber, reward_percentiles: Trailing<Vec<f64>>) -> BoxFuture<FeeHistoryResult, Error> {
+		let block_count: U256 = block_count.into();
+		let block_count = ::std::cmp::min(block_count, U256::from(MAX_FEE_HISTORY_BLOCKS)).low_u64();
+		let percentiles = reward_percentiles.0;
+		let ordered = percentiles.windows(2).all(|pair| pair[0] <= pair[1]);
+		if !ordered || percentiles.iter().any(|p| *p < 0.0 || *p > 100.0) {
+			return future::err(errors::invalid_params("rewardPercentiles", "Percentiles must be increasing values between 0 and 100")).boxed();
+		}
+
+		let newest = match newest_block {
+			BlockNumber::Pending => BlockId::Latest,
+			number => number.into(),
+		};
+
+		future::done(self.fee_history_at(block_count, newest, &percentiles)).boxed()
+	}
+
 	fn accounts(&self, meta: Metadata) -> BoxFuture<Vec<RpcH160>, Error> {
 		let dapp = meta.dapp_id.unwrap_or_default();
 
@@ -463,6 +559,45 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		future::done(res).boxed()
 	}
 
+	fn proof(&self, address: RpcH160, keys: Vec<RpcH256>, num: Trailing<BlockNumber>) -> BoxFuture<EIP1186ProofResponse, Error> {
+		let address: Address = RpcH160::into(address);
+		let client = take_weakf!(self.client);
+
+		if num.0 != BlockNumber::Pending {
+			try_bf!(check_known(&*client, num.0.clone()));
+		}
+
+		let id: BlockId = num.0.into();
+		let account_key = address.sha3();
+		let res = match (client.balance(&address, id.clone()), client.nonce(&address, id.clone()), client.code(&address, id.clone())) {
+			(Some(balance), Some(nonce), Some(code)) => {
+				let storage_proof = keys.into_iter().map(|key| {
+					let key: H256 = key.into();
+					client.storage_at(&address, &key, id.clone())
+						.map(|value| StorageProof {
+							key: key.into(),
+							value: U256::from(value).into(),
+							proof: client.prove_storage(account_key, key.sha3(), 0, id.clone()).into_iter().map(Into::into).collect(),
+						})
+						.ok_or_else(errors::state_pruned)
+				}).collect::<Result<Vec<_>, _>>();
+
+				storage_proof.map(|storage_proof| EIP1186ProofResponse {
+					address: address.into(),
+					account_proof: client.prove_account(account_key, 0, id.clone()).into_iter().map(Into::into).collect(),
+					balance: balance.into(),
+					code_hash: code.map_or(SHA3_EMPTY, |code| code.sha3()).into(),
+					nonce: nonce.into(),
+					storage_hash: client.storage_root(&address, id.clone()).unwrap_or(SHA3_NULL_RLP).into(),
+					storage_proof: storage_proof,
+				})
+			},
+			_ => Err(errors::state_pruned()),
+		};
+
+		future::done(res).boxed()
+	}
+
 	fn block_by_hash(&self, hash: RpcH256, include_txs: bool) -> BoxFuture<Option<RichBlock>, Error> {
 		future::done(self.block(BlockId::Hash(hash.into()), include_txs)).boxed()
 	}
@@ -629,8 +764,11 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		};
 
 		result
-			.map(|b| b.output.into())
 			.map_err(errors::from_call_error)
+			.and_then(|executed| match errors::execution_reverted(&executed.output) {
+				Some(error) if executed.exception.is_some() => Err(error),
+				_ => Ok(executed.output.into()),
+			})
 	}
 
 	fn estimate_gas(&self, request: CallRequest, num: Trailing<BlockNumber>) -> Result<RpcU256, Error> {
@@ -641,6 +779,42 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 			.map_err(errors::from_call_error)
 	}
 
+	fn create_access_list(&self, request: CallRequest, num: Trailing<BlockNumber>) -> Result<AccessListResult, Error> {
+		let request = CallRequest::into(request);
+		let signed = self.sign_call(request)?;
+		let analytics = CallAnalytics {
+			transaction_tracing: false,
+			vm_tracing: true,
+			state_diffing: false,
+		};
+
+		let executed = match num.0 {
+			BlockNumber::Pending => take_weak!(self.miner).call(&*take_weak!(self.client), &signed, analytics),
+			num => take_weak!(self.client).call(&signed, num.into(), analytics),
+		}.map_err(errors::from_call_error)?;
+
+		let sender = signed.sender();
+		let address = match signed.action {
+			Action::Call(ref address) => address.clone(),
+			Action::Create => contract_address(&sender, &signed.nonce),
+		};
+		// sender and recipient are always accessed, list them only with storage keys
+		let access_list = executed.vm_trace.as_ref()
+			.map(|trace| access_list::from_vm_trace(trace, address, &[sender, address]))
+			.unwrap_or_default();
+
+		Ok(AccessListResult {
+			access_list: access_list.into_iter()
+				.map(|(address, keys)| AccessListEntry {
+					address: address.into(),
+					storage_keys: keys.into_iter().map(Into::into).collect(),
+				})
+				.collect(),
+			gas_used: executed.gas_used.into(),
+			error: executed.exception.map(|e| format!("{}", e)),
+		})
+	}
+
 	fn compile_lll(&self, _: String) -> Result<Bytes, Error> {
 		rpc_unimplemented!()
 	}
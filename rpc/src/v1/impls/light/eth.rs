@@ -44,7 +44,7 @@ use v1::helpers::block_import::is_major_importing;
 use v1::traits::Eth;
 use v1::types::{
 	RichBlock, Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo,
-	Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
+	Transaction, CallRequest, Index, Filter, Log, Receipt, Work, EIP1186ProofResponse, FeeHistoryResult, AccessListResult,
 	H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256,
 };
 use v1::metadata::Metadata;
@@ -299,6 +299,14 @@ impl Eth for EthClient {
 		future::err(errors::unimplemented(None)).boxed()
 	}
 
+	fn fee_history(&self, _block_count: RpcU256, _newest_block: BlockNumber, _reward_percentiles: Trailing<Vec<f64>>) -> BoxFuture<FeeHistoryResult, Error> {
+		future::err(errors::unimplemented(None)).boxed()
+	}
+
+	fn proof(&self, _address: RpcH160, _keys: Vec<RpcH256>, _num: Trailing<BlockNumber>) -> BoxFuture<EIP1186ProofResponse, Error> {
+		future::err(errors::unimplemented(None)).boxed()
+	}
+
 	fn send_raw_transaction(&self, raw: Bytes) -> Result<RpcH256, Error> {
 		Err(errors::unimplemented(None))
 	}
@@ -315,6 +323,10 @@ impl Eth for EthClient {
 		Err(errors::unimplemented(None))
 	}
 
+	fn create_access_list(&self, _req: CallRequest, _num: Trailing<BlockNumber>) -> Result<AccessListResult, Error> {
+		Err(errors::unimplemented(None))
+	}
+
 	fn transaction_by_hash(&self, hash: RpcH256) -> Result<Option<Transaction>, Error> {
 		Err(errors::unimplemented(None))
 	}
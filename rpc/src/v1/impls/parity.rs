@@ -139,7 +139,10 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 		let info = store.hardware_accounts_info().map_err(|e| errors::account("Could not fetch account info.", e))?;
 		Ok(info
 			.into_iter()
-			.map(|(a, v)| (H160::from(a), HwAccountInfo { name: v.name, manufacturer: v.meta }))
+			.map(|(a, v)| {
+				let manufacturer = store.account_meta(a).map(|m| m.meta).unwrap_or_default();
+				(H160::from(a), HwAccountInfo { name: v.name, manufacturer: manufacturer })
+			})
 			.collect()
 		)
 	}
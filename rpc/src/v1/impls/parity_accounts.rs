@@ -16,20 +16,30 @@
 
 //! Account management (personal) rpc implementation
 use std::sync::{Arc, Weak};
-use std::collections::BTreeMap;
-use util::{Address};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use util::{Address, Mutex};
 
 use ethkey::{Brain, Generator, Secret};
 use ethcore::account_provider::AccountProvider;
+use ethstore::Error as SSError;
 
+use futures::{future, BoxFuture, Future};
 use jsonrpc_core::Error;
 use v1::helpers::errors;
+use v1::metadata::Metadata;
 use v1::traits::ParityAccounts;
-use v1::types::{H160 as RpcH160, H256 as RpcH256, DappId};
+use v1::types::{H160 as RpcH160, H256 as RpcH256, Bytes, DappId, DappPermissions, HardwareWalletInfo};
+
+/// Maximal number of account exports by a single caller within `EXPORT_PERIOD_SECS`.
+const EXPORT_LIMIT: usize = 5;
+const EXPORT_PERIOD_SECS: u64 = 60;
 
 /// Account management (personal) rpc implementation.
 pub struct ParityAccountsClient {
 	accounts: Weak<AccountProvider>,
+	exports: Mutex<HashMap<Option<IpAddr>, VecDeque<Instant>>>,
 }
 
 impl ParityAccountsClient {
@@ -37,11 +47,43 @@ impl ParityAccountsClient {
 	pub fn new(store: &Arc<AccountProvider>) -> Self {
 		ParityAccountsClient {
 			accounts: Arc::downgrade(store),
+			exports: Mutex::new(HashMap::new()),
 		}
 	}
+
+	/// Records an export made from `caller`, failing if the caller exhausted the limit for the current period.
+	/// Callers without a known address (e.g. IPC) share a single limit.
+	fn note_export(&self, caller: Option<IpAddr>) -> Result<(), Error> {
+		let period = Duration::from_secs(EXPORT_PERIOD_SECS);
+		let now = Instant::now();
+		let mut exports = self.exports.lock();
+		let exports = exports.entry(caller).or_insert_with(VecDeque::new);
+		while exports.front().map_or(false, |time| now.duration_since(*time) >= period) {
+			exports.pop_front();
+		}
+
+		if exports.len() >= EXPORT_LIMIT {
+			let wait = period - now.duration_since(exports[0]);
+			return Err(errors::rate_limited(wait.as_secs() * 1000 + wait.subsec_nanos() as u64 / 1_000_000));
+		}
+
+		exports.push_back(now);
+		Ok(())
+	}
+}
+
+/// Map errors of operations on an existing vault.
+fn vault_error(name: &str, context: &str, error: SSError) -> Error {
+	match error {
+		SSError::VaultNotFound => errors::vault_not_found(name),
+		SSError::InvalidPassword => errors::vault_wrong_password(),
+		error => errors::account(context, error),
+	}
 }
 
 impl ParityAccounts for ParityAccountsClient {
+	type Metadata = Metadata;
+
 	fn all_accounts_info(&self) -> Result<BTreeMap<RpcH160, BTreeMap<String, String>>, Error> {
 		let store = take_weak!(self.accounts);
 		let info = store.accounts_info().map_err(|e| errors::account("Could not fetch account info.", e))?;
@@ -108,6 +150,20 @@ impl ParityAccounts for ParityAccountsClient {
 			.map_err(|e| errors::account("Could not fetch account info.", e))
 	}
 
+	fn export_account(&self, meta: Metadata, account: RpcH160, password: String) -> BoxFuture<Bytes, Error> {
+		let account: Address = account.into();
+		let store = take_weakf!(self.accounts);
+		if store.is_hardware_address(account) {
+			return future::err(errors::account("Cannot export hardware wallet.", account)).boxed();
+		}
+
+		try_bf!(self.note_export(meta.remote_ip));
+		future::done(store.export_account(&account, &password, &password)
+			.map(Into::into)
+			.map_err(|e| errors::account("Could not export account.", e))
+		).boxed()
+	}
+
 	fn kill_account(&self, account: RpcH160, password: String) -> Result<bool, Error> {
 		let account: Address = account.into();
 		take_weak!(self.accounts)
@@ -162,6 +218,41 @@ impl ParityAccounts for ParityAccountsClient {
 			.map(into_vec)
 	}
 
+	fn dapp_permissions(&self, dapp: DappId) -> Result<DappPermissions, Error> {
+		let store = take_weak!(self.accounts);
+
+		let allowed = store.dapps_addresses(dapp.clone().into())
+			.map_err(|e| errors::account("Couldn't get dapps addresses.", e))?;
+		let expiry = store.dapp_expiry(dapp.clone().into())
+			.map_err(|e| errors::account("Couldn't get dapp permissions.", e))?;
+
+		Ok(DappPermissions {
+			allowed_accounts: into_vec(allowed),
+			default_account: store.dapp_default_address(dapp.into()).ok().map(Into::into),
+			expiry_timestamp: expiry,
+		})
+	}
+
+	fn set_dapp_permissions(&self, dapp: DappId, permissions: DappPermissions) -> Result<bool, Error> {
+		let store = take_weak!(self.accounts);
+
+		store.set_dapp_permissions(
+			dapp.into(),
+			into_vec(permissions.allowed_accounts),
+			permissions.default_account.map(Into::into),
+			permissions.expiry_timestamp,
+		).map_err(|e| errors::account("Couldn't set dapp permissions.", e))?;
+		Ok(true)
+	}
+
+	fn clear_unlocked_accounts(&self) -> Result<bool, Error> {
+		let store = take_weak!(self.accounts);
+
+		let locked = store.lock_all_accounts();
+		info!(target: "accounts", "Locked {} unlocked accounts.", locked);
+		Ok(true)
+	}
+
 	fn set_new_dapps_whitelist(&self, whitelist: Option<Vec<RpcH160>>) -> Result<bool, Error> {
 		let store = take_weak!(self.accounts);
 
@@ -202,17 +293,36 @@ impl ParityAccounts for ParityAccountsClient {
 		Ok(into_vec(store.list_geth_accounts(false)))
 	}
 
+	fn hardware_wallets(&self) -> Result<Vec<HardwareWalletInfo>, Error> {
+		let store = take_weak!(self.accounts);
+
+		Ok(store.hardware_wallets()
+			.into_iter()
+			.map(|(wallet, connected)| HardwareWalletInfo {
+				name: wallet.name,
+				manufacturer: wallet.manufacturer,
+				serial: wallet.serial,
+				address: wallet.address.into(),
+				is_hardware: true,
+				connected: connected,
+			})
+			.collect())
+	}
+
 	fn create_vault(&self, name: String, password: String) -> Result<bool, Error> {
 		take_weak!(self.accounts)
 			.create_vault(&name, &password)
-			.map_err(|e| errors::account("Could not create vault.", e))
+			.map_err(|e| match e {
+				SSError::CreationFailed => errors::vault_already_exists(&name),
+				e => errors::account("Could not create vault.", e),
+			})
 			.map(|_| true)
 	}
 
 	fn open_vault(&self, name: String, password: String) -> Result<bool, Error> {
 		take_weak!(self.accounts)
 			.open_vault(&name, &password)
-			.map_err(|e| errors::account("Could not open vault.", e))
+			.map_err(|e| vault_error(&name, "Could not open vault.", e))
 			.map(|_| true)
 	}
 
@@ -238,7 +348,7 @@ impl ParityAccounts for ParityAccountsClient {
 	fn change_vault_password(&self, name: String, new_password: String) -> Result<bool, Error> {
 		take_weak!(self.accounts)
 			.change_vault_password(&name, &new_password)
-			.map_err(|e| errors::account("Could not change vault password.", e))
+			.map_err(|e| vault_error(&name, "Could not change vault password.", e))
 			.map(|_| true)
 	}
 
@@ -16,8 +16,10 @@
 
 //! Account management (personal) rpc implementation
 use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
-use ethcore::account_provider::AccountProvider;
+use ethcore::account_provider::{AccountProvider, UnlockStatus};
+use ethstore::Error as SSError;
 use ethcore::transaction::PendingTransaction;
 
 use util::{Address, U128, Uint, ToPretty};
@@ -89,6 +91,14 @@ impl<D: Dispatcher + 'static> Personal for PersonalClient<D> {
 		};
 		match r {
 			Ok(_) => Ok(true),
+			Err(SSError::TemporarilyLocked) => match store.unlock_attempt_status(account) {
+				UnlockStatus::LockedUntil(until) => {
+					let now = Instant::now();
+					let retry_after = if until > now { until - now } else { Duration::from_secs(0) };
+					Err(errors::rate_limited(retry_after.as_secs() * 1000 + retry_after.subsec_nanos() as u64 / 1_000_000))
+				},
+				UnlockStatus::Open => Ok(false),
+			},
 			// TODO [ToDr] Proper error here?
 			Err(_) => Ok(false),
 		}
@@ -38,7 +38,9 @@ use v1::types::{
 	RichRawTransaction as RpcRichRawTransaction,
 	TransactionRequest as RpcTransactionRequest,
 	ConfirmationPayload as RpcConfirmationPayload,
-	ConfirmationResponse as RpcConfirmationResponse
+	ConfirmationResponse as RpcConfirmationResponse,
+	TypedData as RpcTypedData,
+	TypedDataRequest as RpcTypedDataRequest,
 };
 
 const MAX_PENDING_DURATION: u64 = 60 * 60;
@@ -217,6 +219,33 @@ impl<D: Dispatcher + 'static> EthSigning for SigningQueueClient<D> {
 		}).boxed()
 	}
 
+	fn sign_typed_data(&self, address: RpcH160, data: RpcTypedData) -> BoxFuture<RpcH520, Error> {
+		let (domain_separator, message_hash) = match data.hashes() {
+			Ok(hashes) => hashes,
+			Err(e) => return future::err(errors::invalid_params("Typed data", e)).boxed(),
+		};
+		let request = RpcTypedDataRequest {
+			address: address.clone(),
+			domain_separator: domain_separator.into(),
+			message_hash: message_hash.into(),
+		};
+		let res = self.dispatch(RpcConfirmationPayload::SignTypedData(request), address.into());
+
+		let (ready, p) = futures::oneshot();
+
+		res.then(move |res| {
+			handle_dispatch(res, move |response| {
+				match response {
+					Ok(RpcConfirmationResponse::Signature(sig)) => ready.complete(Ok(sig)),
+					Err(e) => ready.complete(Err(e)),
+					e => ready.complete(Err(errors::internal("Unexpected result.", e))),
+				}
+			});
+
+			p.then(|result| futures::done(result.expect("Ready is never dropped nor canceled.")))
+		}).boxed()
+	}
+
 	fn sign_transaction(&self, meta: Metadata, request: RpcTransactionRequest) -> BoxFuture<RpcRichRawTransaction, Error> {
 		let res = self.dispatch(RpcConfirmationPayload::SignTransaction(request), meta.into());
 
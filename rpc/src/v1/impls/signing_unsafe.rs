@@ -34,6 +34,8 @@ use v1::types::{
 	TransactionRequest as RpcTransactionRequest,
 	ConfirmationPayload as RpcConfirmationPayload,
 	ConfirmationResponse as RpcConfirmationResponse,
+	TypedData as RpcTypedData,
+	TypedDataRequest as RpcTypedDataRequest,
 };
 
 /// Implementation of functions that require signing when no trusted signer is used.
@@ -82,6 +84,26 @@ impl<D: Dispatcher + 'static> EthSigning for SigningUnsafeClient<D>
 			.boxed()
 	}
 
+	fn sign_typed_data(&self, address: RpcH160, data: RpcTypedData) -> BoxFuture<RpcH520, Error> {
+		let (domain_separator, message_hash) = match data.hashes() {
+			Ok(hashes) => hashes,
+			Err(e) => return future::err(errors::invalid_params("Typed data", e)).boxed(),
+		};
+		let request = RpcTypedDataRequest {
+			address: address.clone(),
+			domain_separator: domain_separator.into(),
+			message_hash: message_hash.into(),
+		};
+
+		self.handle(RpcConfirmationPayload::SignTypedData(request), address.into())
+			.then(|res| match res {
+				Ok(RpcConfirmationResponse::Signature(signature)) => Ok(signature),
+				Err(e) => Err(e),
+				e => Err(errors::internal("Unexpected result", e)),
+			})
+			.boxed()
+	}
+
 	fn send_transaction(&self, meta: Metadata, request: RpcTransactionRequest) -> BoxFuture<RpcH256, Error> {
 		self.handle(RpcConfirmationPayload::SendTransaction(request), meta.into())
 			.then(|res| match res {
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::net::IpAddr;
 use jsonrpc_core;
 
 /// RPC methods metadata.
@@ -23,6 +24,10 @@ pub struct Metadata {
 	pub dapp_id: Option<String>,
 	/// Request origin
 	pub origin: Origin,
+	/// Request correlation id
+	pub request_id: Option<String>,
+	/// IP address of the remote peer (HTTP only)
+	pub remote_ip: Option<IpAddr>,
 }
 
 /// RPC request origin
@@ -29,7 +29,9 @@ use ethcore::miner::{MinerOptions, Banning, GasPricer, MinerService, ExternalMin
 use ethcore::account_provider::AccountProvider;
 use ethjson::blockchain::BlockChain;
 use io::IoChannel;
-use util::{U256, H256, Uint, Address, Hashable};
+use rlp::{self, RlpStream, Stream};
+use serde_json;
+use util::{U256, H256, Uint, Address, Hashable, FromHex, MemoryDB, HashDB, Trie, TrieDB};
 
 use jsonrpc_core::IoHandler;
 use v1::impls::{EthClient, SigningUnsafeClient};
@@ -317,6 +319,56 @@ const POSITIVE_NONCE_SPEC: &'static [u8] = br#"{
 }
 "#;
 
+const PROOF_SPEC: &'static [u8] = br#"{
+	"name": "Frontier (Test)",
+	"engine": {
+		"Ethash": {
+			"params": {
+				"gasLimitBoundDivisor": "0x0400",
+				"minimumDifficulty": "0x020000",
+				"difficultyBoundDivisor": "0x0800",
+				"durationLimit": "0x0d",
+				"blockReward": "0x4563918244F40000",
+				"registrar" : "0xc6d9d2cd449a754c494264e1809c50e34d64562b",
+				"homesteadTransition": "0xffffffffffffffff",
+				"daoHardforkTransition": "0xffffffffffffffff",
+				"daoHardforkBeneficiary": "0x0000000000000000000000000000000000000000",
+				"daoHardforkAccounts": []
+			}
+		}
+	},
+	"params": {
+		"accountStartNonce": "0x00",
+		"maximumExtraDataSize": "0x20",
+		"minGasLimit": "0x50000",
+		"networkID" : "0x1"
+	},
+	"genesis": {
+		"seal": {
+			"ethereum": {
+				"nonce": "0x0000000000000042",
+				"mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000"
+			}
+		},
+		"difficulty": "0x400000000",
+		"author": "0x0000000000000000000000000000000000000000",
+		"timestamp": "0x00",
+		"parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+		"extraData": "0x11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa",
+		"gasLimit": "0x50000"
+	},
+	"accounts": {
+		"0000000000000000000000000000000000000001": { "builtin": { "name": "ecrecover", "pricing": { "linear": { "base": 3000, "word": 0 } } } },
+		"0000000000000000000000000000000000000002": { "builtin": { "name": "sha256", "pricing": { "linear": { "base": 60, "word": 12 } } } },
+		"0000000000000000000000000000000000000003": { "builtin": { "name": "ripemd160", "pricing": { "linear": { "base": 600, "word": 120 } } } },
+		"0000000000000000000000000000000000000004": { "builtin": { "name": "identity", "pricing": { "linear": { "base": 15, "word": 3 } } } },
+		"faa34835af5c2ea724333018a515fbb7d5bc0b33": { "balance": "10000000000000", "nonce": "0" },
+		"0000000000000000000000000000000000000aaa": { "balance": "1", "nonce": "0", "code": "0x6001", "storage": { "0x00": "0x2a", "0x01": "0x0100" } },
+		"0000000000000000000000000000000000000bbb": { "balance": "0", "nonce": "0", "code": "0x600054506001545000", "storage": { "0x00": "0x01" } }
+	}
+}
+"#;
+
 #[test]
 fn eth_transaction_count() {
 	let secret = "8a283037bb19c4fed7b1c569e40c7dcff366165eb869110a1b11532963eb9cb2".parse().unwrap();
@@ -462,6 +514,97 @@ fn starting_nonce_test() {
 	assert_eq!(r#"{"jsonrpc":"2.0","result":"0x100","id":15}"#, &sample);
 }
 
+#[test]
+fn eth_get_proof() {
+	let tester = EthTester::from_spec(Spec::load(PROOF_SPEC).expect("invalid chain spec"));
+	let state_root = tester.client.block_header(BlockId::Latest).unwrap().state_root();
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getProof",
+		"params": [
+			"0x0000000000000000000000000000000000000aaa",
+			["0x0000000000000000000000000000000000000000000000000000000000000000", "0x0000000000000000000000000000000000000000000000000000000000000002"],
+			"latest"
+		],
+		"id": 1
+	}"#;
+	let response: serde_json::Value = serde_json::from_str(&tester.handler.handle_request_sync(request).unwrap()).unwrap();
+	let result = response.as_object().unwrap().get("result").unwrap().as_object().unwrap();
+	let field = |name: &str| result.get(name).unwrap().as_str().unwrap().to_owned();
+	let nodes = |proof: &serde_json::Value| proof.as_array().unwrap().iter()
+		.map(|node| node.as_str().unwrap()[2..].from_hex().unwrap())
+		.collect::<Vec<_>>();
+
+	assert_eq!(field("balance"), "0x1");
+	assert_eq!(field("nonce"), "0x0");
+	assert_eq!(field("codeHash"), format!("0x{:?}", vec![0x60u8, 0x01].sha3()));
+
+	// account proof leads from the state root to the account
+	let storage_root: H256 = field("storageHash")[2..].parse().unwrap();
+	let mut account = RlpStream::new_list(4);
+	account.append(&U256::zero()).append(&U256::one()).append(&storage_root).append(&vec![0x60u8, 0x01].sha3());
+	let account_proof = nodes(result.get("accountProof").unwrap());
+	assert_eq!(verify_proof(&state_root, &Address::from(0xaaa).sha3(), &account_proof), Some(account.out()));
+
+	// storage proofs lead from the storage root to the slots
+	let storage_proof = result.get("storageProof").unwrap().as_array().unwrap();
+	assert_eq!(storage_proof.len(), 2);
+
+	let slot = storage_proof[0].as_object().unwrap();
+	assert_eq!(slot.get("value").unwrap().as_str().unwrap(), "0x2a");
+	let proven = verify_proof(&storage_root, &H256::zero().sha3(), &nodes(slot.get("proof").unwrap()));
+	assert_eq!(proven.map(|value| rlp::decode::<U256>(&value)), Some(U256::from(0x2a)));
+
+	// missing slot is proven to be absent
+	let slot = storage_proof[1].as_object().unwrap();
+	assert_eq!(slot.get("value").unwrap().as_str().unwrap(), "0x0");
+	assert_eq!(verify_proof(&storage_root, &H256::from(2).sha3(), &nodes(slot.get("proof").unwrap())), None);
+}
+
+#[test]
+fn eth_create_access_list() {
+	let tester = EthTester::from_spec(Spec::load(PROOF_SPEC).expect("invalid chain spec"));
+
+	// contract at 0xbbb reads slots 0 and 1
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_createAccessList",
+		"params": [{
+			"from": "0xfaa34835af5c2ea724333018a515fbb7d5bc0b33",
+			"to": "0x0000000000000000000000000000000000000bbb"
+		}, "latest"],
+		"id": 1
+	}"#;
+	let response: serde_json::Value = serde_json::from_str(&tester.handler.handle_request_sync(request).unwrap()).unwrap();
+	let result = response.as_object().unwrap().get("result").unwrap().as_object().unwrap();
+
+	assert!(result.get("error").is_none());
+	let access_list = result.get("accessList").unwrap().as_array().unwrap();
+	assert_eq!(access_list.len(), 1);
+	let entry = access_list[0].as_object().unwrap();
+	assert_eq!(entry.get("address").unwrap().as_str().unwrap(), "0x0000000000000000000000000000000000000bbb");
+	let storage_keys = entry.get("storageKeys").unwrap().as_array().unwrap().iter()
+		.map(|key| key.as_str().unwrap().to_owned())
+		.collect::<Vec<_>>();
+	assert_eq!(storage_keys, vec![
+		"0x0000000000000000000000000000000000000000000000000000000000000000".to_owned(),
+		"0x0000000000000000000000000000000000000000000000000000000000000001".to_owned(),
+	]);
+	assert!(result.get("gasUsed").unwrap().as_str().unwrap() != "0x0");
+}
+
+/// Looks up `key` in a trie with given `root` using only the nodes of the proof.
+fn verify_proof(root: &H256, key: &H256, proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+	let mut db = MemoryDB::new();
+	for node in proof {
+		db.insert(node);
+	}
+
+	let trie = TrieDB::new(&db, root).unwrap();
+	trie.get(key).unwrap().map(|value| value.to_vec())
+}
+
 register_test!(eth_transaction_count_1, verify_transaction_counts, "BlockchainTests/bcWalletTest");
 register_test!(eth_transaction_count_2, verify_transaction_counts, "BlockchainTests/bcTotalDifficultyTest");
 register_test!(eth_transaction_count_3, verify_transaction_counts, "BlockchainTests/bcGasPricerTest");
@@ -0,0 +1,71 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use rlp::{UntrustedRlp, View};
+use rustc_serialize::hex::FromHex;
+use serde_json::{self, Value};
+use util::Hashable;
+use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockChainClient, BlockId};
+
+use jsonrpc_core::IoHandler;
+use v1::{Debug, DebugClient};
+
+struct Tester {
+	client: Arc<TestBlockChainClient>,
+	io: IoHandler,
+}
+
+fn io() -> Tester {
+	let client = Arc::new(TestBlockChainClient::new());
+	client.add_blocks(2, EachBlockWith::Transaction);
+	let mut io = IoHandler::new();
+	io.extend_with(DebugClient::new(&client).to_delegate());
+
+	Tester {
+		client: client,
+		io: io,
+	}
+}
+
+#[test]
+fn rpc_debug_get_raw_block() {
+	// given
+	let tester = io();
+	let request = r#"{"jsonrpc": "2.0", "method": "debug_getRawBlock", "params": ["0x1"], "id": 1}"#;
+
+	// when
+	let response: Value = serde_json::from_str(&tester.io.handle_request_sync(request).unwrap()).unwrap();
+
+	// then
+	let raw = response.as_object().unwrap().get("result").unwrap().as_str().unwrap();
+	assert!(raw.starts_with("0x"));
+	let bytes = raw[2..].from_hex().unwrap();
+	let block = UntrustedRlp::new(&bytes);
+	assert_eq!(block.item_count(), 3);
+	let header_hash = block.at(0).unwrap().as_raw().sha3();
+	assert_eq!(Some(header_hash), tester.client.block_hash(BlockId::Number(1)));
+}
+
+#[test]
+fn rpc_debug_get_raw_block_unknown() {
+	let tester = io();
+	let request = r#"{"jsonrpc": "2.0", "method": "debug_getRawBlock", "params": ["0x10"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Unknown block number"},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
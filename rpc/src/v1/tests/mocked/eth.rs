@@ -21,6 +21,7 @@ use std::time::{Instant, Duration};
 use rustc_serialize::hex::{FromHex, ToHex};
 use time::get_time;
 use rlp;
+use serde_json;
 
 use util::{Uint, U256, Address, H256, FixedHash, Mutex};
 use ethkey::Secret;
@@ -318,6 +319,65 @@ fn rpc_eth_sign() {
 	assert_eq!(tester.io.handle_request_sync(&req), Some(res.into()));
 }
 
+#[test]
+fn rpc_eth_sign_typed_data() {
+	use ethkey::{self, Signature};
+
+	let tester = EthTester::default();
+
+	// keccak256("cow")
+	let secret = Secret::from_slice(&"c85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4".from_hex().unwrap()).unwrap();
+	let account = tester.accounts_provider.insert_account(secret, "abcd").unwrap();
+	tester.accounts_provider.unlock_account_permanently(account, "abcd".into()).unwrap();
+
+	let req = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_signTypedData",
+		"params": [
+			""#.to_owned() + &format!("0x{:?}", account) + r#"",
+			{
+				"types": {
+					"EIP712Domain": [
+						{ "name": "name", "type": "string" },
+						{ "name": "version", "type": "string" },
+						{ "name": "chainId", "type": "uint256" },
+						{ "name": "verifyingContract", "type": "address" }
+					],
+					"Permit": [
+						{ "name": "owner", "type": "address" },
+						{ "name": "spender", "type": "address" },
+						{ "name": "value", "type": "uint256" },
+						{ "name": "nonce", "type": "uint256" },
+						{ "name": "deadline", "type": "uint256" }
+					]
+				},
+				"primaryType": "Permit",
+				"domain": {
+					"name": "Test Token",
+					"version": "1",
+					"chainId": 1,
+					"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+				},
+				"message": {
+					"owner": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826",
+					"spender": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB",
+					"value": "1000000000000000000",
+					"nonce": 0,
+					"deadline": "0xffffffff"
+				}
+			}
+		],
+		"id": 1
+	}"#;
+
+	let res = tester.io.handle_request_sync(&req).unwrap();
+	let vrs = res[r#"{"jsonrpc":"2.0","result":"0x"#.len()..r#"{"jsonrpc":"2.0","result":"0x"#.len() + 130].from_hex().unwrap();
+	let signature = Signature::from_rsv(&H256::from_slice(&vrs[1..33]), &H256::from_slice(&vrs[33..65]), vrs[0] - 27);
+	let hash = H256::from("0x63a01cc4317eb8a2254e75cd02a259d9b95f26a63fc9fa78fd2553722841a022");
+	let public = ethkey::recover(&signature, &hash).unwrap();
+	assert_eq!(ethkey::public_to_address(&public), account);
+}
+
 #[test]
 fn rpc_eth_author() {
 	let make_res = |addr| r#"{"jsonrpc":"2.0","result":""#.to_owned() + &format!("0x{:?}", addr) + r#"","id":1}"#;
@@ -363,6 +423,56 @@ fn rpc_eth_gas_price() {
 	assert_eq!(EthTester::default().io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_fee_history() {
+	let tester = EthTester::default();
+	tester.add_blocks(10, EachBlockWith::Transaction);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x4", "latest", [25, 75]], "id": 1}"#;
+	let response: serde_json::Value = serde_json::from_str(&tester.io.handle_request_sync(request).unwrap()).unwrap();
+	let result = response.as_object().unwrap().get("result").unwrap().as_object().unwrap();
+
+	assert_eq!(result.get("oldestBlock").unwrap().as_str().unwrap(), "0x7");
+	let base_fees = result.get("baseFeePerGas").unwrap().as_array().unwrap();
+	assert_eq!(base_fees.len(), 5);
+	assert!(base_fees.iter().all(|fee| fee.as_str().unwrap() == "0x0"));
+	let ratios = result.get("gasUsedRatio").unwrap().as_array().unwrap();
+	assert_eq!(ratios.len(), 4);
+	assert!(ratios.iter().all(|ratio| ratio.as_f64().unwrap() == 0.0));
+	let rewards = result.get("reward").unwrap().as_array().unwrap();
+	assert_eq!(rewards.len(), 4);
+	for reward in rewards {
+		let reward: Vec<_> = reward.as_array().unwrap().iter().map(|r| r.as_str().unwrap()).collect();
+		assert_eq!(reward, vec!["0x2e90edd000", "0x2e90edd000"]);
+	}
+}
+
+#[test]
+fn rpc_eth_fee_history_is_limited() {
+	let tester = EthTester::default();
+	tester.add_blocks(1100, EachBlockWith::Nothing);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x800", "latest"], "id": 1}"#;
+	let response: serde_json::Value = serde_json::from_str(&tester.io.handle_request_sync(request).unwrap()).unwrap();
+	let result = response.as_object().unwrap().get("result").unwrap().as_object().unwrap();
+
+	assert_eq!(result.get("oldestBlock").unwrap().as_str().unwrap(), "0x4d");
+	assert_eq!(result.get("gasUsedRatio").unwrap().as_array().unwrap().len(), 1024);
+	assert_eq!(result.get("baseFeePerGas").unwrap().as_array().unwrap().len(), 1025);
+	assert!(result.get("reward").is_none());
+}
+
+#[test]
+fn rpc_eth_fee_history_unknown_block() {
+	let tester = EthTester::default();
+	tester.add_blocks(10, EachBlockWith::Nothing);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x4", "0x100"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Unknown block number"},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_accounts() {
 	let tester = EthTester::default();
@@ -857,6 +967,43 @@ fn rpc_eth_sign_transaction() {
 	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
 }
 
+#[test]
+fn rpc_eth_sign_transaction_and_send_raw() {
+	let tester = EthTester::default();
+	let address = tester.accounts_provider.new_account("").unwrap();
+	tester.accounts_provider.unlock_account_permanently(address, "".into()).unwrap();
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_signTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}],
+		"id": 1
+	}"#;
+
+	let response = tester.io.handle_request_sync(&request).unwrap();
+	let field = |name: &str| {
+		let start = response.find(&format!("\"{}\":\"", name)).unwrap() + name.len() + 4;
+		let len = response[start..].find('"').unwrap();
+		response[start..start + len].to_owned()
+	};
+	let (raw, hash) = (field("raw"), field("hash"));
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_sendRawTransaction",
+		"params": [""#.to_owned() + &raw + r#""],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + &hash + r#"","id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+}
+
 #[test]
 fn rpc_eth_send_transaction_with_bad_to() {
 	let tester = EthTester::default();
@@ -15,18 +15,24 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
+use std::collections::HashMap;
 
-use ethcore::account_provider::{AccountProvider, AccountProviderSettings};
+use rustc_serialize::hex::FromHex;
+use serde_json;
+use util::Mutex;
+use ethcore::account_provider::{AccountProvider, AccountProviderSettings, SignError};
 use ethstore::EthStore;
-use ethstore::dir::RootDiskDirectory;
+use ethstore::dir::{RootDiskDirectory, MemoryDirectory};
+use hardware_wallet::{WalletInfo, WalletDeviceType};
+use hardware_wallet::mock::MockHardwareWalletManager;
 use devtools::RandomTempPath;
 
 use jsonrpc_core::IoHandler;
-use v1::{ParityAccounts, ParityAccountsClient};
+use v1::{Metadata, ParityAccounts, ParityAccountsClient};
 
 struct ParityAccountsTester {
 	accounts: Arc<AccountProvider>,
-	io: IoHandler,
+	io: IoHandler<Metadata>,
 }
 
 fn accounts_provider() -> Arc<AccountProvider> {
@@ -139,6 +145,42 @@ fn rpc_parity_set_and_get_dapps_accounts() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_set_and_get_dapp_permissions() {
+	// given
+	let tester = setup();
+	tester.accounts.set_address_name(10.into(), "10".into());
+	tester.accounts.set_address_name(11.into(), "11".into());
+
+	// when
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setDappPermissions","params":["app1",{"allowedAccounts":["0x000000000000000000000000000000000000000a","0x000000000000000000000000000000000000000b","0x0000000000000000000000000000000000000001"],"defaultAccount":"0x000000000000000000000000000000000000000b","expiryTimestamp":null}], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	// then
+	assert_eq!(tester.accounts.dapps_addresses("app1".into()).unwrap(), vec![10.into(), 11.into()]);
+	assert_eq!(tester.accounts.dapp_default_address("app1".into()).unwrap(), 11.into());
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_getDappPermissions","params":["app1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"allowedAccounts":["0x000000000000000000000000000000000000000a","0x000000000000000000000000000000000000000b"],"defaultAccount":"0x000000000000000000000000000000000000000b","expiryTimestamp":null},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_set_dapp_permissions_rejects_invisible_default_account() {
+	// given
+	let tester = setup();
+	tester.accounts.set_address_name(10.into(), "10".into());
+
+	// when
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setDappPermissions","params":["app1",{"allowedAccounts":["0x000000000000000000000000000000000000000a"],"defaultAccount":"0x000000000000000000000000000000000000000b","expiryTimestamp":null}], "id": 1}"#;
+	let response = tester.io.handle_request_sync(request).unwrap();
+
+	// then
+	assert!(response.contains(r#""error""#), "Unexpected response: {}", response);
+	assert!(tester.accounts.dapp_default_address("app1".into()).is_err());
+	assert_eq!(tester.accounts.dapps_addresses("app1".into()).unwrap(), tester.accounts.accounts().unwrap());
+}
+
 #[test]
 fn rpc_parity_set_and_get_new_dapps_whitelist() {
 	// given
@@ -233,6 +275,132 @@ fn should_be_able_to_remove_address() {
 	assert_eq!(res, Some(response.into()));
 }
 
+#[test]
+fn rpc_parity_export_account() {
+	let tester = setup();
+	let address = tester.accounts.new_account("password").unwrap();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_exportAccount", "params":[""#.to_owned() + &format!("0x{:?}", address) + r#"", "password"], "id": 1}"#;
+	let response = tester.io.handle_request_sync(&request).unwrap();
+	let prefix = r#"{"jsonrpc":"2.0","result":"0x"#;
+	let suffix = r#"","id":1}"#;
+	assert!(response.starts_with(prefix) && response.ends_with(suffix));
+
+	let exported = response[prefix.len()..response.len() - suffix.len()].from_hex().unwrap();
+	let json: serde_json::Value = serde_json::from_slice(&exported).unwrap();
+	let exported_address = json.as_object().unwrap().get("address").unwrap().as_str().unwrap();
+	assert_eq!(exported_address, format!("{:?}", address));
+}
+
+#[test]
+fn rpc_parity_clear_unlocked_accounts() {
+	// given
+	let tester = setup();
+	let address1 = tester.accounts.new_account("password").unwrap();
+	let address2 = tester.accounts.new_account("password").unwrap();
+	tester.accounts.unlock_account_permanently(address1, "password".into()).unwrap();
+	tester.accounts.unlock_account_permanently(address2, "password".into()).unwrap();
+
+	// when
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_clearUnlockedAccounts", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	// then
+	match tester.accounts.sign(address1, None, Default::default()) {
+		Err(SignError::NotUnlocked) => {},
+		other => panic!("Expected NotUnlocked, got {:?}", other),
+	}
+	match tester.accounts.sign(address2, None, Default::default()) {
+		Err(SignError::NotUnlocked) => {},
+		other => panic!("Expected NotUnlocked, got {:?}", other),
+	}
+}
+
+#[test]
+fn rpc_parity_export_account_is_rate_limited() {
+	let tester = setup();
+	let address = tester.accounts.new_account("password").unwrap();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_exportAccount", "params":[""#.to_owned() + &format!("0x{:?}", address) + r#"", "password"], "id": 1}"#;
+	for _ in 0..5 {
+		assert!(tester.io.handle_request_sync(&request).unwrap().contains("result"));
+	}
+
+	let response = tester.io.handle_request_sync(&request).unwrap();
+	assert!(response.contains(r#""code":-32007"#));
+}
+
+#[test]
+fn rpc_parity_export_account_is_rate_limited_per_caller() {
+	// given
+	let tester = setup();
+	let address = tester.accounts.new_account("password").unwrap();
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_exportAccount", "params":[""#.to_owned() + &format!("0x{:?}", address) + r#"", "password"], "id": 1}"#;
+	let from = |ip: &str| Metadata {
+		remote_ip: Some(ip.parse().unwrap()),
+		..Default::default()
+	};
+	for _ in 0..5 {
+		assert!((*tester.io).handle_request_sync(&request, from("10.0.0.1")).unwrap().contains("result"));
+	}
+
+	// when
+	let limited = (*tester.io).handle_request_sync(&request, from("10.0.0.1")).unwrap();
+	let other_caller = (*tester.io).handle_request_sync(&request, from("10.0.0.2")).unwrap();
+
+	// then
+	assert!(limited.contains(r#""code":-32007"#));
+	assert!(other_caller.contains("result"));
+}
+
+#[test]
+fn rpc_parity_export_hardware_account() {
+	let wallet = WalletInfo {
+		name: "Nano S".into(),
+		manufacturer: "Ledger".into(),
+		serial: "0001".into(),
+		address: 1.into(),
+		firmware_version: None,
+		app_version: None,
+		device_type: WalletDeviceType::Ledger,
+	};
+	let secret_store = EthStore::open(Box::new(MemoryDirectory::default())).unwrap();
+	let hardware_store = MockHardwareWalletManager::from_fixtures(vec![wallet], HashMap::new());
+	let tester = setup_with_accounts_provider(Arc::new(AccountProvider::new_with_hardware(Box::new(secret_store), Some(hardware_store))));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_exportAccount", "params":["0x0000000000000000000000000000000000000001", "password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32023,"message":"Cannot export hardware wallet.","data":"0000000000000000000000000000000000000001"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_list_hardware_wallets() {
+	let wallet = WalletInfo {
+		name: "Nano S".into(),
+		manufacturer: "Ledger".into(),
+		serial: "0001".into(),
+		address: 1.into(),
+		firmware_version: None,
+		app_version: None,
+		device_type: WalletDeviceType::Ledger,
+	};
+	let wallets = Arc::new(Mutex::new(vec![wallet]));
+	let secret_store = EthStore::open(Box::new(MemoryDirectory::default())).unwrap();
+	let hardware_store = MockHardwareWalletManager::from_shared_fixtures(wallets.clone(), HashMap::new());
+	let tester = setup_with_accounts_provider(Arc::new(AccountProvider::new_with_hardware(Box::new(secret_store), Some(hardware_store))));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_listHardwareWallets", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"name":"Nano S","manufacturer":"Ledger","serial":"0001","address":"0x0000000000000000000000000000000000000001","isHardware":true,"connected":true}],"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	// unplug the device
+	wallets.lock().clear();
+
+	let response = r#"{"jsonrpc":"2.0","result":[{"name":"Nano S","manufacturer":"Ledger","serial":"0001","address":"0x0000000000000000000000000000000000000001","isHardware":true,"connected":false}],"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_new_vault() {
 	let temp_path = RandomTempPath::new();
@@ -260,6 +428,27 @@ fn rpc_parity_open_vault() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_vault_errors() {
+	let temp_path = RandomTempPath::new();
+	let tester = setup_with_vaults_support(temp_path.as_str());
+
+	assert!(tester.accounts.create_vault("vault1", "password1").is_ok());
+	assert!(tester.accounts.close_vault("vault1").is_ok());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_newVault", "params":["vault1", "password1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32024,"message":"Vault already exists.","data":"vault1"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_openVault", "params":["vault1", "password2"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32024,"message":"Invalid vault password.","data":null},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_openVault", "params":["vault2", "password1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32024,"message":"Vault not found.","data":"vault2"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_close_vault() {
 	let temp_path = RandomTempPath::new();
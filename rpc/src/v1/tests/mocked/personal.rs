@@ -191,6 +191,30 @@ fn should_unlock_account_temporarily() {
 	assert!(tester.accounts.sign(address, None, Default::default()).is_ok(), "Should unlock account.");
 }
 
+#[test]
+fn should_return_rate_limited_error_after_failed_unlocks() {
+	let tester = setup();
+	let address = tester.accounts.new_account("password123").unwrap();
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "personal_unlockAccount",
+		"params": [
+			""#.to_owned() + &format!("0x{:?}", address) + r#"",
+			"wrong",
+			"0x100"
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	for _ in 0..5 {
+		assert_eq!(tester.io.handle_request_sync(&request), Some(response.into()));
+	}
+
+	let response = tester.io.handle_request_sync(&request).unwrap();
+	assert!(response.contains(r#""code":-32007"#), "Unexpected response: {}", response);
+}
+
 #[test]
 fn should_unlock_account_permanently() {
 	let tester = setup();
@@ -14,11 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::str::FromStr;
 use util::{U256, Uint, Address, ToPretty};
 
 use ethcore::account_provider::AccountProvider;
+use ethkey::Signature;
+use ethstore::EthStore;
+use ethstore::dir::MemoryDirectory;
+use hardware_wallet::{WalletInfo, WalletDeviceType};
+use hardware_wallet::mock::MockHardwareWalletManager;
 use ethcore::client::TestBlockChainClient;
 use ethcore::transaction::{Transaction, Action};
 use rlp::encode;
@@ -55,8 +61,11 @@ fn miner_service() -> Arc<TestMinerService> {
 }
 
 fn signer_tester() -> SignerTester {
+	signer_tester_with_accounts(accounts_provider())
+}
+
+fn signer_tester_with_accounts(accounts: Arc<AccountProvider>) -> SignerTester {
 	let signer = Arc::new(SignerService::new_test(None));
-	let accounts = accounts_provider();
 	let client = blockchain_client();
 	let miner = miner_service();
 
@@ -174,6 +183,36 @@ fn should_not_remove_sign_if_password_is_invalid() {
 	assert_eq!(tester.signer.requests().len(), 1);
 }
 
+#[test]
+fn should_confirm_typed_data_with_hardware_wallet() {
+	// given
+	let address = Address::from(5);
+	let wallet = WalletInfo {
+		name: "Nano S".into(),
+		manufacturer: "Ledger".into(),
+		serial: "0001".into(),
+		address: address,
+		firmware_version: None,
+		app_version: None,
+		device_type: WalletDeviceType::Ledger,
+	};
+	let mut signatures = HashMap::new();
+	signatures.insert(address, Signature::from_rsv(&1.into(), &2.into(), 1));
+	let secret_store = EthStore::open(Box::new(MemoryDirectory::default())).unwrap();
+	let hardware_store = MockHardwareWalletManager::from_fixtures(vec![wallet], signatures);
+	let tester = signer_tester_with_accounts(Arc::new(AccountProvider::new_with_hardware(Box::new(secret_store), Some(hardware_store))));
+	tester.signer.add_request(ConfirmationPayload::SignTypedData(address, 1.into(), 2.into())).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"signer_confirmRequest","params":["0x1",{},""],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x1c00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
 #[test]
 fn should_confirm_transaction_and_dispatch() {
 	//// given
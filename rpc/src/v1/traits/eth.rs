@@ -21,7 +21,7 @@ use jsonrpc_macros::Trailing;
 use futures::BoxFuture;
 
 use v1::types::{RichBlock, BlockNumber, Bytes, CallRequest, Filter, FilterChanges, Index};
-use v1::types::{Log, Receipt, SyncStatus, Transaction, Work};
+use v1::types::{Log, Receipt, SyncStatus, Transaction, Work, EIP1186ProofResponse, FeeHistoryResult, AccessListResult};
 use v1::types::{H64, H160, H256, U256};
 
 build_rpc_trait! {
@@ -53,6 +53,11 @@ build_rpc_trait! {
 		#[rpc(name = "eth_gasPrice")]
 		fn gas_price(&self) -> Result<U256, Error>;
 
+		/// Returns base fees, gas usage and priority fees at given percentiles for a range of blocks
+		/// ending with `newest_block`. At most 1024 blocks are returned.
+		#[rpc(async, name = "eth_feeHistory")]
+		fn fee_history(&self, U256, BlockNumber, Trailing<Vec<f64>>) -> BoxFuture<FeeHistoryResult, Error>;
+
 		/// Returns accounts list.
 		#[rpc(meta, name = "eth_accounts")]
 		fn accounts(&self, Self::Metadata) -> BoxFuture<Vec<H160>, Error>;
@@ -101,6 +106,10 @@ build_rpc_trait! {
 		#[rpc(async, name = "eth_getCode")]
 		fn code_at(&self, H160, Trailing<BlockNumber>) -> BoxFuture<Bytes, Error>;
 
+		/// Returns merkle proofs of the account and given storage keys at given block (EIP-1186).
+		#[rpc(async, name = "eth_getProof")]
+		fn proof(&self, H160, Vec<H256>, Trailing<BlockNumber>) -> BoxFuture<EIP1186ProofResponse, Error>;
+
 		/// Sends signed transaction, returning its hash.
 		#[rpc(name = "eth_sendRawTransaction")]
 		fn send_raw_transaction(&self, Bytes) -> Result<H256, Error>;
@@ -117,6 +126,10 @@ build_rpc_trait! {
 		#[rpc(name = "eth_estimateGas")]
 		fn estimate_gas(&self, CallRequest, Trailing<BlockNumber>) -> Result<U256, Error>;
 
+		/// Returns accounts and storage keys accessed by given call (EIP-2930) and gas it used.
+		#[rpc(name = "eth_createAccessList")]
+		fn create_access_list(&self, CallRequest, Trailing<BlockNumber>) -> Result<AccessListResult, Error>;
+
 		/// Get transaction by its hash.
 		#[rpc(name = "eth_getTransactionByHash")]
 		fn transaction_by_hash(&self, H256) -> Result<Option<Transaction>, Error>;
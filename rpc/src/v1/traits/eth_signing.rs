@@ -19,7 +19,7 @@
 use jsonrpc_core::Error;
 use futures::BoxFuture;
 
-use v1::types::{Bytes, H160, H256, H520, TransactionRequest, RichRawTransaction};
+use v1::types::{Bytes, H160, H256, H520, TransactionRequest, RichRawTransaction, TypedData};
 
 build_rpc_trait! {
 	/// Signing methods implementation relying on unlocked accounts.
@@ -30,6 +30,10 @@ build_rpc_trait! {
 		#[rpc(async, name = "eth_sign")]
 		fn sign(&self, H160, Bytes) -> BoxFuture<H520, Error>;
 
+		/// Signs EIP-712 typed structured data with given address.
+		#[rpc(async, name = "eth_signTypedData")]
+		fn sign_typed_data(&self, H160, TypedData) -> BoxFuture<H520, Error>;
+
 		/// Sends transaction; will block waiting for signer to return the
 		/// transaction hash.
 		/// If Signer is disable it will require the account to be unlocked.
@@ -17,6 +17,7 @@
 //! Ethereum rpc interfaces.
 
 pub mod web3;
+pub mod debug;
 pub mod eth;
 pub mod eth_signing;
 pub mod net;
@@ -30,6 +31,7 @@ pub mod traces;
 pub mod rpc;
 
 pub use self::web3::Web3;
+pub use self::debug::Debug;
 pub use self::eth::{Eth, EthFilter};
 pub use self::eth_signing::EthSigning;
 pub use self::net::Net;
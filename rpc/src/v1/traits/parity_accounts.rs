@@ -18,11 +18,14 @@
 use std::collections::BTreeMap;
 
 use jsonrpc_core::Error;
-use v1::types::{H160, H256, DappId};
+use futures::BoxFuture;
+use v1::types::{H160, H256, Bytes, DappId, DappPermissions, HardwareWalletInfo};
 
 build_rpc_trait! {
 	/// Personal Parity rpc interface.
 	pub trait ParityAccounts {
+		type Metadata;
+
 		/// Returns accounts information.
 		#[rpc(name = "parity_allAccountsInfo")]
 		fn all_accounts_info(&self) -> Result<BTreeMap<H160, BTreeMap<String, String>>, Error>;
@@ -52,6 +55,11 @@ build_rpc_trait! {
 		#[rpc(name = "parity_changePassword")]
 		fn change_password(&self, H160, String, String) -> Result<bool, Error>;
 
+		/// Exports an account as a keystore JSON file encrypted with the same password.
+		/// Arguments: `account`, `password`.
+		#[rpc(meta, name = "parity_exportAccount")]
+		fn export_account(&self, Self::Metadata, H160, String) -> BoxFuture<Bytes, Error>;
+
 		/// Permanently deletes an account.
 		/// Arguments: `account`, `password`.
 		#[rpc(name = "parity_killAccount")]
@@ -83,6 +91,19 @@ build_rpc_trait! {
 		#[rpc(name = "parity_getDappsAddresses")]
 		fn dapps_addresses(&self, DappId) -> Result<Vec<H160>, Error>;
 
+		/// Gets accounts and default account exposed for particular dapp.
+		#[rpc(name = "parity_getDappPermissions")]
+		fn dapp_permissions(&self, DappId) -> Result<DappPermissions, Error>;
+
+		/// Sets accounts, default account and expiry of the permissions for particular dapp.
+		/// Default account is left unchanged if not given.
+		#[rpc(name = "parity_setDappPermissions")]
+		fn set_dapp_permissions(&self, DappId, DappPermissions) -> Result<bool, Error>;
+
+		/// Locks all unlocked accounts immediately.
+		#[rpc(name = "parity_clearUnlockedAccounts")]
+		fn clear_unlocked_accounts(&self) -> Result<bool, Error>;
+
 		/// Sets accounts exposed for new dapps.
 		/// `None` means that all accounts will be exposed.
 		#[rpc(name = "parity_setNewDappsWhitelist")]
@@ -106,6 +127,10 @@ build_rpc_trait! {
 		#[rpc(name = "parity_listGethAccounts")]
 		fn geth_accounts(&self) -> Result<Vec<H160>, Error>;
 
+		/// Returns hardware wallets seen since startup, including the ones disconnected since.
+		#[rpc(name = "parity_listHardwareWallets")]
+		fn hardware_wallets(&self) -> Result<Vec<HardwareWalletInfo>, Error>;
+
 		/// Create new vault.
 		#[rpc(name = "parity_newVault")]
 		fn create_vault(&self, String, String) -> Result<bool, Error>;
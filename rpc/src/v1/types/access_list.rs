@@ -0,0 +1,64 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Access lists (EIP-2930).
+
+use v1::types::{H160, H256, U256};
+
+/// Account accessed by a transaction together with its accessed storage keys.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct AccessListEntry {
+	/// Accessed account.
+	pub address: H160,
+	/// Accessed storage keys.
+	#[serde(rename="storageKeys")]
+	pub storage_keys: Vec<H256>,
+}
+
+/// Access list generated for a transaction.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct AccessListResult {
+	/// Accounts and storage keys accessed by the transaction.
+	#[serde(rename="accessList")]
+	pub access_list: Vec<AccessListEntry>,
+	/// Gas used by the transaction.
+	#[serde(rename="gasUsed")]
+	pub gas_used: U256,
+	/// Execution error, if the transaction failed.
+	#[serde(skip_serializing_if="Option::is_none")]
+	pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{AccessListEntry, AccessListResult};
+
+	#[test]
+	fn access_list_serialization() {
+		let result = AccessListResult {
+			access_list: vec![AccessListEntry {
+				address: 1.into(),
+				storage_keys: vec![2.into()],
+			}],
+			gas_used: 0x5208.into(),
+			error: None,
+		};
+
+		let serialized = serde_json::to_string(&result).unwrap();
+		assert_eq!(serialized, r#"{"accessList":[{"address":"0x0000000000000000000000000000000000000001","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000002"]}],"gasUsed":"0x5208"}"#);
+	}
+}
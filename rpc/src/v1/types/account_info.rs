@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use v1::types::H160;
+
 /// Account information.
 #[derive(Debug, Default, Clone, PartialEq, Serialize)]
 pub struct AccountInfo {
@@ -29,3 +31,21 @@ pub struct HwAccountInfo {
 	/// Device manufacturer.
 	pub manufacturer: String,
 }
+
+/// Hardware wallet seen by the node.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct HardwareWalletInfo {
+	/// Device name.
+	pub name: String,
+	/// Device manufacturer.
+	pub manufacturer: String,
+	/// Device serial number.
+	pub serial: String,
+	/// Account address.
+	pub address: H160,
+	/// Always true, allows telling hardware accounts from other accounts.
+	#[serde(rename="isHardware")]
+	pub is_hardware: bool,
+	/// Whether the device is currently connected.
+	pub connected: bool,
+}
@@ -0,0 +1,70 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Account and storage proofs (EIP-1186).
+
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Merkle proof of an account and some of its storage slots.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct EIP1186ProofResponse {
+	/// Address of the account.
+	pub address: H160,
+	/// Trie nodes on the path from the state root to the account.
+	#[serde(rename="accountProof")]
+	pub account_proof: Vec<Bytes>,
+	/// Account balance.
+	pub balance: U256,
+	/// Hash of the account code.
+	#[serde(rename="codeHash")]
+	pub code_hash: H256,
+	/// Account nonce.
+	pub nonce: U256,
+	/// Root of the account storage trie.
+	#[serde(rename="storageHash")]
+	pub storage_hash: H256,
+	/// Proofs of requested storage slots.
+	#[serde(rename="storageProof")]
+	pub storage_proof: Vec<StorageProof>,
+}
+
+/// Merkle proof of a single storage slot.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct StorageProof {
+	/// Storage key.
+	pub key: H256,
+	/// Storage value.
+	pub value: U256,
+	/// Trie nodes on the path from the storage root to the slot.
+	pub proof: Vec<Bytes>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{EIP1186ProofResponse, StorageProof};
+
+	#[test]
+	fn proof_serialization() {
+		let proof = EIP1186ProofResponse {
+			storage_proof: vec![StorageProof::default()],
+			..Default::default()
+		};
+
+		let serialized = serde_json::to_string(&proof).unwrap();
+		assert_eq!(serialized, r#"{"address":"0x0000000000000000000000000000000000000000","accountProof":[],"balance":"0x0","codeHash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","storageHash":"0x0000000000000000000000000000000000000000000000000000000000000000","storageProof":[{"key":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x0","proof":[]}]}"#);
+	}
+}
@@ -55,6 +55,7 @@ impl fmt::Display for ConfirmationPayload {
 			ConfirmationPayload::SignTransaction(ref transaction) => write!(f, "(Sign only) {}", transaction),
 			ConfirmationPayload::Signature(ref sign) => write!(f, "{}", sign),
 			ConfirmationPayload::Decrypt(ref decrypt) => write!(f, "{}", decrypt),
+			ConfirmationPayload::SignTypedData(ref request) => write!(f, "{}", request),
 		}
 	}
 }
@@ -116,6 +117,31 @@ impl fmt::Display for DecryptRequest {
 	}
 }
 
+/// EIP-712 typed data sign request
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct TypedDataRequest {
+	/// Address
+	pub address: H160,
+	/// Hash of the signing domain
+	#[serde(rename="domainSeparator")]
+	pub domain_separator: H256,
+	/// Hash of the message
+	#[serde(rename="messageHash")]
+	pub message_hash: H256,
+}
+
+impl fmt::Display for TypedDataRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"sign typed data 0x{:?} (domain 0x{:?}) with {}",
+			self.message_hash,
+			self.domain_separator,
+			Colour::White.bold().paint(format!("0x{:?}", self.address)),
+		)
+	}
+}
+
 /// Confirmation response for particular payload
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConfirmationResponse {
@@ -166,6 +192,9 @@ pub enum ConfirmationPayload {
 	/// Decryption
 	#[serde(rename="decrypt")]
 	Decrypt(DecryptRequest),
+	/// Typed data signature
+	#[serde(rename="signTypedData")]
+	SignTypedData(TypedDataRequest),
 }
 
 impl From<helpers::ConfirmationPayload> for ConfirmationPayload {
@@ -181,6 +210,11 @@ impl From<helpers::ConfirmationPayload> for ConfirmationPayload {
 				address: address.into(),
 				msg: msg.into(),
 			}),
+			helpers::ConfirmationPayload::SignTypedData(address, domain_separator, message_hash) => ConfirmationPayload::SignTypedData(TypedDataRequest {
+				address: address.into(),
+				domain_separator: domain_separator.into(),
+				message_hash: message_hash.into(),
+			}),
 		}
 	}
 }
@@ -329,6 +363,24 @@ mod tests {
 		assert_eq!(res.unwrap(), expected.to_owned());
 	}
 
+	#[test]
+	fn should_serialize_typed_data_confirmation() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::SignTypedData(
+				10.into(), 1.into(), 2.into(),
+			),
+		};
+
+		// when
+		let res = serde_json::to_string(&ConfirmationRequest::from(request));
+		let expected = r#"{"id":"0xf","payload":{"signTypedData":{"address":"0x000000000000000000000000000000000000000a","domainSeparator":"0x0000000000000000000000000000000000000000000000000000000000000001","messageHash":"0x0000000000000000000000000000000000000000000000000000000000000002"}}}"#;
+
+		// then
+		assert_eq!(res.unwrap(), expected.to_owned());
+	}
+
 	#[test]
 	fn should_deserialize_modification() {
 		// given
@@ -17,6 +17,7 @@
 //! Dapp Id type
 
 use ethcore::account_provider::DappId as EthDappId;
+use v1::types::H160;
 
 /// Dapplication Internal Id
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
@@ -46,6 +47,20 @@ impl Into<EthDappId> for DappId {
 	}
 }
 
+/// Accounts exposed to a dapp.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DappPermissions {
+	/// Accounts visible to the dapp.
+	#[serde(rename="allowedAccounts")]
+	pub allowed_accounts: Vec<H160>,
+	/// Account used by default.
+	#[serde(rename="defaultAccount")]
+	pub default_account: Option<H160>,
+	/// Unix timestamp after which the permissions fall back to the new dapps policy.
+	#[serde(rename="expiryTimestamp")]
+	pub expiry_timestamp: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
 
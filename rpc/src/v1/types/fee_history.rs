@@ -0,0 +1,55 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Fee history (EIP-1559).
+
+use v1::types::U256;
+
+/// Fees paid in a range of recent blocks.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct FeeHistoryResult {
+	/// Number of the oldest block in the range.
+	#[serde(rename="oldestBlock")]
+	pub oldest_block: U256,
+	/// Base fee of each block in the range and of the block following the newest one.
+	#[serde(rename="baseFeePerGas")]
+	pub base_fee_per_gas: Vec<U256>,
+	/// Ratio of gas used to gas limit of each block.
+	#[serde(rename="gasUsedRatio")]
+	pub gas_used_ratio: Vec<f64>,
+	/// Priority fees at requested percentiles of gas used in each block.
+	#[serde(skip_serializing_if="Option::is_none")]
+	pub reward: Option<Vec<Vec<U256>>>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::FeeHistoryResult;
+
+	#[test]
+	fn fee_history_serialization() {
+		let history = FeeHistoryResult {
+			oldest_block: 1.into(),
+			base_fee_per_gas: vec![0.into(), 0.into()],
+			gas_used_ratio: vec![0.5],
+			reward: None,
+		};
+
+		let serialized = serde_json::to_string(&history).unwrap();
+		assert_eq!(serialized, r#"{"oldestBlock":"0x1","baseFeePerGas":["0x0","0x0"],"gasUsedRatio":[0.5]}"#);
+	}
+}
@@ -0,0 +1,281 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EIP-712 typed structured data.
+
+use std::collections::BTreeMap;
+use std::iter;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use rustc_serialize::hex::FromHex;
+use util::{Address, H256, U256, Uint};
+use util::sha3::Hashable;
+
+/// Name of the type describing signing domain.
+const DOMAIN_TYPE: &'static str = "EIP712Domain";
+
+/// Struct definitions: type name -> list of `(field name, field type)`.
+type Types = BTreeMap<String, Vec<(String, String)>>;
+
+/// Typed structured data (`types`, `primaryType`, `domain` and `message`) as defined by EIP-712.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedData(pub Value);
+
+impl Deserialize for TypedData {
+	fn deserialize<D>(deserializer: &mut D) -> Result<TypedData, D::Error>
+	where D: Deserializer {
+		Value::deserialize(deserializer).map(TypedData)
+	}
+}
+
+impl TypedData {
+	/// Hash to be signed: `keccak256("\x19\x01" ‖ hashStruct(domain) ‖ hashStruct(message))`.
+	pub fn hash(&self) -> Result<H256, String> {
+		let (domain_separator, message_hash) = self.hashes()?;
+		Ok(TypedData::signing_hash(&domain_separator, &message_hash))
+	}
+
+	/// Domain separator and message hash, i.e. `hashStruct(domain)` and `hashStruct(message)`.
+	/// Hardware wallets sign these two instead of the final hash.
+	pub fn hashes(&self) -> Result<(H256, H256), String> {
+		let types = parse_types(member(&self.0, "types").ok_or("Missing `types`.")?)?;
+		let primary_type = member(&self.0, "primaryType").and_then(Value::as_str).ok_or("Missing `primaryType`.")?;
+		let domain = member(&self.0, "domain").ok_or("Missing `domain`.")?;
+		let message = member(&self.0, "message").ok_or("Missing `message`.")?;
+
+		Ok((hash_struct(DOMAIN_TYPE, domain, &types)?, hash_struct(primary_type, message, &types)?))
+	}
+
+	/// Combine domain separator and message hash into the hash to be signed.
+	pub fn signing_hash(domain_separator: &H256, message_hash: &H256) -> H256 {
+		let mut data = vec![0x19, 0x01];
+		data.extend_from_slice(domain_separator);
+		data.extend_from_slice(message_hash);
+		data.sha3()
+	}
+}
+
+fn member<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+	value.as_object().and_then(|object| object.get(key))
+}
+
+fn parse_types(value: &Value) -> Result<Types, String> {
+	let definitions = value.as_object().ok_or("`types` should be an object.")?;
+	let mut types = Types::new();
+	for (name, fields) in definitions {
+		let fields = fields.as_array().ok_or_else(|| format!("Fields of `{}` should be an array.", name))?;
+		let mut parsed = Vec::with_capacity(fields.len());
+		for field in fields {
+			match (member(field, "name").and_then(Value::as_str), member(field, "type").and_then(Value::as_str)) {
+				(Some(field_name), Some(field_type)) => parsed.push((field_name.to_owned(), field_type.to_owned())),
+				_ => return Err(format!("Invalid field definition in `{}`.", name)),
+			}
+		}
+		types.insert(name.clone(), parsed);
+	}
+	Ok(types)
+}
+
+/// Strips array suffixes: `Person[][2]` -> `Person`.
+fn base_type(field_type: &str) -> &str {
+	match field_type.find('[') {
+		Some(index) => &field_type[..index],
+		None => field_type,
+	}
+}
+
+fn dependencies(field_type: &str, types: &Types, found: &mut Vec<String>) {
+	let name = base_type(field_type);
+	if found.iter().any(|dep| dep == name) {
+		return;
+	}
+
+	if let Some(fields) = types.get(name) {
+		found.push(name.to_owned());
+		for &(_, ref field_type) in fields {
+			dependencies(field_type, types, found);
+		}
+	}
+}
+
+/// `Mail(Person from,Person to,string contents)Person(string name,address wallet)`
+fn encode_type(name: &str, types: &Types) -> String {
+	let mut deps = Vec::new();
+	dependencies(name, types, &mut deps);
+	deps.retain(|dep| dep != name);
+	deps.sort();
+
+	iter::once(name).chain(deps.iter().map(|dep| dep.as_str()))
+		.map(|name| {
+			let fields = types[name].iter()
+				.map(|&(ref field_name, ref field_type)| format!("{} {}", field_type, field_name))
+				.collect::<Vec<_>>();
+			format!("{}({})", name, fields.join(","))
+		})
+		.collect()
+}
+
+fn hash_struct(name: &str, data: &Value, types: &Types) -> Result<H256, String> {
+	let fields = types.get(name).ok_or_else(|| format!("Unknown type `{}`.", name))?;
+	let mut encoded = encode_type(name, types).sha3().to_vec();
+	for &(ref field_name, ref field_type) in fields {
+		let value = member(data, field_name).ok_or_else(|| format!("Missing field `{}` of `{}`.", field_name, name))?;
+		encoded.extend_from_slice(&encode_value(field_type, value, types)?);
+	}
+	Ok(encoded.sha3())
+}
+
+fn encode_value(field_type: &str, value: &Value, types: &Types) -> Result<H256, String> {
+	if field_type.ends_with(']') {
+		let item_type = &field_type[..field_type.rfind('[').ok_or_else(|| format!("Invalid type `{}`.", field_type))?];
+		let items = value.as_array().ok_or_else(|| format!("Expected array of `{}`.", item_type))?;
+		let mut encoded = Vec::with_capacity(items.len() * 32);
+		for item in items {
+			encoded.extend_from_slice(&encode_value(item_type, item, types)?);
+		}
+		return Ok(encoded.sha3());
+	}
+
+	if types.contains_key(field_type) {
+		return hash_struct(field_type, value, types);
+	}
+
+	match field_type {
+		"string" => Ok(string(value)?.sha3()),
+		"bytes" => Ok(bytes(value)?.sha3()),
+		"bool" => match value.as_bool() {
+			Some(b) => Ok(U256::from(b as u64).into()),
+			None => Err("Expected boolean.".into()),
+		},
+		"address" => {
+			let address = string(value)?;
+			let address = Address::from_str(address.trim_left_matches("0x")).map_err(|_| format!("Invalid address `{}`.", address))?;
+			Ok(address.into())
+		},
+		_ if field_type.starts_with("bytes") => {
+			let bytes = bytes(value)?;
+			let size: usize = field_type[5..].parse().map_err(|_| format!("Invalid type `{}`.", field_type))?;
+			if bytes.len() != size || size > 32 {
+				return Err(format!("Expected {} bytes.", size));
+			}
+			let mut word = H256::zero();
+			word[..size].copy_from_slice(&bytes);
+			Ok(word)
+		},
+		_ if field_type.starts_with("uint") => match integer(value)? {
+			(false, value) => Ok(value.into()),
+			(true, _) => Err(format!("Negative value for `{}`.", field_type)),
+		},
+		_ if field_type.starts_with("int") => match integer(value)? {
+			(false, value) => Ok(value.into()),
+			// two's complement
+			(true, value) => Ok(U256::zero().overflowing_sub(value).0.into()),
+		},
+		_ => Err(format!("Unknown type `{}`.", field_type)),
+	}
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+	value.as_str().ok_or_else(|| "Expected string.".into())
+}
+
+fn bytes(value: &Value) -> Result<Vec<u8>, String> {
+	let hex = string(value)?;
+	if !hex.starts_with("0x") {
+		return Err(format!("Expected 0x-prefixed hex, got `{}`.", hex));
+	}
+	hex[2..].from_hex().map_err(|_| format!("Invalid hex `{}`.", hex))
+}
+
+/// Parses number given as JSON number, decimal string or 0x-prefixed hex string.
+/// Returns sign and absolute value.
+fn integer(value: &Value) -> Result<(bool, U256), String> {
+	match *value {
+		Value::U64(v) => Ok((false, v.into())),
+		Value::I64(v) => Ok((v < 0, (v.wrapping_abs() as u64).into())),
+		Value::String(ref s) => {
+			let (negative, digits) = if s.starts_with('-') { (true, &s[1..]) } else { (false, &s[..]) };
+			let parsed = if digits.starts_with("0x") {
+				U256::from_str(&digits[2..]).map_err(|_| ())
+			} else {
+				U256::from_dec_str(digits).map_err(|_| ())
+			};
+			parsed.map(|v| (negative, v)).map_err(|_| format!("Invalid number `{}`.", s))
+		},
+		_ => Err("Expected number.".into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use util::H256;
+	use super::TypedData;
+
+	const MAIL: &'static str = r#"{
+		"types": {
+			"EIP712Domain": [
+				{ "name": "name", "type": "string" },
+				{ "name": "version", "type": "string" },
+				{ "name": "chainId", "type": "uint256" },
+				{ "name": "verifyingContract", "type": "address" }
+			],
+			"Person": [
+				{ "name": "name", "type": "string" },
+				{ "name": "wallet", "type": "address" }
+			],
+			"Mail": [
+				{ "name": "from", "type": "Person" },
+				{ "name": "to", "type": "Person" },
+				{ "name": "contents", "type": "string" }
+			]
+		},
+		"primaryType": "Mail",
+		"domain": {
+			"name": "Ether Mail",
+			"version": "1",
+			"chainId": 1,
+			"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+		},
+		"message": {
+			"from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+			"to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+			"contents": "Hello, Bob!"
+		}
+	}"#;
+
+	#[test]
+	fn should_hash_typed_data() {
+		let data: TypedData = serde_json::from_str(MAIL).unwrap();
+		assert_eq!(data.hash().unwrap(), H256::from("0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"));
+	}
+
+	#[test]
+	fn should_split_typed_data_hash() {
+		let data: TypedData = serde_json::from_str(MAIL).unwrap();
+		let (domain_separator, message_hash) = data.hashes().unwrap();
+		assert_eq!(domain_separator, H256::from("0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"));
+		assert_eq!(message_hash, H256::from("0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"));
+		assert_eq!(TypedData::signing_hash(&domain_separator, &message_hash), data.hash().unwrap());
+	}
+
+	#[test]
+	fn should_reject_missing_fields() {
+		let data: TypedData = serde_json::from_str(&MAIL.replace(r#""contents": "Hello, Bob!""#, r#""body": "Hello, Bob!""#)).unwrap();
+		assert!(data.hash().is_err());
+	}
+}
@@ -65,10 +65,17 @@ impl Deref for ServerLoop {
 
 /// Setup a mock signer for tests
 pub fn serve() -> (ServerLoop, usize, GuardedAuthCodes) {
+	serve_with(|builder| builder)
+}
+
+/// Setup a mock signer with customized builder
+pub fn serve_with<F>(process: F) -> (ServerLoop, usize, GuardedAuthCodes) where
+	F: FnOnce(ServerBuilder) -> ServerBuilder,
+{
 	let mut path = RandomTempPath::new();
 	path.panic_on_drop_failure = false;
 	let queue = Arc::new(ConfirmationsQueue::default());
-	let builder = ServerBuilder::new(queue, path.to_path_buf());
+	let builder = process(ServerBuilder::new(queue, path.to_path_buf()));
 	let port = 35000 + rand::random::<usize>() % 10000;
 	let event_loop = RpcEventLoop::spawn();
 	let handler = event_loop.handler(Arc::new(IoHandler::default().into()));
@@ -92,9 +99,12 @@ pub fn request(server: ServerLoop, request: &str) -> http_client::Response {
 #[cfg(test)]
 mod testing {
 	use std::time;
+	use std::io::{Read, Write};
+	use std::net::TcpStream;
 	use util::Hashable;
 	use devtools::http_client;
-	use super::{serve, request};
+	use KeepAlive;
+	use super::{serve, serve_with, request};
 
 	#[test]
 	fn should_reject_invalid_host() {
@@ -302,4 +312,95 @@ mod testing {
 		assert_eq!(response2.status, "HTTP/1.1 403 FORBIDDEN".to_owned());
 		http_client::assert_security_headers_present(&response2.headers, None);
 	}
+
+	/// Opens a session that never answers pings and reads frames until the server closes it.
+	/// Returns everything received, whether the connection got closed and how long it took.
+	fn wait_for_close(keepalive: KeepAlive) -> (Vec<u8>, bool, time::Duration) {
+		let (server, port, _authcodes) = serve_with(|builder| builder.keepalive(Some(keepalive)));
+		let timestamp = time::UNIX_EPOCH.elapsed().unwrap().as_secs();
+		let mut stream = TcpStream::connect(server.addr()).unwrap();
+		stream.set_read_timeout(Some(keepalive.timeout * 4)).unwrap();
+		stream.write_all(format!("\
+			GET / HTTP/1.1\r\n\
+			Host: 127.0.0.1:{}\r\n\
+			Connection: Upgrade\r\n\
+			Upgrade: websocket\r\n\
+			Sec-WebSocket-Key: x3JJHMbDL1EzLkh9GBhXDw==\r\n\
+			Sec-WebSocket-Protocol:{:?}_{}\r\n\
+			Sec-WebSocket-Version: 13\r\n\
+			\r\n\
+			",
+			port,
+			format!("{}:{}", "initial", timestamp).sha3(),
+			timestamp,
+		).as_bytes()).unwrap();
+		let connected = time::Instant::now();
+
+		let mut received = Vec::new();
+		let mut buf = [0u8; 256];
+		let mut closed = false;
+		loop {
+			match stream.read(&mut buf) {
+				Ok(0) => {
+					closed = true;
+					break;
+				},
+				Ok(read) => received.extend_from_slice(&buf[..read]),
+				Err(_) => break,
+			}
+
+			let frames = match received.windows(4).position(|w| w == b"\r\n\r\n") {
+				Some(end) => &received[end + 4..],
+				None => continue,
+			};
+			// server frames are unmasked and have short payloads: [opcode, length, payload...]
+			let mut position = 0;
+			let mut close_frame = false;
+			while position + 1 < frames.len() {
+				close_frame |= frames[position] & 0x0f == 0x8;
+				position += 2 + (frames[position + 1] & 0x7f) as usize;
+			}
+			if close_frame {
+				closed = true;
+				break;
+			}
+		}
+
+		(received, closed, connected.elapsed())
+	}
+
+	#[test]
+	fn should_close_connection_not_answering_pings() {
+		// given
+		let keepalive = KeepAlive {
+			interval: time::Duration::from_millis(100),
+			timeout: time::Duration::from_millis(500),
+		};
+
+		// when
+		let (received, closed, elapsed) = wait_for_close(keepalive);
+
+		// then
+		assert!(received.starts_with(b"HTTP/1.1 101 Switching Protocols"));
+		assert!(closed, "Connection was not closed.");
+		assert!(elapsed < keepalive.timeout + time::Duration::from_secs(1));
+	}
+
+	#[test]
+	fn should_close_connection_within_timeout_regardless_of_ping_interval() {
+		// given
+		let keepalive = KeepAlive {
+			interval: time::Duration::from_secs(30),
+			timeout: time::Duration::from_millis(500),
+		};
+
+		// when
+		let (received, closed, elapsed) = wait_for_close(keepalive);
+
+		// then
+		assert!(received.starts_with(b"HTTP/1.1 101 Switching Protocols"));
+		assert!(closed, "Connection was not closed.");
+		assert!(elapsed >= keepalive.timeout);
+		assert!(elapsed < keepalive.timeout + time::Duration::from_secs(1));
+	}
 }
@@ -23,6 +23,7 @@ use std::ops::Drop;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 use std;
 
 use io::{PanicHandler, OnPanicListener, MayPanic};
@@ -33,6 +34,13 @@ use rpc::informant::RpcStats;
 
 mod session;
 
+pub use self::session::KeepAlive;
+
+/// Default interval between keepalive pings.
+pub const DEFAULT_KEEPALIVE_INTERVAL_SECS: u64 = 30;
+/// Default time to wait for a pong before closing the connection.
+pub const DEFAULT_KEEPALIVE_TIMEOUT_SECS: u64 = 60;
+
 /// Signer startup error
 #[derive(Debug)]
 pub enum ServerError {
@@ -57,6 +65,7 @@ pub struct ServerBuilder {
 	authcodes_path: PathBuf,
 	skip_origin_validation: bool,
 	stats: Option<Arc<RpcStats>>,
+	keepalive: Option<KeepAlive>,
 }
 
 impl ServerBuilder {
@@ -67,6 +76,10 @@ impl ServerBuilder {
 			authcodes_path: authcodes_path,
 			skip_origin_validation: false,
 			stats: None,
+			keepalive: Some(KeepAlive {
+				interval: Duration::from_secs(DEFAULT_KEEPALIVE_INTERVAL_SECS),
+				timeout: Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+			}),
 		}
 	}
 
@@ -83,6 +96,13 @@ impl ServerBuilder {
 		self
 	}
 
+	/// Configure keepalive pings. Connections not answering with a pong within
+	/// the timeout are closed. `None` disables the pings.
+	pub fn keepalive(mut self, keepalive: Option<KeepAlive>) -> Self {
+		self.keepalive = keepalive;
+		self
+	}
+
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
 	pub fn start<M: Metadata, S: Middleware<M>>(self, addr: SocketAddr, handler: RpcHandler<M, S>) -> Result<Server, ServerError> {
@@ -93,6 +113,7 @@ impl ServerBuilder {
 			self.authcodes_path,
 			self.skip_origin_validation,
 			self.stats,
+			self.keepalive,
 		)
 	}
 }
@@ -121,6 +142,7 @@ impl Server {
 		authcodes_path: PathBuf,
 		skip_origin_validation: bool,
 		stats: Option<Arc<RpcStats>>,
+		keepalive: Option<KeepAlive>,
 	) -> Result<Server, ServerError> {
 		let config = {
 			let mut config = ws::Settings::default();
@@ -135,7 +157,7 @@ impl Server {
 		let origin = format!("{}", addr);
 		let port = addr.port();
 		let ws = ws::Builder::new().with_settings(config).build(
-			session::Factory::new(handler, origin, port, authcodes_path, skip_origin_validation, stats)
+			session::Factory::new(handler, origin, port, authcodes_path, skip_origin_validation, stats, keepalive)
 		)?;
 
 		let panic_handler = PanicHandler::new_in_arc();
@@ -21,6 +21,7 @@ use authcode_store::AuthCodes;
 use std::path::{PathBuf, Path};
 use std::sync::Arc;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 use jsonrpc_core::{Metadata, Middleware};
 use jsonrpc_core::reactor::RpcHandler;
 use rpc::informant::RpcStats;
@@ -67,6 +68,24 @@ mod ui {
 
 const HOME_DOMAIN: &'static str = "parity.web3.site";
 
+/// Timeout token of the keepalive ping.
+const PING_TOKEN: ws::util::Token = ws::util::Token(1);
+/// Timeout token of the check closing connections without a recent pong.
+const PONG_CHECK_TOKEN: ws::util::Token = ws::util::Token(2);
+
+/// WebSocket keepalive settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeepAlive {
+	/// Interval between ping frames.
+	pub interval: Duration,
+	/// Connection is closed if no pong is received within this time.
+	pub timeout: Duration,
+}
+
+fn as_millis(duration: Duration) -> u64 {
+	duration.as_secs() * 1000 + (duration.subsec_nanos() / 1_000_000) as u64
+}
+
 fn origin_is_allowed(self_origin: &str, header: Option<&[u8]>) -> bool {
 	match header.map(|h| String::from_utf8_lossy(h).into_owned()) {
 		Some(ref origin) if origin.starts_with("chrome-extension://") => true,
@@ -134,6 +153,21 @@ pub struct Session<M: Metadata, S: Middleware<M>> {
 	handler: RpcHandler<M, S>,
 	file_handler: Arc<ui::Handler>,
 	stats: Option<Arc<RpcStats>>,
+	keepalive: Option<KeepAlive>,
+	last_pong: Instant,
+}
+
+impl<M: Metadata, S: Middleware<M>> Session<M, S> {
+	fn schedule_ping(&self, keepalive: &KeepAlive) -> ws::Result<()> {
+		self.out.timeout(as_millis(keepalive.interval), PING_TOKEN)
+	}
+
+	/// Schedules the pong check at `last_pong + timeout`.
+	fn schedule_pong_check(&self, keepalive: &KeepAlive) -> ws::Result<()> {
+		let elapsed = self.last_pong.elapsed();
+		let remaining = if elapsed < keepalive.timeout { keepalive.timeout - elapsed } else { Duration::from_secs(0) };
+		self.out.timeout(as_millis(remaining), PONG_CHECK_TOKEN)
+	}
 }
 
 impl<M: Metadata, S: Middleware<M>> Drop for Session<M, S> {
@@ -211,6 +245,46 @@ impl<M: Metadata, S: Middleware<M>> ws::Handler for Session<M, S> {
 			))
 	}
 
+	fn on_open(&mut self, _shake: ws::Handshake) -> ws::Result<()> {
+		self.last_pong = Instant::now();
+		match self.keepalive {
+			Some(ref keepalive) => {
+				self.schedule_ping(keepalive)?;
+				self.schedule_pong_check(keepalive)
+			},
+			None => Ok(()),
+		}
+	}
+
+	fn on_frame(&mut self, frame: ws::Frame) -> ws::Result<Option<ws::Frame>> {
+		if frame.opcode() == ws::OpCode::Pong {
+			self.last_pong = Instant::now();
+		}
+		Ok(Some(frame))
+	}
+
+	fn on_timeout(&mut self, token: ws::util::Token) -> ws::Result<()> {
+		let keepalive = match self.keepalive {
+			Some(keepalive) => keepalive,
+			None => return Ok(()),
+		};
+
+		if token == PING_TOKEN {
+			self.out.ping(Vec::new())?;
+			return self.schedule_ping(&keepalive);
+		}
+
+		if token == PONG_CHECK_TOKEN {
+			if self.last_pong.elapsed() >= keepalive.timeout {
+				debug!(target: "signer", "No pong received within {:?}, closing connection.", keepalive.timeout);
+				return self.out.close(ws::CloseCode::Away);
+			}
+			return self.schedule_pong_check(&keepalive);
+		}
+
+		Ok(())
+	}
+
 	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
 		let req = msg.as_text()?;
 		let out = self.out.clone();
@@ -237,6 +311,7 @@ pub struct Factory<M: Metadata, S: Middleware<M>> {
 	authcodes_path: PathBuf,
 	file_handler: Arc<ui::Handler>,
 	stats: Option<Arc<RpcStats>>,
+	keepalive: Option<KeepAlive>,
 }
 
 impl<M: Metadata, S: Middleware<M>> Factory<M, S> {
@@ -247,6 +322,7 @@ impl<M: Metadata, S: Middleware<M>> Factory<M, S> {
 		authcodes_path: PathBuf,
 		skip_origin_validation: bool,
 		stats: Option<Arc<RpcStats>>,
+		keepalive: Option<KeepAlive>,
 	) -> Self {
 		Factory {
 			handler: handler,
@@ -256,6 +332,7 @@ impl<M: Metadata, S: Middleware<M>> Factory<M, S> {
 			authcodes_path: authcodes_path,
 			file_handler: Arc::new(ui::Handler::default()),
 			stats: stats,
+			keepalive: keepalive,
 		}
 	}
 }
@@ -275,6 +352,8 @@ impl<M: Metadata, S: Middleware<M>> ws::Factory for Factory<M, S> {
 			authcodes_path: self.authcodes_path.clone(),
 			file_handler: self.file_handler.clone(),
 			stats: self.stats.clone(),
+			keepalive: self.keepalive,
+			last_pong: Instant::now(),
 		}
 	}
 }